@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rpki::cert::Cert;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Cert::decode(data);
+});
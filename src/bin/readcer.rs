@@ -19,12 +19,13 @@ fn main() {
             return;
         }
     };
-    let _cert = match Cert::decode(data.as_ref()) {
+    let cert = match Cert::decode(data.as_ref()) {
         Ok(cert) => cert,
         Err(err) => {
             println!("Can’t decode cert: {}", err);
             return
         }
     };
+    print!("{}", cert.dump());
 }
 
@@ -19,7 +19,7 @@ fn main() {
             return;
         }
     };
-    let _cert = match Manifest::decode(data.as_ref(), false) {
+    let _cert = match Manifest::from_slice(&data, false) {
         Ok(cert) => cert,
         Err(err) => {
             println!("Can’t decode manifest: {}", err);
@@ -9,7 +9,7 @@ use crate::resources::{
     IpResourcesBuilder
 };
 use crate::uri;
-use crate::x509::Name;
+use crate::x509::{Name, Serial};
 use super::Validity;
 
 
@@ -33,9 +33,9 @@ pub struct CertBuilder {
 
     /// Serial number.
     ///
-    /// This is required for all certificates. The standard demands twenty
-    /// digits, u128 gives us 38, so this should be fine.
-    serial_number: u128,
+    /// This is required for all certificates. It must be positive and no
+    /// longer than twenty octets.
+    serial_number: Serial,
 
     /// Signature.
     ///
@@ -59,7 +59,7 @@ pub struct CertBuilder {
     /// This needs to be present for all certifications. In RPKI, we commonly
     /// derive the name from the public key of the certificate, so this is an
     /// option. If it is not set explicitely, we derive the name.
-    subject: Option<Name>, // XXX NameBuilder?
+    subject: Option<Name>,
 
     /// Subject Public Key Info
     ///
@@ -170,7 +170,7 @@ impl CertBuilder {
         ca: bool
     ) -> Self {
         CertBuilder {
-            serial_number,
+            serial_number: serial_number.into(),
             issuer,
             validity,
             subject: None,
@@ -188,6 +188,18 @@ impl CertBuilder {
         }
     }
 
+    /// Sets the serial number of the certificate.
+    ///
+    /// This replaces the serial number given when creating the builder,
+    /// allowing serial numbers taken from a [`SerialSource`] or longer
+    /// than 128 bits.
+    ///
+    /// [`SerialSource`]: ../../x509/trait.SerialSource.html
+    pub fn serial(&mut self, serial: Serial) -> &mut Self {
+        self.serial_number = serial;
+        self
+    }
+
     pub fn subject(&mut self, name: Name) -> &mut Self {
         self.subject = Some(name);
         self
@@ -264,7 +276,7 @@ impl CertBuilder {
     }
     
     /// Finalizes the certificate and returns an encoder for it.
-    pub fn encode<S: Signer>(
+    pub fn encode<S: Signer + ?Sized>(
         self,
         signer: &S,
         key: &S::KeyId,
@@ -272,7 +284,9 @@ impl CertBuilder {
         public_key: &PublicKey,
     ) -> Result<impl encode::Values, SigningError<S::Error>> {
         let tbs_cert = self.encode_tbs_cert(alg, public_key);
-        let (alg, signature) = signer.sign(key, alg, &tbs_cert)?.unwrap();
+        let (alg, signature) = signer.sign(
+            key, alg, tbs_cert.as_slice()
+        )?.unwrap();
         Ok(encode::sequence((
             tbs_cert,
             alg.x509_encode(),
@@ -435,6 +449,10 @@ impl CertBuilder {
 }
 
 
+/// Returns a value encoder for an extension.
+///
+/// The critical flag has a default value of false which, under DER, must
+/// not be included in the encoding.
 pub(crate) fn extension<V: encode::Values>(
     oid: &'static ConstOid,
     critical: bool,
@@ -442,7 +460,7 @@ pub(crate) fn extension<V: encode::Values>(
 ) -> impl encode::Values {
     encode::sequence((
         oid.encode(),
-        critical.encode(),
+        if critical { Some(critical.encode()) } else { None },
         OctetString::encode_wrapped(Mode::Der, content)
     ))
 }
@@ -463,6 +481,7 @@ mod signer_test {
     use crate::crypto::softsigner::OpenSslSigner;
     use crate::resources::{AsId, Prefix};
     use crate::tal::TalInfo;
+    use crate::x509::{RandomSerial, SerialSource};
     use super::*;
         
     #[test]
@@ -475,7 +494,9 @@ mod signer_test {
         let mut builder = CertBuilder::new(
             12, pubkey.to_subject_name(), Validity::from_secs(86400), true
         );
+        let serial = RandomSerial::new(&signer).next_serial().unwrap();
         builder
+            .serial(serial)
             .ca_repository(uri.clone())
             .rpki_manifest(uri.clone())
             .v4_blocks(|blocks| blocks.push(Prefix::new(0, 0)))
@@ -484,6 +505,7 @@ mod signer_test {
             &signer, &key, SignatureAlgorithm::default(), &pubkey
         ).unwrap().to_captured(Mode::Der);
         let cert = Cert::decode(captured.as_slice()).unwrap();
+        assert_eq!(cert.serial_number(), serial);
         let talinfo = TalInfo::from_name("foo".into()).into_arc();
         cert.validate_ta(talinfo, true).unwrap();
     }
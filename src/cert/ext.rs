@@ -8,7 +8,7 @@ use bcder::{
 };
 use bcder::encode::PrimitiveContent;
 use bytes::Bytes;
-use crate::crypto::PublicKey;
+use crate::crypto::{KeyIdentifier, PublicKey};
 use crate::oid;
 use crate::resources::{AsResources, IpResources};
 use crate::uri;
@@ -85,19 +85,16 @@ impl Extensions {
         }
     }
 
-    pub fn subject_key_id(&self) -> &OctetString {
-        &self.subject_key_id.subject_key_id()
+    pub fn subject_key_id(&self) -> KeyIdentifier {
+        self.subject_key_id.subject_key_id()
     }
 
     pub fn crl_distribution(&self) -> Option<&UriGeneralNames> {
         self.crl_distribution.as_ref()
     }
 
-    pub fn authority_key_id(&self) -> Option<&OctetString> {
-        match &self.authority_key_id {
-            Some(a) => Some(a.authority_key_id()),
-            None => None
-        }
+    pub fn authority_key_id(&self) -> Option<KeyIdentifier> {
+        self.authority_key_id.as_ref().map(|a| a.authority_key_id())
     }
 
     pub fn authority_info_access(&self) -> Option<&UriGeneralName> {
@@ -527,46 +524,6 @@ impl BasicCa {
 }
 
 
-//------------ KeyIdentifier -------------------------------------------------
-
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct KeyIdentifier(OctetString);
-
-impl KeyIdentifier {
-    pub fn new(key_info: &PublicKey) -> Self {
-        let ki = key_info.key_identifier();
-        let b = Bytes::copy_from_slice(ki.as_ref());
-        KeyIdentifier(OctetString::new(b))
-    }
-
-    pub fn key_id(&self) -> &OctetString {
-        &self.0
-    }
-
-    pub fn encode(self) -> impl encode::Values {
-        self.0.encode()
-    }
-
-    pub fn encode_as(self, tag: Tag) -> impl encode::Values {
-        self.0.encode_as(tag)
-    }
-
-    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
-        self.0.encode_ref()
-    }
-
-    pub fn encode_ref_as<'a>(&'a self, tag: Tag) -> impl encode::Values + 'a {
-        self.0.encode_ref_as(tag)
-    }
-}
-
-impl From<OctetString> for KeyIdentifier {
-    fn from(o: OctetString) -> Self {
-        KeyIdentifier(o)
-    }
-}
-
-
 //------------ SubjectKeyIdentifier ------------------------------------------
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -578,11 +535,11 @@ pub struct SubjectKeyIdentifier {
 ///
 impl SubjectKeyIdentifier {
     pub fn new(key_info: &PublicKey) -> Self {
-        Self{subject_key_id: KeyIdentifier::new(key_info)}
+        Self{subject_key_id: key_info.key_identifier()}
     }
 
-    pub fn subject_key_id(&self) -> &OctetString {
-        self.subject_key_id.key_id()
+    pub fn subject_key_id(&self) -> KeyIdentifier {
+        self.subject_key_id
     }
 }
 
@@ -607,12 +564,12 @@ impl SubjectKeyIdentifier {
         subject_key_id: &mut Option<Self>
     ) -> Result<(), S::Err> {
         update_once(subject_key_id, || {
-            let subject_key_id = OctetString::take_from(cons)?;
-            if critical || subject_key_id.len() != 20 {
+            let subject_key_id = KeyIdentifier::take_from(cons)?;
+            if critical {
                 xerr!(Err(decode::Malformed.into()))
             }
             else {
-                Ok(Self{subject_key_id: subject_key_id.into()} )
+                Ok(Self{subject_key_id})
             }
         })
     }
@@ -629,7 +586,7 @@ impl SubjectKeyIdentifier {
         encode_extension(
             oid::CE_SUBJECT_KEY_IDENTIFIER,
             false,
-            self.subject_key_id.encode_ref()
+            self.subject_key_id.encode()
         )
     }
 }
@@ -639,21 +596,18 @@ impl SubjectKeyIdentifier {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct AuthorityKeyIdentifier {
-    authority_key_id: OctetString
+    authority_key_id: KeyIdentifier
 }
 
 /// # Creating and Data Access
 ///
 impl AuthorityKeyIdentifier {
     pub fn new(key_info: &PublicKey) -> Self {
-        let ki = key_info.key_identifier();
-        let b = Bytes::copy_from_slice(ki.as_ref());
-
-        Self{authority_key_id: OctetString::new(b)}
+        Self{authority_key_id: key_info.key_identifier()}
     }
 
-    pub fn authority_key_id(&self) -> &OctetString {
-        &self.authority_key_id
+    pub fn authority_key_id(&self) -> KeyIdentifier {
+        self.authority_key_id
     }
 
 }
@@ -676,7 +630,8 @@ impl AuthorityKeyIdentifier {
     /// KeyIdentifier ::= OCTET STRING
     /// ```
     ///
-    /// Only keyIdentifier MUST be present.
+    /// Only keyIdentifier MUST be present. RFC 6487 forbids the other two
+    /// fields, so a value containing them is rejected as malformed.
     pub fn take<S: decode::Source>(
         cons: &mut decode::Constructed<S>,
         critical: bool,
@@ -684,9 +639,9 @@ impl AuthorityKeyIdentifier {
     ) -> Result<(), S::Err> {
         update_once(authority_key_id, || {
             let authority_key_id = cons.take_sequence(|cons| {
-                cons.take_value_if(Tag::CTX_0, OctetString::from_content)
+                cons.take_value_if(Tag::CTX_0, KeyIdentifier::from_content)
             })?;
-            if critical || authority_key_id.len() != 20 {
+            if critical {
                 Err(decode::Malformed.into())
             }
             else {
@@ -710,7 +665,7 @@ impl AuthorityKeyIdentifier {
             oid::CE_AUTHORITY_KEY_IDENTIFIER,
             false,
             encode::sequence(
-                 self.authority_key_id.encode_ref_as(Tag::CTX_0)
+                 self.authority_key_id.encode_as(Tag::CTX_0)
             )
         )
     }
@@ -1160,5 +1115,47 @@ mod tests {
             v
         );
     }
+
+    #[test]
+    fn key_identifiers() {
+        let cert = crate::cert::Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let key = cert.subject_public_key_info();
+        let key_id = key.key_identifier();
+
+        let ski = SubjectKeyIdentifier::new(key);
+        assert_eq!(ski.subject_key_id(), key_id);
+        let mut v = Vec::new();
+        ski.encode_ref().write_encoded(Mode::Der, &mut v).unwrap();
+        assert_eq!(
+            &v[..12],
+            b"\x30\x20\x06\x03\x55\x1d\x0e\x01\x01\x00\x04\x16"
+        );
+        let mut decoded = None;
+        Mode::Der.decode(&v[12..], |cons| {
+            SubjectKeyIdentifier::take(cons, false, &mut decoded)
+        }).unwrap();
+        assert_eq!(decoded, Some(ski));
+
+        let aki = AuthorityKeyIdentifier::new(key);
+        assert_eq!(
+            aki.authority_key_id().to_string(),
+            "E8552B1FD6D1A4F7E404C6D8E5680D1EBC163FC3"
+        );
+        let mut v = Vec::new();
+        aki.encode_ref().write_encoded(Mode::Der, &mut v).unwrap();
+        let mut decoded = None;
+        Mode::Der.decode(&v[12..], |cons| {
+            AuthorityKeyIdentifier::take(cons, false, &mut decoded)
+        }).unwrap();
+        assert_eq!(decoded, Some(aki.clone()));
+
+        // A critical extension is rejected.
+        let mut decoded = None;
+        assert!(Mode::Der.decode(&v[12..], |cons| {
+            AuthorityKeyIdentifier::take(cons, true, &mut decoded)
+        }).is_err());
+    }
 }
 
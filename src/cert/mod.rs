@@ -23,32 +23,43 @@ pub use self::builder::CertBuilder;
 pub mod builder;
 pub mod ext;
 
-use std::{borrow, ops};
+use std::{borrow, error, fmt, io, ops};
+use std::cmp::min;
 use std::iter::FromIterator;
 use std::sync::Arc;
 use bcder::{decode, encode};
 use bcder::xerr;
-use bcder::encode::PrimitiveContent;
+use bcder::encode::{PrimitiveContent, Values};
 use bcder::{
-    BitString, Captured, ConstOid, Ia5String, Mode, OctetString, Oid, Tag
+    BitString, Captured, ConstOid, Ia5String, Mode, OctetString, Oid, Tag,
+    Unsigned
 };
 use bytes::Bytes;
+use chrono::Duration;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use crate::oid;
+use crate::crl::{Crl, CrlValidationError};
 use crate::resources::{AsBlocks, IpBlocks};
 use crate::tal::TalInfo;
 use crate::uri;
 use crate::x509::{
-    Name, SignedData, Serial, Time, Validity, ValidationError,
-    encode_extension, update_first, update_once
+    Name, ReadError, SignedData, Serial, Time, Validity, ValidationError,
+    encode_extension, read_value, update_first, update_once,
+    verify_canonical
 };
 use crate::crypto::{
-    KeyIdentifier, PublicKey, SignatureAlgorithm, Signer, SigningError
+    AlgorithmPolicy, KeyIdentifier, PublicKey, SignatureAlgorithm, Signer,
+    SigningError
 };
 use crate::resources::{
     AddressFamily, AsBlock, AsBlocksBuilder, AsResources, AsResourcesBuilder,
     IpBlock, IpBlocksBuilder, IpResources, IpResourcesBuilder
 };
+#[cfg(feature = "softkeys")] use crate::crypto::PublicKeyFormat;
+#[cfg(feature = "softkeys")] use crate::crypto::softsigner::{
+    KeyId, OpenSslSigner
+};
+#[cfg(feature = "softkeys")] use crate::resources::{AsId, Prefix};
 
 
 //------------ Cert ----------------------------------------------------------
@@ -81,6 +92,11 @@ use crate::resources::{
 /// further processing. In addition, various methods exist to access
 /// information contained in the certificate.
 ///
+/// All the data of a certificate is kept in reference counted buffers, so
+/// cloning a certificate is cheap and doesn’t copy the encoded data. If a
+/// certificate needs to be shared between threads, cloning it is therefore
+/// just as good as wrapping it in an `Arc`.
+///
 /// [`ResourceCert`]: struct.ResourceCert.html
 /// [`decode`]: #method.decode
 /// [`take_from`]: #method.take_from
@@ -103,7 +119,120 @@ pub struct Cert {
 impl Cert {
     /// Decodes a source as a certificate.
     pub fn decode<S: decode::Source>(source: S) -> Result<Self, S::Err> {
-        Mode::Der.decode(source, Self::take_from)
+        Self::decode_with_limits(source, DecodeLimits::default())
+    }
+
+    /// Decodes a certificate from its DER encoded octets.
+    ///
+    /// This is a shortcut for the common case of having the content of a
+    /// certificate file at hand. It accepts anything that can be turned
+    /// into a slice, such as a `Vec<u8>` or `Bytes`. Like
+    /// [`decode_detailed`], it reports why decoding failed.
+    ///
+    /// [`decode_detailed`]: #method.decode_detailed
+    pub fn from_der<T: AsRef<[u8]>>(
+        data: T
+    ) -> Result<Self, CertDecodeError> {
+        Self::decode_detailed(data.as_ref())
+    }
+
+    /// Decodes a certificate from a byte slice.
+    ///
+    /// The slice is only borrowed while decoding, so it can be the content
+    /// of a memory-mapped file that is unmapped again afterwards. The parts
+    /// of the certificate that are kept in the returned value are copied.
+    /// Unlike [`from_der`], this doesn’t explain why decoding failed.
+    ///
+    /// [`from_der`]: #method.from_der
+    pub fn from_slice(slice: &[u8]) -> Result<Self, decode::Error> {
+        Self::decode(slice)
+    }
+
+    /// Decodes a certificate reporting why decoding failed.
+    ///
+    /// This is the same as [`decode`] but returns a [`CertDecodeError`]
+    /// that, where possible, explains what is wrong with the certificate.
+    ///
+    /// [`decode`]: #method.decode
+    /// [`CertDecodeError`]: enum.CertDecodeError.html
+    pub fn decode_detailed<S: decode::Source>(
+        source: S
+    ) -> Result<Self, CertDecodeError> {
+        let mut err = None;
+        Mode::Der.decode(source, |cons| {
+            cons.take_sequence(|cons| {
+                Self::from_constructed_detailed(
+                    cons, &DecodeOptions::default(), &mut err
+                )
+            })
+        }).map_err(|_| err.unwrap_or(CertDecodeError::Malformed))
+    }
+
+    /// Reads and decodes a single certificate from a reader.
+    ///
+    /// Only the octets of the certificate are read from `reader`, so any
+    /// data following it remains available. This makes it possible to read
+    /// several certificates arriving back-to-back by passing a mutable
+    /// reference to the reader.
+    ///
+    /// Certificates with more than 1 MiB of content are rejected without
+    /// reading them.
+    pub fn from_reader<R: io::Read>(reader: R) -> Result<Self, ReadError> {
+        Self::decode(read_value(reader, MAX_READ_LEN)?).map_err(Into::into)
+    }
+
+    /// Decodes all certificates of a bundle.
+    ///
+    /// The bundle can either be a DER encoded PKCS #7 “certs-only”
+    /// message, i.e., signed data without signers as produced by
+    /// `openssl crl2pkcs7 -nocrl`, or simply a sequence of DER encoded
+    /// certificates following each other. PEM encoding is not supported.
+    ///
+    /// Rather than failing altogether, the method returns all certificates
+    /// that could be decoded and an error for each one that couldn’t. If
+    /// the data can’t be split into individual values anymore, a final
+    /// error is added and the rest of the data is ignored.
+    pub fn decode_all(bytes: &[u8]) -> (Vec<Self>, Vec<ReadError>) {
+        let certs_only = take_certs_only(bytes);
+        let mut reader = match certs_only {
+            Some(ref certs) => certs.as_slice(),
+            None => bytes
+        };
+        let mut res = Vec::new();
+        let mut errs = Vec::new();
+        while !reader.is_empty() {
+            match read_value(&mut reader, bytes.len() as u64) {
+                Ok(value) => {
+                    match Self::decode(value) {
+                        Ok(cert) => res.push(cert),
+                        Err(err) => errs.push(err.into())
+                    }
+                }
+                Err(err) => {
+                    errs.push(err);
+                    break
+                }
+            }
+        }
+        (res, errs)
+    }
+
+    /// Decodes a source as a certificate observing the given size limits.
+    pub fn decode_with_limits<S: decode::Source>(
+        source: S,
+        limits: DecodeLimits
+    ) -> Result<Self, S::Err> {
+        Self::decode_with_options(source, &limits.into())
+    }
+
+    /// Decodes a source as a certificate using the given options.
+    pub fn decode_with_options<S: decode::Source>(
+        source: S,
+        options: &DecodeOptions
+    ) -> Result<Self, S::Err> {
+        Mode::Der.decode(source, |cons| {
+            Self::take_from_with_options(cons, options)
+        })
     }
 
     /// Takes an encoded certificate from the beginning of a value.
@@ -113,17 +242,60 @@ impl Cert {
     pub fn take_from<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
-        cons.take_sequence(Self::from_constructed)
+        Self::take_from_with_limits(cons, DecodeLimits::default())
+    }
+
+    /// Takes an encoded certificate observing the given size limits.
+    pub fn take_from_with_limits<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        limits: DecodeLimits
+    ) -> Result<Self, S::Err> {
+        Self::take_from_with_options(cons, &limits.into())
+    }
+
+    /// Takes an encoded certificate using the given options.
+    pub fn take_from_with_options<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        options: &DecodeOptions
+    ) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            Self::from_constructed_with_options(cons, options)
+        })
     }
 
     /// Parses the content of a Certificate sequence.
     pub fn from_constructed<S: decode::Source>(
         cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        Self::from_constructed_with_limits(cons, DecodeLimits::default())
+    }
+
+    /// Parses the content of a Certificate sequence observing size limits.
+    pub fn from_constructed_with_limits<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        limits: DecodeLimits
+    ) -> Result<Self, S::Err> {
+        Self::from_constructed_with_options(cons, &limits.into())
+    }
+
+    /// Parses the content of a Certificate sequence using the options.
+    pub fn from_constructed_with_options<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        options: &DecodeOptions
+    ) -> Result<Self, S::Err> {
+        Self::from_constructed_detailed(cons, options, &mut None)
+    }
+
+    /// Parses the content noting the reason for failing in `err`.
+    fn from_constructed_detailed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        options: &DecodeOptions,
+        err: &mut Option<CertDecodeError>
     ) -> Result<Self, S::Err> {
         let signed_data = SignedData::from_constructed(cons)?;
-        let tbs = signed_data.data().clone().decode(
-            TbsCert::from_constructed
-        )?;
+        let tbs = signed_data.data().clone().decode(|cons| {
+            TbsCert::from_constructed_detailed(cons, options, err)
+        })?;
         Ok(Self { signed_data, tbs })
     }
 
@@ -136,6 +308,117 @@ impl Cert {
     pub fn to_captured(&self) -> Captured {
         Captured::from_values(Mode::Der, self.encode_ref())
     }
+
+    /// Returns the DER encoding of the certificate.
+    ///
+    /// The result is a standard X.509 certificate that can be handed to
+    /// other X.509 libraries. The to-be-signed portion is reproduced
+    /// exactly as it was decoded, so the signature remains valid.
+    pub fn to_der(&self) -> Bytes {
+        self.to_captured().into_bytes()
+    }
+
+    /// Writes the DER encoding of the certificate to a writer.
+    ///
+    /// This produces the same octets as [`to_der`] but writes them
+    /// directly without collecting them in memory first, which is useful
+    /// when storing many certificates in files.
+    ///
+    /// [`to_der`]: #method.to_der
+    pub fn write_der<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        self.encode_ref().write_encoded(Mode::Der, target)
+    }
+
+    /// Checks that the certificate was encoded in canonical DER.
+    ///
+    /// Decoding accepts some encodings that are valid BER but not DER,
+    /// most notably unsorted sets and content that is only skipped, such
+    /// as unknown extensions. This method checks the to-be-signed portion
+    /// of the certificate as it was decoded against all the rules listed
+    /// with [`verify_canonical`]. Since the outer structure is
+    /// re-encoded anyway, it is not checked.
+    ///
+    /// [`verify_canonical`]: ../x509/fn.verify_canonical.html
+    pub fn verify_canonical(&self) -> Result<(), decode::Error> {
+        verify_canonical(self.signed_data.data().as_slice())
+    }
+}
+
+
+/// # Dumping
+///
+impl Cert {
+    /// Returns a human-readable summary of the certificate.
+    ///
+    /// The summary is modelled after the text output of OpenSSL and spans
+    /// multiple lines. It contains the version, serial number, signature
+    /// algorithm, issuer, validity, subject, the public key’s algorithm and
+    /// size, and the subject key identifier, authority key identifier, and
+    /// basic constraints extensions if present. It is intended for
+    /// debugging and its format will stay stable.
+    pub fn dump(&self) -> String {
+        let mut res = String::new();
+        self.dump_fmt(&mut res).expect("writing to a string failed");
+        res
+    }
+
+    /// Writes the summary produced by `dump` to `target`.
+    fn dump_fmt<W: fmt::Write>(&self, target: &mut W) -> fmt::Result {
+        const TIME_FMT: &str = "%b %e %H:%M:%S %Y GMT";
+
+        writeln!(target, "Certificate:")?;
+        writeln!(target, "    Data:")?;
+        writeln!(target, "        Version: 3 (0x2)")?;
+        writeln!(
+            target, "        Serial Number: {} (0x{:x})",
+            self.serial_number, self.serial_number
+        )?;
+        writeln!(
+            target, "        Signature Algorithm: {}",
+            match self.signature {
+                SignatureAlgorithm::RsaSha256 => "sha256WithRSAEncryption",
+                SignatureAlgorithm::RsaPssSha256 => "rsassaPss",
+            }
+        )?;
+        writeln!(target, "        Issuer: {}", self.issuer)?;
+        writeln!(target, "        Validity")?;
+        writeln!(
+            target, "            Not Before: {}",
+            self.validity.not_before().format(TIME_FMT)
+        )?;
+        writeln!(
+            target, "            Not After : {}",
+            self.validity.not_after().format(TIME_FMT)
+        )?;
+        writeln!(target, "        Subject: {}", self.subject)?;
+        writeln!(target, "        Subject Public Key Info:")?;
+        writeln!(target, "            Public Key Algorithm: rsaEncryption")?;
+        match self.subject_public_key_info.key_size() {
+            Some(size) => {
+                writeln!(
+                    target, "                Public-Key: ({} bit)", size
+                )?;
+            }
+            None => {
+                writeln!(target, "                Public-Key: (invalid)")?;
+            }
+        }
+        writeln!(target, "        X509v3 extensions:")?;
+        if let Some(ca) = self.basic_ca {
+            writeln!(target, "            X509v3 Basic Constraints:")?;
+            writeln!(
+                target, "                CA:{}",
+                if ca { "TRUE" } else { "FALSE" }
+            )?;
+        }
+        writeln!(target, "            X509v3 Subject Key Identifier:")?;
+        writeln!(target, "                {}", self.subject_key_identifier)?;
+        if let Some(aki) = self.authority_key_identifier {
+            writeln!(target, "            X509v3 Authority Key Identifier:")?;
+            writeln!(target, "                keyid:{}", aki)?;
+        }
+        Ok(())
+    }
 }
 
 
@@ -161,15 +444,120 @@ impl Cert {
         strict: bool,
         now: Time,
     ) -> Result<ResourceCert, ValidationError> {
-        self.validate_basics(strict, now)?;
+        self.validate_ta_with(
+            tal, strict, Some(now), &AlgorithmPolicy::default()
+        )
+    }
+
+    /// Validates the certificate as a trust anchor under a local policy.
+    ///
+    /// This is the same as [`validate_ta_at`] but the certificate’s
+    /// signature algorithm must be permitted by `policy` rather than be
+    /// the one mandated for RPKI.
+    ///
+    /// [`validate_ta_at`]: #method.validate_ta_at
+    pub fn validate_ta_with_policy(
+        self,
+        tal: Arc<TalInfo>,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+    ) -> Result<ResourceCert, ValidationError> {
+        self.validate_ta_with(tal, strict, Some(now), policy)
+    }
+
+    /// Validates the certificate as a trust anchor without consuming it.
+    ///
+    /// This performs the same checks as [`validate_ta_at`] but only
+    /// reports whether they succeeded.
+    ///
+    /// [`validate_ta_at`]: #method.validate_ta_at
+    pub fn validate_ta_ref(
+        &self,
+        strict: bool,
+        now: Time,
+    ) -> Result<(), ValidationError> {
+        self.validate_ta_checks(
+            strict, Some(now), &AlgorithmPolicy::default()
+        ).map(|_| ())
+    }
+
+    /// Validates the certificate as a trust anchor, reporting expiry.
+    ///
+    /// This is the same as [`validate_ta_at`] but if the certificate fails
+    /// only because it has expired, returns
+    /// [`TaValidationError::Expired`] with the time the certificate
+    /// expired. This allows callers to decide whether they still want to
+    /// use the certificate, e.g., for historical analysis.
+    ///
+    /// [`validate_ta_at`]: #method.validate_ta_at
+    /// [`TaValidationError::Expired`]: enum.TaValidationError.html
+    pub fn validate_ta_detailed_at(
+        self,
+        tal: Arc<TalInfo>,
+        strict: bool,
+        now: Time,
+    ) -> Result<ResourceCert, TaValidationError> {
+        let validity = self.validity;
+        let res = self.validate_ta_with(
+            tal, strict, None, &AlgorithmPolicy::default()
+        )?;
+        validity.not_before().validate_not_before(now)?;
+        if validity.not_after().validate_not_after(now).is_err() {
+            return Err(TaValidationError::Expired {
+                not_after: validity.not_after()
+            })
+        }
+        Ok(res)
+    }
+
+    /// Validates the certificate as a trust anchor without checking time.
+    ///
+    /// This performs all the checks of [`validate_ta`] except for checking
+    /// that the current time is within the certificate’s validity period.
+    /// It is intended for tools inspecting trust anchors. Relying parties
+    /// should use [`validate_ta`] instead.
+    ///
+    /// [`validate_ta`]: #method.validate_ta
+    pub fn validate_ta_ignoring_time(
+        self,
+        tal: Arc<TalInfo>,
+        strict: bool,
+    ) -> Result<ResourceCert, ValidationError> {
+        self.validate_ta_with(tal, strict, None, &AlgorithmPolicy::default())
+    }
+
+    /// Validates the certificate as a trust anchor.
+    ///
+    /// The validity period is only checked if `now` is some time.
+    fn validate_ta_with(
+        self,
+        tal: Arc<TalInfo>,
+        strict: bool,
+        now: Option<Time>,
+        policy: &AlgorithmPolicy,
+    ) -> Result<ResourceCert, ValidationError> {
+        let resources = self.validate_ta_checks(strict, now, policy)?;
+        Ok(self.into_resource_cert(resources, tal))
+    }
+
+    /// Performs the checks for a trust anchor certificate.
+    ///
+    /// Returns the certificate’s resources upon success.
+    fn validate_ta_checks(
+        &self,
+        strict: bool,
+        now: Option<Time>,
+        policy: &AlgorithmPolicy,
+    ) -> Result<(IpBlocks, IpBlocks, AsBlocks), ValidationError> {
+        self.validate_basics(strict, now, policy)?;
         self.validate_ca_basics(strict)?;
 
         // 4.8.3. Authority Key Identifier. May be present, if so, must be
-        // equal to the subject key indentifier.
-        if let Some(ref aki) = self.authority_key_identifier {
-            if *aki != self.subject_key_identifier {
-                return Err(ValidationError);
-            }
+        // equal to the subject key indentifier. The certificate must also
+        // be signed by its own key.
+        if !self.is_self_signed() {
+            return Err(ValidationError);
         }
 
         // 4.8.6. CRL Distribution Points. There musn’t be one.
@@ -197,17 +585,7 @@ impl Cert {
             self.as_resources.as_ref()
         )?;
 
-        self.signed_data.verify_signature(
-            &self.subject_public_key_info
-        )?;
-
-        Ok(ResourceCert {
-            cert: self,
-            v4_resources,
-            v6_resources,
-            as_resources,
-            tal
-        })
+        Ok((v4_resources, v6_resources, as_resources))
     }
 
     /// Validates the certificate as a CA certificate.
@@ -230,11 +608,63 @@ impl Cert {
         strict: bool,
         now: Time,
     ) -> Result<ResourceCert, ValidationError> {
-        self.validate_basics(strict, now)?;
+        self.validate_ca_with_policy(
+            issuer, strict, now, &AlgorithmPolicy::default()
+        )
+    }
+
+    /// Validates the certificate as a CA certificate under a local policy.
+    ///
+    /// This is the same as [`validate_ca_at`] but the certificate’s
+    /// signature algorithm must be permitted by `policy` rather than be
+    /// the one mandated for RPKI.
+    ///
+    /// [`validate_ca_at`]: #method.validate_ca_at
+    pub fn validate_ca_with_policy(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+    ) -> Result<ResourceCert, ValidationError> {
+        let resources = self.validate_ca_checks(
+            issuer, strict, now, policy
+        )?;
+        Ok(self.into_resource_cert(resources, issuer.tal.clone()))
+    }
+
+    /// Validates the certificate as a CA certificate without consuming it.
+    ///
+    /// This performs the same checks as [`validate_ca_at`] but only
+    /// reports whether they succeeded.
+    ///
+    /// [`validate_ca_at`]: #method.validate_ca_at
+    pub fn validate_ca_ref(
+        &self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+    ) -> Result<(), ValidationError> {
+        self.validate_ca_checks(
+            issuer, strict, now, &AlgorithmPolicy::default()
+        ).map(|_| ())
+    }
+
+    /// Performs the checks for a CA certificate.
+    ///
+    /// Returns the certificate’s resources upon success.
+    fn validate_ca_checks(
+        &self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+    ) -> Result<(IpBlocks, IpBlocks, AsBlocks), ValidationError> {
+        self.validate_basics(strict, Some(now), policy)?;
         self.validate_ca_basics(strict)?;
         self.validate_issued(issuer, strict)?;
         self.validate_signature(issuer, strict)?;
-        self.validate_resources(issuer, strict)
+        self.issued_resources(issuer, strict)
     }
 
     /// Validates the certificate as an EE certificate.
@@ -257,55 +687,213 @@ impl Cert {
         strict: bool,
         now: Time,
     ) -> Result<ResourceCert, ValidationError>  {
-        self.validate_basics(strict, now)?;
+        self.validate_ee_with_policy(
+            issuer, strict, now, &AlgorithmPolicy::default()
+        )
+    }
+
+    /// Validates the certificate as an EE certificate under a local policy.
+    ///
+    /// This is the same as [`validate_ee_at`] but the certificate’s
+    /// signature algorithm must be permitted by `policy` rather than be
+    /// the one mandated for RPKI.
+    ///
+    /// [`validate_ee_at`]: #method.validate_ee_at
+    pub fn validate_ee_with_policy(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+    ) -> Result<ResourceCert, ValidationError>  {
+        let resources = self.validate_ee_checks(
+            issuer, strict, now, policy
+        )?;
+        Ok(self.into_resource_cert(resources, issuer.tal.clone()))
+    }
+
+    /// Validates the certificate as an EE certificate without consuming it.
+    ///
+    /// This performs the same checks as [`validate_ee_at`] but only
+    /// reports whether they succeeded.
+    ///
+    /// [`validate_ee_at`]: #method.validate_ee_at
+    pub fn validate_ee_ref(
+        &self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+    ) -> Result<(), ValidationError>  {
+        self.validate_ee_checks(
+            issuer, strict, now, &AlgorithmPolicy::default()
+        ).map(|_| ())
+    }
+
+    /// Performs the checks for an EE certificate.
+    ///
+    /// Returns the certificate’s resources upon success.
+    fn validate_ee_checks(
+        &self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+    ) -> Result<(IpBlocks, IpBlocks, AsBlocks), ValidationError>  {
+        self.validate_basics(strict, Some(now), policy)?;
         self.validate_issued(issuer, strict)?;
+        self.validate_ee_basics(strict)?;
+        self.validate_signature(issuer, strict)?;
+        self.issued_resources(issuer, strict)
+    }
 
-        // 4.8.1. Basic Constraints: Must not be present.
-        if self.basic_ca.is_some(){
-            return Err(ValidationError)
+    /// Validates the certificate including its revocation status.
+    ///
+    /// This is a shortcut for validating a certificate as either a CA or EE
+    /// certificate depending on its key usage and checking the CRL of its
+    /// issuer at the same time. The checks are performed in the following
+    /// order, each reported via its own variant of [`CertValidationError`]:
+    ///
+    /// * `Basics`: the certificate complies with section 4 of RFC 6487,
+    ///   including the requirements for CA or EE certificates, and it is
+    ///   valid at `now`;
+    /// * `Issuer`: the authority key identifier matches the subject key
+    ///   identifier of `issuer` and the CRL distribution point and
+    ///   authority information access extensions are present;
+    /// * `Signature`: the certificate was signed by the key of `issuer`;
+    /// * `Crl`: the CRL was signed by the key of `issuer` and names it via
+    ///   its authority key identifier. In strict mode, the CRL also must
+    ///   not be stale at `now`;
+    /// * `Revoked`: the certificate’s serial number is not on the CRL;
+    /// * `Resources`: the certificate’s IP and AS resources are covered by
+    ///   those of `issuer`.
+    ///
+    /// [`CertValidationError`]: enum.CertValidationError.html
+    pub fn validate_full(
+        self,
+        issuer: &ResourceCert,
+        crl: &Crl,
+        strict: bool,
+        now: Time,
+    ) -> Result<ResourceCert, CertValidationError> {
+        self.validate_basics(
+            strict, Some(now), &AlgorithmPolicy::default()
+        ).map_err(|_| {
+            CertValidationError::Basics
+        })?;
+        let role = if self.basic_ca == Some(true) {
+            KeyUsage::Ca
+        }
+        else {
+            KeyUsage::Ee
+        };
+        if self.key_usage != role {
+            return Err(CertValidationError::KeyUsage)
+        }
+        match role {
+            KeyUsage::Ca => self.validate_ca_basics(strict),
+            KeyUsage::Ee => self.validate_ee_basics(strict),
+        }.map_err(|_| CertValidationError::Basics)?;
+        self.validate_issued(issuer, strict).map_err(|_| {
+            CertValidationError::Issuer
+        })?;
+        self.validate_signature(issuer, strict).map_err(|_| {
+            CertValidationError::Signature
+        })?;
+        if *crl.authority_key_identifier() != issuer.subject_key_identifier() {
+            return Err(CertValidationError::Crl(ValidationError.into()))
         }
+        crl.validate_at(
+            issuer.subject_public_key_info(), strict, now
+        ).map_err(CertValidationError::Crl)?;
+        if crl.contains(self.serial_number) {
+            return Err(CertValidationError::Revoked)
+        }
+        self.validate_resources(issuer, strict).map_err(|_| {
+            CertValidationError::Resources
+        })
+    }
 
-        // 4.8.4. Key Usage. Bits for CA or not CA have been checked during
-        // parsing already.
-        if self.key_usage != KeyUsage::Ee {
-            return Err(ValidationError)
+    /// Returns whether the certificate is self-signed.
+    ///
+    /// This is the case if the authority key identifier is either absent
+    /// or equal to the subject key identifier and the signature verifies
+    /// against the certificate’s own public key. This can be used to decide
+    /// whether to validate a certificate as a trust anchor or as an issued
+    /// certificate.
+    pub fn is_self_signed(&self) -> bool {
+        if let Some(aki) = self.authority_key_identifier {
+            if aki != self.subject_key_identifier {
+                return false
+            }
         }
+        self.signed_data.verify_signature(
+            &self.subject_public_key_info
+        ).is_ok()
+    }
 
-        // 4.8.8.  Subject Information Access. We need the signed object
-        // but not the other ones.
-        if self.ca_repository.is_some() || self.rpki_manifest.is_some()
-            || self.signed_object.is_none()
+    /// Verifies that the certificate was issued by the given certificate.
+    ///
+    /// This only checks that the authority key identifier matches the
+    /// issuer’s subject key identifier and that the certificate was signed
+    /// by the issuer’s key. None of the RPKI profile checks of the other
+    /// validation methods are applied, so this is useful for certificates
+    /// used outside of the RPKI proper, such as the identity certificates of
+    /// the provisioning and publication protocols.
+    pub fn verify_issued_by(
+        &self,
+        issuer: &Cert
+    ) -> Result<(), ValidationError> {
+        if self.authority_key_identifier()
+            != Some(issuer.subject_key_identifier())
         {
             return Err(ValidationError)
         }
-        if self.rpki_notify.is_some() && strict {
-            return Err(ValidationError)
-        }
-
-        self.validate_signature(issuer, strict)?;
-        self.validate_resources(issuer, strict)
+        self.signed_data.verify_signature(issuer.subject_public_key_info())
     }
 
 
     //--- Validation Components
 
     /// Validates basic compliance with section 4 of RFC 6487.
+    ///
+    /// The validity period is only checked if `now` is some time. The
+    /// signature algorithm must be permitted by `policy`.
     fn validate_basics(
         &self,
         strict: bool,
-        now: Time
+        now: Option<Time>,
+        policy: &AlgorithmPolicy,
     ) -> Result<(), ValidationError> {
         // The following lists all such constraints in the RFC, noting those
         // that we cannot check here.
 
         // 4.2 Serial Number: must be unique over the CA. We cannot check
         // here, and -- XXX --- probably don’t care?
+        //
+        // RFC 5280 additionally requires it to be a positive integer of at
+        // most 20 octets. Decoding checks this but can be told to tolerate
+        // a zero serial. Strict mode doesn’t.
+        if strict && self.serial_number.is_zero() {
+            return Err(ValidationError)
+        }
+
+        // RFC 5280 requires DER. Decoding already enforces most of it but
+        // the complete check is somewhat expensive, so only do it in
+        // strict mode.
+        if strict && self.verify_canonical().is_err() {
+            return Err(ValidationError)
+        }
 
-        // 4.3 Signature Algorithm: limited to those in RFC 6485. Already
-        // checked in parsing.
+        // 4.3 Signature Algorithm: limited to those in RFC 6485. Parsing
+        // also accepts algorithms for non-RPKI certificates, so we need to
+        // check here. The policy defaults to exactly those algorithms but
+        // operators may choose differently.
         //
-        // However, RFC 5280 demands that the two mentions of the signature
-        // algorithm are the same. So we do that here.
+        // Additionally, RFC 5280 demands that the two mentions of the
+        // signature algorithm are the same. So we do that here, too.
+        if !policy.permits(self.signature) {
+            return Err(ValidationError)
+        }
         if self.signature != self.signed_data.signature().algorithm() {
             return Err(ValidationError)
         }
@@ -317,7 +905,9 @@ impl Cert {
         Name::validate_rpki(&self.subject, strict)?;
         
         // 4.6 Validity. Check according to RFC 5280.
-        self.validity.validate_at(now)?;
+        if let Some(now) = now {
+            self.validity.validate_at(now)?;
+        }
 
         // 4.7 Subject Public Key Info: limited algorithms. Already checked
         // during parsing.
@@ -326,14 +916,17 @@ impl Cert {
         // certificates.
         
         // 4.8.2. Subject Key Identifer. Must be the SHA-1 hash of the octets
-        // of the subjectPublicKey.
+        // of the subjectPublicKey. PublicKey::key_identifier takes care of
+        // choosing the digest algorithm.
         if self.subject_key_identifier() != 
                              self.subject_public_key_info().key_identifier() {
             return Err(ValidationError)
         }
 
         // 4.8.3. Authority Key Identifier. Differing requirements of TA and
-        // other certificates.
+        // other certificates. If present, it must only contain the
+        // keyIdentifier field. Decoding already rejects the
+        // authorityCertIssuer and authorityCertSerialNumber fields.
 
         // 4.8.4. Key Usage. Differs between CA and EE certificates.
 
@@ -361,6 +954,15 @@ impl Cert {
         // 4.8.11.  AS Resources. Differs between trust anchor and issued
         // certificates.
 
+        // 4.8. Other extensions. Critical extensions outside the profile
+        // are only present if tolerated by the decode options. The profile
+        // doesn’t allow them, so they are an error in strict mode.
+        if strict
+            && self.other_extensions.iter().any(OtherExtension::is_critical)
+        {
+            return Err(ValidationError)
+        }
+
         Ok(())
     }
 
@@ -408,11 +1010,8 @@ impl Cert {
             return Err(ValidationError)
         }
 
-        // 4.8.4. Key Usage. Bits for CA or not CA have been checked during
-        // parsing already.
-        if self.key_usage() != KeyUsage::Ca {
-            return Err(ValidationError)
-        }
+        // 4.8.4. Key Usage. Must assert keyCertSign and cRLSign.
+        self.validate_key_usage(KeyUsage::Ca)?;
 
         // 4.8.8.  Subject Information Access.
         if self.ca_repository().is_none() || self.rpki_manifest().is_none()
@@ -424,6 +1023,50 @@ impl Cert {
         Ok(())
     }
 
+    /// Validates that the certificate is a valid EE certificate.
+    fn validate_ee_basics(
+        &self,
+        strict: bool
+    ) -> Result<(), ValidationError> {
+        // 4.8.1. Basic Constraints: Must not be present.
+        if self.basic_ca.is_some(){
+            return Err(ValidationError)
+        }
+
+        // 4.8.4. Key Usage. Must assert digitalSignature.
+        self.validate_key_usage(KeyUsage::Ee)?;
+
+        // 4.8.8.  Subject Information Access. We need the signed object
+        // but not the other ones.
+        if self.ca_repository.is_some() || self.rpki_manifest.is_some()
+            || self.signed_object.is_none()
+        {
+            return Err(ValidationError)
+        }
+        if self.rpki_notify.is_some() && strict {
+            return Err(ValidationError)
+        }
+
+        Ok(())
+    }
+
+    /// Validates that the key usage fits the role of the certificate.
+    ///
+    /// Decoding has already made sure that the key usage either asserts
+    /// keyCertSign and cRLSign or digitalSignature. The wrong one of these
+    /// is an error in both strict and lax mode.
+    fn validate_key_usage(
+        &self,
+        role: KeyUsage,
+    ) -> Result<(), ValidationError> {
+        if self.key_usage == role {
+            Ok(())
+        }
+        else {
+            Err(ValidationError)
+        }
+    }
+
     /// Validates the certificate’s signature.
     fn validate_signature(
         &self,
@@ -441,29 +1084,99 @@ impl Cert {
     fn validate_resources(
         self,
         issuer: &ResourceCert,
-        _strict: bool
+        strict: bool
     ) -> Result<ResourceCert, ValidationError> {
-        Ok(ResourceCert {
+        let resources = self.issued_resources(issuer, strict)?;
+        Ok(self.into_resource_cert(resources, issuer.tal.clone()))
+    }
+
+    /// Returns the resources of an issued certificate.
+    fn issued_resources(
+        &self,
+        issuer: &ResourceCert,
+        _strict: bool
+    ) -> Result<(IpBlocks, IpBlocks, AsBlocks), ValidationError> {
+        Ok((
             // 4.8.10.  IP Resources. If present, must be encompassed by or
             // trimmed down to the issuer certificate.
-            v4_resources: issuer.v4_resources.validate_issued(
+            issuer.v4_resources.validate_issued(
                 self.v4_resources(), self.overclaim
             )?,
-            v6_resources: issuer.v6_resources.validate_issued(
+            issuer.v6_resources.validate_issued(
                 self.v6_resources(), self.overclaim
             )?,
             // 4.8.11.  AS Resources. If present, must be encompassed by or
             // trimmed down to the issuer.
-            as_resources: issuer.as_resources.validate_issued(
+            issuer.as_resources.validate_issued(
                 self.as_resources(), self.overclaim()
             )?,
+        ))
+    }
+
+    /// Converts a validated certificate into a resource certificate.
+    fn into_resource_cert(
+        self,
+        (v4_resources, v6_resources, as_resources):
+            (IpBlocks, IpBlocks, AsBlocks),
+        tal: Arc<TalInfo>,
+    ) -> ResourceCert {
+        ResourceCert {
             cert: self,
-            tal: issuer.tal.clone(),
-        })
+            v4_resources,
+            v6_resources,
+            as_resources,
+            tal
+        }
     }
 }
 
 
+/// # Test Support
+///
+#[cfg(feature = "softkeys")]
+impl Cert {
+    /// Generates a throwaway trust anchor certificate.
+    ///
+    /// Creates a fresh key in a new [`OpenSslSigner`] and uses it to issue
+    /// a self-signed CA certificate that holds all IP and AS resources and
+    /// is valid for a year. Returns the certificate together with the
+    /// signer and the identifier of the key so that further objects can be
+    /// issued under it.
+    ///
+    /// This is useful for tests that need a valid certificate without
+    /// relying on fixture files. The function is only available if the
+    /// `softkeys` feature is enabled.
+    ///
+    /// [`OpenSslSigner`]: ../crypto/softsigner/struct.OpenSslSigner.html
+    pub fn generate_test_ta() -> Result<
+        (Self, OpenSslSigner, KeyId), SigningError<io::Error>
+    > {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default())?;
+        let pubkey = signer.get_key_info(&key)?;
+        let repo = uri::Rsync::from_slice(
+            b"rsync://localhost/repo/"
+        ).unwrap();
+        let mft = uri::Rsync::from_slice(
+            b"rsync://localhost/repo/ta.mft"
+        ).unwrap();
+
+        let mut cert = TbsCert::new(
+            1u64.into(), pubkey.to_subject_name(),
+            Validity::from_secs(365 * 86400), None, pubkey, KeyUsage::Ca,
+            Overclaim::Refuse
+        );
+        cert.set_basic_ca(Some(true));
+        cert.set_ca_repository(Some(repo));
+        cert.set_rpki_manifest(Some(mft));
+        cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+        cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let cert = cert.into_cert(&signer, &key)?;
+        Ok((cert, signer, key))
+    }
+}
+
 //--- Deref, AsRef, and Borrow
 
 impl ops::Deref for Cert {
@@ -604,6 +1317,11 @@ pub struct TbsCert {
 
     /// AS Resources
     as_resources: Option<AsResources>,
+
+    /// Extensions tolerated via the decode options.
+    ///
+    /// These are kept in the order they appear in the certificate.
+    other_extensions: Vec<OtherExtension>,
 }
 
 
@@ -649,17 +1367,18 @@ impl TbsCert {
             v4_resources: None,
             v6_resources: None,
             as_resources: None,
+            other_extensions: Vec::new(),
         }
     }
 
     /// Converts the value into a signed certificate.
-    pub fn into_cert<S: Signer>(
+    pub fn into_cert<S: Signer + ?Sized>(
         self,
         signer: &S,
         key: &S::KeyId,
     ) -> Result<Cert, SigningError<S::Error>> {
         let data = Captured::from_values(Mode::Der, self.encode_ref());
-        let signature = signer.sign(key, self.signature, &data)?;
+        let signature = signer.sign(key, self.signature, data.as_slice())?;
         Ok(Cert {
             signed_data: SignedData::new(data, signature),
             tbs: self
@@ -701,6 +1420,46 @@ impl TbsCert {
         self.validity = validity
     }
 
+    /// Returns whether the certificate expires within `duration` of `now`.
+    ///
+    /// This is also true if the certificate has already expired.
+    pub fn expires_within(&self, duration: Duration, now: Time) -> bool {
+        self.validity.not_after() <= now + duration
+    }
+
+    /// Returns the time remaining until the certificate expires.
+    ///
+    /// Returns `None` if the certificate has already expired at `now`.
+    pub fn remaining_validity(&self, now: Time) -> Option<Duration> {
+        let not_after = self.validity.not_after();
+        if now > not_after {
+            None
+        }
+        else {
+            Some(not_after.signed_duration_since(*now))
+        }
+    }
+
+    /// Checks that the validity period isn’t longer than `max`.
+    ///
+    /// This is a policy check independent of whether the certificate is
+    /// currently valid. It allows rejecting certificates that were issued
+    /// with an unreasonably long lifetime.
+    pub fn validate_max_lifetime(
+        &self,
+        max: Duration
+    ) -> Result<(), ValidationError> {
+        let lifetime = self.validity.not_after().signed_duration_since(
+            *self.validity.not_before()
+        );
+        if lifetime > max {
+            Err(ValidationError)
+        }
+        else {
+            Ok(())
+        }
+    }
+
     /// Returns a reference to the subject.
     pub fn subject(&self) -> &Name {
         &self.subject
@@ -947,6 +1706,16 @@ impl TbsCert {
             AsResources::blocks(AsBlocks::from_iter(iter))
         )
     }
+
+    /// Returns the extensions tolerated via the decode options.
+    ///
+    /// The slice is empty unless the certificate was decoded with
+    /// [`DecodeOptions`] that tolerate additional extensions.
+    ///
+    /// [`DecodeOptions`]: struct.DecodeOptions.html
+    pub fn other_extensions(&self) -> &[OtherExtension] {
+        &self.other_extensions
+    }
 }
 
 
@@ -957,13 +1726,59 @@ impl TbsCert {
     pub fn from_constructed<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
+        Self::from_constructed_with_limits(cons, DecodeLimits::default())
+    }
+
+    /// Parses the content of a Certificate sequence observing size limits.
+    pub fn from_constructed_with_limits<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        limits: DecodeLimits
+    ) -> Result<Self, S::Err> {
+        Self::from_constructed_with_options(cons, &limits.into())
+    }
+
+    /// Parses the content of a Certificate sequence using the options.
+    ///
+    /// Critical extensions not known to the RPKI profile are rejected
+    /// unless they appear in the list of tolerated extensions of
+    /// `options`. Those extensions are kept as they are and can be
+    /// accessed via [`other_extensions`]. Extensions that are part of the
+    /// profile are always interpreted, even if they appear in the list.
+    ///
+    /// [`other_extensions`]: #method.other_extensions
+    pub fn from_constructed_with_options<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        options: &DecodeOptions
+    ) -> Result<Self, S::Err> {
+        Self::from_constructed_detailed(cons, options, &mut None)
+    }
+
+    /// Parses the content noting the reason for failing in `err`.
+    fn from_constructed_detailed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        options: &DecodeOptions,
+        err: &mut Option<CertDecodeError>
+    ) -> Result<Self, S::Err> {
+        let limits = options.limits();
         cons.take_sequence(|cons| {
             // version [0] EXPLICIT Version DEFAULT v1.
             //  -- we need extensions so apparently, we want v3 which,
             //     confusingly, is 2.
-            cons.take_constructed_if(Tag::CTX_0, |c| c.skip_u8_if(2))?;
+            let version = cons.take_opt_constructed_if(
+                Tag::CTX_0, |c| c.take_u8()
+            )?.unwrap_or(0);
+            if version != 2 {
+                *err = Some(CertDecodeError::UnsupportedVersion(version));
+                xerr!(return Err(decode::Malformed.into()))
+            }
 
-            let serial_number = Serial::take_from(cons)?;
+            let serial_number = Serial::take_limited_from(
+                cons, limits.serial_len()
+            )?;
+            if serial_number.is_zero() && !options.tolerates_zero_serial() {
+                *err = Some(CertDecodeError::ZeroSerial);
+                xerr!(return Err(decode::Malformed.into()))
+            }
             let signature = SignatureAlgorithm::x509_take_from(cons)?;
             let issuer = Name::take_from(cons)?;
             let validity = Validity::take_from(cons)?;
@@ -986,12 +1801,37 @@ impl TbsCert {
             let mut ip_overclaim = None;
             let mut as_resources = None;
             let mut as_overclaim = None;
+            let mut other_extensions = Vec::new();
+
+            // RFC 5280 forbids more than one instance of an extension. The
+            // extensions we know are checked when parsing their values, but
+            // unknown ones are skipped, so we keep track of all of them.
+            let mut seen: Vec<Oid> = Vec::new();
 
+            // The size limits are checked against the length octets of
+            // each extension value before its content is taken.
+            let mut extensions_len = 0usize;
             cons.take_constructed_if(Tag::CTX_3, |c| c.take_sequence(|cons| {
                 while let Some(()) = cons.take_opt_sequence(|cons| {
                     let id = Oid::take_from(cons)?;
+                    if seen.contains(&id) {
+                        xerr!(return Err(decode::Malformed.into()))
+                    }
+                    seen.push(id.clone());
                     let critical = cons.take_opt_bool()?.unwrap_or(false);
-                    let value = OctetString::take_from(cons)?;
+                    let value = cons.take_value_if(Tag::OCTET_STRING, |c| {
+                        let prim = c.as_primitive()?;
+                        let len = prim.remaining();
+                        extensions_len = extensions_len.saturating_add(len);
+                        if len > limits.extension_value_len()
+                            || extensions_len > limits.extensions_len()
+                        {
+                            xerr!(return Err(decode::Malformed.into()))
+                        }
+                        prim.take_all()
+                    })?;
+                    let value = OctetString::new(value);
+                    let mut other = false;
                     Mode::Der.decode(value.to_source(), |content| {
                         if id == oid::CE_BASIC_CONSTRAINTS {
                             Self::take_basic_constraints(
@@ -1035,6 +1875,10 @@ impl TbsCert {
                         } else if let Some(m) = Overclaim::from_as_res(&id) {
                             as_overclaim = Some(m);
                             Self::take_as_resources(content, &mut as_resources)
+                        } else if options.is_tolerated(&id) {
+                            // Kept as is below.
+                            other = true;
+                            Ok(())
                         } else if critical {
                             xerr!(Err(decode::Malformed))
                         } else {
@@ -1044,11 +1888,34 @@ impl TbsCert {
                             Ok(())
                         }
                     })?;
+                    if other {
+                        other_extensions.push(OtherExtension {
+                            id, critical, value
+                        });
+                    }
                     Ok(())
                 })? { }
+                // Extensions ::= SEQUENCE SIZE (1..MAX) OF Extension, so an
+                // empty sequence is malformed in itself. Reject it here
+                // rather than later for lack of a subject key identifier.
+                if seen.is_empty() {
+                    *err = Some(CertDecodeError::EmptyExtensions);
+                    xerr!(return Err(decode::Malformed.into()))
+                }
                 Ok(())
             }))?;
 
+            // The subject key identifier is required by RFC 6487 and is
+            // used to build the certification path, so we insist on it.
+            let subject_key_id = match subject_key_id {
+                Some(id) => id,
+                None => {
+                    *err = Some(
+                        CertDecodeError::MissingSubjectKeyIdentifier
+                    );
+                    xerr!(return Err(decode::Malformed.into()))
+                }
+            };
             if ip_resources.is_none() && as_resources.is_none() {
                 xerr!(return Err(decode::Malformed.into()))
             }
@@ -1080,8 +1947,7 @@ impl TbsCert {
                 subject,
                 subject_public_key_info,
                 basic_ca,
-                subject_key_identifier:
-                    subject_key_id.ok_or(decode::Malformed)?,
+                subject_key_identifier: subject_key_id,
                 authority_key_identifier: authority_key_id,
                 key_usage: key_usage.ok_or(decode::Malformed)?,
                 extended_key_usage,
@@ -1095,6 +1961,7 @@ impl TbsCert {
                 v4_resources,
                 v6_resources,
                 as_resources,
+                other_extensions,
             })
         })
     }
@@ -1582,14 +2449,22 @@ impl TbsCert {
                     None
                 },
 
-                // AS Resources
-                self.as_resources.as_ref().map(|res| {
-                    encode_extension(
-                        self.overclaim.as_res_id(), true,
-                        res.encode_ref()
-                    )
-                })
+                (
+                    // AS Resources
+                    self.as_resources.as_ref().map(|res| {
+                        encode_extension(
+                            self.overclaim.as_res_id(), true,
+                            res.encode_ref()
+                        )
+                    }),
 
+                    // Other Extensions
+                    encode::iter(
+                        self.other_extensions.iter().map(|ext| {
+                            ext.encode_ref()
+                        })
+                    )
+                )
             )))
         ))
     }
@@ -1598,6 +2473,34 @@ impl TbsCert {
 
 //------------ Helpers for Decoding and Encoding -----------------------------
 
+/// The maximum length of the content of a certificate read from a reader.
+const MAX_READ_LEN: u64 = 1 << 20;
+
+/// Returns the certificates of a PKCS #7 certs-only message.
+///
+/// The certificates are returned as their concatenated encodings. Returns
+/// `None` if `bytes` isn’t such a message.
+fn take_certs_only(bytes: &[u8]) -> Option<Captured> {
+    Mode::Der.decode(bytes, |cons| {
+        cons.take_sequence(|cons| { // ContentInfo
+            oid::SIGNED_DATA.skip_if(cons)?; // contentType
+            cons.take_constructed_if(Tag::CTX_0, |cons| { // content
+                cons.take_sequence(|cons| { // SignedData
+                    Unsigned::take_from(cons)?; // version
+                    // digestAlgorithms and encapContentInfo
+                    cons.take_set(|cons| cons.skip_all())?;
+                    cons.take_sequence(|cons| cons.skip_all())?;
+                    let certs = cons.take_constructed_if( // certificates
+                        Tag::CTX_0, |cons| cons.capture_all()
+                    )?;
+                    cons.skip_all()?; // crls and signerInfos
+                    Ok(certs)
+                })
+            })
+        })
+    }).ok()
+}
+
 /// Parses a URI from the content of a GeneralNames sequence.
 ///
 /// ```text
@@ -1722,6 +2625,38 @@ impl ResourceCert {
     pub fn into_tal(self) -> Arc<TalInfo> {
         self.tal
     }
+
+    /// Validates a certificate issued by this certificate.
+    ///
+    /// Depending on its key usage, `cert` is validated either as a CA
+    /// certificate via [`Cert::validate_ca`] or as an EE certificate via
+    /// [`Cert::validate_ee`]. Either way, its resources have to be covered
+    /// by the resources of this certificate or be inherited from them.
+    ///
+    /// Note that this does _not_ check the CRL.
+    ///
+    /// [`Cert::validate_ca`]: struct.Cert.html#method.validate_ca
+    /// [`Cert::validate_ee`]: struct.Cert.html#method.validate_ee
+    pub fn validate_issued(
+        &self,
+        cert: Cert,
+        strict: bool
+    ) -> Result<ResourceCert, ValidationError> {
+        self.validate_issued_at(cert, strict, Time::now())
+    }
+
+    /// Validates a certificate issued by this certificate at a given time.
+    pub fn validate_issued_at(
+        &self,
+        cert: Cert,
+        strict: bool,
+        now: Time
+    ) -> Result<ResourceCert, ValidationError> {
+        match cert.key_usage() {
+            KeyUsage::Ca => cert.validate_ca_at(self, strict, now),
+            KeyUsage::Ee => cert.validate_ee_at(self, strict, now),
+        }
+    }
 }
 
 
@@ -1748,6 +2683,187 @@ impl AsRef<TbsCert> for ResourceCert {
 }
 
 
+//------------ CertDecodeError -----------------------------------------------
+
+/// An error happened while decoding a certificate.
+///
+/// This is returned by [`Cert::decode_detailed`].
+///
+/// [`Cert::decode_detailed`]: struct.Cert.html#method.decode_detailed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CertDecodeError {
+    /// The certificate isn’t correctly encoded.
+    ///
+    /// This is used for all encoding problems not covered by one of the
+    /// more specific variants.
+    Malformed,
+
+    /// The certificate isn’t a version 3 certificate.
+    ///
+    /// The value is the one of the encoded version field which is one less
+    /// than the version number, i.e., 0 for a version 1 certificate. It is
+    /// 0 if the field is missing.
+    UnsupportedVersion(u8),
+
+    /// The certificate has an empty extensions sequence.
+    ///
+    /// RFC 5280 requires at least one extension if the field is present.
+    EmptyExtensions,
+
+    /// The certificate lacks the subject key identifier extension.
+    MissingSubjectKeyIdentifier,
+
+    /// The certificate has a serial number of zero.
+    ///
+    /// RFC 5280 requires serial numbers to be positive. Zero is only
+    /// accepted if tolerated by the decode options.
+    ZeroSerial,
+}
+
+
+//--- Display and Error
+
+impl fmt::Display for CertDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CertDecodeError::Malformed => {
+                f.write_str("malformed certificate")
+            }
+            CertDecodeError::UnsupportedVersion(version) => {
+                write!(
+                    f, "got certificate version {}, expected 3",
+                    u16::from(version) + 1
+                )
+            }
+            CertDecodeError::EmptyExtensions => {
+                f.write_str("empty certificate extensions")
+            }
+            CertDecodeError::MissingSubjectKeyIdentifier => {
+                f.write_str("missing subject key identifier")
+            }
+            CertDecodeError::ZeroSerial => {
+                f.write_str("serial number of zero")
+            }
+        }
+    }
+}
+
+impl error::Error for CertDecodeError { }
+
+
+//------------ CertValidationError -------------------------------------------
+
+/// An error happened while fully validating a certificate.
+///
+/// This is returned by [`Cert::validate_full`] and identifies the stage of
+/// validation that failed.
+///
+/// [`Cert::validate_full`]: struct.Cert.html#method.validate_full
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CertValidationError {
+    /// The certificate violates the profile or isn’t valid at this time.
+    Basics,
+
+    /// The key usage doesn’t fit the role of the certificate.
+    KeyUsage,
+
+    /// The certificate wasn’t issued by the issuer.
+    Issuer,
+
+    /// The signature of the certificate is invalid.
+    Signature,
+
+    /// The CRL is invalid.
+    Crl(CrlValidationError),
+
+    /// The certificate has been revoked.
+    Revoked,
+
+    /// The resources of the certificate aren’t covered by the issuer.
+    Resources,
+}
+
+
+//--- From
+
+impl From<CertValidationError> for ValidationError {
+    fn from(_: CertValidationError) -> Self {
+        ValidationError
+    }
+}
+
+
+//--- Display and Error
+
+impl fmt::Display for CertValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CertValidationError::Basics => {
+                f.write_str("certificate violates the RPKI profile")
+            }
+            CertValidationError::KeyUsage => {
+                f.write_str("key usage doesn’t fit certificate role")
+            }
+            CertValidationError::Issuer => {
+                f.write_str("certificate not issued by the issuer")
+            }
+            CertValidationError::Signature => {
+                f.write_str("invalid certificate signature")
+            }
+            CertValidationError::Crl(ref err) => {
+                write!(f, "invalid CRL: {}", err)
+            }
+            CertValidationError::Revoked => {
+                f.write_str("certificate has been revoked")
+            }
+            CertValidationError::Resources => {
+                f.write_str("certificate resources not covered by issuer")
+            }
+        }
+    }
+}
+
+impl error::Error for CertValidationError { }
+
+
+//------------ TaValidationError ---------------------------------------------
+
+/// An error happened while validating a trust anchor certificate.
+///
+/// This is returned by [`Cert::validate_ta_detailed_at`] and distinguishes
+/// a certificate that has expired but is otherwise valid from one that is
+/// invalid for other reasons.
+///
+/// [`Cert::validate_ta_detailed_at`]: struct.Cert.html#method.validate_ta_detailed_at
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TaValidationError {
+    /// The certificate is valid except that it expired at the given time.
+    Expired { not_after: Time },
+
+    /// The certificate is invalid.
+    Invalid(ValidationError),
+}
+
+impl From<ValidationError> for TaValidationError {
+    fn from(err: ValidationError) -> Self {
+        TaValidationError::Invalid(err)
+    }
+}
+
+impl fmt::Display for TaValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TaValidationError::Expired { not_after } => {
+                write!(f, "certificate expired at {}", not_after.to_rfc3339())
+            }
+            TaValidationError::Invalid(ref err) => err.fmt(f)
+        }
+    }
+}
+
+impl error::Error for TaValidationError { }
+
+
 //------------ KeyUsage ------------------------------------------------------
 
 /// The allowed key usages of a resource certificate.
@@ -1858,26 +2974,258 @@ impl Overclaim {
 }
 
 
-//============ Tests =========================================================
+//------------ OtherExtension ------------------------------------------------
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// An extension not part of the RPKI certificate profile.
+///
+/// Values of this type are only created for extensions explicitly tolerated
+/// via [`DecodeOptions`]. They are kept as they were found in the
+/// certificate without any interpretation of their content.
+///
+/// [`DecodeOptions`]: struct.DecodeOptions.html
+#[derive(Clone, Debug)]
+pub struct OtherExtension {
+    /// The object identifier of the extension.
+    id: Oid,
 
-    #[test]
-    fn decode_certs() {
-        Cert::decode(
-            include_bytes!("../../test-data/ta.cer").as_ref()
-        ).unwrap();
-        Cert::decode(
-            include_bytes!("../../test-data/ca1.cer").as_ref()
-        ).unwrap();
+    /// Whether the extension was marked as critical.
+    critical: bool,
+
+    /// The content of the extension value.
+    value: OctetString,
+}
+
+impl OtherExtension {
+    /// Returns the object identifier of the extension.
+    pub fn id(&self) -> &Oid {
+        &self.id
     }
 
-    #[test]
-    fn serde_cert() {
-        let der = include_bytes!("../../test-data/ta.cer");
-        let cert = Cert::decode(Bytes::from_static(der)).unwrap();
+    /// Returns whether the extension is marked as critical.
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    /// Returns the content of the extension value.
+    pub fn value(&self) -> &OctetString {
+        &self.value
+    }
+
+    /// Returns an encoder for the extension.
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        encode::sequence((
+            self.id.encode_ref(),
+            if self.critical {
+                Some(self.critical.encode())
+            }
+            else {
+                None
+            },
+            self.value.encode_ref()
+        ))
+    }
+}
+
+
+//------------ DecodeLimits --------------------------------------------------
+
+/// Size limits applied while decoding a certificate.
+///
+/// Certificates are routinely taken from untrusted sources. In order to
+/// avoid spending resources on unreasonably large values, decoding fails
+/// with a malformed error if any of the limits is exceeded. The limits are
+/// checked against the length of a value before its content is taken.
+///
+/// The default limits are generous enough for all certificates seen in the
+/// wild, including those with very large resource sets.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DecodeLimits {
+    /// The maximum number of content octets of the serial number.
+    serial_len: usize,
+
+    /// The maximum combined length of all extension values.
+    extensions_len: usize,
+
+    /// The maximum length of the value of a single extension.
+    extension_value_len: usize,
+}
+
+impl DecodeLimits {
+    /// The largest serial number length possible.
+    ///
+    /// RFC 5280 limits serial numbers to 20 octets.
+    pub const MAX_SERIAL_LEN: usize = 20;
+
+    /// The default maximum combined length of the extension values.
+    pub const DEFAULT_EXTENSIONS_LEN: usize = 4 * 1024 * 1024;
+
+    /// The default maximum length of a single extension value.
+    pub const DEFAULT_EXTENSION_VALUE_LEN: usize = 4 * 1024 * 1024;
+
+    /// Returns the maximum number of content octets of the serial number.
+    pub fn serial_len(self) -> usize {
+        self.serial_len
+    }
+
+    /// Sets the maximum number of content octets of the serial number.
+    ///
+    /// Values larger than 20 are reduced to 20 as serial numbers can never
+    /// be longer.
+    pub fn set_serial_len(&mut self, len: usize) {
+        self.serial_len = min(len, Self::MAX_SERIAL_LEN)
+    }
+
+    /// Returns the maximum combined length of all extension values.
+    pub fn extensions_len(self) -> usize {
+        self.extensions_len
+    }
+
+    /// Sets the maximum combined length of all extension values.
+    pub fn set_extensions_len(&mut self, len: usize) {
+        self.extensions_len = len
+    }
+
+    /// Returns the maximum length of the value of a single extension.
+    pub fn extension_value_len(self) -> usize {
+        self.extension_value_len
+    }
+
+    /// Sets the maximum length of the value of a single extension.
+    pub fn set_extension_value_len(&mut self, len: usize) {
+        self.extension_value_len = len
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            serial_len: Self::MAX_SERIAL_LEN,
+            extensions_len: Self::DEFAULT_EXTENSIONS_LEN,
+            extension_value_len: Self::DEFAULT_EXTENSION_VALUE_LEN,
+        }
+    }
+}
+
+
+//------------ DecodeOptions -------------------------------------------------
+
+/// Options for decoding a certificate.
+///
+/// In addition to the [`DecodeLimits`], the options contain a list of
+/// extensions that are tolerated even if they are marked as critical.
+/// Normally, a certificate with a critical extension not part of the RPKI
+/// profile is rejected. Certificates from a broader PKI context sometimes
+/// contain critical policy-related extensions, though. Tools that only want
+/// to inspect such certificates can add these extensions to the list. They
+/// will then be available via [`TbsCert::other_extensions`]. Validation in
+/// strict mode still rejects certificates with such critical extensions.
+///
+/// Similarly, certificates with a serial number of zero are rejected
+/// unless the options say to tolerate them.
+///
+/// The default options use the default limits and tolerate neither
+/// extensions nor a zero serial number, i.e., they strictly follow the
+/// RPKI profile. The options returned by [`lax`] tolerate the
+/// inhibitAnyPolicy and policyConstraints extensions as well as a serial
+/// number of zero.
+///
+/// [`DecodeLimits`]: struct.DecodeLimits.html
+/// [`TbsCert::other_extensions`]: struct.TbsCert.html#method.other_extensions
+/// [`lax`]: #method.lax
+#[derive(Clone, Debug, Default)]
+pub struct DecodeOptions {
+    /// The size limits.
+    limits: DecodeLimits,
+
+    /// The object identifiers of the tolerated extensions.
+    tolerated: Vec<ConstOid>,
+
+    /// Whether a serial number of zero is tolerated.
+    zero_serial: bool,
+}
+
+impl DecodeOptions {
+    /// Creates options that tolerate common policy-related extensions.
+    pub fn lax() -> Self {
+        DecodeOptions {
+            limits: DecodeLimits::default(),
+            tolerated: vec![
+                oid::CE_INHIBIT_ANY_POLICY,
+                oid::CE_POLICY_CONSTRAINTS,
+            ],
+            zero_serial: true,
+        }
+    }
+
+    /// Returns the size limits.
+    pub fn limits(&self) -> DecodeLimits {
+        self.limits
+    }
+
+    /// Sets the size limits.
+    pub fn set_limits(&mut self, limits: DecodeLimits) {
+        self.limits = limits
+    }
+
+    /// Returns the object identifiers of the tolerated extensions.
+    pub fn tolerated(&self) -> &[ConstOid] {
+        &self.tolerated
+    }
+
+    /// Adds an extension to the list of tolerated extensions.
+    pub fn tolerate(&mut self, id: ConstOid) {
+        if !self.is_tolerated(&id) {
+            self.tolerated.push(id)
+        }
+    }
+
+    /// Returns whether the extension with the given identifier is tolerated.
+    pub fn is_tolerated<T: AsRef<[u8]>>(&self, id: &Oid<T>) -> bool {
+        self.tolerated.iter().any(|item| item.0 == id.0.as_ref())
+    }
+
+    /// Returns whether a serial number of zero is tolerated.
+    pub fn tolerates_zero_serial(&self) -> bool {
+        self.zero_serial
+    }
+
+    /// Sets whether a serial number of zero is tolerated.
+    pub fn set_tolerate_zero_serial(&mut self, tolerate: bool) {
+        self.zero_serial = tolerate
+    }
+}
+
+impl From<DecodeLimits> for DecodeOptions {
+    fn from(limits: DecodeLimits) -> Self {
+        DecodeOptions {
+            limits,
+            tolerated: Vec::new(),
+            zero_serial: false,
+        }
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_certs() {
+        Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).unwrap();
+    }
+
+    #[test]
+    fn serde_cert() {
+        let der = include_bytes!("../../test-data/ta.cer");
+        let cert = Cert::decode(Bytes::from_static(der)).unwrap();
 
         let serialize = serde_json::to_string(&cert).unwrap();
         let des_cert: Cert = serde_json::from_str(&serialize).unwrap();
@@ -1885,16 +3233,697 @@ mod test {
         assert_eq!(cert.to_captured().into_bytes(), des_cert.to_captured().into_bytes());
 
     }
+
+    #[test]
+    fn from_der() {
+        let der = include_bytes!("../../test-data/ta.cer");
+        let cert = Cert::from_der(der.as_ref()).unwrap();
+        assert_eq!(cert.to_der().as_ref(), der.as_ref());
+        Cert::from_der(der.to_vec()).unwrap();
+        Cert::from_der(Bytes::from_static(der)).unwrap();
+        assert_eq!(
+            Cert::from_der(&der[..100]).unwrap_err(),
+            CertDecodeError::Malformed
+        );
+        assert_eq!(
+            Cert::from_der(Vec::new()).unwrap_err(),
+            CertDecodeError::Malformed
+        );
+    }
+
+    #[test]
+    fn from_slice() {
+        let der = include_bytes!("../../test-data/ta.cer");
+        let data = der.to_vec();
+        let cert = Cert::from_slice(&data).unwrap();
+        drop(data);
+        assert_eq!(cert.to_der().as_ref(), der.as_ref());
+        assert!(Cert::from_slice(b"foo").is_err());
+    }
+
+    #[test]
+    fn clone_shares_data() {
+        let cert = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let data = cert.signed_data.data().as_slice().as_ptr();
+        let clones: Vec<_> = (0..100_000).map(|_| cert.clone()).collect();
+        for clone in &clones {
+            assert_eq!(clone.signed_data.data().as_slice().as_ptr(), data);
+            assert_eq!(
+                clone.subject_public_key_info().bits().as_ptr(),
+                cert.subject_public_key_info().bits().as_ptr()
+            );
+        }
+        assert_eq!(clones[99_999].to_der(), cert.to_der());
+    }
+
+    #[test]
+    fn to_der() {
+        let der = include_bytes!("../../test-data/ta.cer");
+        let cert = Cert::decode(der.as_ref()).unwrap();
+        assert_eq!(cert.to_der().as_ref(), der.as_ref());
+    }
+
+    #[test]
+    fn write_der() {
+        let mut target = Vec::new();
+        for der in &[
+            include_bytes!("../../test-data/ta.cer").as_ref(),
+            include_bytes!("../../test-data/ca1.cer").as_ref(),
+        ] {
+            let cert = Cert::decode(*der).unwrap();
+            let start = target.len();
+            cert.write_der(&mut target).unwrap();
+            assert_eq!(&target[start..], *der);
+        }
+    }
+
+    /// Asserts that a certificate is re-encoded into identical octets.
+    ///
+    /// The name is only used to identify the certificate when failing.
+    fn assert_round_trip(name: &str, der: &[u8]) {
+        let cert = match Cert::decode(der) {
+            Ok(cert) => cert,
+            Err(err) => panic!("{}: failed to decode: {}", name, err)
+        };
+        assert!(
+            cert.to_der().as_ref() == der,
+            "{}: re-encoded certificate differs", name
+        );
+    }
+
+    #[test]
+    fn round_trip_test_certs() {
+        // Certificates that are broken on purpose and must not decode.
+        const REJECTED: &[&str] = &[
+            "aki-issuer-serial.cer", "critical-policy-constraints.cer",
+            "dup-ext-ski.cer", "dup-ext-unknown.cer", "empty-ext.cer",
+            "no-ski.cer"
+        ];
+
+        let mut dirs = vec![
+            std::path::PathBuf::from(
+                concat!(env!("CARGO_MANIFEST_DIR"), "/test-data")
+            )
+        ];
+        let mut count = 0;
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                if path.extension().map(|ext| ext != "cer").unwrap_or(true) {
+                    continue;
+                }
+                let name = path.display().to_string();
+                let der = std::fs::read(&path).unwrap();
+                let file_name = path.file_name().unwrap().to_str().unwrap();
+                if REJECTED.contains(&file_name) {
+                    assert!(
+                        Cert::decode(der.as_slice()).is_err(),
+                        "{}: unexpectedly decoded", name
+                    );
+                }
+                else {
+                    assert_round_trip(&name, &der);
+                    count += 1;
+                }
+            }
+        }
+        assert!(count > 0, "no test certificates found");
+    }
+
+    #[test]
+    fn duplicate_extensions() {
+        // ta.cer with an added non-critical extension 1.2.3.4, once and
+        // twice, and with its subject key identifier extension duplicated.
+        Cert::decode(
+            include_bytes!("../../test-data/single-ext-unknown.cer").as_ref()
+        ).unwrap();
+        assert!(Cert::decode(
+            include_bytes!("../../test-data/dup-ext-unknown.cer").as_ref()
+        ).is_err());
+        assert!(Cert::decode(
+            include_bytes!("../../test-data/dup-ext-ski.cer").as_ref()
+        ).is_err());
+    }
+
+    #[test]
+    fn decode_version() {
+        let der = include_bytes!("../../test-data/ta.cer");
+        assert!(Cert::decode_detailed(der.as_ref()).is_ok());
+
+        // The version field is at offset 8 as a0 03 02 01 02.
+        assert_eq!(&der[8..13], b"\xa0\x03\x02\x01\x02");
+        let mut v2 = der.to_vec();
+        v2[12] = 1;
+        assert!(Cert::decode(v2.as_slice()).is_err());
+        let err = Cert::decode_detailed(v2.as_slice()).unwrap_err();
+        assert_eq!(err, CertDecodeError::UnsupportedVersion(1));
+        assert_eq!(err.to_string(), "got certificate version 2, expected 3");
+
+        // Without the version field and adjusted lengths it’s version 1.
+        let mut v1 = der.to_vec();
+        v1.drain(8..13);
+        v1[2..4].copy_from_slice(b"\x04\x05");
+        v1[6..8].copy_from_slice(b"\x02\xed");
+        assert_eq!(
+            Cert::decode_detailed(v1.as_slice()).unwrap_err(),
+            CertDecodeError::UnsupportedVersion(0)
+        );
+
+        // Other problems are plain malformed errors.
+        assert_eq!(
+            Cert::decode_detailed(&der[..100]).unwrap_err(),
+            CertDecodeError::Malformed
+        );
+    }
+
+    #[test]
+    fn missing_extensions() {
+        // ta.cer with an empty extensions sequence and with its subject
+        // key identifier extension removed.
+        assert!(Cert::decode(
+            include_bytes!("../../test-data/empty-ext.cer").as_ref()
+        ).is_err());
+        assert_eq!(
+            Cert::decode_detailed(
+                include_bytes!("../../test-data/empty-ext.cer").as_ref()
+            ).unwrap_err(),
+            CertDecodeError::EmptyExtensions
+        );
+        assert!(Cert::decode(
+            include_bytes!("../../test-data/no-ski.cer").as_ref()
+        ).is_err());
+        assert_eq!(
+            Cert::decode_detailed(
+                include_bytes!("../../test-data/no-ski.cer").as_ref()
+            ).unwrap_err(),
+            CertDecodeError::MissingSubjectKeyIdentifier
+        );
+        assert!(Cert::decode_with_options(
+            include_bytes!("../../test-data/no-ski.cer").as_ref(),
+            &DecodeOptions::lax()
+        ).is_err());
+    }
+
+    #[test]
+    fn tolerated_extensions() {
+        // ta.cer with an added critical policy constraints extension.
+        let der = include_bytes!(
+            "../../test-data/critical-policy-constraints.cer"
+        ).as_ref();
+        assert!(Cert::decode(der).is_err());
+        assert!(
+            Cert::decode_with_options(der, &DecodeOptions::default()).is_err()
+        );
+
+        let cert = Cert::decode_with_options(
+            der, &DecodeOptions::lax()
+        ).unwrap();
+        assert_eq!(cert.other_extensions().len(), 1);
+        let ext = &cert.other_extensions()[0];
+        assert_eq!(*ext.id(), oid::CE_POLICY_CONSTRAINTS);
+        assert!(ext.is_critical());
+        assert_eq!(ext.value().to_bytes().as_ref(), b"\x30\x03\x80\x01\x00");
+        assert_eq!(cert.to_der().as_ref(), der);
+
+        let mut options = DecodeOptions::default();
+        options.tolerate(oid::CE_POLICY_CONSTRAINTS);
+        assert!(Cert::decode_with_options(der, &options).is_ok());
+
+        // Known extensions are not affected.
+        let cert = Cert::decode_with_options(
+            include_bytes!("../../test-data/ta.cer").as_ref(),
+            &DecodeOptions::lax()
+        ).unwrap();
+        assert!(cert.other_extensions().is_empty());
+    }
+
+    #[test]
+    fn authority_key_id_fields() {
+        // ca1.cer with authorityCertIssuer and authorityCertSerialNumber
+        // added to its authority key identifier.
+        assert!(Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).is_ok());
+        assert!(Cert::decode(
+            include_bytes!("../../test-data/aki-issuer-serial.cer").as_ref()
+        ).is_err());
+    }
+
+    #[test]
+    fn canonical_der() {
+        for der in &[
+            include_bytes!("../../test-data/ta.cer").as_ref(),
+            include_bytes!("../../test-data/ca1.cer").as_ref(),
+        ] {
+            Cert::decode(*der).unwrap().verify_canonical().unwrap();
+        }
+
+        // DER decoding rejects non-minimal lengths of the outer value ...
+        let der = include_bytes!("../../test-data/ta.cer");
+        let mut bad = b"\x30\x83\x00\x04\x0a".to_vec();
+        bad.extend_from_slice(&der[4..]);
+        assert!(crate::x509::verify_canonical(&bad).is_err());
+        assert!(Cert::decode(bad.as_slice()).is_err());
+
+        // ... and of the to-be-signed portion.
+        let mut bad = b"\x30\x82\x04\x0b\x30\x83\x00\x02\xf2".to_vec();
+        bad.extend_from_slice(&der[8..]);
+        assert!(crate::x509::verify_canonical(&bad).is_err());
+        assert!(Cert::decode(bad.as_slice()).is_err());
+    }
+
+    #[test]
+    fn dump() {
+        let cert = Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        assert_eq!(
+            cert.dump(),
+            "Certificate:\n\
+            \x20   Data:\n\
+            \x20       Version: 3 (0x2)\n\
+            \x20       Serial Number: 214 (0xd6)\n\
+            \x20       Signature Algorithm: sha256WithRSAEncryption\n\
+            \x20       Issuer: CN=ripe-ncc-ta\n\
+            \x20       Validity\n\
+            \x20           Not Before: Feb 26 13:14:44 2019 GMT\n\
+            \x20           Not After : Jul  1 00:00:00 2020 GMT\n\
+            \x20       Subject: CN=2a7dd1d787d793e4c8af56e197d4eed92af6ba13\n\
+            \x20       Subject Public Key Info:\n\
+            \x20           Public Key Algorithm: rsaEncryption\n\
+            \x20               Public-Key: (2048 bit)\n\
+            \x20       X509v3 extensions:\n\
+            \x20           X509v3 Basic Constraints:\n\
+            \x20               CA:TRUE\n\
+            \x20           X509v3 Subject Key Identifier:\n\
+            \x20               2A7DD1D787D793E4C8AF56E197D4EED92AF6BA13\n\
+            \x20           X509v3 Authority Key Identifier:\n\
+            \x20               keyid:\
+                                E8552B1FD6D1A4F7E404C6D8E5680D1EBC163FC3\n"
+        );
+    }
+
+    #[test]
+    fn decode_limits() {
+        let der = include_bytes!("../../test-data/ca1.cer").as_ref();
+
+        let mut limits = DecodeLimits::default();
+        limits.set_serial_len(2);
+        assert!(Cert::decode_with_limits(der, limits).is_ok());
+        limits.set_serial_len(1);
+        assert!(Cert::decode_with_limits(der, limits).is_err());
+        limits.set_serial_len(100);
+        assert_eq!(limits.serial_len(), DecodeLimits::MAX_SERIAL_LEN);
+
+        let mut limits = DecodeLimits::default();
+        limits.set_extensions_len(64);
+        assert!(Cert::decode_with_limits(der, limits).is_err());
+        limits.set_extensions_len(4096);
+        assert!(Cert::decode_with_limits(der, limits).is_ok());
+
+        let mut limits = DecodeLimits::default();
+        limits.set_extension_value_len(16);
+        assert!(Cert::decode_with_limits(der, limits).is_err());
+    }
+
+    #[test]
+    fn expiry() {
+        // ta.cer is valid from 2017-11-28T14:39:55Z to 2117-11-28T14:39:55Z.
+        let cert = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let not_after = Time::utc(2117, 11, 28, 14, 39, 55);
+
+        // Before the validity window.
+        let now = Time::utc(2010, 1, 1, 0, 0, 0);
+        assert!(!cert.expires_within(Duration::days(365), now));
+        assert_eq!(
+            cert.remaining_validity(now),
+            Some(not_after.signed_duration_since(*now))
+        );
+
+        // Inside the validity window.
+        let now = not_after - Duration::days(10);
+        assert!(!cert.expires_within(Duration::days(9), now));
+        assert!(cert.expires_within(Duration::days(10), now));
+        assert!(cert.expires_within(Duration::days(11), now));
+        assert_eq!(cert.remaining_validity(now), Some(Duration::days(10)));
+        assert_eq!(
+            cert.remaining_validity(not_after), Some(Duration::zero())
+        );
+
+        // After the validity window.
+        let now = not_after + Duration::seconds(1);
+        assert!(cert.expires_within(Duration::zero(), now));
+        assert_eq!(cert.remaining_validity(now), None);
+    }
+
+    #[test]
+    fn validate_ta_expired() {
+        // ta.cer is valid from 2017-11-28T14:39:55Z to 2117-11-28T14:39:55Z.
+        let tal = TalInfo::from_name("foo".into()).into_arc();
+        let cert = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let not_after = Time::utc(2117, 11, 28, 14, 39, 55);
+        let later = not_after + Duration::seconds(1);
+
+        assert!(
+            cert.clone().validate_ta_at(tal.clone(), false, later).is_err()
+        );
+        assert_eq!(
+            cert.clone().validate_ta_detailed_at(
+                tal.clone(), false, later
+            ).unwrap_err(),
+            TaValidationError::Expired { not_after }
+        );
+        assert!(cert.clone().validate_ta_detailed_at(
+            tal.clone(), false, not_after
+        ).is_ok());
+        assert_eq!(
+            cert.clone().validate_ta_detailed_at(
+                tal.clone(), false, Time::utc(2010, 1, 1, 0, 0, 0)
+            ).unwrap_err(),
+            TaValidationError::Invalid(ValidationError)
+        );
+        assert!(
+            cert.validate_ta_ignoring_time(tal.clone(), false).is_ok()
+        );
+
+        // ca1.cer isn’t self-signed and fails regardless of time.
+        let cert = Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        assert!(cert.clone().validate_ta_ignoring_time(tal.clone(), false)
+            .is_err());
+        assert_eq!(
+            cert.validate_ta_detailed_at(tal, false, later).unwrap_err(),
+            TaValidationError::Invalid(ValidationError)
+        );
+    }
+
+    #[test]
+    fn validate_issued() {
+        // ca1.cer is issued by ta.cer and valid from 2019-02-26 until
+        // 2020-07-01.
+        let tal = TalInfo::from_name("foo".into()).into_arc();
+        let now = Time::utc(2020, 1, 1, 0, 0, 0);
+        let ta = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap().validate_ta_at(tal, false, now).unwrap();
+        let ca1 = Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).unwrap();
+
+        let ca1 = ta.validate_issued_at(ca1, false, now).unwrap();
+        assert_eq!(ca1.key_usage(), KeyUsage::Ca);
+        assert!(ta.v4_resources().contains(ca1.v4_resources()));
+        assert!(ta.as_resources().contains(ca1.as_resources()));
+        assert!(Arc::ptr_eq(ta.tal(), ca1.tal()));
+
+        // Outside the validity of ca1.
+        let later = Time::utc(2021, 1, 1, 0, 0, 0);
+        assert!(ta.validate_issued_at(ca1.as_cert().clone(), false, later)
+            .is_err());
+
+        // Not issued by ca1.
+        assert!(ca1.validate_issued_at(ta.as_cert().clone(), false, now)
+            .is_err());
+    }
+
+    #[test]
+    fn validate_ref() {
+        let tal = TalInfo::from_name("foo".into()).into_arc();
+        let now = Time::utc(2020, 1, 1, 0, 0, 0);
+        let ta = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let ca1 = Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).unwrap();
+
+        assert!(ta.validate_ta_ref(false, now).is_ok());
+        assert!(ca1.validate_ta_ref(false, now).is_err());
+
+        let ta = ta.validate_ta_at(tal, false, now).unwrap();
+        assert!(ca1.validate_ca_ref(&ta, false, now).is_ok());
+        assert!(ca1.validate_ee_ref(&ta, false, now).is_err());
+        assert!(
+            ca1.validate_ca_ref(&ta, false, Time::utc(2021, 1, 1, 0, 0, 0))
+                .is_err()
+        );
+
+        // The borrowing versions agree with the consuming ones.
+        assert!(ca1.validate_ca_at(&ta, false, now).is_ok());
+    }
+
+    #[test]
+    fn validate_with_policy() {
+        let tal = TalInfo::from_name("foo".into()).into_arc();
+        let now = Time::utc(2020, 1, 1, 0, 0, 0);
+        let ta = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let ca1 = Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        let none = AlgorithmPolicy::none();
+        let no_rsa = AlgorithmPolicy::all().forbid(
+            SignatureAlgorithm::RsaSha256
+        );
+        let only_rsa = AlgorithmPolicy::none().allow(
+            SignatureAlgorithm::RsaSha256
+        );
+
+        assert!(
+            ta.clone().validate_ta_with_policy(
+                tal.clone(), false, now, &none
+            ).is_err()
+        );
+        let ta = ta.validate_ta_with_policy(
+            tal.clone(), false, now, &AlgorithmPolicy::all()
+        ).unwrap();
+        assert!(
+            ca1.clone().validate_ca_with_policy(&ta, false, now, &none)
+                .is_err()
+        );
+        assert!(
+            ca1.clone().validate_ca_with_policy(&ta, false, now, &no_rsa)
+                .is_err()
+        );
+        assert!(
+            ca1.validate_ca_with_policy(&ta, false, now, &only_rsa).is_ok()
+        );
+
+        // The EE certificate of a signed object issued by the TA.
+        let now = Time::utc(2019, 5, 1, 0, 0, 0);
+        let ta = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap().validate_ta_at(tal, false, now).unwrap();
+        let ee = crate::sigobj::SignedObject::decode(
+            include_bytes!("../../test-data/sigobj-der.mft").as_ref(), true
+        ).unwrap().cert().clone();
+        assert!(
+            ee.clone().validate_ee_with_policy(&ta, false, now, &no_rsa)
+                .is_err()
+        );
+        assert!(
+            ee.validate_ee_with_policy(&ta, false, now, &only_rsa).is_ok()
+        );
+    }
+
+    #[test]
+    fn validate_full() {
+        // ta.crl is current from 2019-02-26 to 2019-05-26 and doesn’t list
+        // ca1.cer.
+        let tal = TalInfo::from_name("foo".into()).into_arc();
+        let now = Time::utc(2019, 3, 1, 0, 0, 0);
+        let ta = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let ca1 = Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        let crl = Crl::decode(
+            include_bytes!("../../test-data/ta.crl").as_ref(), false
+        ).unwrap();
+        let ta = ta.validate_ta_at(tal, false, now).unwrap();
+
+        let res = ca1.clone().validate_full(&ta, &crl, false, now).unwrap();
+        assert_eq!(res.serial_number(), ca1.serial_number());
+
+        // A stale CRL is fine in non-strict mode.
+        let later = Time::utc(2020, 1, 1, 0, 0, 0);
+        assert!(ca1.clone().validate_full(&ta, &crl, false, later).is_ok());
+
+        // Expired certificate.
+        let expired = Time::utc(2021, 1, 1, 0, 0, 0);
+        assert_eq!(
+            ca1.clone().validate_full(&ta, &crl, false, expired).unwrap_err(),
+            CertValidationError::Basics
+        );
+
+        // The trust anchor isn’t issued by itself.
+        assert_eq!(
+            ta.as_cert().clone().validate_full(
+                &ta, &crl, false, now
+            ).unwrap_err(),
+            CertValidationError::Issuer
+        );
+
+        // CRL of a different issuer.
+        let other_crl = Crl::decode(
+            include_bytes!("../../test-data/ca1.crl").as_ref(), false
+        ).unwrap();
+        assert_eq!(
+            ca1.validate_full(&ta, &other_crl, false, now).unwrap_err(),
+            CertValidationError::Crl(CrlValidationError::Invalid(
+                ValidationError
+            ))
+        );
+    }
+
+    #[test]
+    fn from_reader() {
+        let ta = include_bytes!("../../test-data/ta.cer");
+        let ca1 = include_bytes!("../../test-data/ca1.cer");
+        let mut data = Vec::new();
+        data.extend_from_slice(ta);
+        data.extend_from_slice(ca1);
+        data.extend_from_slice(b"trailing");
+
+        // Two certificates back-to-back leave the trailing data alone.
+        let mut reader = data.as_slice();
+        let cert = Cert::from_reader(&mut reader).unwrap();
+        assert_eq!(
+            cert.subject_key_identifier(),
+            Cert::decode(ta.as_ref()).unwrap().subject_key_identifier()
+        );
+        let cert = Cert::from_reader(&mut reader).unwrap();
+        assert_eq!(
+            cert.subject_key_identifier(),
+            Cert::decode(ca1.as_ref()).unwrap().subject_key_identifier()
+        );
+        assert_eq!(reader, b"trailing");
+        assert!(Cert::from_reader(&mut reader).is_err());
+
+        // Truncated input.
+        match Cert::from_reader(&ta[..ta.len() - 1]) {
+            Err(ReadError::Io(_)) => { }
+            _ => panic!("expected IO error")
+        }
+
+        // Indefinite length.
+        match Cert::from_reader(b"\x30\x80\x00\x00".as_ref()) {
+            Err(ReadError::Decode(_)) => { }
+            _ => panic!("expected decoding error")
+        }
+
+        // Too large.
+        match Cert::from_reader(b"\x30\x84\x10\x00\x00\x00".as_ref()) {
+            Err(ReadError::TooLarge(0x1000_0000)) => { }
+            _ => panic!("expected size error")
+        }
+    }
+
+    #[test]
+    fn decode_all() {
+        let ta = include_bytes!("../../test-data/ta.cer");
+        let ca1 = include_bytes!("../../test-data/ca1.cer");
+        let ta_ski = Cert::decode(
+            ta.as_ref()
+        ).unwrap().subject_key_identifier();
+        let ca1_ski = Cert::decode(
+            ca1.as_ref()
+        ).unwrap().subject_key_identifier();
+
+        // Certs-only message created with openssl crl2pkcs7 -nocrl.
+        let (certs, errs) = Cert::decode_all(
+            include_bytes!("../../test-data/certs-only.p7b").as_ref()
+        );
+        assert!(errs.is_empty());
+        assert_eq!(certs.len(), 2);
+        assert_eq!(certs[0].subject_key_identifier(), ta_ski);
+        assert_eq!(certs[1].subject_key_identifier(), ca1_ski);
+
+        // Concatenation with a value that isn’t a certificate in between.
+        let mut data = Vec::new();
+        data.extend_from_slice(ta);
+        data.extend_from_slice(b"\x30\x03\x02\x01\x05");
+        data.extend_from_slice(ca1);
+        let (certs, errs) = Cert::decode_all(&data);
+        assert_eq!(certs.len(), 2);
+        assert_eq!(certs[0].subject_key_identifier(), ta_ski);
+        assert_eq!(certs[1].subject_key_identifier(), ca1_ski);
+        assert_eq!(errs.len(), 1);
+        match errs[0] {
+            ReadError::Decode(_) => { }
+            _ => panic!("expected decoding error")
+        }
+
+        // Truncated last certificate.
+        let mut data = Vec::new();
+        data.extend_from_slice(ta);
+        data.extend_from_slice(&ca1[..ca1.len() - 1]);
+        let (certs, errs) = Cert::decode_all(&data);
+        assert_eq!(certs.len(), 1);
+        assert_eq!(errs.len(), 1);
+        match errs[0] {
+            ReadError::Io(_) => { }
+            _ => panic!("expected IO error")
+        }
+
+        // Nothing at all.
+        let (certs, errs) = Cert::decode_all(b"");
+        assert!(certs.is_empty());
+        assert!(errs.is_empty());
+    }
+
+    #[test]
+    fn self_signed() {
+        let ta = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let ca1 = Cert::decode(
+            include_bytes!("../../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        assert!(ta.is_self_signed());
+        assert!(!ca1.is_self_signed());
+    }
+
+    #[test]
+    fn max_lifetime() {
+        // ta.cer is valid for exactly one hundred years.
+        let cert = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let lifetime = Time::utc(2117, 11, 28, 14, 39, 55)
+            .signed_duration_since(*Time::utc(2017, 11, 28, 14, 39, 55));
+        assert!(cert.validate_max_lifetime(Duration::days(365)).is_err());
+        assert!(
+            cert.validate_max_lifetime(
+                lifetime - Duration::seconds(1)
+            ).is_err()
+        );
+        assert!(cert.validate_max_lifetime(lifetime).is_ok());
+        assert!(cert.validate_max_lifetime(Duration::days(36525)).is_ok());
+    }
 }
 
 #[cfg(all(test, feature="softkeys"))]
 mod signer_test {
-    use std::str::FromStr;
+    use chrono::Datelike;
     use crate::cert::Cert;
     use crate::crypto::PublicKeyFormat;
     use crate::crypto::softsigner::OpenSslSigner;
-    use crate::resources::{AsId, Prefix};
     use crate::tal::TalInfo;
+    use crate::util::test::make_cert;
     use super::*;
 
 
@@ -1903,22 +3932,224 @@ mod signer_test {
         let mut signer = OpenSslSigner::new();
         let key = signer.create_key(PublicKeyFormat::default()).unwrap();
         let pubkey = signer.get_key_info(&key).unwrap();
-        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
-        let mut cert = TbsCert::new(
-            12u64.into(), pubkey.to_subject_name(),
-            Validity::from_secs(86400), None, pubkey, KeyUsage::Ca,
-            Overclaim::Trim
+        let cert = make_cert(pubkey, None, KeyUsage::Ca);
+        let cert = cert.into_cert(&signer, &key).unwrap().to_captured();
+        let cert = Cert::decode(cert.as_slice()).unwrap();
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        cert.validate_ta(talinfo, true).unwrap();
+    }
+
+    #[test]
+    fn critical_other_extension() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let mut cert = make_cert(pubkey, None, KeyUsage::Ca);
+        cert.other_extensions.push(OtherExtension {
+            id: Oid(Bytes::from_static(oid::CE_POLICY_CONSTRAINTS.0)),
+            critical: true,
+            value: OctetString::new(
+                Bytes::from_static(b"\x30\x03\x80\x01\x00")
+            ),
+        });
+        let cert = cert.into_cert(&signer, &key).unwrap().to_captured();
+        let cert = Cert::decode_with_options(
+            cert.as_slice(), &DecodeOptions::lax()
+        ).unwrap();
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        assert!(cert.clone().validate_ta(talinfo.clone(), true).is_err());
+        cert.validate_ta(talinfo, false).unwrap();
+    }
+
+    #[test]
+    fn zero_serial() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let mut cert = make_cert(pubkey, None, KeyUsage::Ca);
+        cert.set_serial_number(0u64);
+        let cert = cert.into_cert(&signer, &key).unwrap().to_captured();
+        assert_eq!(
+            Cert::decode_detailed(cert.as_slice()).unwrap_err(),
+            CertDecodeError::ZeroSerial
+        );
+        let cert = Cert::decode_with_options(
+            cert.as_slice(), &DecodeOptions::lax()
+        ).unwrap();
+        assert!(cert.serial_number().is_zero());
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        assert!(cert.clone().validate_ta(talinfo.clone(), true).is_err());
+        cert.validate_ta(talinfo, false).unwrap();
+    }
+
+    #[test]
+    fn generalized_time_validity() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let mut cert = make_cert(pubkey, None, KeyUsage::Ca);
+        cert.set_validity(
+            Validity::new(Time::now(), Time::utc(2051, 1, 1, 0, 0, 0))
         );
-        cert.set_basic_ca(Some(true));
-        cert.set_ca_repository(Some(uri.clone()));
-        cert.set_rpki_manifest(Some(uri.clone()));
-        cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
-        cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
-        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
         let cert = cert.into_cert(&signer, &key).unwrap().to_captured();
         let cert = Cert::decode(cert.as_slice()).unwrap();
+        assert_eq!(cert.validity().not_after().year(), 2051);
+        assert_eq!(
+            cert.validity().not_after(), Time::utc(2051, 1, 1, 0, 0, 0)
+        );
         let talinfo = TalInfo::from_name("foo".into()).into_arc();
         cert.validate_ta(talinfo, true).unwrap();
     }
+
+    #[test]
+    fn validate_full_revocation() {
+        use crate::crl::{CrlEntry, TbsCertList};
+
+        let (ta, mut signer, ta_key) = Cert::generate_test_ta().unwrap();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let other = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let ta = ta.validate_ta(talinfo, true).unwrap();
+
+        // Issues a CA certificate with serial number 12 for `key`, signing
+        // it with `signing_key`.
+        let issue = |key: &KeyId, signing_key: &KeyId| {
+            let pubkey = signer.get_key_info(key).unwrap();
+            let cert = make_cert(pubkey, Some(ta.as_cert()), KeyUsage::Ca);
+            let cert = cert.into_cert(&signer, signing_key).unwrap();
+            Cert::decode(cert.to_captured().as_slice()).unwrap()
+        };
+        let make_crl = |serial: u64, next_update: Time| {
+            TbsCertList::new(
+                Default::default(), ta.subject().clone(),
+                Time::now() - Duration::minutes(1), next_update,
+                vec![CrlEntry::new(serial.into(), Time::now())],
+                ta.subject_key_identifier(), 1u64.into()
+            ).into_crl(&signer, &ta_key).unwrap()
+        };
+
+        let cert = issue(&key, &ta_key);
+        let crl = make_crl(13, Time::tomorrow());
+        assert!(
+            cert.clone().validate_full(&ta, &crl, true, Time::now()).is_ok()
+        );
+
+        let crl = make_crl(12, Time::tomorrow());
+        assert_eq!(
+            cert.clone().validate_full(
+                &ta, &crl, true, Time::now()
+            ).unwrap_err(),
+            CertValidationError::Revoked
+        );
+
+        let crl = make_crl(13, Time::now() - Duration::seconds(1));
+        assert!(
+            cert.clone().validate_full(&ta, &crl, false, Time::now()).is_ok()
+        );
+        assert_eq!(
+            cert.validate_full(&ta, &crl, true, Time::now()).unwrap_err(),
+            CertValidationError::Crl(CrlValidationError::Stale)
+        );
+
+        // Signed with the wrong key.
+        let cert = issue(&key, &other);
+        let crl = make_crl(13, Time::tomorrow());
+        assert_eq!(
+            cert.validate_full(&ta, &crl, true, Time::now()).unwrap_err(),
+            CertValidationError::Signature
+        );
+    }
+
+    #[test]
+    fn validate_key_usage() {
+        use crate::crl::{CrlEntry, TbsCertList};
+
+        let (ta, mut signer, ta_key) = Cert::generate_test_ta().unwrap();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let ta = ta.validate_ta(talinfo, true).unwrap();
+        let crl = TbsCertList::new(
+            Default::default(), ta.subject().clone(),
+            Time::now() - Duration::minutes(1), Time::tomorrow(),
+            Vec::<CrlEntry>::new(), ta.subject_key_identifier(), 1u64.into()
+        ).into_crl(&signer, &ta_key).unwrap();
+        let now = Time::now();
+
+        // Issues a CA or EE certificate with the given key usage.
+        let issue = |ca: bool, key_usage: KeyUsage| {
+            let pubkey = signer.get_key_info(&key).unwrap();
+            let mut cert = make_cert(
+                pubkey, Some(ta.as_cert()),
+                if ca { KeyUsage::Ca } else { KeyUsage::Ee }
+            );
+            cert.set_key_usage(key_usage);
+            let cert = cert.into_cert(&signer, &ta_key).unwrap();
+            Cert::decode(cert.to_captured().as_slice()).unwrap()
+        };
+
+        let cert = issue(true, KeyUsage::Ca);
+        assert!(cert.validate_ca_ref(&ta, true, now).is_ok());
+        assert!(cert.validate_full(&ta, &crl, true, now).is_ok());
+
+        let cert = issue(true, KeyUsage::Ee);
+        assert!(cert.validate_ca_ref(&ta, true, now).is_err());
+        assert!(cert.validate_ca_ref(&ta, false, now).is_err());
+        for &strict in &[true, false] {
+            assert_eq!(
+                cert.clone().validate_full(
+                    &ta, &crl, strict, now
+                ).unwrap_err(),
+                CertValidationError::KeyUsage
+            );
+        }
+
+        let cert = issue(false, KeyUsage::Ee);
+        assert!(cert.validate_ee_ref(&ta, true, now).is_ok());
+        assert!(cert.validate_full(&ta, &crl, true, now).is_ok());
+
+        let cert = issue(false, KeyUsage::Ca);
+        assert!(cert.validate_ee_ref(&ta, true, now).is_err());
+        assert!(cert.validate_ee_ref(&ta, false, now).is_err());
+        for &strict in &[true, false] {
+            assert_eq!(
+                cert.clone().validate_full(
+                    &ta, &crl, strict, now
+                ).unwrap_err(),
+                CertValidationError::KeyUsage
+            );
+        }
+    }
+
+    #[test]
+    fn generate_test_ta() {
+        let (cert, signer, key) = Cert::generate_test_ta().unwrap();
+        assert_eq!(
+            cert.subject_public_key_info(),
+            &signer.get_key_info(&key).unwrap()
+        );
+        assert!(!cert.expires_within(Duration::days(364), Time::now()));
+        let cert = Cert::decode(cert.to_captured().as_slice()).unwrap();
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        cert.validate_ta(talinfo, true).unwrap();
+    }
+
+    #[test]
+    fn to_der_is_x509() {
+        for der in &[
+            include_bytes!("../../test-data/ta.cer").as_ref(),
+            include_bytes!("../../test-data/ca1.cer").as_ref(),
+        ] {
+            let cert = Cert::decode(*der).unwrap();
+            let x509 = openssl::x509::X509::from_der(
+                cert.to_der().as_ref()
+            ).unwrap();
+            assert_eq!(
+                x509.serial_number().to_bn().unwrap()
+                    .to_dec_str().unwrap().to_string(),
+                cert.serial_number().to_string()
+            );
+            assert_eq!(x509.to_der().unwrap(), cert.to_der().as_ref());
+        }
+    }
 }
 
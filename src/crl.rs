@@ -14,17 +14,19 @@
 //! [`Crl`]: struct.Crl.html
 //! [`CrlStore`]: struct.CrlStore.html
 
-use std::ops;
+use std::{error, fmt, ops};
 use std::collections::HashSet;
 use std::str::FromStr;
 use bcder::{decode, encode};
 use bcder::{Captured, Mode, OctetString, Oid, Tag, xerr};
 use bcder::encode::PrimitiveContent;
 use bytes::Bytes;
+use log::{info, warn};
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use crate::{oid, uri};
 use crate::crypto::{
-    KeyIdentifier, PublicKey, SignatureAlgorithm, Signer, SigningError
+    AlgorithmPolicy, KeyIdentifier, PublicKey, SignatureAlgorithm, Signer,
+    SigningError
 };
 use crate::x509::{
     Name, RepresentationError, Serial, SignedData, Time, ValidationError,
@@ -70,6 +72,14 @@ impl Crl {
     ///
     /// Doing this will speed up calls to `contains` later on at the price
     /// of additional memory consumption.
+    ///
+    /// The cache is built right away from the already decoded list of
+    /// revoked certificates. It is never built implicitly, so without
+    /// calling this method, `contains` keeps walking the list. Calling the
+    /// method again rebuilds the cache, which yields the same result since
+    /// a CRL can’t be changed. The cache is cloned along with the CRL. It
+    /// isn’t part of the encoded or serialized CRL, so a CRL decoded or
+    /// deserialized again starts out without a cache.
     pub fn cache_serials(&mut self) {
         self.serials = Some(
             self.tbs.revoked_certs.iter().map(|entry| entry.user_certificate)
@@ -78,12 +88,33 @@ impl Crl {
     }
 
     /// Returns whether the given serial number is on this revocation list.
+    ///
+    /// If the serial numbers have been cached via `cache_serials`, this is
+    /// a hash lookup. Otherwise, the list of revoked certificates is
+    /// decoded and walked for every call.
     pub fn contains(&self, serial: Serial) -> bool {
         match self.serials {
             Some(ref set) => set.contains(&serial),
             None => self.tbs.revoked_certs.contains(serial)
         }
     }
+
+    /// Returns whether this CRL is newer than another CRL.
+    ///
+    /// If both CRLs have a CRL number, the CRL with the larger number is
+    /// newer and the thisUpdate times are only compared if the numbers are
+    /// equal. Otherwise, only the thisUpdate times are compared. Both CRLs
+    /// should be issued by the same CA for the result to be meaningful.
+    pub fn is_newer_than(&self, other: &Crl) -> bool {
+        if let (Some(number), Some(other_number)) = (
+            self.crl_number(), other.crl_number()
+        ) {
+            if number != other_number {
+                return number > other_number
+            }
+        }
+        self.this_update() > other.this_update()
+    }
 }
 
 
@@ -91,37 +122,125 @@ impl Crl {
 ///
 impl Crl {
     /// Parses a source as a certificate revocation list.
-    pub fn decode<S: decode::Source>(source: S) -> Result<Self, S::Err> {
-        Mode::Der.decode(source, Self::take_from)
+    ///
+    /// Decoding checks the CRL against the profile defined in RFC 6487.
+    /// Delta CRLs and CRLs with an issuing distribution point are always
+    /// rejected. If `strict` is `true`, CRL entry extensions and unknown
+    /// CRL extensions are rejected, too, as is a missing CRL number.
+    /// Otherwise, extensions are ignored unless the latter are marked as
+    /// critical and a missing CRL number is only logged as a warning.
+    pub fn decode<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        Mode::Der.decode(source, |cons| Self::take_from(cons, strict))
+    }
+
+    /// Decodes a CRL reporting why decoding failed.
+    ///
+    /// This is the same as [`decode`] but returns a [`CrlDecodeError`]
+    /// that, where possible, explains what is wrong with the CRL.
+    ///
+    /// [`decode`]: #method.decode
+    /// [`CrlDecodeError`]: enum.CrlDecodeError.html
+    pub fn decode_detailed<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, CrlDecodeError> {
+        let mut err = None;
+        Mode::Der.decode(source, |cons| {
+            cons.take_sequence(|cons| {
+                Self::from_constructed_detailed(cons, strict, &mut err)
+            })
+        }).map_err(|_| err.unwrap_or(CrlDecodeError::Malformed))
     }
 
     /// Takes an encoded CRL from the beginning of a constructed value.
     pub fn take_from<S: decode::Source>(
-        cons: &mut decode::Constructed<S>
+        cons: &mut decode::Constructed<S>,
+        strict: bool
     ) -> Result<Self, S::Err> {
-        cons.take_sequence(Self::from_constructed)
+        cons.take_sequence(|cons| Self::from_constructed(cons, strict))
     }
 
     /// Parses the content of a certificate revocation list.
     pub fn from_constructed<S: decode::Source>(
-        cons: &mut decode::Constructed<S>
+        cons: &mut decode::Constructed<S>,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        Self::from_constructed_detailed(cons, strict, &mut None)
+    }
+
+    /// Parses the content of a CRL remembering why decoding failed.
+    fn from_constructed_detailed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        strict: bool,
+        err: &mut Option<CrlDecodeError>
     ) -> Result<Self, S::Err> {
         let signed_data = SignedData::from_constructed(cons)?;
-        let tbs = signed_data.data().clone().decode(TbsCertList::take_from)?;
+        let tbs = signed_data.data().clone().decode(|cons| {
+            TbsCertList::take_from_detailed(cons, strict, err)
+        })?;
         Ok(Self { signed_data, tbs, serials: None })
     }
 
     /// Validates the certificate revocation list.
     ///
-    /// The list’s signature is validated against the provided public key.
+    /// The list’s signature is validated against the provided public key
+    /// which should be the key of the certificate that issued the CRL.
+    /// The signature algorithm must be the one allowed for RPKI and both
+    /// mentions of it in the CRL must be the same.
     pub fn validate(
         &self,
         public_key: &PublicKey
+    ) -> Result<(), ValidationError> {
+        self.validate_with_policy(public_key, &AlgorithmPolicy::default())
+    }
+
+    /// Validates the certificate revocation list under a local policy.
+    ///
+    /// This is the same as [`validate`] but the signature algorithm must
+    /// be permitted by `policy` rather than be the one allowed for RPKI.
+    ///
+    /// [`validate`]: #method.validate
+    pub fn validate_with_policy(
+        &self,
+        public_key: &PublicKey,
+        policy: &AlgorithmPolicy,
     ) -> Result<(), ValidationError> {
         if self.tbs.signature != self.signed_data.signature().algorithm() {
             return Err(ValidationError)
         }
-        self.signed_data.verify_signature(public_key)
+        self.signed_data.verify_signature_with_policy(
+            public_key, policy
+        ).map_err(Into::into)
+    }
+
+    /// Validates the CRL and checks whether it is stale.
+    ///
+    /// This does the same as [`validate`] and additionally checks whether
+    /// the CRL’s nextUpdate time has passed at the time given by `now`. If
+    /// so, a stale CRL is an error in strict mode. Otherwise, it is
+    /// accepted and a [`CrlWarning::Stale`] is returned.
+    ///
+    /// [`validate`]: #method.validate
+    /// [`CrlWarning::Stale`]: enum.CrlWarning.html
+    pub fn validate_at(
+        &self,
+        public_key: &PublicKey,
+        strict: bool,
+        now: Time
+    ) -> Result<Option<CrlWarning>, CrlValidationError> {
+        self.validate(public_key)?;
+        if !self.is_stale_at(now) {
+            Ok(None)
+        }
+        else if strict {
+            Err(CrlValidationError::Stale)
+        }
+        else {
+            Ok(Some(CrlWarning::Stale))
+        }
     }
 
     pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
@@ -169,11 +288,108 @@ impl<'de> Deserialize<'de> for Crl {
         let string = String::deserialize(deserializer)?;
         let decoded = base64::decode(&string).map_err(de::Error::custom)?;
         let bytes = Bytes::from(decoded);
-        Crl::decode(bytes).map_err(de::Error::custom)
+        Crl::decode(bytes, true).map_err(de::Error::custom)
+    }
+}
+
+
+//------------ CrlWarning ----------------------------------------------------
+
+/// A problem with a CRL that was tolerated during validation.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CrlWarning {
+    /// The CRL’s nextUpdate time has passed.
+    Stale,
+}
+
+impl fmt::Display for CrlWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CrlWarning::Stale => f.write_str("CRL is stale"),
+        }
     }
 }
 
 
+//------------ CrlDecodeError ------------------------------------------------
+
+/// An error happened while decoding a CRL.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CrlDecodeError {
+    /// The CRL is not correctly encoded or violates the RPKI profile.
+    Malformed,
+
+    /// A CRL entry has an extension.
+    ///
+    /// RFC 6487 forbids CRL entry extensions. This is only an error in
+    /// strict mode. Contains the serial number of the entry and the
+    /// extension’s OID.
+    EntryExtension(Serial, Oid),
+}
+
+impl fmt::Display for CrlDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CrlDecodeError::Malformed => {
+                f.write_str("malformed CRL")
+            }
+            CrlDecodeError::EntryExtension(serial, ref id) => {
+                write!(
+                    f, "CRL entry for serial {} has forbidden extension {}",
+                    serial, id
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for CrlDecodeError { }
+
+
+//------------ CrlValidationError --------------------------------------------
+
+/// An error happened while validating a CRL.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CrlValidationError {
+    /// The CRL’s signature or signature algorithm is invalid.
+    Invalid(ValidationError),
+
+    /// The CRL’s nextUpdate time has passed.
+    ///
+    /// This is only an error in strict mode.
+    Stale,
+}
+
+
+//--- From
+
+impl From<ValidationError> for CrlValidationError {
+    fn from(err: ValidationError) -> Self {
+        CrlValidationError::Invalid(err)
+    }
+}
+
+impl From<CrlValidationError> for ValidationError {
+    fn from(_: CrlValidationError) -> Self {
+        ValidationError
+    }
+}
+
+
+//--- Display and Error
+
+impl fmt::Display for CrlValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CrlValidationError::Invalid(ref err) => err.fmt(f),
+            CrlValidationError::Stale => f.write_str("CRL is stale"),
+        }
+    }
+}
+
+impl error::Error for CrlValidationError { }
+
+
 //------------ TbsCertList ---------------------------------------------------
 
 /// The payload of a certificate revocation list.
@@ -202,7 +418,10 @@ pub struct TbsCertList<C> {
     authority_key_id: KeyIdentifier,
 
     /// CRL Number
-    crl_number: Serial,
+    ///
+    /// RFC 6487 requires this extension, but in lenient mode a CRL without
+    /// it is accepted.
+    crl_number: Option<Serial>,
 }
 
 /// # Creating and Converting
@@ -225,12 +444,12 @@ impl<C> TbsCertList<C> {
             next_update,
             revoked_certs,
             authority_key_id,
-            crl_number
+            crl_number: Some(crl_number)
         }
     }
 
     /// Converts the value into a signed CRL.
-    pub fn into_crl<S: Signer>(
+    pub fn into_crl<S: Signer + ?Sized>(
         self,
         signer: &S,
         key: &S::KeyId
@@ -241,7 +460,7 @@ impl<C> TbsCertList<C> {
     {
         let tbs: TbsCertList<RevokedCertificates> = self.into();
         let data = Captured::from_values(Mode::Der, tbs.encode_ref());
-        let signature = signer.sign(key, tbs.signature, &data)?;
+        let signature = signer.sign(key, tbs.signature, data.as_slice())?;
         Ok(Crl {
             signed_data: SignedData::new(data, signature),
             tbs,
@@ -283,14 +502,22 @@ impl<C> TbsCertList<C> {
         self.this_update = this_update
     }
 
-    /// Returns the time of next update if present.
+    /// Returns the time of next update.
+    ///
+    /// While optional in X.509, RFC 6487 requires it to be present, so
+    /// decoding fails if it is missing.
     pub fn next_update(&self) -> Time {
         self.next_update
     }
 
     /// Returns whether the CRL’s nextUpdate time has passed.
     pub fn is_stale(&self) -> bool {
-        self.next_update < Time::now()
+        self.is_stale_at(Time::now())
+    }
+
+    /// Returns whether the CRL’s nextUpdate time has passed at `now`.
+    pub fn is_stale_at(&self, now: Time) -> bool {
+        self.next_update < now
     }
 
     /// Sets the time of next update.
@@ -313,7 +540,11 @@ impl<C> TbsCertList<C> {
         self.revoked_certs = revoked_certs
     }
 
-    /// Returns a reference to the authority key identifier if present.
+    /// Returns a reference to the authority key identifier.
+    ///
+    /// This is the subject key identifier of the certificate that issued
+    /// the CRL and can be used to find the issuer without relying on the
+    /// issuer name.
     pub fn authority_key_identifier(&self) -> &KeyIdentifier {
         &self.authority_key_id
     }
@@ -323,14 +554,17 @@ impl<C> TbsCertList<C> {
         self.authority_key_id = id
     }
 
-    /// Returns the CRL number.
-    pub fn crl_number(&self) -> Serial {
+    /// Returns the CRL number if present.
+    ///
+    /// The CRL number is only missing if the CRL was decoded in lenient
+    /// mode.
+    pub fn crl_number(&self) -> Option<Serial> {
         self.crl_number
     }
 
     /// Sets the CRL number.
     pub fn set_crl_number(&mut self, crl_number: Serial) {
-        self.crl_number = crl_number
+        self.crl_number = Some(crl_number)
     }
 }
 
@@ -339,8 +573,22 @@ impl<C> TbsCertList<C> {
 ///
 impl TbsCertList<RevokedCertificates> {
     /// Takes a value from the beginning of a encoded constructed value.
+    ///
+    /// See [`Crl::decode`] for the checks performed depending on `strict`.
+    ///
+    /// [`Crl::decode`]: struct.Crl.html#method.decode
     pub fn take_from<S: decode::Source>(
-        cons: &mut decode::Constructed<S>
+        cons: &mut decode::Constructed<S>,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        Self::take_from_detailed(cons, strict, &mut None)
+    }
+
+    /// Takes a value remembering why decoding failed.
+    fn take_from_detailed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        strict: bool,
+        err: &mut Option<CrlDecodeError>
     ) -> Result<Self, S::Err> {
         cons.take_sequence(|cons| {
             // version. Technically it is optional but we need v2, so it must
@@ -350,14 +598,16 @@ impl TbsCertList<RevokedCertificates> {
             let issuer = Name::take_from(cons)?;
             let this_update = Time::take_from(cons)?;
             let next_update = Time::take_from(cons)?;
-            let revoked_certs = RevokedCertificates::take_from(cons)?;
+            let revoked_certs = RevokedCertificates::take_from_detailed(
+                cons, strict, err
+            )?;
             let mut authority_key_id = None;
             let mut crl_number = None;
             cons.take_constructed_if(Tag::CTX_0, |cons| {
                 cons.take_sequence(|cons| {
                     while let Some(()) = cons.take_opt_sequence(|cons| {
                         let id = Oid::take_from(cons)?;
-                        let _critical = cons.take_opt_bool()?.unwrap_or(false);
+                        let critical = cons.take_opt_bool()?.unwrap_or(false);
                         let value = OctetString::take_from(cons)?;
                         Mode::Der.decode(value.to_source(), |content| {
                             if id == oid::CE_AUTHORITY_KEY_IDENTIFIER {
@@ -370,19 +620,35 @@ impl TbsCertList<RevokedCertificates> {
                                     content, &mut crl_number
                                 )
                             }
-                            else {
+                            else if id == oid::CE_DELTA_CRL_INDICATOR
+                                || id == oid::CE_ISSUING_DISTRIBUTION_POINT
+                            {
+                                // RFC 6487 forbids delta CRLs and partial
+                                // CRLs, so we can’t use these in any case.
+                                xerr!(Err(decode::Malformed))
+                            }
+                            else if strict || critical {
                                 // RFC 6487 says that no other extensions are
-                                // allowed. So we fail even if there is only
-                                // non-critical extension.
+                                // allowed. So we fail in strict mode even if
+                                // there is only non-critical extension.
                                 xerr!(Err(decode::Malformed))
                             }
+                            else {
+                                content.skip_all()
+                            }
                         }).map_err(Into::into)
                     })? { }
                     Ok(())
                 })
             })?;
             let authority_key_id = authority_key_id.ok_or(decode::Malformed)?;
-            let crl_number = crl_number.ok_or(decode::Malformed)?;
+            if crl_number.is_none() {
+                // RFC 6487 requires the CRL number.
+                if strict {
+                    xerr!(return Err(decode::Malformed.into()))
+                }
+                warn!("CRL without CRL number extension.");
+            }
             Ok(Self {
                 signature,
                 issuer,
@@ -434,10 +700,11 @@ impl TbsCertList<RevokedCertificates> {
                             self.authority_key_id.encode_ref_as(Tag::CTX_0)
                         )
                     ),
-                    encode_extension(
-                        &oid::CE_CRL_NUMBER, false,
-                        self.crl_number.encode()
-                    ),
+                    self.crl_number.map(|number| {
+                        encode_extension(
+                            &oid::CE_CRL_NUMBER, false, number.encode()
+                        )
+                    }),
                 ))
             )
         ))
@@ -477,12 +744,27 @@ pub struct RevokedCertificates(Captured);
 
 impl RevokedCertificates {
     /// Takes a revoked certificates list from the beginning of a value.
+    ///
+    /// If `strict` is `true`, entries with CRL entry extensions are
+    /// rejected.
     pub fn take_from<S: decode::Source>(
-        cons: &mut decode::Constructed<S>
+        cons: &mut decode::Constructed<S>,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        Self::take_from_detailed(cons, strict, &mut None)
+    }
+
+    /// Takes the list remembering why decoding failed.
+    fn take_from_detailed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        strict: bool,
+        err: &mut Option<CrlDecodeError>
     ) -> Result<Self, S::Err> {
         let res = cons.take_opt_sequence(|cons| {
             cons.capture(|cons| {
-                while CrlEntry::take_opt_from(cons)?.is_some() { }
+                while let Some(()) = cons.take_opt_sequence(|cons| {
+                    CrlEntry::skip_content(cons, strict, err)
+                })? { }
                 Ok(())
             })
         })?;
@@ -560,12 +842,33 @@ pub struct CrlEntry {
 
     /// The time of revocation.
     revocation_date: Time,
+
+    /// The reason for the revocation if given.
+    ///
+    /// RFC 6487 forbids CRL entry extensions, so this will only ever be
+    /// present in CRLs decoded in lenient mode.
+    reason: Option<CrlReason>,
 }
 
 impl CrlEntry {
     /// Creates a new CrlEntry for inclusion on a new Crl
     pub fn new(user_certificate: Serial, revocation_date: Time) -> Self {
-        CrlEntry { user_certificate, revocation_date }
+        CrlEntry { user_certificate, revocation_date, reason: None }
+    }
+
+    /// Returns the serial number of the revoked certificate.
+    pub fn user_certificate(&self) -> Serial {
+        self.user_certificate
+    }
+
+    /// Returns the time the certificate was revoked.
+    pub fn revocation_date(&self) -> Time {
+        self.revocation_date
+    }
+
+    /// Returns the reason code of the entry if present.
+    pub fn reason(&self) -> Option<CrlReason> {
+        self.reason
     }
 
     /// Takes a single CRL entry from the beginning of a constructed value.
@@ -583,14 +886,89 @@ impl CrlEntry {
     }
 
     /// Parses the content of a CRL entry.
+    ///
+    /// A reason code extension is parsed, any other non-critical CRL entry
+    /// extensions are ignored.
     pub fn from_constructed<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
-        Ok(CrlEntry {
-            user_certificate: Serial::take_from(cons)?,
-            revocation_date: Time::take_from(cons)?,
-            // crlEntryExtensions are forbidden by RFC 6487.
-        })
+        let user_certificate = Serial::take_from(cons)?;
+        let revocation_date = Time::take_from(cons)?;
+        let reason = Self::take_opt_extensions(cons, |_| Ok(()))?;
+        Ok(CrlEntry { user_certificate, revocation_date, reason })
+    }
+
+    /// Checks and skips over the content of a CRL entry.
+    ///
+    /// CRL entry extensions are forbidden by RFC 6487, so in strict mode
+    /// their presence results in an error and `err` is set to
+    /// `CrlDecodeError::EntryExtension` naming the serial number of the
+    /// entry and the extension. Otherwise, a warning naming both is logged.
+    fn skip_content<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        strict: bool,
+        err: &mut Option<CrlDecodeError>
+    ) -> Result<(), S::Err> {
+        let serial = Serial::take_from(cons)?;
+        Time::take_from(cons)?;
+        Self::take_opt_extensions(cons, |id| {
+            if strict {
+                info!(
+                    "CRL entry for serial {} has forbidden extension {}.",
+                    serial, id
+                );
+                *err = Some(
+                    CrlDecodeError::EntryExtension(serial, id.clone())
+                );
+                xerr!(Err(decode::Malformed))
+            }
+            else {
+                warn!(
+                    "CRL entry for serial {} has forbidden extension {}.",
+                    serial, id
+                );
+                Ok(())
+            }
+        })?;
+        Ok(())
+    }
+
+    /// Takes the optional CRL entry extensions.
+    ///
+    /// Calls `op` with the OID of every extension found and returns the
+    /// reason code if present. Unknown critical extensions are rejected.
+    fn take_opt_extensions<S, F>(
+        cons: &mut decode::Constructed<S>,
+        mut op: F
+    ) -> Result<Option<CrlReason>, S::Err>
+    where
+        S: decode::Source,
+        F: FnMut(&Oid) -> Result<(), decode::Error>
+    {
+        let res = cons.take_opt_sequence(|cons| {
+            let mut reason = None;
+            while let Some(()) = cons.take_opt_sequence(|cons| {
+                let id = Oid::take_from(cons)?;
+                let critical = cons.take_opt_bool()?.unwrap_or(false);
+                let value = OctetString::take_from(cons)?;
+                op(&id)?;
+                if id == oid::CE_CRL_REASONS {
+                    update_once(&mut reason, || {
+                        Mode::Der.decode(
+                            value.to_source(), CrlReason::take_from
+                        )
+                    }).map_err(Into::into)
+                }
+                else if critical {
+                    xerr!(Err(decode::Malformed.into()))
+                }
+                else {
+                    Ok(())
+                }
+            })? { }
+            Ok(reason)
+        })?;
+        Ok(res.flatten())
     }
 
     /// Returns a value encoder for the entry.
@@ -598,6 +976,13 @@ impl CrlEntry {
         encode::sequence((
             self.user_certificate.encode(),
             self.revocation_date.encode_varied(),
+            self.reason.map(|reason| {
+                encode::sequence(
+                    encode_extension(
+                        &oid::CE_CRL_REASONS, false, reason.encode()
+                    )
+                )
+            })
         ))
     }
 }
@@ -624,6 +1009,60 @@ impl FromStr for CrlEntry {
 }
 
 
+//------------ CrlReason -----------------------------------------------------
+
+/// The reason why a certificate was revoked.
+///
+/// This is the value of the reason code CRL entry extension defined in
+/// RFC 5280. RPKI doesn’t allow this extension but it still appears in
+/// the wild.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CrlReason {
+    Unspecified = 0,
+    KeyCompromise = 1,
+    CaCompromise = 2,
+    AffiliationChanged = 3,
+    Superseded = 4,
+    CessationOfOperation = 5,
+    CertificateHold = 6,
+    RemoveFromCrl = 8,
+    PrivilegeWithdrawn = 9,
+    AaCompromise = 10,
+}
+
+impl CrlReason {
+    /// Takes a reason code from the beginning of a constructed value.
+    ///
+    /// ```text
+    /// CRLReason ::= ENUMERATED
+    /// ```
+    pub fn take_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        cons.take_primitive_if(Tag::ENUMERATED, |prim| {
+            match prim.to_u8()? {
+                0 => Ok(CrlReason::Unspecified),
+                1 => Ok(CrlReason::KeyCompromise),
+                2 => Ok(CrlReason::CaCompromise),
+                3 => Ok(CrlReason::AffiliationChanged),
+                4 => Ok(CrlReason::Superseded),
+                5 => Ok(CrlReason::CessationOfOperation),
+                6 => Ok(CrlReason::CertificateHold),
+                8 => Ok(CrlReason::RemoveFromCrl),
+                9 => Ok(CrlReason::PrivilegeWithdrawn),
+                10 => Ok(CrlReason::AaCompromise),
+                _ => xerr!(Err(decode::Malformed.into()))
+            }
+        })
+    }
+
+    /// Returns a value encoder for the reason code.
+    pub fn encode(self) -> impl encode::Values {
+        (self as u8).encode_as(Tag::ENUMERATED)
+    }
+}
+
+
 //------------ CrlStore ------------------------------------------------------
 
 /// A place to cache CRLs for reuse.
@@ -688,22 +1127,196 @@ impl Default for CrlStore {
 
 #[cfg(test)]
 mod test {
+    use bcder::encode::Values;
+    use crate::cert::Cert;
     use super::*;
 
     #[test]
     fn decode_certs() {
         Crl::decode(
-            include_bytes!("../test-data/ta.crl").as_ref()
+            include_bytes!("../test-data/ta.crl").as_ref(), true
         ).unwrap();
         Crl::decode(
-            include_bytes!("../test-data/ca1.crl").as_ref()
+            include_bytes!("../test-data/ca1.crl").as_ref(), true
+        ).unwrap();
+    }
+
+    #[test]
+    fn data_access() {
+        let crl = Crl::decode(
+            include_bytes!("../test-data/ta.crl").as_ref(), true
+        ).unwrap();
+        assert_eq!(
+            crl.this_update(),
+            Time::from_str("2019-02-26T13:14:44Z").unwrap()
+        );
+        assert_eq!(
+            crl.next_update(),
+            Time::from_str("2019-05-26T13:14:44Z").unwrap()
+        );
+        assert_eq!(crl.crl_number(), Some(Serial::from(50u64)));
+        assert_eq!(
+            *crl.authority_key_identifier(),
+            b"\xe8\x55\x2b\x1f\xd6\xd1\xa4\xf7\xe4\x04\
+              \xc6\xd8\xe5\x68\x0d\x1e\xbc\x16\x3f\xc3".as_ref()
+        );
+        assert!(crl.contains(Serial::from(0xccu64)));
+        assert!(!crl.contains(Serial::from(0xcdu64)));
+    }
+
+    #[test]
+    fn validate() {
+        let ta = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let ca = Cert::decode(
+            include_bytes!("../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        let ta_crl = Crl::decode(
+            include_bytes!("../test-data/ta.crl").as_ref(), true
+        ).unwrap();
+        let ca_crl = Crl::decode(
+            include_bytes!("../test-data/ca1.crl").as_ref(), true
+        ).unwrap();
+
+        ta_crl.validate(ta.subject_public_key_info()).unwrap();
+        ca_crl.validate(ca.subject_public_key_info()).unwrap();
+
+        // Wrong keys.
+        assert!(ta_crl.validate(ca.subject_public_key_info()).is_err());
+        assert!(ca_crl.validate(ta.subject_public_key_info()).is_err());
+    }
+
+    #[test]
+    fn validate_at() {
+        let ta = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let ca = Cert::decode(
+            include_bytes!("../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        let crl = Crl::decode(
+            include_bytes!("../test-data/ta.crl").as_ref(), true
+        ).unwrap();
+        let key = ta.subject_public_key_info();
+        let fresh = Time::utc(2019, 3, 1, 0, 0, 0);
+        let stale = Time::utc(2019, 6, 1, 0, 0, 0);
+
+        assert!(!crl.is_stale_at(fresh));
+        assert!(crl.is_stale_at(stale));
+        assert_eq!(crl.validate_at(key, false, fresh), Ok(None));
+        assert_eq!(crl.validate_at(key, true, fresh), Ok(None));
+        assert_eq!(
+            crl.validate_at(key, false, stale), Ok(Some(CrlWarning::Stale))
+        );
+        assert_eq!(
+            crl.validate_at(key, true, stale), Err(CrlValidationError::Stale)
+        );
+        assert_eq!(
+            crl.validate_at(ca.subject_public_key_info(), false, fresh),
+            Err(CrlValidationError::Invalid(ValidationError))
+        );
+    }
+
+    #[test]
+    fn missing_next_update() {
+        // ta.crl with nextUpdate removed and the lengths adjusted.
+        let der = include_bytes!("../test-data/ta.crl");
+        let mut bad = b"\x30\x82\x02\x01\x30\x81\xea".to_vec();
+        bad.extend_from_slice(&der[7..64]);
+        bad.extend_from_slice(&der[79..]);
+        assert!(Crl::decode(bad.as_slice(), false).is_err());
+        assert!(Crl::decode(bad.as_slice(), true).is_err());
+    }
+
+    #[test]
+    fn entry_extensions() {
+        // Two entries, the first one with a reasonCode extension.
+        let data = b"\x30\x36\x30\x20\x02\x01\x0c\x17\x0d190226131444Z\
+                     \x30\x0c\x30\x0a\x06\x03\x55\x1d\x15\x04\x03\x0a\
+                     \x01\x01\x30\x12\x02\x01\x0d\x17\x0d190226131444Z";
+        assert!(
+            Mode::Der.decode(data.as_ref(), |cons| {
+                RevokedCertificates::take_from(cons, true)
+            }).is_err()
+        );
+        let certs = Mode::Der.decode(data.as_ref(), |cons| {
+            RevokedCertificates::take_from(cons, false)
+        }).unwrap();
+        assert!(certs.contains(Serial::from(12u64)));
+        assert!(certs.contains(Serial::from(13u64)));
+        assert_eq!(
+            certs.iter().map(|entry| {
+                (entry.user_certificate(), entry.reason())
+            }).collect::<Vec<_>>(),
+            vec![
+                (Serial::from(12u64), Some(CrlReason::KeyCompromise)),
+                (Serial::from(13u64), None),
+            ]
+        );
+
+        // An unknown critical entry extension is always rejected.
+        let data = b"\x30\x24\x30\x22\x02\x01\x0c\x17\x0d190226131444Z\
+                     \x30\x0e\x30\x0c\x06\x03\x55\x1d\x18\x01\x01\xff\
+                     \x04\x02\x05\x00";
+        assert!(
+            Mode::Der.decode(data.as_ref(), |cons| {
+                RevokedCertificates::take_from(cons, false)
+            }).is_err()
+        );
+    }
+
+    #[test]
+    fn reason_code() {
+        let der = include_bytes!("../test-data/reason-code.crl");
+        assert!(Crl::decode(der.as_ref(), true).is_err());
+        assert_eq!(
+            Crl::decode_detailed(der.as_ref(), true).unwrap_err(),
+            CrlDecodeError::EntryExtension(
+                Serial::from(12u64), Oid(oid::CE_CRL_REASONS.0.into())
+            )
+        );
+        assert!(Crl::decode_detailed(der.as_ref(), false).is_ok());
+        let crl = Crl::decode(der.as_ref(), false).unwrap();
+        assert!(crl.contains(Serial::from(12u64)));
+        assert!(crl.contains(Serial::from(13u64)));
+        assert!(!crl.contains(Serial::from(14u64)));
+        let entries = crl.revoked_certs().iter().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].user_certificate(), Serial::from(12u64));
+        assert_eq!(entries[0].reason(), Some(CrlReason::KeyCompromise));
+        assert_eq!(
+            entries[0].revocation_date(), Time::utc(2020, 3, 1, 12, 0, 0)
+        );
+        assert_eq!(entries[1].user_certificate(), Serial::from(13u64));
+        assert_eq!(entries[1].reason(), None);
+    }
+
+    #[test]
+    fn encode_reason_code() {
+        let entry = CrlEntry {
+            user_certificate: Serial::from(12u64),
+            revocation_date: Time::utc(2019, 2, 26, 13, 14, 44),
+            reason: Some(CrlReason::KeyCompromise),
+        };
+        let mut encoded = Vec::new();
+        entry.encode().write_encoded(Mode::Der, &mut encoded).unwrap();
+        assert_eq!(
+            encoded,
+            b"\x30\x20\x02\x01\x0c\x17\x0d190226131444Z\
+              \x30\x0c\x30\x0a\x06\x03\x55\x1d\x15\x04\x03\x0a\
+              \x01\x01".as_ref()
+        );
+        let decoded = Mode::Der.decode(
+            encoded.as_slice(), CrlEntry::take_from
         ).unwrap();
+        assert_eq!(decoded.reason(), Some(CrlReason::KeyCompromise));
     }
 
     #[test]
     fn serde_crl() {
         let der = include_bytes!("../test-data/ta.crl");
-        let crl = Crl::decode(Bytes::from_static(der)).unwrap();
+        let crl = Crl::decode(Bytes::from_static(der), true).unwrap();
 
         let serialized = serde_json::to_string(&crl).unwrap();
         let deser_crl: Crl = serde_json::from_str(&serialized).unwrap();
@@ -719,7 +1332,7 @@ mod test {
 mod signer_test {
     use super::*;
     use crate::crypto::PublicKeyFormat;
-    use crate::crypto::softsigner::OpenSslSigner;
+    use crate::crypto::softsigner::{KeyId, OpenSslSigner};
 
     #[test]
     fn build_ta_cert() {
@@ -736,7 +1349,159 @@ mod signer_test {
             12u64.into()
         );
         let crl = crl.into_crl(&signer, &key).unwrap().to_captured();
-        let _crl = Crl::decode(crl.as_slice()).unwrap();
+        let crl = Crl::decode(crl.as_slice(), true).unwrap();
+        crl.validate(&pubkey).unwrap();
+
+        let other = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let other = signer.get_key_info(&other).unwrap();
+        assert!(crl.validate(&other).is_err());
+    }
+
+    #[test]
+    fn validate_with_policy() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let crl = TbsCertList::new(
+            SignatureAlgorithm::RsaPssSha256,
+            pubkey.to_subject_name(),
+            Time::now(),
+            Time::tomorrow(),
+            vec![CrlEntry::new(12u64.into(), Time::now())],
+            KeyIdentifier::from_public_key(&pubkey),
+            12u64.into()
+        );
+        let crl = crl.into_crl(&signer, &key).unwrap().to_captured();
+        let crl = Crl::decode(crl.as_slice(), true).unwrap();
+        assert!(crl.validate(&pubkey).is_err());
+        crl.validate_with_policy(&pubkey, &AlgorithmPolicy::all()).unwrap();
+        assert!(
+            crl.validate_with_policy(
+                &pubkey,
+                &AlgorithmPolicy::all().forbid(
+                    SignatureAlgorithm::RsaPssSha256
+                )
+            ).is_err()
+        );
+    }
+
+    fn make_tbs(
+        signer: &mut OpenSslSigner, number: u64
+    ) -> (TbsCertList<Vec<CrlEntry>>, KeyId) {
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let tbs = TbsCertList::new(
+            Default::default(),
+            pubkey.to_subject_name(),
+            Time::utc(2020, 3, 1, 12, 0, 0),
+            Time::utc(2020, 3, 2, 12, 0, 0),
+            vec![CrlEntry::new(12u64.into(), Time::now())],
+            KeyIdentifier::from_public_key(&pubkey),
+            number.into()
+        );
+        (tbs, key)
+    }
+
+    #[test]
+    fn is_newer_than() {
+        let mut signer = OpenSslSigner::new();
+        let (tbs, key) = make_tbs(&mut signer, 12);
+        let older = tbs.clone().into_crl(&signer, &key).unwrap();
+        let mut tbs = tbs;
+        tbs.set_crl_number(13u64.into());
+        let newer = tbs.into_crl(&signer, &key).unwrap();
+        let older = Crl::decode(older.to_captured().as_slice(), true).unwrap();
+        let newer = Crl::decode(newer.to_captured().as_slice(), true).unwrap();
+        assert_eq!(older.crl_number(), Some(Serial::from(12u64)));
+        assert_eq!(newer.crl_number(), Some(Serial::from(13u64)));
+        assert!(newer.is_newer_than(&older));
+        assert!(!older.is_newer_than(&newer));
+        assert!(!older.is_newer_than(&older));
+    }
+
+    #[test]
+    fn missing_crl_number() {
+        let mut signer = OpenSslSigner::new();
+        let (mut tbs, key) = make_tbs(&mut signer, 12);
+        let older = tbs.clone().into_crl(&signer, &key).unwrap();
+        tbs.crl_number = None;
+        tbs.set_this_update(Time::utc(2020, 3, 1, 13, 0, 0));
+        let crl = tbs.into_crl(&signer, &key).unwrap().to_captured();
+        assert!(Crl::decode(crl.as_slice(), true).is_err());
+        let crl = Crl::decode(crl.as_slice(), false).unwrap();
+        assert_eq!(crl.crl_number(), None);
+        assert!(crl.is_newer_than(&older));
+        assert!(!older.is_newer_than(&crl));
+    }
+
+    fn make_crl<I>(now: Time, next: Time, entries: I) -> Crl
+    where I: IntoIterator<Item = Serial>, I::IntoIter: Clone {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let crl = TbsCertList::new(
+            Default::default(),
+            pubkey.to_subject_name(),
+            now,
+            next,
+            entries.into_iter().map(move |serial| CrlEntry::new(serial, now)),
+            KeyIdentifier::from_public_key(&pubkey),
+            12u64.into()
+        );
+        let crl = crl.into_crl(&signer, &key).unwrap().to_captured();
+        Crl::decode(crl.as_slice(), true).unwrap()
+    }
+
+    #[test]
+    fn contains_many() {
+        let mut crl = make_crl(
+            Time::utc(2020, 3, 1, 12, 0, 0), Time::utc(2020, 3, 2, 12, 0, 0),
+            (0..10_000u64).map(|x| Serial::from(x * 2))
+        );
+        assert!(crl.contains(Serial::from(19_998u64)));
+        assert!(!crl.contains(Serial::from(19_999u64)));
+
+        // With the cache, this is 20,000 hash lookups rather than walks
+        // through the list.
+        crl.cache_serials();
+        for x in 0..20_000u64 {
+            assert_eq!(crl.contains(Serial::from(x)), x % 2 == 0);
+        }
+
+        let clone = crl.clone();
+        assert!(clone.contains(Serial::from(2u64)));
+        assert!(!clone.contains(Serial::from(3u64)));
+    }
+
+    #[test]
+    fn contains_serials_differing_in_length() {
+        let revoked = [
+            Serial::from(0x01u64),
+            Serial::from(0x0100u64),
+            Serial::from(0x0100_0000u64),
+            Serial::from_slice(b"\x01\x00\x00\x00\x00\x00\x00\x00\x00")
+                .unwrap(),
+        ];
+        let others = [
+            Serial::from(0x0001_0000u64),
+            Serial::from(0x0001_0000_0000u64),
+            Serial::from(0x0101u64),
+            Serial::from_slice(b"\x01\x00\x00\x00\x00\x00\x00\x00")
+                .unwrap(),
+        ];
+        let mut crl = make_crl(
+            Time::utc(2020, 3, 1, 12, 0, 0), Time::utc(2020, 3, 2, 12, 0, 0),
+            revoked.iter().cloned()
+        );
+        for _ in 0..2 {
+            for serial in &revoked {
+                assert!(crl.contains(*serial), "{}", serial);
+            }
+            for serial in &others {
+                assert!(!crl.contains(*serial), "{}", serial);
+            }
+            crl.cache_serials();
+        }
     }
 }
 
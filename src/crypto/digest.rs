@@ -1,6 +1,7 @@
 //! Digest algorithm and operations.
 
-use std::io;
+use std::{error, fmt, io, str};
+use std::convert::TryFrom;
 use ring::digest;
 use bcder::{decode, encode};
 use bcder::encode::PrimitiveContent;
@@ -37,6 +38,11 @@ impl DigestAlgorithm {
     pub fn start(self) -> Context {
         Context(digest::Context::new(&digest::SHA256))
     }
+
+    /// Returns the length of a digest in octets.
+    pub fn digest_len(self) -> usize {
+        digest::SHA256.output_len
+    }
 }
 
 
@@ -154,6 +160,128 @@ impl DigestAlgorithm {
 }
 
 
+//------------ Sha256Hash ----------------------------------------------------
+
+/// A SHA-256 hash value.
+///
+/// This is used for the hashes of published objects in the publication
+/// protocol as well as for the hashes of the files of an RRDP repository.
+/// In both cases, the hash is represented in XML as a string of exactly 64
+/// hexadecimal digits. When parsing, both upper and lower case digits are
+/// accepted. The hash is always displayed with lower case digits.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct Sha256Hash([u8; 32]);
+
+impl Sha256Hash {
+    /// Calculates the hash of the given content.
+    pub fn from_content(content: &[u8]) -> Self {
+        let mut res = [0u8; 32];
+        res.copy_from_slice(
+            DigestAlgorithm::default().digest(content).as_ref()
+        );
+        Sha256Hash(res)
+    }
+
+    /// Returns the octets of the hash.
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+
+    /// Returns whether the hash matches the given content.
+    ///
+    /// The comparison is done in constant time.
+    pub fn matches(&self, content: &[u8]) -> bool {
+        ring::constant_time::verify_slices_are_equal(
+            &self.0, DigestAlgorithm::default().digest(content).as_ref()
+        ).is_ok()
+    }
+}
+
+
+//--- From, TryFrom, and FromStr
+
+impl From<[u8; 32]> for Sha256Hash {
+    fn from(value: [u8; 32]) -> Self {
+        Sha256Hash(value)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Sha256Hash {
+    type Error = Sha256HashError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        if value.len() != 32 {
+            return Err(Sha256HashError)
+        }
+        let mut res = [0u8; 32];
+        res.copy_from_slice(value);
+        Ok(Sha256Hash(res))
+    }
+}
+
+impl str::FromStr for Sha256Hash {
+    type Err = Sha256HashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.as_bytes();
+        if s.len() != 64 {
+            return Err(Sha256HashError)
+        }
+        let mut res = [0u8; 32];
+        for (octet, digits) in res.iter_mut().zip(s.chunks(2)) {
+            let first = char::from(digits[0]).to_digit(16)
+                .ok_or(Sha256HashError)?;
+            let second = char::from(digits[1]).to_digit(16)
+                .ok_or(Sha256HashError)?;
+            *octet = (first << 4 | second) as u8;
+        }
+        Ok(Sha256Hash(res))
+    }
+}
+
+
+//--- AsRef
+
+impl AsRef<[u8]> for Sha256Hash {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+
+//--- Display and Debug
+
+impl fmt::Display for Sha256Hash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &ch in self.0.iter() {
+            write!(f, "{:02x}", ch)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Sha256Hash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Sha256Hash({})", self)
+    }
+}
+
+
+//------------ Sha256HashError -----------------------------------------------
+
+/// A string or octet sequence was not a valid SHA-256 hash.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Sha256HashError;
+
+impl fmt::Display for Sha256HashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid SHA-256 hash")
+    }
+}
+
+impl error::Error for Sha256HashError { }
+
+
 //------------ Sha1 ----------------------------------------------------------
 
 pub fn sha1_digest(data: &[u8]) -> Digest {
@@ -191,3 +319,37 @@ impl io::Write for Context {
     }
 }
 
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn sha256_hash() {
+        let hash = Sha256Hash::from_str(
+            "C047E305FE71F2936720948E129A14C0\
+             819DED9CDECF31CFAF02C71200EB6F7C"
+        ).unwrap();
+        assert_eq!(
+            hash.to_string(),
+            "c047e305fe71f2936720948e129a14c0819ded9cdecf31cfaf02c71200eb6f7c"
+        );
+        assert_eq!(Sha256Hash::from_str(&hash.to_string()), Ok(hash));
+        assert_eq!(Sha256Hash::try_from(hash.as_slice()), Ok(hash));
+        assert!(Sha256Hash::try_from(&hash.as_slice()[1..]).is_err());
+        assert!(Sha256Hash::from_str("c047e305").is_err());
+        assert!(Sha256Hash::from_str(&"00ff".repeat(17)).is_err());
+        assert!(Sha256Hash::from_str(&"0g".repeat(32)).is_err());
+
+        let hash = Sha256Hash::from_content(b"foo");
+        assert_eq!(
+            hash.as_slice(),
+            digest::digest(&digest::SHA256, b"foo").as_ref()
+        );
+        assert!(hash.matches(b"foo"));
+        assert!(!hash.matches(b"bar"));
+    }
+}
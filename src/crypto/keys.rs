@@ -4,7 +4,7 @@ use std::{error, fmt, io, str};
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
 use bcder::{decode, encode};
-use bcder::{BitString, Mode, OctetString, Tag};
+use bcder::{BitString, Mode, OctetString, Tag, Unsigned, xerr};
 use bcder::encode::{PrimitiveContent, Values};
 use bytes::Bytes;
 use ring::{digest, signature};
@@ -15,7 +15,7 @@ use untrusted::Input;
 use crate::oid;
 use crate::util::hex;
 use crate::x509::{Name, RepresentationError};
-use super::signature::Signature;
+use super::signature::{Signature, SignatureAlgorithm};
 use ring::signature::VerificationAlgorithm;
 
 
@@ -85,7 +85,18 @@ impl PublicKeyFormat{
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PublicKey {
     algorithm: PublicKeyFormat,
-    bits: BitString,
+
+    /// The octets of the subjectPublicKey bit string.
+    ///
+    /// Bit strings with unused bits are rejected when decoding, so the
+    /// bit string is always a whole number of octets.
+    bits: Bytes,
+
+    /// The key identifier of the key.
+    ///
+    /// Since it is needed for validating pretty much every object, we
+    /// calculate it once when decoding the key.
+    key_id: KeyIdentifier,
 }
 
 
@@ -95,28 +106,141 @@ impl PublicKey {
     }
 
     pub fn bits(&self) -> &[u8] {
-        self.bits.octet_slice().unwrap()
+        self.bits.as_ref()
+    }
+
+    /// Returns the size of the key in bits.
+    ///
+    /// For RSA keys, this is the size of the modulus. Returns `None` if the
+    /// key bits cannot be decoded.
+    pub fn key_size(&self) -> Option<usize> {
+        let modulus = Mode::Der.decode(self.bits(), |cons| {
+            cons.take_sequence(|cons| {
+                let modulus = Unsigned::take_from(cons)?;
+                let _exponent = Unsigned::take_from(cons)?;
+                Ok(modulus)
+            })
+        }).ok()?;
+        let mut octets = modulus.as_slice().iter().skip_while(|&&x| x == 0);
+        let first = octets.next()?;
+        Some(
+            (octets.count() + 1) * 8 - first.leading_zeros() as usize
+        )
     }
 
+    /// Returns the key identifier of the key.
+    ///
+    /// RFC 6487 requires the key identifiers used in RPKI certificates to
+    /// be the SHA-1 hash of the octets of the subjectPublicKey, i.e., of
+    /// the key’s bits. This method is the one place where the digest
+    /// algorithm for key identifiers is chosen and should be used whenever
+    /// a key identifier needs to be calculated.
+    ///
+    /// The identifier is calculated once when the key is decoded, so
+    /// calling this method is cheap.
     pub fn key_identifier(&self) -> KeyIdentifier {
-        KeyIdentifier::try_from(
-            digest::digest(
-                &digest::SHA1_FOR_LEGACY_USE_ONLY,
-                self.bits.octet_slice().unwrap()
-            ).as_ref()
-        ).unwrap()
+        self.key_id
+    }
+
+    /// Returns a key identifier derived from SHA-256.
+    ///
+    /// The identifier is the leftmost 160 bits of the SHA-256 hash of the
+    /// key’s bits as described in method 1 of section 2 of RFC 7093. Such
+    /// identifiers are not valid in RPKI and this method is only intended
+    /// for experimentation. It is only available with the `sha256-ski`
+    /// feature.
+    #[cfg(feature = "sha256-ski")]
+    pub fn sha256_key_identifier(&self) -> KeyIdentifier {
+        Self::bits_identifier(&digest::SHA256, self.bits())
+    }
+
+    /// Calculates a key identifier using the given digest algorithm.
+    ///
+    /// If the digest is longer than 20 octets, it is truncated.
+    fn bits_identifier(
+        algorithm: &'static digest::Algorithm, bits: &[u8]
+    ) -> KeyIdentifier {
+        let digest = digest::digest(algorithm, bits);
+        KeyIdentifier::try_from(&digest.as_ref()[..20]).unwrap()
     }
 
     /// Verifies a signature using this public key.
+    ///
+    /// The signature is verified using the algorithm given in `signature`.
+    /// Note that this means that this method will also verify signatures
+    /// using algorithms not allowed for RPKI objects.
     pub fn verify(
         &self, message: &[u8], signature: &Signature
     ) -> Result<(), VerificationError> {
-        signature::RSA_PKCS1_2048_8192_SHA256.verify(
+        let alg: &dyn VerificationAlgorithm = match signature.algorithm() {
+            SignatureAlgorithm::RsaSha256 => {
+                &signature::RSA_PKCS1_2048_8192_SHA256
+            }
+            SignatureAlgorithm::RsaPssSha256 => {
+                &signature::RSA_PSS_2048_8192_SHA256
+            }
+        };
+        alg.verify(
             Input::from(self.bits()),
             Input::from(message),
             Input::from(signature.value().as_ref())
         ).map_err(Into::into)
     }
+
+    /// Verifies a signature over a precomputed digest.
+    ///
+    /// This is the same as [`verify`] except that it takes the digest of
+    /// the signed message rather than the message itself. This is useful if
+    /// the digest has already been calculated elsewhere, e.g., by a
+    /// hardware security module. The digest must have been created with
+    /// the digest algorithm of the signature’s algorithm.
+    ///
+    /// Since _ring_ doesn’t support verifying digests, this method uses
+    /// OpenSSL and is only available with the `prehashed` feature.
+    ///
+    /// [`verify`]: #method.verify
+    #[cfg(feature = "prehashed")]
+    pub fn verify_prehashed(
+        &self, digest: &[u8], signature: &Signature
+    ) -> Result<(), VerificationError> {
+        use openssl::rsa::{Padding, Rsa};
+
+        let alg = signature.algorithm();
+        if digest.len() != alg.digest_algorithm().digest_len() {
+            return Err(VerificationError)
+        }
+        let rsa = Rsa::public_key_from_der_pkcs1(
+            self.bits()
+        ).map_err(|_| VerificationError)?;
+
+        // Same key sizes as accepted by `verify`.
+        let mod_bits = rsa.n().num_bits() as usize;
+        if mod_bits < 2048 || mod_bits > 8192 {
+            return Err(VerificationError)
+        }
+
+        let padding = match alg {
+            SignatureAlgorithm::RsaSha256 => Padding::PKCS1,
+            SignatureAlgorithm::RsaPssSha256 => Padding::NONE,
+        };
+        let mut em = vec![0; rsa.size() as usize];
+        let len = rsa.public_decrypt(
+            signature.value().as_ref(), &mut em, padding
+        ).map_err(|_| VerificationError)?;
+        em.truncate(len);
+
+        let ok = match alg {
+            SignatureAlgorithm::RsaSha256 => {
+                em.len() == SHA256_DIGEST_INFO.len() + digest.len()
+                    && em.starts_with(SHA256_DIGEST_INFO)
+                    && em.ends_with(digest)
+            }
+            SignatureAlgorithm::RsaPssSha256 => {
+                verify_pss(&em, digest, mod_bits)
+            }
+        };
+        if ok { Ok(()) } else { Err(VerificationError) }
+    }
 }
 
 
@@ -134,24 +258,30 @@ impl PublicKey {
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
         cons.take_sequence(|cons| {
-            Ok(PublicKey {
-                algorithm: PublicKeyFormat::take_from(cons)?,
-                bits: BitString::take_from(cons)?
-            })
+            let algorithm = PublicKeyFormat::take_from(cons)?;
+            let bits = BitString::take_from(cons)?;
+            if bits.unused() != 0 {
+                xerr!(return Err(decode::Malformed.into()))
+            }
+            let bits = bits.octet_bytes();
+            let key_id = Self::bits_identifier(
+                &digest::SHA1_FOR_LEGACY_USE_ONLY, bits.as_ref()
+            );
+            Ok(PublicKey { algorithm, bits, key_id })
         })
     }
 
     pub fn encode(self) -> impl encode::Values {
         encode::sequence((
             self.algorithm.encode(),
-            self.bits.encode()
+            BitString::new(0, self.bits).encode()
         ))
     }
 
     pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
         encode::sequence((
             self.algorithm.encode(),
-            self.bits.encode_ref()
+            BitString::new(0, self.bits.clone()).encode()
         ))
     }
 
@@ -193,7 +323,7 @@ impl<'a> PrimitiveContent for PublicKeyCn<'a> {
     const TAG: Tag = Tag::PRINTABLE_STRING;
 
     fn encoded_len(&self, _mode: Mode) -> usize {
-        self.0.bits.octet_len() * 2
+        self.0.bits.len() * 2
     }
 
     fn write_encoded<W: io::Write>(
@@ -201,7 +331,7 @@ impl<'a> PrimitiveContent for PublicKeyCn<'a> {
         _mode: Mode, 
         target: &mut W
     ) -> Result<(), io::Error> {
-        for ch in self.0.bits.octets() {
+        for &ch in self.0.bits.iter() {
             target.write_all(&hex::encode_u8(ch))?
         }
         Ok(())
@@ -213,14 +343,15 @@ impl<'a> PrimitiveContent for PublicKeyCn<'a> {
 
 /// A key identifier.
 ///
-/// This is the SHA-1 hash over the public key’s bits.
+/// This is the SHA-1 hash over the public key’s bits. It is calculated by
+/// `PublicKey::key_identifier`.
 #[derive(Clone, Copy, Eq, Hash)]
 pub struct KeyIdentifier([u8; 20]);
 
 impl KeyIdentifier {
     /// Creates a new identifier for the given key.
     pub fn from_public_key(key: &PublicKey) -> Self {
-        Self(key.key_identifier().as_ref().try_into().unwrap())
+        key.key_identifier()
     }
 
     /// Returns an octet slice of the key identifer’s value.
@@ -274,7 +405,25 @@ impl KeyIdentifier {
 }
 
 
-//--- TryFrom and FromStr
+//--- From, TryFrom, and FromStr
+
+impl<'a> From<&'a PublicKey> for KeyIdentifier {
+    fn from(key: &'a PublicKey) -> Self {
+        key.key_identifier()
+    }
+}
+
+impl From<[u8; 20]> for KeyIdentifier {
+    fn from(value: [u8; 20]) -> Self {
+        KeyIdentifier(value)
+    }
+}
+
+impl From<KeyIdentifier> for OctetString {
+    fn from(key_id: KeyIdentifier) -> Self {
+        OctetString::new(Bytes::copy_from_slice(key_id.as_slice()))
+    }
+}
 
 impl<'a> TryFrom<&'a [u8]> for KeyIdentifier {
     type Error = RepresentationError;
@@ -420,3 +569,195 @@ impl fmt::Display for VerificationError {
 }
 
 impl error::Error for VerificationError { }
+
+
+//------------ Helpers -------------------------------------------------------
+
+/// The DER encoded DigestInfo for SHA-256 without the digest itself.
+///
+/// This is what a PKCS #1 v1.5 signature wraps the digest in.
+#[cfg(feature = "prehashed")]
+const SHA256_DIGEST_INFO: &[u8] = &[
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03,
+    0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20
+];
+
+/// The salt length of RSASSA-PSS signatures.
+#[cfg(feature = "prehashed")]
+const PSS_SALT_LEN: usize = 32;
+
+/// Verifies an RSASSA-PSS encoded message against a SHA-256 digest.
+///
+/// This is EMSA-PSS-VERIFY of section 9.1.2 of RFC 8017 starting at step
+/// 3 with MGF1 using SHA-256. The encoded message `em` is the raw result
+/// of the RSA operation, `mod_bits` is the size of the modulus in bits.
+#[cfg(feature = "prehashed")]
+fn verify_pss(em: &[u8], m_hash: &[u8], mod_bits: usize) -> bool {
+    let h_len = m_hash.len();
+    let em_bits = mod_bits - 1;
+    let em_len = (em_bits + 7) / 8;
+
+    // The RSA operation produces as many octets as the modulus has. If
+    // that is one more than emLen, the leading octet must be zero.
+    let em = if em.len() == em_len + 1 && em[0] == 0 {
+        &em[1..]
+    }
+    else if em.len() == em_len {
+        em
+    }
+    else {
+        return false
+    };
+
+    if em_len < h_len + PSS_SALT_LEN + 2 || em[em_len - 1] != 0xbc {
+        return false
+    }
+    let (masked_db, h) = em[..em_len - 1].split_at(em_len - h_len - 1);
+    let mask = 0xffu8 >> (8 * em_len - em_bits);
+    if masked_db[0] & !mask != 0 {
+        return false
+    }
+
+    let mut db = mgf1(h, masked_db.len());
+    for (db, masked) in db.iter_mut().zip(masked_db) {
+        *db ^= *masked
+    }
+    db[0] &= mask;
+    let ps_len = em_len - h_len - PSS_SALT_LEN - 2;
+    if db[..ps_len].iter().any(|&x| x != 0) || db[ps_len] != 0x01 {
+        return false
+    }
+    let salt = &db[ps_len + 1..];
+
+    let mut ctx = digest::Context::new(&digest::SHA256);
+    ctx.update(&[0u8; 8]);
+    ctx.update(m_hash);
+    ctx.update(salt);
+    ctx.finish().as_ref() == h
+}
+
+/// Generates a mask of the given length using MGF1 with SHA-256.
+#[cfg(feature = "prehashed")]
+fn mgf1(seed: &[u8], len: usize) -> Vec<u8> {
+    let mut res = Vec::with_capacity(len + digest::SHA256.output_len);
+    let mut counter = 0u32;
+    while res.len() < len {
+        let mut ctx = digest::Context::new(&digest::SHA256);
+        ctx.update(seed);
+        ctx.update(&counter.to_be_bytes());
+        res.extend_from_slice(ctx.finish().as_ref());
+        counter += 1;
+    }
+    res.truncate(len);
+    res
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use crate::cert::Cert;
+    use super::*;
+
+    #[test]
+    fn key_identifier() {
+        let cert = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let key = cert.subject_public_key_info();
+        assert_eq!(
+            key.key_identifier(),
+            b"\xe8\x55\x2b\x1f\xd6\xd1\xa4\xf7\xe4\x04\
+              \xc6\xd8\xe5\x68\x0d\x1e\xbc\x16\x3f\xc3".as_ref()
+        );
+        assert_eq!(KeyIdentifier::from_public_key(key), key.key_identifier());
+        assert_eq!(KeyIdentifier::from(key), key.key_identifier());
+    }
+
+    #[test]
+    fn key_identifier_conversions() {
+        let hex = "E8552B1FD6D1A4F7E404C6D8E5680D1EBC163FC3";
+        let key_id = KeyIdentifier::from_str(hex).unwrap();
+        assert_eq!(key_id.to_string(), hex);
+        assert_eq!(
+            KeyIdentifier::from_str(&hex.to_lowercase()).unwrap(), key_id
+        );
+        assert!(KeyIdentifier::from_str(&hex[2..]).is_err());
+
+        let mut value = [0u8; 20];
+        value.copy_from_slice(key_id.as_slice());
+        assert_eq!(KeyIdentifier::from(value), key_id);
+
+        let octets = OctetString::from(key_id);
+        assert_eq!(octets.as_slice(), Some(key_id.as_slice()));
+        let mut encoded = Vec::new();
+        octets.encode_ref().write_encoded(Mode::Der, &mut encoded).unwrap();
+        let decoded = Mode::Der.decode(
+            encoded.as_slice(), KeyIdentifier::take_from
+        ).unwrap();
+        assert_eq!(decoded, key_id);
+    }
+
+    #[test]
+    fn unused_bits() {
+        // A subjectPublicKeyInfo with rsaEncryption and the key bits as
+        // the last five octets.
+        let info = |bits: &[u8]| {
+            let mut res = b"\x30\x14\x30\x0d\x06\x09\x2a\x86\x48\x86\
+                            \xf7\x0d\x01\x01\x01\x05\x00".to_vec();
+            res.extend_from_slice(bits);
+            res
+        };
+        let key = PublicKey::decode(
+            info(b"\x03\x03\x00\xab\xcd").as_slice()
+        ).unwrap();
+        assert_eq!(key.bits(), b"\xab\xcd");
+        assert_eq!(
+            key.to_info_bytes().as_ref(),
+            info(b"\x03\x03\x00\xab\xcd").as_slice()
+        );
+        assert!(
+            PublicKey::decode(
+                info(b"\x03\x03\x01\xab\xcc").as_slice()
+            ).is_err()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sha256-ski")]
+    fn sha256_key_identifier() {
+        let cert = Cert::decode(
+            include_bytes!("../../test-data/ta.cer").as_ref()
+        ).unwrap();
+        assert_eq!(
+            cert.subject_public_key_info().sha256_key_identifier(),
+            b"\x2f\x59\xf8\xb9\x3c\xf1\x1e\xe2\x1a\xd1\
+              \x85\x9e\x13\x3d\xe8\x4a\xad\x63\xfe\x8a".as_ref()
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "prehashed", feature = "softkeys"))]
+    fn verify_prehashed() {
+        use crate::crypto::digest::DigestAlgorithm;
+        use crate::crypto::signer::Signer;
+        use crate::crypto::softsigner::OpenSslSigner;
+
+        let mut signer = OpenSslSigner::new();
+        let key_id = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let key = signer.get_key_info(&key_id).unwrap();
+        let digest = DigestAlgorithm::default().digest(b"foobar");
+        let other = DigestAlgorithm::default().digest(b"barfoo");
+        for alg in &[
+            SignatureAlgorithm::RsaSha256, SignatureAlgorithm::RsaPssSha256
+        ] {
+            let sig = signer.sign(&key_id, *alg, b"foobar").unwrap();
+            key.verify_prehashed(digest.as_ref(), &sig).unwrap();
+            assert!(key.verify_prehashed(other.as_ref(), &sig).is_err());
+            assert!(
+                key.verify_prehashed(&digest.as_ref()[..31], &sig).is_err()
+            );
+        }
+    }
+}
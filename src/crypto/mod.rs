@@ -1,12 +1,14 @@
 //! Signing related implementations.
 //!
 
-pub use self::digest::{Digest, DigestAlgorithm};
+pub use self::digest::{Digest, DigestAlgorithm, Sha256Hash};
 pub use self::keys::{
     KeyIdentifier, PublicKey, PublicKeyFormat, VerificationError
 };
 pub use self::signer::{Signer, SigningError};
-pub use self::signature::{Signature, SignatureAlgorithm};
+pub use self::signature::{
+    AlgorithmPolicy, Signature, SignatureAlgorithm
+};
 
 pub mod digest;
 pub mod keys;
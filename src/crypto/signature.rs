@@ -2,9 +2,10 @@
 
 use bcder::{decode, encode};
 use bcder::encode::PrimitiveContent;
-use bcder::Oid;
+use bcder::{Oid, Tag};
 use bytes::Bytes;
 use crate::oid;
+use super::digest::DigestAlgorithm;
 use super::keys::PublicKeyFormat;
 
 
@@ -14,13 +15,29 @@ use super::keys::PublicKeyFormat;
 ///
 /// These are the algorithms used for creating and verifying signatures. For
 /// RPKI, [RFC 7935] allows only one algorithm, RSA PKCS #1 v1.5 with
-/// SHA-256. Because of that, this type is currently a zero-sized struct.
-/// Should additional algorithms ever be allowed, it will change into an
-/// enum.
+/// SHA-256. This is the default value of the type.
 ///
+/// In addition, RSASSA-PSS with SHA-256 as defined in [RFC 4055] is
+/// supported so that certificates using it outside of the RPKI proper can
+/// be decoded and verified. Objects signed with it will fail RPKI
+/// validation, though. Use [`is_rpki`] to check whether an algorithm is
+/// acceptable for RPKI objects.
+///
+/// [RFC 4055]: https://tools.ietf.org/html/rfc4055
 /// [RFC 7935]: https://tools.ietf.org/html/rfc7935
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
-pub struct SignatureAlgorithm(());
+/// [`is_rpki`]: #method.is_rpki
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SignatureAlgorithm {
+    /// RSA PKCS #1 v1.5 with SHA-256.
+    RsaSha256,
+
+    /// RSASSA-PSS with SHA-256.
+    ///
+    /// Only the parameters recommended by RFC 4055 are supported: SHA-256
+    /// as the hash function, MGF1 with SHA-256 as the mask generation
+    /// function, and a salt length of 32 octets.
+    RsaPssSha256,
+}
 
 
 impl SignatureAlgorithm {
@@ -28,6 +45,28 @@ impl SignatureAlgorithm {
     pub fn public_key_format(self) -> PublicKeyFormat {
         PublicKeyFormat::default()
     }
+
+    /// Returns the digest algorithm used by this algorithm.
+    pub fn digest_algorithm(self) -> DigestAlgorithm {
+        DigestAlgorithm::default()
+    }
+
+    /// Returns whether the algorithm is allowed for RPKI objects.
+    pub fn is_rpki(self) -> bool {
+        match self {
+            SignatureAlgorithm::RsaSha256 => true,
+            SignatureAlgorithm::RsaPssSha256 => false,
+        }
+    }
+}
+
+
+//--- Default
+
+impl Default for SignatureAlgorithm {
+    fn default() -> Self {
+        SignatureAlgorithm::RsaSha256
+    }
 }
 
 
@@ -60,14 +99,29 @@ impl SignatureAlgorithm {
 /// absent field for the latter as well. When constructing identifiers,
 /// we will always include a parameters field and set it to NULL.
 ///
+/// For X.509 objects, we also accept `id-RSASSA-PSS` from [RFC 4055]. Its
+/// parameters are mandatory and defined as follows:
+///
+/// ```txt
+/// RSASSA-PSS-params  ::=  SEQUENCE  {
+///     hashAlgorithm      [0] HashAlgorithm DEFAULT sha1Identifier,
+///     maskGenAlgorithm   [1] MaskGenAlgorithm DEFAULT mgf1SHA1Identifier,
+///     saltLength         [2] INTEGER DEFAULT 20,
+///     trailerField       [3] INTEGER DEFAULT 1  }
+/// ```
+///
+/// Since the defaults are based on SHA-1, the first three fields must be
+/// present and must select SHA-256, MGF1 with SHA-256, and a salt length of
+/// 32. Any other parameters result in a malformed error.
+///
 /// [RFC 3370]: https://tools.ietf.org/html/rfc3370
 /// [RFC 4055]: https://tools.ietf.org/html/rfc4055
 /// [RFC 7935]: https://tools.ietf.org/html/rfc7935
 impl SignatureAlgorithm {
     /// Takes a signature algorithm identifier for X.509 objects.
     ///
-    /// Returns a malformed error if the algorithm isn’t the allowed for RPKI
-    /// or if it isn’t correctly encoded.
+    /// Returns a malformed error if the algorithm isn’t one of the
+    /// supported algorithms or if it isn’t correctly encoded.
     pub fn x509_take_from<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
@@ -78,9 +132,43 @@ impl SignatureAlgorithm {
     fn x509_from_constructed<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
-        oid::SHA256_WITH_RSA_ENCRYPTION.skip_if(cons)?;
-        cons.take_opt_null()?;
-        Ok(SignatureAlgorithm::default())
+        let oid = Oid::take_from(cons)?;
+        if oid == oid::SHA256_WITH_RSA_ENCRYPTION {
+            cons.take_opt_null()?;
+            Ok(SignatureAlgorithm::RsaSha256)
+        }
+        else if oid == oid::RSASSA_PSS {
+            Self::take_pss_params(cons)?;
+            Ok(SignatureAlgorithm::RsaPssSha256)
+        }
+        else {
+            Err(decode::Malformed.into())
+        }
+    }
+
+    /// Takes the RSASSA-PSS parameters and checks them against our profile.
+    fn take_pss_params<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<(), S::Err> {
+        cons.take_sequence(|cons| {
+            // hashAlgorithm
+            cons.take_constructed_if(Tag::CTX_0, DigestAlgorithm::take_from)?;
+            // maskGenAlgorithm
+            cons.take_constructed_if(Tag::CTX_1, |cons| {
+                cons.take_sequence(|cons| {
+                    oid::MGF1.skip_if(cons)?;
+                    DigestAlgorithm::take_from(cons)
+                })
+            })?;
+            // saltLength
+            cons.take_constructed_if(Tag::CTX_2, |cons| cons.skip_u8_if(32))?;
+            // trailerField. In DER, the default value must not be present
+            // but we are lenient here.
+            cons.take_opt_constructed_if(Tag::CTX_3, |cons| {
+                cons.skip_u8_if(1)
+            })?;
+            Ok(())
+        })
     }
 
     /// Takes a signature algorithm identifier for CMS objects.
@@ -108,22 +196,137 @@ impl SignatureAlgorithm {
 
     /// Provides an encoder for X.509 objects.
     pub fn x509_encode(self) -> impl encode::Values {
-        encode::sequence((
-            oid::SHA256_WITH_RSA_ENCRYPTION.encode(),
-            ().encode(),
-        ))
+        match self {
+            SignatureAlgorithm::RsaSha256 => {
+                encode::Choice2::One(encode::sequence((
+                    oid::SHA256_WITH_RSA_ENCRYPTION.encode(),
+                    ().encode(),
+                )))
+            }
+            SignatureAlgorithm::RsaPssSha256 => {
+                encode::Choice2::Two(Self::pss_encode())
+            }
+        }
     }
 
     /// Provides an encoder for CMS objects.
     pub fn cms_encode(self) -> impl encode::Values {
+        match self {
+            SignatureAlgorithm::RsaSha256 => {
+                encode::Choice2::One(encode::sequence((
+                    oid::RSA_ENCRYPTION.encode(),
+                    ().encode(),
+                )))
+            }
+            SignatureAlgorithm::RsaPssSha256 => {
+                encode::Choice2::Two(Self::pss_encode())
+            }
+        }
+    }
+
+    /// Provides an encoder for the RSASSA-PSS algorithm identifier.
+    fn pss_encode() -> impl encode::Values {
         encode::sequence((
-            oid::RSA_ENCRYPTION.encode(),
-            ().encode(),
+            oid::RSASSA_PSS.encode(),
+            encode::sequence((
+                encode::sequence_as(Tag::CTX_0,
+                    DigestAlgorithm::default().encode()
+                ),
+                encode::sequence_as(Tag::CTX_1,
+                    encode::sequence((
+                        oid::MGF1.encode(),
+                        DigestAlgorithm::default().encode(),
+                    ))
+                ),
+                encode::sequence_as(Tag::CTX_2, 32u8.encode()),
+            ))
         ))
     }
 }
 
 
+//------------ AlgorithmPolicy -----------------------------------------------
+
+/// The set of signature algorithms accepted during validation.
+///
+/// By default, the policy permits only the algorithm mandated for RPKI by
+/// [RFC 7935], i.e., RSA PKCS #1 v1.5 with SHA-256. Operators with local
+/// requirements can start from [`rpki`], [`all`], or [`none`] and then
+/// [`allow`] or [`forbid`] individual algorithms.
+///
+/// [RFC 7935]: https://tools.ietf.org/html/rfc7935
+/// [`rpki`]: #method.rpki
+/// [`all`]: #method.all
+/// [`none`]: #method.none
+/// [`allow`]: #method.allow
+/// [`forbid`]: #method.forbid
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct AlgorithmPolicy {
+    /// Is RSA PKCS #1 v1.5 with SHA-256 permitted?
+    rsa_sha256: bool,
+
+    /// Is RSASSA-PSS with SHA-256 permitted?
+    rsa_pss_sha256: bool,
+}
+
+impl AlgorithmPolicy {
+    /// Creates a policy that permits exactly the algorithms allowed for RPKI.
+    pub fn rpki() -> Self {
+        AlgorithmPolicy {
+            rsa_sha256: SignatureAlgorithm::RsaSha256.is_rpki(),
+            rsa_pss_sha256: SignatureAlgorithm::RsaPssSha256.is_rpki(),
+        }
+    }
+
+    /// Creates a policy that permits all supported algorithms.
+    pub fn all() -> Self {
+        AlgorithmPolicy { rsa_sha256: true, rsa_pss_sha256: true }
+    }
+
+    /// Creates a policy that doesn’t permit any algorithm at all.
+    pub fn none() -> Self {
+        AlgorithmPolicy { rsa_sha256: false, rsa_pss_sha256: false }
+    }
+
+    /// Returns the policy with the given algorithm permitted.
+    pub fn allow(mut self, algorithm: SignatureAlgorithm) -> Self {
+        *self.flag_mut(algorithm) = true;
+        self
+    }
+
+    /// Returns the policy with the given algorithm not permitted.
+    pub fn forbid(mut self, algorithm: SignatureAlgorithm) -> Self {
+        *self.flag_mut(algorithm) = false;
+        self
+    }
+
+    /// Returns whether the policy permits the given algorithm.
+    pub fn permits(&self, algorithm: SignatureAlgorithm) -> bool {
+        match algorithm {
+            SignatureAlgorithm::RsaSha256 => self.rsa_sha256,
+            SignatureAlgorithm::RsaPssSha256 => self.rsa_pss_sha256,
+        }
+    }
+
+    /// Returns a mutable reference to the flag for the given algorithm.
+    fn flag_mut(&mut self, algorithm: SignatureAlgorithm) -> &mut bool {
+        match algorithm {
+            SignatureAlgorithm::RsaSha256 => &mut self.rsa_sha256,
+            SignatureAlgorithm::RsaPssSha256 => &mut self.rsa_pss_sha256,
+        }
+    }
+}
+
+
+//--- Default
+
+impl Default for AlgorithmPolicy {
+    fn default() -> Self {
+        AlgorithmPolicy::rpki()
+    }
+}
+
+
 //------------ Signature -----------------------------------------------------
 
 #[derive(Clone, Debug)]
@@ -150,3 +353,72 @@ impl Signature {
     }
 }
 
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use bcder::Mode;
+    use bcder::encode::Values;
+    use super::*;
+
+    #[test]
+    fn x509_round_trip() {
+        for alg in &[
+            SignatureAlgorithm::RsaSha256, SignatureAlgorithm::RsaPssSha256
+        ] {
+            let encoded = alg.x509_encode().to_captured(Mode::Der);
+            assert_eq!(
+                Mode::Der.decode(
+                    encoded.as_slice(), SignatureAlgorithm::x509_take_from
+                ).unwrap(),
+                *alg
+            );
+        }
+    }
+
+    #[test]
+    fn algorithm_policy() {
+        let rpki = AlgorithmPolicy::default();
+        assert_eq!(rpki, AlgorithmPolicy::rpki());
+        assert!(rpki.permits(SignatureAlgorithm::RsaSha256));
+        assert!(!rpki.permits(SignatureAlgorithm::RsaPssSha256));
+
+        let all = AlgorithmPolicy::all();
+        assert!(all.permits(SignatureAlgorithm::RsaSha256));
+        assert!(all.permits(SignatureAlgorithm::RsaPssSha256));
+        assert_eq!(
+            rpki.allow(SignatureAlgorithm::RsaPssSha256), all
+        );
+
+        let none = AlgorithmPolicy::none();
+        assert!(!none.permits(SignatureAlgorithm::RsaSha256));
+        assert!(!none.permits(SignatureAlgorithm::RsaPssSha256));
+        assert_eq!(rpki.forbid(SignatureAlgorithm::RsaSha256), none);
+    }
+
+    #[test]
+    fn pss_bad_params() {
+        // id-RSASSA-PSS with a salt length of 20.
+        let data = b"\x30\x41\x06\x09\x2a\x86\x48\x86\xf7\x0d\x01\x01\
+            \x0a\x30\x34\xa0\x0f\x30\x0d\x06\x09\x60\x86\x48\
+            \x01\x65\x03\x04\x02\x01\x05\x00\xa1\x1c\x30\x1a\
+            \x06\x09\x2a\x86\x48\x86\xf7\x0d\x01\x01\x08\x30\
+            \x0d\x06\x09\x60\x86\x48\x01\x65\x03\x04\x02\x01\
+            \x05\x00\xa2\x03\x02\x01\x14";
+        assert!(
+            Mode::Der.decode(
+                data.as_ref(), SignatureAlgorithm::x509_take_from
+            ).is_err()
+        );
+
+        // id-RSASSA-PSS with all default parameters, i.e., SHA-1.
+        let data = b"\x30\x0d\x06\x09\x2a\x86\x48\x86\xf7\x0d\x01\x01\
+            \x0a\x30\x00";
+        assert!(
+            Mode::Der.decode(
+                data.as_ref(), SignatureAlgorithm::x509_take_from
+            ).is_err()
+        );
+    }
+}
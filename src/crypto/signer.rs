@@ -8,6 +8,12 @@ use super::signature::{Signature, SignatureAlgorithm};
 //------------ Signer --------------------------------------------------------
 
 /// A type that allow creating signatures.
+///
+/// All types that create signed objects take a signer as a generic `&S`
+/// with `S: Signer + ?Sized`. The trait is object safe, so this can also
+/// be a trait object such as `&dyn Signer<KeyId = _, Error = _>`, allowing
+/// the concrete signer – be it software keys or an HSM – to be chosen at
+/// run time.
 pub trait Signer {
     /// The type used for identifying keys.
     type KeyId;
@@ -38,21 +44,21 @@ pub trait Signer {
     ) -> Result<(), KeyError<Self::Error>>;
 
     /// Signs data.
-    fn sign<D: AsRef<[u8]> + ?Sized>(
+    fn sign(
         &self,
         key: &Self::KeyId,
         algorithm: SignatureAlgorithm,
-        data: &D
+        data: &[u8]
     ) -> Result<Signature, SigningError<Self::Error>>;
 
     /// Signs data using a one time use keypair.
     ///
     /// Returns both the signature and the public key of the key pair,
     /// but will not store this key pair.
-    fn sign_one_off<D: AsRef<[u8]> + ?Sized>(
+    fn sign_one_off(
         &self,
         algorithm: SignatureAlgorithm,
-        data: &D
+        data: &[u8]
     ) -> Result<(Signature, PublicKey), Self::Error>;
 
     /// Creates random data.
@@ -132,3 +138,179 @@ impl<S: fmt::Display> fmt::Display for SigningError<S> {
     }
 }
 
+
+
+//============ Testing =======================================================
+
+/// A signer for use in tests.
+///
+/// The signer doesn’t need any crypto library. All its keys share the
+/// public key of the test trust anchor certificate and the signatures are
+/// merely a SHA-256 digest over the key and the data. They cannot be
+/// verified with the public key but only via [`MockSigner::verify`].
+///
+/// [`MockSigner::verify`]: struct.MockSigner.html#method.verify
+#[cfg(test)]
+pub mod mock {
+    use std::convert::Infallible;
+    use bytes::Bytes;
+    use crate::cert::Cert;
+    use crate::crypto::keys::{PublicKey, PublicKeyFormat};
+    use crate::crypto::signature::{Signature, SignatureAlgorithm};
+    use super::{KeyError, Signer, SigningError};
+
+    /// A signer creating fake signatures.
+    #[derive(Clone, Debug, Default)]
+    pub struct MockSigner {
+        /// Whether the key with the index has not been destroyed yet.
+        keys: Vec<bool>,
+    }
+
+    impl MockSigner {
+        /// Creates a new signer without any keys.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Returns whether `signature` was created over `data` by `key`.
+        pub fn verify(
+            &self, key: &usize, data: &[u8], signature: &Signature
+        ) -> bool {
+            signature.value().as_ref() == Self::digest(*key, data).as_ref()
+        }
+
+        fn public_key() -> PublicKey {
+            Cert::decode(
+                include_bytes!("../../test-data/ta.cer").as_ref()
+            ).unwrap().subject_public_key_info().clone()
+        }
+
+        fn digest(key: usize, data: &[u8]) -> Bytes {
+            let mut context = ring::digest::Context::new(
+                &ring::digest::SHA256
+            );
+            context.update(&(key as u64).to_be_bytes());
+            context.update(data);
+            Bytes::copy_from_slice(context.finish().as_ref())
+        }
+    }
+
+    impl Signer for MockSigner {
+        type KeyId = usize;
+        type Error = Infallible;
+
+        fn create_key(
+            &mut self,
+            _algorithm: PublicKeyFormat
+        ) -> Result<Self::KeyId, Self::Error> {
+            self.keys.push(true);
+            Ok(self.keys.len() - 1)
+        }
+
+        fn get_key_info(
+            &self,
+            key: &Self::KeyId
+        ) -> Result<PublicKey, KeyError<Self::Error>> {
+            if self.keys.get(*key).cloned().unwrap_or(false) {
+                Ok(Self::public_key())
+            }
+            else {
+                Err(KeyError::KeyNotFound)
+            }
+        }
+
+        fn destroy_key(
+            &mut self,
+            key: &Self::KeyId
+        ) -> Result<(), KeyError<Self::Error>> {
+            match self.keys.get_mut(*key) {
+                Some(item) if *item => {
+                    *item = false;
+                    Ok(())
+                }
+                _ => Err(KeyError::KeyNotFound)
+            }
+        }
+
+        fn sign(
+            &self,
+            key: &Self::KeyId,
+            algorithm: SignatureAlgorithm,
+            data: &[u8]
+        ) -> Result<Signature, SigningError<Self::Error>> {
+            self.get_key_info(key)?;
+            Ok(Signature::new(algorithm, Self::digest(*key, data)))
+        }
+
+        fn sign_one_off(
+            &self,
+            algorithm: SignatureAlgorithm,
+            data: &[u8]
+        ) -> Result<(Signature, PublicKey), Self::Error> {
+            let digest = Self::digest(usize::max_value(), data);
+            Ok((Signature::new(algorithm, digest), Self::public_key()))
+        }
+
+        fn rand(&self, target: &mut [u8]) -> Result<(), Self::Error> {
+            for (i, item) in target.iter_mut().enumerate() {
+                *item = i as u8 ^ 0x5a;
+            }
+            Ok(())
+        }
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use crate::crl::{CrlEntry, TbsCertList};
+    use crate::crypto::keys::{KeyIdentifier, PublicKeyFormat};
+    use crate::crypto::signature::SignatureAlgorithm;
+    use crate::x509::Time;
+    use super::*;
+    use super::mock::MockSigner;
+
+    #[test]
+    fn boxed_signer() {
+        let mut signer: Box<
+            dyn Signer<KeyId = usize, Error = std::convert::Infallible>
+        > = Box::new(MockSigner::new());
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let signature = signer.sign(
+            &key, SignatureAlgorithm::default(), b"foobar"
+        ).unwrap();
+        assert_eq!(signature.algorithm(), SignatureAlgorithm::default());
+
+        // Builders accept the signer as a trait object.
+        let crl = TbsCertList::new(
+            Default::default(),
+            pubkey.to_subject_name(),
+            Time::now(),
+            Time::tomorrow(),
+            vec![CrlEntry::new(12u64.into(), Time::now())],
+            KeyIdentifier::from_public_key(&pubkey),
+            12u64.into()
+        ).into_crl(signer.as_ref(), &key).unwrap();
+        assert!(crl.contains(12u64.into()));
+
+        signer.destroy_key(&key).unwrap();
+        assert!(signer.get_key_info(&key).is_err());
+        assert!(signer.destroy_key(&key).is_err());
+    }
+
+    #[test]
+    fn mock_signer() {
+        let mut signer = MockSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let other = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let signature = signer.sign(
+            &key, SignatureAlgorithm::default(), b"foobar"
+        ).unwrap();
+        assert!(signer.verify(&key, b"foobar", &signature));
+        assert!(!signer.verify(&key, b"barfoo", &signature));
+        assert!(!signer.verify(&other, b"foobar", &signature));
+    }
+}
@@ -5,14 +5,20 @@
 //! software keys to sign things, such as an RPKI Certificate Authority or
 //! Publication Server. In particular, this is not required when validating.
 
-use std::io;
+use std::{fs, io};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use bcder::{encode, Mode, OctetString};
+use bcder::encode::{PrimitiveContent, Values};
 use openssl::rsa::Rsa;
 use openssl::pkey::{PKey, Private};
 use openssl::hash::MessageDigest;
+use openssl::rsa::Padding;
+use openssl::sign::RsaPssSaltlen;
 use ring::rand;
 use ring::rand::SecureRandom;
-use slab::Slab;
-use super::keys::{PublicKey, PublicKeyFormat};
+use crate::oid;
+use super::keys::{KeyIdentifier, PublicKey, PublicKeyFormat};
 use super::signature::{Signature, SignatureAlgorithm};
 use super::signer::{KeyError, Signer, SigningError};
 
@@ -22,26 +28,85 @@ use super::signer::{KeyError, Signer, SigningError};
 
 /// An OpenSSL based signer.
 ///
-/// Keeps the keys in memory (for now).
+/// Keeps the keys in memory. If the signer was created via [`open`], all
+/// keys are additionally stored in a directory, one file per key, so that
+/// they survive a restart.
+///
+/// [`open`]: #method.open
 pub struct OpenSslSigner {
-    keys: Slab<KeyPair>,
+    keys: HashMap<KeyId, KeyPair>,
+    key_dir: Option<PathBuf>,
     rng: rand::SystemRandom,
 }
 
 impl OpenSslSigner {
+    /// Creates a new signer keeping its keys in memory only.
     pub fn new() -> OpenSslSigner {
         OpenSslSigner {
-            keys: Slab::new(),
+            keys: HashMap::new(),
+            key_dir: None,
             rng: rand::SystemRandom::new(),
         }
     }
 
+    /// Creates a signer storing its keys in the given directory.
+    ///
+    /// The directory is created if it doesn’t exist yet. All keys already
+    /// present in it are loaded. Each key is kept in a file named after
+    /// the hex-encoded key identifier and contains the private key in
+    /// PKCS #8 DER encoding. Other files are ignored.
+    ///
+    /// On Unix, the directory and key files are made accessible by the
+    /// owner only. Key files are written to a temporary file first and
+    /// then moved into place, so an interrupted write never leaves a
+    /// truncated key file behind.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<OpenSslSigner, io::Error> {
+        let dir = dir.as_ref();
+        create_key_dir(dir)?;
+        let mut keys = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let id = match entry.file_name().to_str().and_then(|name| {
+                name.parse::<KeyIdentifier>().ok()
+            }) {
+                Some(id) => KeyId(id),
+                None => continue
+            };
+            let key = KeyPair::from_der(&fs::read(entry.path())?)?;
+            if key.key_id()? != id {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "key file {} contains a different key",
+                        entry.path().display()
+                    )
+                ))
+            }
+            keys.insert(id, key);
+        }
+        Ok(OpenSslSigner {
+            keys,
+            key_dir: Some(dir.into()),
+            rng: rand::SystemRandom::new(),
+        })
+    }
+
     pub fn key_from_der(&mut self, der: &[u8]) -> Result<KeyId, io::Error> {
-        Ok(KeyId(self.keys.insert(KeyPair::from_der(der)?)))
+        self.insert_key(KeyPair::from_der(der)?)
     }
 
     pub fn key_from_pem(&mut self, pem: &[u8]) -> Result<KeyId, io::Error> {
-        Ok(KeyId(self.keys.insert(KeyPair::from_pem(pem)?)))
+        self.insert_key(KeyPair::from_pem(pem)?)
+    }
+
+    /// Adds a key to the signer, storing it if there is a key directory.
+    fn insert_key(&mut self, key: KeyPair) -> Result<KeyId, io::Error> {
+        let id = key.key_id()?;
+        if let Some(ref dir) = self.key_dir {
+            write_key_file(dir, id, &key.to_pkcs8()?)?;
+        }
+        self.keys.insert(id, key);
+        Ok(id)
     }
 }
 
@@ -52,14 +117,14 @@ impl Signer for OpenSslSigner {
     fn create_key(
         &mut self, algorithm: PublicKeyFormat
     ) -> Result<Self::KeyId, Self::Error> {
-        Ok(KeyId(self.keys.insert(KeyPair::new(algorithm)?)))
+        self.insert_key(KeyPair::new(algorithm)?)
     }
 
     fn get_key_info(
         &self,
         id: &Self::KeyId
     ) -> Result<PublicKey, KeyError<Self::Error>> {
-        match self.keys.get(id.0) {
+        match self.keys.get(id) {
             Some(key) => {
                 key.get_key_info().map_err(KeyError::Signer)
             }
@@ -70,35 +135,43 @@ impl Signer for OpenSslSigner {
     fn destroy_key(
         &mut self, key: &Self::KeyId
     ) -> Result<(), KeyError<Self::Error>> {
-        if self.keys.contains(key.0) {
-            self.keys.remove(key.0);
-            Ok(())
+        if !self.keys.contains_key(key) {
+            return Err(KeyError::KeyNotFound)
         }
-        else {
-            Err(KeyError::KeyNotFound)
+
+        // Remove the file first so the key doesn’t come back on the next
+        // open if that fails.
+        if let Some(ref dir) = self.key_dir {
+            match fs::remove_file(key.key_file(dir)) {
+                Ok(()) => { }
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => { }
+                Err(err) => return Err(KeyError::Signer(err))
+            }
         }
+        self.keys.remove(key);
+        Ok(())
     }
 
-    fn sign<D: AsRef<[u8]> + ?Sized>(
+    fn sign(
         &self,
         key: &Self::KeyId,
         algorithm: SignatureAlgorithm,
-        data: &D
+        data: &[u8]
     ) -> Result<Signature, SigningError<Self::Error>> {
-        match self.keys.get(key.0) {
-            Some(key) => key.sign(algorithm, data.as_ref()).map_err(Into::into),
+        match self.keys.get(key) {
+            Some(key) => key.sign(algorithm, data).map_err(Into::into),
             None => Err(SigningError::KeyNotFound)
         }
     }
 
-    fn sign_one_off<D: AsRef<[u8]> + ?Sized>(
+    fn sign_one_off(
         &self,
         algorithm: SignatureAlgorithm,
-        data: &D
+        data: &[u8]
     ) -> Result<(Signature, PublicKey), Self::Error> {
         let key = KeyPair::new(algorithm.public_key_format())?;
         let info = key.get_key_info()?;
-        let sig = key.sign(algorithm, data.as_ref())?;
+        let sig = key.sign(algorithm, data)?;
         Ok((sig, info))
     }
 
@@ -120,11 +193,26 @@ impl Default for OpenSslSigner {
 //------------ KeyId ---------------------------------------------------------
 
 /// This signer’s key identifier.
+///
+/// The identifier is derived from the key identifier of the public key and
+/// therefore stays the same if the key is loaded again later.
 //
-//  We wrap this in a newtype so that people won’t start mucking about with
-//  the integers.
-#[derive(Clone, Copy, Debug)]
-pub struct KeyId(usize);
+//  We wrap this in a newtype so that people won’t start mixing it up with
+//  key identifiers of keys this signer doesn’t know.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct KeyId(KeyIdentifier);
+
+impl KeyId {
+    /// Returns the key identifier of the key’s public key.
+    pub fn key_identifier(self) -> KeyIdentifier {
+        self.0
+    }
+
+    /// Returns the path of the file for the key in the given directory.
+    fn key_file(self, dir: &Path) -> PathBuf {
+        dir.join(self.0.to_string())
+    }
+}
 
 
 //------------ KeyPair -------------------------------------------------------
@@ -171,23 +259,115 @@ impl KeyPair {
         Ok(PublicKey::decode(der.as_ref()).unwrap())
     }
 
+    fn key_id(&self) -> Result<KeyId, io::Error> {
+        Ok(KeyId(self.get_key_info()?.key_identifier()))
+    }
+
+    /// Returns the private key encoded as a PKCS #8 PrivateKeyInfo.
+    ///
+    /// ```txt
+    /// PrivateKeyInfo ::= SEQUENCE {
+    ///     version                   INTEGER,
+    ///     privateKeyAlgorithm       AlgorithmIdentifier,
+    ///     privateKey                OCTET STRING }
+    /// ```
+    ///
+    /// The private key is the PKCS #1 RSAPrivateKey.
+    fn to_pkcs8(&self) -> Result<Vec<u8>, io::Error> {
+        let key = self.0.rsa()?.private_key_to_der()?;
+        let mut res = Vec::new();
+        encode::sequence((
+            0u8.encode(),
+            encode::sequence((
+                oid::RSA_ENCRYPTION.encode(),
+                ().encode(),
+            )),
+            OctetString::encode_slice(key.as_slice()),
+        )).write_encoded(Mode::Der, &mut res)?;
+        Ok(res)
+    }
+
     fn sign(
         &self,
-        _algorithm: SignatureAlgorithm,
+        algorithm: SignatureAlgorithm,
         data: &[u8]
     ) -> Result<Signature, io::Error> {
         let mut signer = ::openssl::sign::Signer::new(
             MessageDigest::sha256(), &self.0
         )?;
+        if let SignatureAlgorithm::RsaPssSha256 = algorithm {
+            signer.set_rsa_padding(Padding::PKCS1_PSS)?;
+            signer.set_rsa_mgf1_md(MessageDigest::sha256())?;
+            signer.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+        }
         signer.update(data)?;
         Ok(Signature::new(
-            SignatureAlgorithm::default(),
+            algorithm,
             signer.sign_to_vec()?.into()
         ))
     }
 }
 
 
+//------------ Helper Functions ----------------------------------------------
+
+/// Creates the key directory if necessary.
+///
+/// On Unix, the directory is made accessible by the owner only, even if it
+/// existed already.
+fn create_key_dir(dir: &Path) -> Result<(), io::Error> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+
+        builder.mode(0o700);
+    }
+    builder.create(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Atomically writes the file for a key readable by the owner only.
+///
+/// The content is written to a new temporary file which is then renamed
+/// to the key file, replacing an existing file including its permissions.
+/// The name of the temporary file doesn’t parse as a key identifier, so a
+/// left-over file is ignored when opening the directory.
+fn write_key_file(
+    dir: &Path,
+    id: KeyId,
+    content: &[u8]
+) -> Result<(), io::Error> {
+    use std::io::Write;
+
+    let tmp = dir.join(format!(".{}.tmp", id.0));
+    match fs::remove_file(&tmp) {
+        Ok(()) => { }
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => { }
+        Err(err) => return Err(err)
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&tmp, id.key_file(dir))
+}
+
+
 //------------ Tests ---------------------------------------------------------
 
 #[cfg(test)]
@@ -210,5 +390,88 @@ pub mod tests {
         let s = OpenSslSigner::new();
         s.sign_one_off(SignatureAlgorithm::default(), b"foobar").unwrap();
     }
+
+    #[test]
+    fn sign_verify() {
+        let mut s = OpenSslSigner::new();
+        let ki = s.create_key(PublicKeyFormat::default()).unwrap();
+        let key = s.get_key_info(&ki).unwrap();
+        for alg in &[
+            SignatureAlgorithm::RsaSha256, SignatureAlgorithm::RsaPssSha256
+        ] {
+            let sig = s.sign(&ki, *alg, b"foobar").unwrap();
+            assert_eq!(sig.algorithm(), *alg);
+            key.verify(b"foobar", &sig).unwrap();
+            assert!(key.verify(b"barfoo", &sig).is_err());
+        }
+    }
+
+    #[test]
+    fn key_dir() {
+        use crate::cert::{Cert, KeyUsage};
+        use crate::util::test::make_cert;
+        use crate::x509::SignedData;
+
+        let dir = std::env::temp_dir().join(
+            format!("rpki-softsigner-test-{}", std::process::id())
+        );
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut s = OpenSslSigner::open(&dir).unwrap();
+        let ki = s.create_key(PublicKeyFormat::default()).unwrap();
+        let key = s.get_key_info(&ki).unwrap();
+        assert_eq!(ki.key_identifier(), key.key_identifier());
+        let path = ki.key_file(&dir);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            assert_eq!(
+                fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+                0o600
+            );
+        }
+        drop(s);
+
+        // Permissions are tightened even if the directory or a key file
+        // existed already.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let der = Rsa::generate(2048).unwrap()
+                .private_key_to_der().unwrap();
+            let mode = |path: &Path| {
+                fs::metadata(path).unwrap().permissions().mode() & 0o777
+            };
+            fs::set_permissions(
+                &dir, fs::Permissions::from_mode(0o755)
+            ).unwrap();
+            let mut s = OpenSslSigner::open(&dir).unwrap();
+            assert_eq!(mode(&dir), 0o700);
+            let other = s.key_from_der(&der).unwrap();
+            let other_path = other.key_file(&dir);
+            fs::set_permissions(
+                &other_path, fs::Permissions::from_mode(0o644)
+            ).unwrap();
+            assert_eq!(s.key_from_der(&der).unwrap(), other);
+            assert_eq!(mode(&other_path), 0o600);
+            s.destroy_key(&other).unwrap();
+            assert!(!other_path.exists());
+        }
+
+        // Load the key again and build a self-signed certificate with it.
+        let mut s = OpenSslSigner::open(&dir).unwrap();
+        assert_eq!(s.get_key_info(&ki).unwrap(), key);
+        let cert = make_cert(key.clone(), None, KeyUsage::Ca);
+        let cert = cert.into_cert(&s, &ki).unwrap().to_captured();
+        SignedData::decode(cert.as_slice()).unwrap()
+            .verify_signature(&key).unwrap();
+        assert!(Cert::decode(cert.as_slice()).unwrap().is_self_signed());
+
+        s.destroy_key(&ki).unwrap();
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
 
@@ -131,6 +131,24 @@ impl Csr {
     pub fn validate(&self) -> Result<(), ValidationError> {
         self.signed_data.verify_signature(self.public_key())
     }
+
+    /// Validates the CSR as a request for an RPKI CA certificate.
+    ///
+    /// In addition to checking the signature, this checks that a CA
+    /// certificate is requested and that the caRepository and rpkiManifest
+    /// SIA values required by section 6 of RFC 6487 are present. A parent
+    /// should do this before issuing a certificate in response to an
+    /// issue request of the provisioning protocol.
+    pub fn validate_rpki_ca(&self) -> Result<(), ValidationError> {
+        self.validate()?;
+        if !self.basic_ca() || self.key_usage() != KeyUsage::Ca {
+            return Err(ValidationError)
+        }
+        if self.ca_repository().is_none() || self.rpki_manifest().is_none() {
+            return Err(ValidationError)
+        }
+        Ok(())
+    }
 }
 
 /// # Encoding
@@ -158,7 +176,7 @@ impl Csr {
     /// required SIA entries for 'id-ad-caRepository' and
     /// 'id-ad-rpkiManifest' (see RFC6487), and the optional entry for
     /// 'id-ad-rpkiNotify' (see RFC8182), need to be specified.
-    pub fn construct<S: Signer>(
+    pub fn construct<S: Signer + ?Sized>(
         signer: &S,
         key: &S::KeyId,
         ca_repository: &uri::Rsync,
@@ -209,7 +227,7 @@ impl Csr {
         let (alg, signature) = signer.sign(
             key,
             SignatureAlgorithm::default(),
-            &content
+            content.as_slice()
         )?.unwrap();
 
         Ok(Captured::from_values(Mode::Der,
@@ -355,7 +373,6 @@ mod test {
         uri::Rsync::from_str(s).unwrap()
     }
 
-    #[allow(dead_code)]
     fn https(s: &str) -> uri::Https {
         uri::Https::from_str(s).unwrap()
     }
@@ -367,6 +384,7 @@ mod test {
         let csr = Csr::decode(bytes.as_ref()).unwrap();
 
         csr.validate().unwrap();
+        csr.validate_rpki_ca().unwrap();
 
         assert!(csr.basic_ca());
 
@@ -379,6 +397,16 @@ mod test {
         assert_eq!(None, csr.rpki_notify());
     }
 
+    #[test]
+    fn bad_signature() {
+        // drl-csr.der with the last octet of the signature changed.
+        let csr = Csr::decode(
+            include_bytes!("../test-data/drl-csr-bad-signature.der").as_ref()
+        ).unwrap();
+        assert!(csr.validate().is_err());
+        assert!(csr.validate_rpki_ca().is_err());
+    }
+
     #[test]
     #[cfg(all(test, feature="softkeys"))]
     fn build_csr() {
@@ -404,6 +432,7 @@ mod test {
 
         let csr = Csr::decode(enc.as_slice()).unwrap();
         csr.validate().unwrap();
+        csr.validate_rpki_ca().unwrap();
 
         let pub_key = signer.get_key_info(&key).unwrap();
 
@@ -414,6 +443,71 @@ mod test {
         assert_eq!(Some(&rpki_not), csr.rpki_notify());
     }
 
+    /// Returns the encoded attributes of a CSR.
+    fn attributes(csr: &Csr) -> Captured {
+        csr.signed_data.data().clone().decode(|cons| {
+            cons.take_sequence(|cons| {
+                cons.skip_u8_if(0)?;
+                Name::take_from(cons)?;
+                PublicKey::take_from(cons)?;
+                cons.capture_all()
+            })
+        }).unwrap()
+    }
+
+    #[test]
+    fn parse_openssl_csr() {
+        // Created with OpenSSL 3 using the extensions from build_csr.
+        let csr = Csr::decode(
+            include_bytes!("../test-data/openssl-csr.der").as_ref()
+        ).unwrap();
+        csr.validate().unwrap();
+        csr.validate_rpki_ca().unwrap();
+        assert!(csr.basic_ca());
+        assert_eq!(csr.key_usage(), KeyUsage::Ca);
+        assert_eq!(
+            Some(&rsync("rsync://localhost/repo/")), csr.ca_repository()
+        );
+        assert_eq!(
+            Some(&rsync("rsync://localhost/repo/ca.mft")),
+            csr.rpki_manifest()
+        );
+        assert_eq!(
+            Some(&https("https://localhost/repo/notify.xml")),
+            csr.rpki_notify()
+        );
+    }
+
+    #[test]
+    #[cfg(all(test, feature="softkeys"))]
+    fn build_csr_matches_openssl() {
+        use crate::crypto::softsigner::OpenSslSigner;
+        use crate::crypto::PublicKeyFormat;
+
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+
+        let enc = Csr::construct(
+            &signer,
+            &key,
+            &rsync("rsync://localhost/repo/"),
+            &rsync("rsync://localhost/repo/ca.mft"),
+            Some(&https("https://localhost/repo/notify.xml"))
+        ).unwrap();
+        let csr = Csr::decode(enc.as_slice()).unwrap();
+
+        // Round trip through our own encoder.
+        assert_eq!(csr.to_captured().as_slice(), enc.as_slice());
+
+        // The extension request must be encoded exactly as OpenSSL does.
+        let openssl = Csr::decode(
+            include_bytes!("../test-data/openssl-csr.der").as_ref()
+        ).unwrap();
+        assert_eq!(
+            attributes(&csr).as_slice(), attributes(&openssl).as_slice()
+        );
+    }
+
     #[test]
     fn serde_csr() {
         let bytes = include_bytes!("../test-data/drl-csr.der");
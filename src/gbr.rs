@@ -0,0 +1,478 @@
+//! Ghostbusters records.
+//!
+//! Ghostbusters records provide contact information for the operators of
+//! an RPKI CA in the form of a vCard. They are defined in [RFC 6493].
+//!
+//! This module defines the type [`Gbr`] that represents a decoded
+//! Ghostbusters record and the type [`VCard`] for its content.
+//!
+//! [RFC 6493]: https://tools.ietf.org/html/rfc6493
+//! [`Gbr`]: struct.Gbr.html
+//! [`VCard`]: struct.VCard.html
+
+use std::{error, fmt, str};
+use bcder::{decode, encode};
+use bcder::{Captured, Mode, xerr};
+use bytes::Bytes;
+use crate::oid;
+use crate::cert::{Cert, ResourceCert};
+use crate::sigobj::{AsSignedObject, SignedObject, SignedObjectError};
+use crate::x509::Time;
+
+
+//------------ Gbr -----------------------------------------------------------
+
+/// A decoded Ghostbusters record.
+#[derive(Clone, Debug)]
+pub struct Gbr {
+    signed: SignedObject,
+    content: VCard,
+}
+
+impl Gbr {
+    /// Decodes a Ghostbusters record from a source.
+    ///
+    /// The vCard is parsed, too. In strict mode, it has to follow the
+    /// restrictions of RFC 6493. See [`VCard::parse`] for details.
+    ///
+    /// [`VCard::parse`]: struct.VCard.html#method.parse
+    pub fn decode<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        let signed = SignedObject::decode_if_type(
+            source, &oid::CT_RPKI_GHOSTBUSTERS, strict
+        )?;
+        let content = match VCard::parse(
+            signed.content().to_bytes(), strict
+        ) {
+            Ok(content) => content,
+            Err(_) => xerr!(return Err(decode::Malformed.into()))
+        };
+        Ok(Gbr { signed, content })
+    }
+
+    /// Decodes a Ghostbusters record reporting why decoding failed.
+    ///
+    /// See [`SignedObject::decode_detailed`] for details.
+    ///
+    /// [`SignedObject::decode_detailed`]: ../sigobj/struct.SignedObject.html#method.decode_detailed
+    pub fn decode_detailed<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, SignedObjectError> {
+        let signed = SignedObject::decode_detailed(
+            source, &oid::CT_RPKI_GHOSTBUSTERS, strict
+        )?;
+        let content = VCard::parse(
+            signed.content().to_bytes(), strict
+        ).map_err(|_| SignedObjectError::MalformedContent)?;
+        Ok(Gbr { signed, content })
+    }
+
+    /// Validates the Ghostbusters record.
+    ///
+    /// You need to pass in the certificate of the issuing CA. If validation
+    /// succeeds, the result will be the EE certificate of the record and
+    /// its vCard.
+    pub fn validate(
+        self,
+        cert: &ResourceCert,
+        strict: bool,
+    ) -> Result<(ResourceCert, VCard), SignedObjectError> {
+        self.validate_at(cert, strict, Time::now())
+    }
+
+    /// Validates the Ghostbusters record at the given time.
+    pub fn validate_at(
+        self,
+        cert: &ResourceCert,
+        strict: bool,
+        now: Time
+    ) -> Result<(ResourceCert, VCard), SignedObjectError> {
+        let cert = self.signed.validate_at(cert, strict, now)?;
+        Ok((cert, self.content))
+    }
+
+    /// Returns a value encoder for a reference to the record.
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        self.signed.encode_ref()
+    }
+
+    /// Returns a DER encoded Captured for this.
+    pub fn to_captured(&self) -> Captured {
+        Captured::from_values(Mode::Der, self.encode_ref())
+    }
+
+    /// Returns a reference to the EE certificate of this record.
+    pub fn cert(&self) -> &Cert {
+        self.signed.cert()
+    }
+
+    /// Returns a reference to the vCard of this record.
+    pub fn content(&self) -> &VCard {
+        &self.content
+    }
+}
+
+
+//--- AsSignedObject
+
+impl AsSignedObject for Gbr {
+    fn signed_object(&self) -> &SignedObject {
+        &self.signed
+    }
+}
+
+
+//------------ VCard ---------------------------------------------------------
+
+/// The vCard of a Ghostbusters record.
+///
+/// RFC 6493 restricts the vCard to a small number of properties. Of these,
+/// the formatted name and the address, telephone, and email properties are
+/// available through this type. The complete vCard is available as raw
+/// bytes via [`as_bytes`].
+///
+/// The values of the formatted name and email properties are unescaped.
+/// The address and telephone properties are structured or URI values,
+/// respectively, and are provided as they appear in the vCard.
+///
+/// [`as_bytes`]: #method.as_bytes
+#[derive(Clone, Debug)]
+pub struct VCard {
+    raw: Bytes,
+    full_name: Option<String>,
+    addresses: Vec<String>,
+    telephones: Vec<String>,
+    emails: Vec<String>,
+}
+
+impl VCard {
+    /// Parses a vCard.
+    ///
+    /// The vCard must be UTF-8 encoded and start with `BEGIN:VCARD` and end
+    /// with `END:VCARD`. Folded lines are unfolded before parsing.
+    ///
+    /// In strict mode, the vCard must additionally follow the profile of
+    /// RFC 6493: it must have version 4.0, must contain the formatted name
+    /// property FN and at least one of the ADR, TEL, or EMAIL properties,
+    /// and must not contain any properties other than those and N and ORG.
+    pub fn parse(raw: Bytes, strict: bool) -> Result<Self, VCardError> {
+        let mut res = VCard {
+            raw: raw.clone(),
+            full_name: None,
+            addresses: Vec::new(),
+            telephones: Vec::new(),
+            emails: Vec::new(),
+        };
+        let mut version = None;
+        let mut lines = unfold(
+            str::from_utf8(raw.as_ref()).map_err(|_| VCardError)?
+        ).into_iter();
+
+        match lines.next() {
+            Some(ref line) if is_marker(line, "BEGIN") => { }
+            _ => return Err(VCardError)
+        }
+        let mut end = false;
+        for line in lines {
+            if end {
+                // Nothing may follow END:VCARD.
+                return Err(VCardError)
+            }
+            let (name, value) = split_property(&line)?;
+            match name.as_str() {
+                "END" => {
+                    if !value.eq_ignore_ascii_case("VCARD") {
+                        return Err(VCardError)
+                    }
+                    end = true;
+                }
+                "VERSION" => version = Some(value.to_string()),
+                "FN" => {
+                    if res.full_name.is_none() {
+                        res.full_name = Some(unescape(value))
+                    }
+                }
+                "ADR" => res.addresses.push(value.to_string()),
+                "TEL" => res.telephones.push(value.to_string()),
+                "EMAIL" => res.emails.push(unescape(value)),
+                "N" | "ORG" => { }
+                _ => {
+                    if strict {
+                        return Err(VCardError)
+                    }
+                }
+            }
+        }
+        if !end {
+            return Err(VCardError)
+        }
+        if strict {
+            if version.as_deref() != Some("4.0") || res.full_name.is_none() {
+                return Err(VCardError)
+            }
+            if res.addresses.is_empty() && res.telephones.is_empty()
+                && res.emails.is_empty()
+            {
+                return Err(VCardError)
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns the raw bytes of the vCard.
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.raw
+    }
+
+    /// Returns the formatted name if present.
+    ///
+    /// The formatted name is only optional in lenient mode.
+    pub fn full_name(&self) -> Option<&str> {
+        self.full_name.as_deref()
+    }
+
+    /// Returns the values of the address properties.
+    pub fn addresses(&self) -> &[String] {
+        &self.addresses
+    }
+
+    /// Returns the values of the telephone properties.
+    pub fn telephones(&self) -> &[String] {
+        &self.telephones
+    }
+
+    /// Returns the values of the email properties.
+    pub fn emails(&self) -> &[String] {
+        &self.emails
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Unfolds the lines of a vCard.
+///
+/// Lines are separated by CRLF or, leniently, just LF. A line starting with
+/// a space or horizontal tab is a continuation of the previous line. Empty
+/// lines are dropped.
+fn unfold(data: &str) -> Vec<String> {
+    let mut res: Vec<String> = Vec::new();
+    for line in data.split('\n') {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(last) = res.last_mut() {
+                last.push_str(&line[1..]);
+                continue;
+            }
+        }
+        if !line.is_empty() {
+            res.push(line.to_string())
+        }
+    }
+    res
+}
+
+/// Splits a content line into the upper-cased property name and the value.
+///
+/// Parameters and a group prefix of the name are dropped.
+fn split_property(line: &str) -> Result<(String, &str), VCardError> {
+    // The value starts after the first colon not inside a quoted parameter
+    // value.
+    let mut quoted = false;
+    let mut colon = None;
+    for (pos, ch) in line.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            ':' if !quoted => {
+                colon = Some(pos);
+                break
+            }
+            _ => { }
+        }
+    }
+    let colon = colon.ok_or(VCardError)?;
+    let name = &line[..colon];
+    let name = match name.find(';') {
+        Some(pos) => &name[..pos],
+        None => name
+    };
+    let name = match name.rfind('.') {
+        Some(pos) => &name[pos + 1..],
+        None => name
+    };
+    if name.is_empty() {
+        return Err(VCardError)
+    }
+    Ok((name.to_ascii_uppercase(), &line[colon + 1..]))
+}
+
+/// Returns whether a line is `BEGIN:VCARD` or `END:VCARD`.
+fn is_marker(line: &str, marker: &str) -> bool {
+    match split_property(line) {
+        Ok((name, value)) => {
+            name == marker && value.eq_ignore_ascii_case("VCARD")
+        }
+        Err(_) => false
+    }
+}
+
+/// Unescapes a vCard text value.
+fn unescape(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            res.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => res.push('\n'),
+            Some(ch) => res.push(ch),
+            None => res.push('\\'),
+        }
+    }
+    res
+}
+
+
+//------------ VCardError ----------------------------------------------------
+
+/// A vCard was not correctly formatted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VCardError;
+
+impl fmt::Display for VCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid vCard")
+    }
+}
+
+impl error::Error for VCardError { }
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use crate::tal::TalInfo;
+    use super::*;
+
+    // The records in the test data are issued by gbr-ta.cer which is valid
+    // from 2020 to 2040.
+
+    fn issuer() -> ResourceCert {
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        Cert::decode(
+            include_bytes!("../test-data/gbr-ta.cer").as_ref()
+        ).unwrap().validate_ta_at(talinfo, false, now()).unwrap()
+    }
+
+    fn now() -> Time {
+        Time::utc(2021, 1, 1, 0, 0, 0)
+    }
+
+    #[test]
+    fn decode_and_validate() {
+        let gbr = Gbr::decode(
+            include_bytes!("../test-data/example.gbr").as_ref(), true
+        ).unwrap();
+        let card = gbr.content();
+        assert_eq!(card.full_name(), Some("Human Being"));
+        assert_eq!(
+            card.addresses(),
+            &[String::from(";;42 Twisty Passage;Deep Cavern;WA;98666;U.S.A.")]
+        );
+        assert_eq!(
+            card.telephones(),
+            &[
+                String::from("tel:+1-666-555-1212"),
+                String::from("tel:+1-666-555-1213"),
+            ]
+        );
+        assert_eq!(card.emails(), &[String::from("human@example.com")]);
+        assert!(card.as_bytes().starts_with(b"BEGIN:VCARD\r\n"));
+
+        let (_, card) = gbr.validate_at(&issuer(), false, now()).unwrap();
+        assert_eq!(card.full_name(), Some("Human Being"));
+    }
+
+    #[test]
+    fn decode_missing_full_name() {
+        let der = include_bytes!("../test-data/gbr-no-fn.gbr");
+        assert!(Gbr::decode(der.as_ref(), true).is_err());
+        assert_eq!(
+            Gbr::decode_detailed(der.as_ref(), true).err(),
+            Some(SignedObjectError::MalformedContent)
+        );
+        let gbr = Gbr::decode(der.as_ref(), false).unwrap();
+        assert_eq!(gbr.content().full_name(), None);
+        assert_eq!(
+            gbr.content().emails(), &[String::from("human@example.com")]
+        );
+        gbr.validate_at(&issuer(), false, now()).unwrap();
+    }
+
+    #[test]
+    fn decode_wrong_content_type() {
+        assert!(
+            Gbr::decode(
+                include_bytes!("../test-data/example-ripe.roa").as_ref(),
+                false
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn parse_vcard() {
+        let parse = |s: &'static str, strict| {
+            VCard::parse(Bytes::from_static(s.as_bytes()), strict)
+        };
+
+        // Folding, groups, parameters, and escaping.
+        let card = parse(
+            "begin:vcard\r\n\
+             VERSION:4.0\r\n\
+             item1.FN;LANGUAGE=en:Hostmaster\\, Exa\r\n mple\r\n\
+             EMAIL;TYPE=\"work:main\":hostmaster@example.com\r\n\
+             END:VCARD\r\n",
+            true
+        ).unwrap();
+        assert_eq!(card.full_name(), Some("Hostmaster, Example"));
+        assert_eq!(card.emails(), &[String::from("hostmaster@example.com")]);
+
+        // Bare LF line endings are fine, too.
+        assert!(parse(
+            "BEGIN:VCARD\nVERSION:4.0\nFN:X\nEMAIL:x@example.com\nEND:VCARD",
+            true
+        ).is_ok());
+
+        // Properties outside the profile only in lenient mode.
+        let card = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:X\r\n\
+                    EMAIL:x@example.com\r\nNOTE:hello\r\nEND:VCARD\r\n";
+        assert!(parse(card, true).is_err());
+        assert!(parse(card, false).is_ok());
+
+        // Wrong version and missing contact only in lenient mode.
+        let card = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:X\r\n\
+                    EMAIL:x@example.com\r\nEND:VCARD\r\n";
+        assert!(parse(card, true).is_err());
+        assert!(parse(card, false).is_ok());
+        let card = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:X\r\nEND:VCARD\r\n";
+        assert!(parse(card, true).is_err());
+        assert!(parse(card, false).is_ok());
+
+        // Broken structure is always rejected.
+        for card in &[
+            "VERSION:4.0\r\nFN:X\r\nEND:VCARD\r\n",
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:X\r\n",
+            "BEGIN:VCARD\r\nFN:X\r\nEND:VCARD\r\nFN:Y\r\n",
+            "BEGIN:VCARD\r\nFN X\r\nEND:VCARD\r\n",
+        ] {
+            assert!(parse(*card, false).is_err());
+        }
+        assert!(VCard::parse(Bytes::from_static(b"\xff"), false).is_err());
+    }
+}
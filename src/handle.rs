@@ -0,0 +1,172 @@
+//! Handles identifying the parties of the RPKI protocols.
+//!
+//! The parties taking part in the provisioning and publication protocols
+//! identify themselves and each other by handles. These are agreed upon
+//! during the out-of-band setup described in [RFC 8183] which limits them
+//! to at most 255 characters of ASCII letters and digits, hyphens,
+//! underscores, and slashes.
+//!
+//! [RFC 8183]: https://tools.ietf.org/html/rfc8183
+
+use std::{borrow, error, fmt, str};
+use std::convert::TryFrom;
+
+
+//------------ Handle --------------------------------------------------------
+
+/// The handle of a party in the RPKI protocols.
+///
+/// A value of this type is guaranteed to be a valid handle. It can be
+/// created from a string via `FromStr` or `TryFrom<String>` and displays
+/// as the string itself.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Handle(String);
+
+impl Handle {
+    /// The maximum length of a handle in characters.
+    pub const MAX_LEN: usize = 255;
+
+    /// Returns whether a string is a valid handle.
+    pub fn is_valid(s: &str) -> bool {
+        !s.is_empty() && s.len() <= Self::MAX_LEN
+            && s.bytes().all(|ch| {
+                ch.is_ascii_alphanumeric() || ch == b'-' || ch == b'_'
+                    || ch == b'/'
+            })
+    }
+
+    /// Returns a string slice of the handle.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Converts the handle into a string.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+
+//--- FromStr and TryFrom
+
+impl str::FromStr for Handle {
+    type Err = InvalidHandle;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if Self::is_valid(s) {
+            Ok(Handle(s.into()))
+        }
+        else {
+            Err(InvalidHandle)
+        }
+    }
+}
+
+impl TryFrom<String> for Handle {
+    type Error = InvalidHandle;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        if Self::is_valid(&s) {
+            Ok(Handle(s))
+        }
+        else {
+            Err(InvalidHandle)
+        }
+    }
+}
+
+
+//--- From
+
+impl From<Handle> for String {
+    fn from(handle: Handle) -> Self {
+        handle.0
+    }
+}
+
+
+//--- AsRef and Borrow
+
+impl AsRef<str> for Handle {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl borrow::Borrow<str> for Handle {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+
+//--- PartialEq
+
+impl PartialEq<str> for Handle {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Handle {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+
+//--- Display
+
+impl fmt::Display for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+//------------ InvalidHandle -------------------------------------------------
+
+/// A string was not a valid handle.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidHandle;
+
+impl fmt::Display for InvalidHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid handle")
+    }
+}
+
+impl error::Error for InvalidHandle { }
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid_handles() {
+        assert!(Handle::is_valid("Bob"));
+        assert!(Handle::is_valid("alice_1-2/3"));
+        assert!(Handle::is_valid(&"a".repeat(255)));
+        assert!(!Handle::is_valid(""));
+        assert!(!Handle::is_valid(&"a".repeat(256)));
+        assert!(!Handle::is_valid("B b"));
+        assert!(!Handle::is_valid("Bö"));
+        assert!(!Handle::is_valid("a.b"));
+        assert!(!Handle::is_valid("a<b"));
+    }
+
+    #[test]
+    fn from_str_and_display() {
+        let handle: Handle = "Alice/Bob-42".parse().unwrap();
+        assert_eq!(handle.as_str(), "Alice/Bob-42");
+        assert_eq!(handle.to_string(), "Alice/Bob-42");
+        assert_eq!(handle, "Alice/Bob-42");
+        assert_eq!(String::from(handle), "Alice/Bob-42");
+        assert_eq!("a.b".parse::<Handle>(), Err(InvalidHandle));
+        assert!(Handle::try_from(String::from("Bob")).is_ok());
+        assert!(Handle::try_from(String::new()).is_err());
+    }
+}
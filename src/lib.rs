@@ -14,16 +14,53 @@
 //!
 //! Documentation for the items in this crate is currently somewhat sparse.
 //! This will be rectified in upcoming releases.
+//!
+//! # Features
+//!
+//! Decoding and validating RPKI objects, including the verification of
+//! their signatures, only needs the default features. In addition, the
+//! following features are available, none of which is enabled by default:
+//!
+//! * `softkeys`: enables the module `crypto::softsigner` with a signer
+//!   that keeps its keys in memory or, if created via
+//!   `OpenSslSigner::open`, also stores them in a directory on disk. This
+//!   is needed to create and sign objects without an external signer and
+//!   adds a dependency on OpenSSL.
+//! * `prehashed`: enables `PublicKey::verify_prehashed` for verifying
+//!   signatures over a digest instead of the signed data itself. This also
+//!   adds a dependency on OpenSSL.
+//! * `sha256-ski`: enables `PublicKey::sha256_key_identifier` for
+//!   experimenting with key identifiers derived from SHA-256.
+//! * `extra-debug`: enables the `extra-debug` feature of the _bcder_ crate
+//!   which reports where exactly decoding of an object failed.
+//!
+//! # Use without std
+//!
+//! The crate currently requires the standard library. All methods that
+//! check the validity of an object at a given time have a variant with an
+//! `_at` suffix that takes the time as an argument, so validation doesn’t
+//! rely on the system clock. However, the _bcder_ crate used for all
+//! decoding and encoding as well as the _quick-xml_ crate used by the
+//! protocol messages require the standard library, and encoding is built
+//! on `std::io::Write`. Decoding objects without the standard library
+//! therefore isn’t possible until these dependencies support it.
 
 pub mod cert;
 pub mod crl;
 pub mod crypto;
 pub mod csr;
+pub mod gbr;
+pub mod handle;
 pub mod manifest;
 pub mod oid;
+pub mod oob;
+pub mod origins;
+pub mod provisioning;
+pub mod publication;
 pub mod resources;
 pub mod roa;
 pub mod rrdp;
+pub mod sigmsg;
 pub mod sigobj;
 pub mod tal;
 pub mod uri;
@@ -10,18 +10,24 @@
 //! [`Manifest`]: struct.Manifest.html
 //! [`ManifestContent`]: struct.ManifestContent.html
 
-use std::{borrow, ops};
+use std::{borrow, error, fmt, ops};
 use bcder::{decode, encode};
 use bcder::{
     BitString, Captured, Ia5String, Mode, OctetString, Oid, Tag, xerr
 };
 use bcder::encode::{PrimitiveContent, Values};
 use bytes::Bytes;
+use chrono::Duration;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use crate::{oid, uri};
 use crate::cert::{Cert, ResourceCert};
-use crate::crypto::{DigestAlgorithm, Signer, SigningError};
-use crate::sigobj::{SignedObject, SignedObjectBuilder};
+use crate::crl::Crl;
+use crate::crypto::{
+    AlgorithmPolicy, DigestAlgorithm, Signer, SigningError
+};
+use crate::sigobj::{
+    AsSignedObject, SignedObject, SignedObjectBuilder, SignedObjectError
+};
 use crate::x509::{Serial, Time, ValidationError};
 
 
@@ -44,16 +50,46 @@ impl Manifest {
         source: S,
         strict: bool
     ) -> Result<Self, S::Err> {
-        let signed = SignedObject::decode(source, strict)?;
-        if signed.content_type().ne(&oid::CT_RPKI_MANIFEST) {
-            return Err(decode::Malformed.into())
-        }
+        let signed = SignedObject::decode_if_type(
+            source, &oid::CT_RPKI_MANIFEST, strict
+        )?;
         let content = signed.decode_content(
             |cons| ManifestContent::take_from(cons)
         )?;
         Ok(Manifest { signed, content })
     }
 
+    /// Decodes a manifest from a byte slice.
+    ///
+    /// The slice is only borrowed while decoding, so it can be the content
+    /// of a memory-mapped file that is unmapped again afterwards. Decoding
+    /// itself doesn’t copy the slice into a buffer first, but the parts of
+    /// the manifest that are kept in the returned value are copied.
+    pub fn from_slice(
+        slice: &[u8],
+        strict: bool
+    ) -> Result<Self, decode::Error> {
+        Self::decode(slice, strict)
+    }
+
+    /// Decodes a manifest from a source reporting why decoding failed.
+    ///
+    /// See [`SignedObject::decode_detailed`] for details.
+    ///
+    /// [`SignedObject::decode_detailed`]: ../sigobj/struct.SignedObject.html#method.decode_detailed
+    pub fn decode_detailed<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, SignedObjectError> {
+        let signed = SignedObject::decode_detailed(
+            source, &oid::CT_RPKI_MANIFEST, strict
+        )?;
+        let content = signed.decode_content(
+            |cons| ManifestContent::take_from(cons)
+        ).map_err(|_| SignedObjectError::MalformedContent)?;
+        Ok(Manifest { signed, content })
+    }
+
     /// Validates the manifest.
     ///
     /// You need to pass in the certificate of the issuing CA. If validation
@@ -77,6 +113,128 @@ impl Manifest {
         Ok((cert, self.content))
     }
 
+    /// Validates the manifest under a local algorithm policy.
+    ///
+    /// This is the same as [`validate_at`] but the signature algorithms
+    /// used by the manifest must be permitted by `policy`. See the method of
+    /// the same name of [`SignedObject`] for details.
+    ///
+    /// [`validate_at`]: #method.validate_at
+    /// [`SignedObject`]: ../sigobj/struct.SignedObject.html
+    pub fn validate_with_policy(
+        self,
+        cert: &ResourceCert,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+    ) -> Result<(ResourceCert, ManifestContent), ValidationError> {
+        let cert = self.signed.validate_with_policy(
+            cert, strict, now, policy
+        )?;
+        Ok((cert, self.content))
+    }
+
+    /// Validates the manifest allowing it to be stale for some time.
+    ///
+    /// This is like [`validate`] but additionally checks the manifest’s
+    /// nextUpdate time. If it has passed by no more than `grace`, the
+    /// manifest is still accepted but a [`ManifestWarning::Stale`] is
+    /// returned alongside the result. If it has passed by more than that,
+    /// validation fails with [`ManifestValidationError::Stale`]. An expired
+    /// EE certificate is always an error and is reported as
+    /// [`ManifestValidationError::Expired`].
+    ///
+    /// [`validate`]: #method.validate
+    /// [`ManifestWarning::Stale`]: enum.ManifestWarning.html
+    /// [`ManifestValidationError::Stale`]: enum.ManifestValidationError.html
+    /// [`ManifestValidationError::Expired`]: enum.ManifestValidationError.html
+    pub fn validate_with_grace(
+        self,
+        cert: &ResourceCert,
+        strict: bool,
+        grace: Duration,
+    ) -> Result<
+        (ResourceCert, ManifestContent, Option<ManifestWarning>),
+        ManifestValidationError
+    > {
+        self.validate_with_grace_at(cert, strict, grace, Time::now())
+    }
+
+    /// Validates the manifest at the given time allowing it to be stale.
+    ///
+    /// See [`validate_with_grace`] for details.
+    ///
+    /// [`validate_with_grace`]: #method.validate_with_grace
+    pub fn validate_with_grace_at(
+        self,
+        cert: &ResourceCert,
+        strict: bool,
+        grace: Duration,
+        now: Time
+    ) -> Result<
+        (ResourceCert, ManifestContent, Option<ManifestWarning>),
+        ManifestValidationError
+    > {
+        if self.cert().validity().not_after() < now {
+            return Err(ManifestValidationError::Expired)
+        }
+        let cert = self.signed.validate_at(cert, strict, now)?;
+        let content = self.content;
+        let warning = if content.next_update < now {
+            if content.next_update + grace < now {
+                return Err(ManifestValidationError::Stale)
+            }
+            Some(ManifestWarning::Stale)
+        }
+        else {
+            None
+        };
+        Ok((cert, content, warning))
+    }
+
+    /// Validates the manifest together with the CRL of its publication point.
+    ///
+    /// This checks that the manifest lists `crl` with a matching hash via
+    /// [`check_crl`], that the CRL was signed by the issuing CA, and that
+    /// the manifest’s EE certificate has not been revoked by it before
+    /// validating the manifest itself as [`validate`] does.
+    ///
+    /// [`check_crl`]: #method.check_crl
+    /// [`validate`]: #method.validate
+    pub fn validate_with_crl(
+        self,
+        cert: &ResourceCert,
+        crl: &Crl,
+        strict: bool,
+    ) -> Result<(ResourceCert, ManifestContent), ManifestValidationError> {
+        self.validate_with_crl_at(cert, crl, strict, Time::now())
+    }
+
+    /// Validates the manifest and CRL at the given time.
+    ///
+    /// See [`validate_with_crl`] for details.
+    ///
+    /// [`validate_with_crl`]: #method.validate_with_crl
+    pub fn validate_with_crl_at(
+        self,
+        cert: &ResourceCert,
+        crl: &Crl,
+        strict: bool,
+        now: Time
+    ) -> Result<(ResourceCert, ManifestContent), ManifestValidationError> {
+        if !self.check_crl(crl) {
+            return Err(ManifestValidationError::CrlMismatch)
+        }
+        if crl.validate(cert.subject_public_key_info()).is_err() {
+            return Err(ManifestValidationError::CrlMismatch)
+        }
+        if crl.contains(self.cert().serial_number()) {
+            return Err(ManifestValidationError::Revoked)
+        }
+        let cert = self.signed.validate_at(cert, strict, now)?;
+        Ok((cert, self.content))
+    }
+
     /// Returns a value encoder for a reference to the manifest.
     pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
         self.signed.encode_ref()
@@ -96,6 +254,52 @@ impl Manifest {
     pub fn content(&self) -> &ManifestContent {
         &self.content
     }
+
+    /// Returns the file list entry for the CRL of the publication point.
+    ///
+    /// [RFC 6486] requires the manifest to list the CRL issued by the CA.
+    /// This is the CRL named by the CRL distribution point of the
+    /// manifest’s EE certificate, so the entry is picked by matching the
+    /// last path segment of that URI. Returns `None` if the EE certificate
+    /// has no CRL URI or the manifest doesn’t list the file.
+    ///
+    /// [RFC 6486]: https://tools.ietf.org/html/rfc6486
+    pub fn crl_entry(&self) -> Option<FileAndHash<Bytes, Bytes>> {
+        let crl_uri = self.cert().crl_uri()?;
+        let name = match crl_uri.path().rfind('/') {
+            Some(pos) => &crl_uri.path()[pos + 1..],
+            None => crl_uri.path()
+        };
+        self.content.iter().find(|item| {
+            item.file().as_ref() == name.as_bytes()
+        })
+    }
+
+    /// Returns whether the manifest lists the given CRL.
+    ///
+    /// This is the case if [`crl_entry`] returns an entry and its hash
+    /// matches the encoded CRL.
+    ///
+    /// [`crl_entry`]: #method.crl_entry
+    pub fn check_crl(&self, crl: &Crl) -> bool {
+        match self.crl_entry() {
+            Some(entry) => {
+                ManifestHash::new(
+                    entry.into_pair().1, self.content.file_hash_alg
+                ).verify(crl.to_captured().as_slice()).is_ok()
+            }
+            None => false
+        }
+    }
+}
+
+
+//--- AsSignedObject
+
+impl AsSignedObject for Manifest {
+    fn signed_object(&self) -> &SignedObject {
+        &self.signed
+    }
 }
 
 
@@ -215,7 +419,7 @@ impl ManifestContent {
         }
     }
 
-    pub fn into_manifest<S: Signer>(
+    pub fn into_manifest<S: Signer + ?Sized>(
         self,
         mut sigobj: SignedObjectBuilder,
         signer: &S,
@@ -254,6 +458,11 @@ impl ManifestContent {
     }
 
     /// Returns the hash algorithm for the file list entries.
+    ///
+    /// [RFC 6486] requires SHA-256. Manifests declaring any other algorithm
+    /// are rejected during decoding, so this is always SHA-256.
+    ///
+    /// [RFC 6486]: https://tools.ietf.org/html/rfc6486
     pub fn file_hash_alg(&self) -> DigestAlgorithm {
         self.file_hash_alg
     }
@@ -266,18 +475,19 @@ impl ManifestContent {
     /// Returns an iterator over URL and hash pairs.
     ///
     /// The iterator assumes that all files referred to in the manifest are
-    /// relative to the given rsync URI.
+    /// relative to the given rsync URI. If a file name cannot be joined
+    /// with the URI, for instance because it contains “..” segments, the
+    /// iterator returns an error for this item.
     pub fn iter_uris<'a>(
         &'a self,
         base: &'a uri::Rsync
-    ) -> impl Iterator<Item = (uri::Rsync, ManifestHash)> + 'a {
+    ) -> impl Iterator<
+        Item = Result<(uri::Rsync, ManifestHash), uri::Error>
+    > + 'a {
         let alg = self.file_hash_alg;
         self.iter().map(move |item| {
             let (file, hash) = item.into_pair();
-            (
-                base.join(file.as_ref()),
-                ManifestHash::new(hash, alg)
-            )
+            Ok((base.join(&file)?, ManifestHash::new(hash, alg)))
         })
     }
 
@@ -311,6 +521,8 @@ impl ManifestContent {
             let manifest_number = Serial::take_from(cons)?;
             let this_update = Time::take_from(cons)?;
             let next_update = Time::take_from(cons)?;
+            // This only accepts SHA-256 which prevents downgrading the
+            // file hashes to a weaker algorithm.
             let file_hash_alg = DigestAlgorithm::take_oid_from(cons)?;
             if this_update > next_update {
                 xerr!(return Err(decode::Malformed.into()));
@@ -353,6 +565,88 @@ impl ManifestContent {
 }
 
 
+//------------ ManifestWarning -----------------------------------------------
+
+/// A problem with a manifest that was tolerated during validation.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ManifestWarning {
+    /// The manifest’s nextUpdate time has passed but within the grace period.
+    Stale,
+}
+
+impl fmt::Display for ManifestWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ManifestWarning::Stale => f.write_str("manifest is stale"),
+        }
+    }
+}
+
+
+//------------ ManifestValidationError ---------------------------------------
+
+/// An error happened while validating a manifest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ManifestValidationError {
+    /// The signed object or its EE certificate failed validation.
+    Object(SignedObjectError),
+
+    /// The EE certificate has expired.
+    ///
+    /// This is never covered by a grace period.
+    Expired,
+
+    /// The manifest’s nextUpdate time has passed beyond the grace period.
+    Stale,
+
+    /// The CRL is not listed on the manifest or wasn’t issued by the CA.
+    CrlMismatch,
+
+    /// The EE certificate has been revoked by the CRL.
+    Revoked,
+}
+
+
+//--- From
+
+impl From<SignedObjectError> for ManifestValidationError {
+    fn from(err: SignedObjectError) -> Self {
+        ManifestValidationError::Object(err)
+    }
+}
+
+impl From<ManifestValidationError> for ValidationError {
+    fn from(_: ManifestValidationError) -> Self {
+        ValidationError
+    }
+}
+
+
+//--- Display and Error
+
+impl fmt::Display for ManifestValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ManifestValidationError::Object(ref err) => err.fmt(f),
+            ManifestValidationError::Expired => {
+                f.write_str("EE certificate has expired")
+            }
+            ManifestValidationError::Stale => {
+                f.write_str("manifest is stale beyond grace period")
+            }
+            ManifestValidationError::CrlMismatch => {
+                f.write_str("CRL does not match manifest")
+            }
+            ManifestValidationError::Revoked => {
+                f.write_str("EE certificate has been revoked")
+            }
+        }
+    }
+}
+
+impl error::Error for ManifestValidationError { }
+
+
 //------------ FileListIter --------------------------------------------------
 
 /// An iterator over the content of a file list.
@@ -517,42 +811,179 @@ mod test {
         ).unwrap();
         assert!(obj.validate_at(&issuer, false, at).is_err());
     }
+
+    #[test]
+    fn validate_with_policy() {
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+        let issuer = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let issuer = issuer.validate_ta_at(talinfo, false, at).unwrap();
+        let obj = Manifest::decode(
+            include_bytes!("../test-data/ta.mft").as_ref(),
+            false
+        ).unwrap();
+        assert!(
+            obj.clone().validate_with_policy(
+                &issuer, false, at, &AlgorithmPolicy::none()
+            ).is_err()
+        );
+        obj.validate_with_policy(
+            &issuer, false, at, &AlgorithmPolicy::rpki()
+        ).unwrap();
+    }
+
+    #[test]
+    fn check_crl() {
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+        let issuer = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let issuer = issuer.validate_ta_at(talinfo, false, at).unwrap();
+        let obj = Manifest::decode(
+            include_bytes!("../test-data/ta.mft").as_ref(),
+            false
+        ).unwrap();
+        let ta_crl = Crl::decode(
+            include_bytes!("../test-data/ta.crl").as_ref(), true
+        ).unwrap();
+        let ca_crl = Crl::decode(
+            include_bytes!("../test-data/ca1.crl").as_ref(), true
+        ).unwrap();
+        assert_eq!(
+            obj.crl_entry().unwrap().file().as_ref(),
+            b"ripe-ncc-ta.crl"
+        );
+        assert!(obj.check_crl(&ta_crl));
+        assert!(!obj.check_crl(&ca_crl));
+        assert_eq!(
+            obj.clone().validate_with_crl_at(
+                &issuer, &ca_crl, false, at
+            ).err(),
+            Some(ManifestValidationError::CrlMismatch)
+        );
+        obj.validate_with_crl_at(&issuer, &ta_crl, false, at).unwrap();
+    }
+
+    #[test]
+    fn decode_file_hash_alg() {
+        let obj = Manifest::decode(
+            include_bytes!("../test-data/ta.mft").as_ref(),
+            false
+        ).unwrap();
+        assert_eq!(obj.content().file_hash_alg(), DigestAlgorithm::default());
+
+        // The same manifest with SHA-384 as the file hash algorithm.
+        let der = include_bytes!("../test-data/mft-sha384.mft");
+        assert!(Manifest::decode(der.as_ref(), false).is_err());
+        assert!(Manifest::decode(der.as_ref(), true).is_err());
+    }
+
+    #[test]
+    fn from_slice() {
+        let data = include_bytes!("../test-data/ta.mft").to_vec();
+        let obj = Manifest::from_slice(&data, false).unwrap();
+        drop(data);
+        assert_eq!(obj.content().len(), 2);
+        assert!(Manifest::from_slice(b"foo", false).is_err());
+    }
+
+    #[test]
+    fn iter_uris() {
+        let base = uri::Rsync::from_string(
+            "rsync://example.com/module/ca/".into()
+        ).unwrap();
+        let content = ManifestContent::new(
+            1u64.into(), Time::now(), Time::now(),
+            DigestAlgorithm::default(),
+            [
+                FileAndHash::new(b"a.roa".as_ref(), b"hash".as_ref()),
+                FileAndHash::new(b"../b.roa".as_ref(), b"hash".as_ref()),
+            ].iter()
+        );
+        let mut iter = content.iter_uris(&base);
+        assert_eq!(
+            iter.next().unwrap().unwrap().0.as_str(),
+            "rsync://example.com/module/ca/a.roa"
+        );
+        assert_eq!(
+            iter.next().unwrap().unwrap_err(), uri::Error::DotSegments
+        );
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn decode_wrong_content_type() {
+        // A ROA is not a manifest.
+        let der = include_bytes!("../test-data/example-ripe.roa");
+        assert!(Manifest::decode(der.as_ref(), false).is_err());
+        assert!(Manifest::decode(der.as_ref(), true).is_err());
+        match Manifest::decode_detailed(der.as_ref(), false) {
+            Err(SignedObjectError::WrongContentType { .. }) => { }
+            _ => panic!("expected wrong content type")
+        }
+    }
+
+    #[test]
+    fn validate_detailed() {
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+        let issuer = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let issuer = issuer.validate_ta_at(talinfo, false, at).unwrap();
+        let obj = Manifest::decode_detailed(
+            include_bytes!(
+                "../test-data/sigobj-digest-mismatch.mft"
+            ).as_ref(),
+            true
+        ).unwrap();
+        assert_eq!(
+            obj.validate_with_grace_at(
+                &issuer, false, Duration::zero(), at
+            ).err(),
+            Some(ManifestValidationError::Object(
+                SignedObjectError::MessageDigestMismatch
+            ))
+        );
+        assert_eq!(
+            Manifest::decode_detailed(
+                include_bytes!(
+                    "../test-data/sigobj-two-signers.mft"
+                ).as_ref(),
+                true
+            ).err(),
+            Some(SignedObjectError::MultipleSignerInfos)
+        );
+    }
 }
 
 #[cfg(all(test, feature="softkeys"))]
 mod signer_test {
-    use std::str::FromStr;
     use bcder::encode::Values;
-    use crate::cert::{KeyUsage, Overclaim, TbsCert};
+    use crate::cert::KeyUsage;
     use crate::crypto::{PublicKeyFormat, Signer};
     use crate::crypto::softsigner::OpenSslSigner;
-    use crate::resources::{AsId, Prefix};
-    use crate::uri;
     use crate::tal::TalInfo;
+    use crate::util::test::{make_cert, test_uri};
     use crate::x509::Validity;
     use super::*;
 
-    fn make_test_manifest() -> Manifest {
+    fn make_manifest(
+        this_update: Time, next_update: Time, validity: Validity
+    ) -> (Manifest, ResourceCert) {
         let mut signer = OpenSslSigner::new();
         let key = signer.create_key(PublicKeyFormat::default()).unwrap();
         let pubkey = signer.get_key_info(&key).unwrap();
-        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+        let uri = test_uri();
 
-        let mut cert = TbsCert::new(
-            12u64.into(), pubkey.to_subject_name(),
-            Validity::from_secs(86400), None, pubkey, KeyUsage::Ca,
-            Overclaim::Trim
-        );
-        cert.set_basic_ca(Some(true));
-        cert.set_ca_repository(Some(uri.clone()));
-        cert.set_rpki_manifest(Some(uri.clone()));
-        cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
-        cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
-        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let cert = make_cert(pubkey, None, KeyUsage::Ca);
         let cert = cert.into_cert(&signer, &key).unwrap();
 
         let content = ManifestContent::new(
-            12u64.into(), Time::now(), Time::now(),
+            12u64.into(), this_update, next_update,
             DigestAlgorithm::default(),
             [
                 FileAndHash::new(b"file".as_ref(), b"hash".as_ref()),
@@ -562,8 +993,7 @@ mod signer_test {
 
         let manifest = content.into_manifest(
             SignedObjectBuilder::new(
-                12u64.into(), Validity::from_secs(86400), uri.clone(),
-                uri.clone(), uri.clone()
+                12u64.into(), validity, uri.clone(), uri.clone(), uri
             ),
             &signer, &key
         ).unwrap();
@@ -573,11 +1003,59 @@ mod signer_test {
         let cert = cert.validate_ta(
             TalInfo::from_name("foo".into()).into_arc(), true
         ).unwrap();
-        manifest.clone().validate(&cert, true).unwrap();
+        (manifest, cert)
+    }
 
+    fn make_test_manifest() -> Manifest {
+        let (manifest, cert) = make_manifest(
+            Time::now(), Time::now(), Validity::from_secs(86400)
+        );
+        manifest.clone().validate(&cert, true).unwrap();
         manifest
     }
 
+    fn hours(hours: i64) -> Time {
+        Time::new(*Time::now() + Duration::hours(hours))
+    }
+
+    #[test]
+    fn validate_with_grace() {
+        // Fresh manifest.
+        let (manifest, cert) = make_manifest(
+            hours(-1), hours(1), Validity::from_secs(86400)
+        );
+        let (_, _, warning) = manifest.validate_with_grace(
+            &cert, true, Duration::zero()
+        ).unwrap();
+        assert_eq!(warning, None);
+
+        // Stale manifest within and beyond the grace period.
+        let (manifest, cert) = make_manifest(
+            hours(-2), hours(-1), Validity::from_secs(86400)
+        );
+        let (_, _, warning) = manifest.clone().validate_with_grace(
+            &cert, true, Duration::hours(2)
+        ).unwrap();
+        assert_eq!(warning, Some(ManifestWarning::Stale));
+        assert_eq!(
+            manifest.validate_with_grace(
+                &cert, true, Duration::zero()
+            ).unwrap_err(),
+            ManifestValidationError::Stale
+        );
+
+        // Expired EE certificate is fatal regardless of grace.
+        let (manifest, cert) = make_manifest(
+            hours(-1), hours(1), Validity::new(hours(-2), hours(-1))
+        );
+        assert_eq!(
+            manifest.validate_with_grace(
+                &cert, true, Duration::days(365)
+            ).unwrap_err(),
+            ManifestValidationError::Expired
+        );
+    }
+
     #[test]
     fn encode_manifest() {
         make_test_manifest();
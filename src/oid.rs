@@ -25,56 +25,243 @@ pub const RSA_ENCRYPTION: ConstOid
 pub const SHA256_WITH_RSA_ENCRYPTION: ConstOid
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 11]);
 
+/// [RFC 4055](https://tools.ietf.org/html/rfc4055) `id-RSASSA-PSS`
+///
+/// Identifies the RSASSA-PSS signature algorithm. The hash and mask
+/// generation functions are given in the algorithm parameters.
+pub const RSASSA_PSS: ConstOid
+    = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 10]);
+
+/// [RFC 4055](https://tools.ietf.org/html/rfc4055) `id-mgf1`
+///
+/// Identifies the mask generation function MGF1 used with RSASSA-PSS.
+pub const MGF1: ConstOid
+    = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 8]);
 
+
+/// [RFC 5652](https://tools.ietf.org/html/rfc5652) `id-signedData`
+///
+/// Identifies the CMS signed-data content type.
 pub const SIGNED_DATA: Oid<&[u8]>
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 7, 2]);
+
+/// [RFC 5652](https://tools.ietf.org/html/rfc5652) `id-contentType`
+///
+/// Identifies the content-type signed attribute of CMS.
 pub const CONTENT_TYPE: Oid<&[u8]>
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 3]);
+
+/// [RFC 6492](https://tools.ietf.org/html/rfc6492) `id-ct-xml`
+///
+/// Identifies the XML content of the provisioning and publication
+/// protocol messages.
 pub const PROTOCOL_CONTENT_TYPE: Oid<&[u8]>
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 28]);
+
+/// [RFC 5652](https://tools.ietf.org/html/rfc5652) `id-messageDigest`
+///
+/// Identifies the message-digest signed attribute of CMS.
 pub const MESSAGE_DIGEST: Oid<&[u8]>
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 4]);
+
+/// [RFC 5652](https://tools.ietf.org/html/rfc5652) `id-signingTime`
+///
+/// Identifies the signing-time signed attribute of CMS.
 pub const SIGNING_TIME: Oid<&[u8]>
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 5]);
+
+/// [RFC 6019](https://tools.ietf.org/html/rfc6019) `id-aa-binarySigningTime`
+///
+/// Identifies the binary-signing-time signed attribute of CMS.
 pub const AA_BINARY_SIGNING_TIME: Oid<&[u8]> =
     Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 2, 46]);
 
 
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ad-caIssuers`
+///
+/// Identifies the access description pointing to the issuer’s
+/// certificate.
 pub const AD_CA_ISSUERS: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 48, 2]);
+
+/// [RFC 6487](https://tools.ietf.org/html/rfc6487) `id-ad-caRepository`
+///
+/// Identifies the access description pointing to the repository
+/// publication point of a CA.
 pub const AD_CA_REPOSITORY: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 48, 5]);
+
+/// [RFC 6487](https://tools.ietf.org/html/rfc6487) `id-ad-rpkiManifest`
+///
+/// Identifies the access description pointing to the manifest of a CA.
 pub const AD_RPKI_MANIFEST: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 48, 10]);
+
+/// [RFC 8182](https://tools.ietf.org/html/rfc8182) `id-ad-rpkiNotify`
+///
+/// Identifies the access description pointing to the RRDP notification
+/// file of a CA’s repository.
 pub const AD_RPKI_NOTIFY: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 48, 13]);
+
+/// [RFC 6487](https://tools.ietf.org/html/rfc6487) `id-ad-signedObject`
+///
+/// Identifies the access description pointing to the signed object an
+/// EE certificate is used for.
 pub const AD_SIGNED_OBJECT: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 48, 11]);
 
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-at-commonName`
+///
+/// Identifies the common name attribute of a distinguished name.
 pub const AT_COMMON_NAME: Oid<&[u8]> = Oid(&[85, 4, 3]); // 2 5 4 3
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-at-serialNumber`
+///
+/// Identifies the serial number attribute of a distinguished name.
 pub const AT_SERIAL_NUMBER: Oid<&[u8]> = Oid(&[85, 4, 5]); // 2 5 4 5
 
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-at-countryName`
+///
+/// Identifies the country name attribute of a distinguished name.
+pub const AT_COUNTRY_NAME: Oid<&[u8]> = Oid(&[85, 4, 6]); // 2 5 4 6
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-at-organizationName`
+///
+/// Identifies the organization name attribute of a distinguished name.
+pub const AT_ORGANIZATION_NAME: Oid<&[u8]>
+    = Oid(&[85, 4, 10]); // 2 5 4 10
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-at-organizationalUnitName`
+///
+/// Identifies the organizational unit name attribute of a distinguished
+/// name.
+pub const AT_ORGANIZATIONAL_UNIT_NAME: Oid<&[u8]>
+    = Oid(&[85, 4, 11]); // 2 5 4 11
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-authorityKeyIdentifier`
+///
+/// Identifies the authority key identifier extension.
 pub const CE_AUTHORITY_KEY_IDENTIFIER: Oid<&[u8]> = Oid(&[85, 29, 35]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-basicConstraints`
+///
+/// Identifies the basic constraints extension.
 pub const CE_BASIC_CONSTRAINTS: Oid<&[u8]> = Oid(&[85, 29, 19]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-certificatePolicies`
+///
+/// Identifies the certificate policies extension.
 pub const CE_CERTIFICATE_POLICIES: Oid<&[u8]> = Oid(&[85, 29, 32]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-cRLDistributionPoints`
+///
+/// Identifies the CRL distribution points extension.
 pub const CE_CRL_DISTRIBUTION_POINTS: Oid<&[u8]> = Oid(&[85, 29, 31]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-cRLNumber`
+///
+/// Identifies the CRL number extension.
 pub const CE_CRL_NUMBER: Oid<&[u8]> = Oid(&[85, 29, 20]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-cRLReasons`
+///
+/// Identifies the reason code CRL entry extension.
+pub const CE_CRL_REASONS: Oid<&[u8]> = Oid(&[85, 29, 21]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-deltaCRLIndicator`
+///
+/// Identifies the delta CRL indicator extension.
+pub const CE_DELTA_CRL_INDICATOR: Oid<&[u8]> = Oid(&[85, 29, 27]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-extKeyUsage`
+///
+/// Identifies the extended key usage extension.
 pub const CE_EXTENDED_KEY_USAGE: Oid<&[u8]> = Oid(&[85, 29, 37]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-inhibitAnyPolicy`
+///
+/// Identifies the inhibit anyPolicy extension.
+pub const CE_INHIBIT_ANY_POLICY: Oid<&[u8]> = Oid(&[85, 29, 54]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-issuingDistributionPoint`
+///
+/// Identifies the issuing distribution point CRL extension.
+pub const CE_ISSUING_DISTRIBUTION_POINT: Oid<&[u8]> = Oid(&[85, 29, 28]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-keyUsage`
+///
+/// Identifies the key usage extension.
 pub const CE_KEY_USAGE: Oid<&[u8]> = Oid(&[85, 29, 15]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-policyConstraints`
+///
+/// Identifies the policy constraints extension.
+pub const CE_POLICY_CONSTRAINTS: Oid<&[u8]> = Oid(&[85, 29, 36]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-ce-subjectKeyIdentifier`
+///
+/// Identifies the subject key identifier extension.
 pub const CE_SUBJECT_KEY_IDENTIFIER: Oid<&[u8]> = Oid(&[85, 29, 14]);
 
+/// [RFC 6484](https://tools.ietf.org/html/rfc6484) `id-cp-ipAddr-asNumber`
+///
+/// Identifies the certificate policy for the RPKI.
 pub const CP_IPADDR_ASNUMBER: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 14, 2]);
-pub const CP_IPADDR_ASNUMBER_V2: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 14, 3]);
 
+/// [RFC 8360](https://tools.ietf.org/html/rfc8360) `id-cp-ipAddr-asNumber-v2`
+///
+/// Identifies the certificate policy for the RPKI using the reconsidered
+/// validation algorithm.
+pub const CP_IPADDR_ASNUMBER_V2: Oid<&[u8]>
+    = Oid(&[43, 6, 1, 5, 5, 7, 14, 3]);
+
+/// [RFC 6486](https://tools.ietf.org/html/rfc6486) `id-ct-rpkiManifest`
+///
+/// Identifies the content type of RPKI manifests.
 pub const CT_RPKI_MANIFEST: ConstOid
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 26]);
 
+/// [RFC 6493](https://tools.ietf.org/html/rfc6493) `id-ct-rpkiGhostbusters`
+///
+/// Identifies the content type of Ghostbusters records.
+pub const CT_RPKI_GHOSTBUSTERS: ConstOid
+    = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 35]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-pe-authorityInfoAccess`
+///
+/// Identifies the authority information access extension.
 pub const PE_AUTHORITY_INFO_ACCESS: Oid<&[u8]>
     = Oid(&[43, 6, 1, 5, 5, 7, 1, 1]);
+
+/// [RFC 3779](https://tools.ietf.org/html/rfc3779) `id-pe-ipAddrBlocks`
+///
+/// Identifies the IP address delegation extension.
 pub const PE_IP_ADDR_BLOCK: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 1, 7]);
+
+/// [RFC 8360](https://tools.ietf.org/html/rfc8360) `id-pe-ipAddrBlocks-v2`
+///
+/// Identifies the IP address delegation extension for the reconsidered
+/// validation algorithm.
 pub const PE_IP_ADDR_BLOCK_V2: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 1, 28]);
+
+/// [RFC 3779](https://tools.ietf.org/html/rfc3779) `id-pe-autonomousSysIds`
+///
+/// Identifies the AS identifier delegation extension.
 pub const PE_AUTONOMOUS_SYS_IDS: Oid<&[u8]>
     = Oid(&[43, 6, 1, 5, 5, 7, 1, 8]);
+
+/// [RFC 8360](https://tools.ietf.org/html/rfc8360) `id-pe-autonomousSysIds-v2`
+///
+/// Identifies the AS identifier delegation extension for the reconsidered
+/// validation algorithm.
 pub const PE_AUTONOMOUS_SYS_IDS_V2: Oid<&[u8]>
     = Oid(&[43, 6, 1, 5, 5, 7, 1, 29]);
+
+/// [RFC 5280](https://tools.ietf.org/html/rfc5280) `id-pe-subjectInfoAccess`
+///
+/// Identifies the subject information access extension.
 pub const PE_SUBJECT_INFO_ACCESS: Oid<&[u8]>
     = Oid(&[43, 6, 1, 5, 5, 7, 1, 11]);
 
+/// [RFC 6482](https://tools.ietf.org/html/rfc6482) `id-ct-routeOriginAuthz`
+///
+/// Identifies the content type of Route Origin Authorizations.
 pub const ROUTE_ORIGIN_AUTHZ: ConstOid
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 24]);
 
@@ -0,0 +1,1287 @@
+//! Out-of-band setup of the provisioning and publication protocols.
+//!
+//! Before a child CA can talk to its parent or a publisher to its
+//! repository, the two parties need to exchange their identity certificates
+//! and handles. [RFC 8183] defines a set of XML documents for this
+//! exchange that are passed between the parties by some other means, such
+//! as a web form or email.
+//!
+//! The setup of the provisioning protocol starts with a [`ChildRequest`]
+//! sent by the child which is answered by the parent with a
+//! [`ParentResponse`]. The setup of the publication protocol starts with a
+//! [`PublisherRequest`] sent by a publisher to a repository. If the parent
+//! referred the child to another repository, the child includes the
+//! [`Authorization`] from the referral in its publisher request.
+//! [`ParentResponse::to_publisher_request`] creates such a request.
+//!
+//! [RFC 8183]: https://tools.ietf.org/html/rfc8183
+//! [`Authorization`]: struct.Authorization.html
+//! [`ChildRequest`]: struct.ChildRequest.html
+//! [`ParentResponse`]: struct.ParentResponse.html
+//! [`ParentResponse::to_publisher_request`]: struct.ParentResponse.html#method.to_publisher_request
+//! [`PublisherRequest`]: struct.PublisherRequest.html
+
+use std::{io, str};
+use bytes::Bytes;
+use crate::cert::Cert;
+use crate::handle::Handle;
+use crate::xml::decode::{AttrValue, Content, Error, Name, Reader};
+use crate::xml::encode::{write_attr, write_opt_attr};
+
+
+//------------ PublisherRequest ----------------------------------------------
+
+/// A request by a publisher to be served by a repository.
+///
+/// The request contains the publisher’s handle, an optional tag for
+/// correlating the repository’s response, and the publisher’s BPKI trust
+/// anchor certificate. If the publisher was referred to the repository by
+/// its parent, the request also contains the referral’s authorization.
+#[derive(Clone, Debug)]
+pub struct PublisherRequest {
+    /// The optional tag of the request.
+    tag: Option<String>,
+
+    /// The handle the publisher would like to be known by.
+    publisher_handle: Handle,
+
+    /// The publisher’s BPKI trust anchor certificate.
+    id_cert: Cert,
+
+    /// The optional authorization from a referral.
+    referral: Option<Authorization>,
+}
+
+impl PublisherRequest {
+    /// Creates a new publisher request.
+    ///
+    /// The request does not contain a referral. Returns an error if the
+    /// certificate isn’t a self-signed CA certificate.
+    pub fn new(
+        tag: Option<String>,
+        publisher_handle: Handle,
+        id_cert: Cert
+    ) -> Result<Self, Error> {
+        if !is_bpki_ta(&id_cert) {
+            return Err(Error::Malformed)
+        }
+        Ok(PublisherRequest {
+            tag, publisher_handle, id_cert,
+            referral: None,
+        })
+    }
+
+    /// Returns the tag of the request if present.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_ref().map(AsRef::as_ref)
+    }
+
+    /// Returns the handle the publisher would like to be known by.
+    pub fn publisher_handle(&self) -> &Handle {
+        &self.publisher_handle
+    }
+
+    /// Returns the publisher’s BPKI trust anchor certificate.
+    pub fn id_cert(&self) -> &Cert {
+        &self.id_cert
+    }
+
+    /// Returns the authorization from a referral if present.
+    pub fn referral(&self) -> Option<&Authorization> {
+        self.referral.as_ref()
+    }
+
+    /// Sets the authorization from a referral.
+    pub fn set_referral(&mut self, referral: Option<Authorization>) {
+        self.referral = referral
+    }
+
+    /// Converts the request into its tag, handle, certificate, and referral.
+    pub fn unpack(
+        self
+    ) -> (Option<String>, Handle, Cert, Option<Authorization>) {
+        (self.tag, self.publisher_handle, self.id_cert, self.referral)
+    }
+
+    /// Parses a publisher request from its XML representation.
+    ///
+    /// The base64 encoded certificate may contain white space and line
+    /// breaks. It must be a self-signed CA certificate. Its validity period
+    /// isn’t checked.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let (tag, publisher_handle, id_cert, referral) = parse_request(
+            reader, PUBLISHER_REQUEST, b"publisher_handle", PUBLISHER_BPKI_TA,
+            true
+        )?;
+        let mut res = Self::new(tag, publisher_handle, id_cert)?;
+        res.referral = referral;
+        Ok(res)
+    }
+
+    /// Writes the XML representation of the request to a target.
+    ///
+    /// The certificate and the referral’s authorization are written in
+    /// lines of 64 characters.
+    pub fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write_request(
+            target, "publisher_request",
+            "publisher_handle", &self.publisher_handle, self.tag(),
+            "publisher_bpki_ta", &self.id_cert, self.referral.as_ref()
+        )
+    }
+}
+
+
+//------------ ChildRequest --------------------------------------------------
+
+/// A request by a child CA to be provisioned by a parent CA.
+///
+/// The request contains the child’s handle, an optional tag for correlating
+/// the parent’s response, and the child’s BPKI trust anchor certificate.
+#[derive(Clone, Debug)]
+pub struct ChildRequest {
+    /// The optional tag of the request.
+    tag: Option<String>,
+
+    /// The handle the child would like to be known by.
+    child_handle: Handle,
+
+    /// The child’s BPKI trust anchor certificate.
+    id_cert: Cert,
+}
+
+impl ChildRequest {
+    /// Creates a new child request.
+    ///
+    /// Returns an error if the certificate isn’t a self-signed CA
+    /// certificate.
+    pub fn new(
+        tag: Option<String>,
+        child_handle: Handle,
+        id_cert: Cert
+    ) -> Result<Self, Error> {
+        if !is_bpki_ta(&id_cert) {
+            return Err(Error::Malformed)
+        }
+        Ok(ChildRequest { tag, child_handle, id_cert })
+    }
+
+    /// Returns the tag of the request if present.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_ref().map(AsRef::as_ref)
+    }
+
+    /// Returns the handle the child would like to be known by.
+    pub fn child_handle(&self) -> &Handle {
+        &self.child_handle
+    }
+
+    /// Returns the child’s BPKI trust anchor certificate.
+    pub fn id_cert(&self) -> &Cert {
+        &self.id_cert
+    }
+
+    /// Converts the request into its tag, handle, and certificate.
+    pub fn unpack(self) -> (Option<String>, Handle, Cert) {
+        (self.tag, self.child_handle, self.id_cert)
+    }
+
+    /// Creates the parent’s response to this request.
+    ///
+    /// The response uses the tag and child handle of the request. The
+    /// remaining values are taken from the parent’s configuration. The
+    /// response neither offers repository service nor contains referrals.
+    /// These can be added to the returned value.
+    pub fn to_parent_response(
+        &self,
+        parent_handle: Handle,
+        id_cert: Cert,
+        service_uri: String
+    ) -> Result<ParentResponse, Error> {
+        ParentResponse::new(
+            self.tag.clone(), service_uri,
+            self.child_handle.clone(), parent_handle, id_cert
+        )
+    }
+
+    /// Parses a child request from its XML representation.
+    ///
+    /// The base64 encoded certificate may contain white space and line
+    /// breaks. It must be a self-signed CA certificate. Its validity period
+    /// isn’t checked.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let (tag, child_handle, id_cert, _) = parse_request(
+            reader, CHILD_REQUEST, b"child_handle", CHILD_BPKI_TA, false
+        )?;
+        Self::new(tag, child_handle, id_cert)
+    }
+
+    /// Writes the XML representation of the request to a target.
+    ///
+    /// The certificate is written in lines of 64 characters.
+    pub fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write_request(
+            target, "child_request",
+            "child_handle", &self.child_handle, self.tag(),
+            "child_bpki_ta", &self.id_cert, None
+        )
+    }
+}
+
+
+//------------ ParentResponse ------------------------------------------------
+
+/// The response of a parent CA to a child request.
+///
+/// The response tells the child how to reach the parent and contains the
+/// parent’s BPKI trust anchor certificate. In addition, the parent can
+/// offer to act as a repository for the child or refer the child to other
+/// repositories.
+#[derive(Clone, Debug)]
+pub struct ParentResponse {
+    /// The optional tag copied from the child request.
+    tag: Option<String>,
+
+    /// The URI of the parent’s provisioning service for this child.
+    service_uri: String,
+
+    /// The handle the parent has assigned to the child.
+    child_handle: Handle,
+
+    /// The parent’s handle.
+    parent_handle: Handle,
+
+    /// The parent’s BPKI trust anchor certificate.
+    id_cert: Cert,
+
+    /// Whether the parent offers repository service to the child.
+    offer: bool,
+
+    /// Referrals to other repositories.
+    referrals: Vec<Referral>,
+}
+
+impl ParentResponse {
+    /// Creates a new parent response.
+    ///
+    /// The response neither offers repository service nor contains
+    /// referrals. Returns an error if the service URI is empty or if the
+    /// certificate isn’t a self-signed CA certificate.
+    pub fn new(
+        tag: Option<String>,
+        service_uri: String,
+        child_handle: Handle,
+        parent_handle: Handle,
+        id_cert: Cert
+    ) -> Result<Self, Error> {
+        if service_uri.is_empty() || !is_bpki_ta(&id_cert) {
+            return Err(Error::Malformed)
+        }
+        Ok(ParentResponse {
+            tag, service_uri, child_handle, parent_handle, id_cert,
+            offer: false,
+            referrals: Vec::new(),
+        })
+    }
+
+    /// Returns the tag of the response if present.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_ref().map(AsRef::as_ref)
+    }
+
+    /// Returns the URI of the parent’s provisioning service.
+    pub fn service_uri(&self) -> &str {
+        &self.service_uri
+    }
+
+    /// Returns the handle the parent has assigned to the child.
+    pub fn child_handle(&self) -> &Handle {
+        &self.child_handle
+    }
+
+    /// Returns the parent’s handle.
+    pub fn parent_handle(&self) -> &Handle {
+        &self.parent_handle
+    }
+
+    /// Returns the parent’s BPKI trust anchor certificate.
+    pub fn id_cert(&self) -> &Cert {
+        &self.id_cert
+    }
+
+    /// Returns whether the parent offers repository service.
+    pub fn offer(&self) -> bool {
+        self.offer
+    }
+
+    /// Returns the referrals to other repositories.
+    pub fn referrals(&self) -> &[Referral] {
+        &self.referrals
+    }
+
+    /// Sets whether the parent offers repository service.
+    ///
+    /// Setting this to true removes all referrals since a response can
+    /// only contain one or the other.
+    pub fn set_offer(&mut self, offer: bool) {
+        self.offer = offer;
+        if offer {
+            self.referrals.clear()
+        }
+    }
+
+    /// Adds a referral to another repository.
+    ///
+    /// This removes a repository service offer since a response can only
+    /// contain one or the other.
+    pub fn push_referral(&mut self, referral: Referral) {
+        self.offer = false;
+        self.referrals.push(referral)
+    }
+
+    /// Creates the child’s publisher request to a repository.
+    ///
+    /// The request uses the tag of the response. If `referral` is given,
+    /// the request is for the repository of this referral and contains its
+    /// authorization. Otherwise, it is for the parent’s offered repository.
+    /// The remaining values are taken from the child’s configuration.
+    pub fn to_publisher_request(
+        &self,
+        referral: Option<&Referral>,
+        publisher_handle: Handle,
+        id_cert: Cert
+    ) -> Result<PublisherRequest, Error> {
+        let mut res = PublisherRequest::new(
+            self.tag.clone(), publisher_handle, id_cert
+        )?;
+        res.referral = referral.map(Referral::to_authorization);
+        Ok(res)
+    }
+
+    /// Parses a parent response from its XML representation.
+    ///
+    /// The base64 encoded certificate may contain white space and line
+    /// breaks. It must be a self-signed CA certificate. Its validity period
+    /// isn’t checked.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+
+        let mut tag = None;
+        let mut service_uri = None;
+        let mut child_handle = None;
+        let mut parent_handle = None;
+        let mut outer = reader.start(|element| {
+            if element.name() != PARENT_RESPONSE {
+                return Err(Error::Malformed)
+            }
+            let mut version = false;
+            element.attributes(|name, value| match name {
+                b"version" => {
+                    take_version(value)?;
+                    version = true;
+                    Ok(())
+                }
+                b"tag" => {
+                    tag = Some(value.ascii_into::<String>()?);
+                    Ok(())
+                }
+                b"service_uri" => {
+                    service_uri = Some(value.ascii_into::<String>()?);
+                    Ok(())
+                }
+                b"child_handle" => {
+                    child_handle = Some(value.ascii_into::<Handle>()?);
+                    Ok(())
+                }
+                b"parent_handle" => {
+                    parent_handle = Some(value.ascii_into::<Handle>()?);
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })?;
+            if version { Ok(()) } else { Err(Error::Malformed) }
+        })?;
+
+        let id_cert = take_bpki_ta(&mut outer, &mut reader, PARENT_BPKI_TA)?;
+
+        let mut offer = false;
+        let mut referrals = Vec::new();
+        loop {
+            let mut is_offer = false;
+            let mut referrer = None;
+            let mut contact_uri = None;
+            let inner = outer.take_opt_element(&mut reader, |element| {
+                if element.name() == OFFER {
+                    is_offer = true;
+                    element.attributes(|_, _| Err(Error::Malformed))
+                }
+                else if element.name() == REFERRAL {
+                    element.attributes(|name, value| match name {
+                        b"referrer" => {
+                            referrer = Some(value.ascii_into::<Handle>()?);
+                            Ok(())
+                        }
+                        b"contact_uri" => {
+                            contact_uri = Some(
+                                value.ascii_into::<String>()?
+                            );
+                            Ok(())
+                        }
+                        _ => Err(Error::Malformed)
+                    })
+                }
+                else {
+                    Err(Error::Malformed)
+                }
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+
+            // There can be either one offer or any number of referrals.
+            if offer || (is_offer && !referrals.is_empty()) {
+                return Err(Error::Malformed)
+            }
+            if is_offer {
+                offer = true;
+            }
+            else {
+                referrals.push(Referral::new(
+                    referrer.ok_or(Error::Malformed)?,
+                    contact_uri,
+                    inner.take_base64(&mut reader, REFERRAL)?
+                ));
+            }
+            inner.take_end(&mut reader)?;
+        }
+        reader.end()?;
+
+        let mut res = Self::new(
+            tag,
+            service_uri.ok_or(Error::Malformed)?,
+            child_handle.ok_or(Error::Malformed)?,
+            parent_handle.ok_or(Error::Malformed)?,
+            id_cert
+        )?;
+        res.offer = offer;
+        res.referrals = referrals;
+        Ok(res)
+    }
+
+    /// Writes the XML representation of the response to a target.
+    ///
+    /// The certificate and referral authorizations are written in lines
+    /// of 64 characters.
+    pub fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "<parent_response")?;
+        write_attr(target, "xmlns", str::from_utf8(NS).unwrap())?;
+        write_attr(target, "version", VERSION)?;
+        write_attr(target, "service_uri", &self.service_uri)?;
+        write_attr(target, "child_handle", &self.child_handle)?;
+        write_attr(target, "parent_handle", &self.parent_handle)?;
+        write_opt_attr(target, "tag", self.tag.as_ref())?;
+        writeln!(target, ">")?;
+        writeln!(target, "  <parent_bpki_ta>")?;
+        write_base64(target, &self.id_cert.to_captured().into_bytes())?;
+        writeln!(target, "  </parent_bpki_ta>")?;
+        if self.offer {
+            writeln!(target, "  <offer/>")?;
+        }
+        for referral in &self.referrals {
+            referral.write_xml(target)?;
+        }
+        writeln!(target, "</parent_response>")
+    }
+}
+
+
+//------------ Referral ------------------------------------------------------
+
+/// A referral to a repository other than the parent.
+///
+/// The referral contains an authorization token created by the referring
+/// party that the child presents to the repository. This token is kept as
+/// is.
+#[derive(Clone, Debug)]
+pub struct Referral {
+    /// The handle of the referring party.
+    referrer: Handle,
+
+    /// An optional URI for contacting the repository.
+    contact_uri: Option<String>,
+
+    /// The authorization token.
+    authorization: Bytes,
+}
+
+impl Referral {
+    /// Creates a new referral.
+    pub fn new(
+        referrer: Handle,
+        contact_uri: Option<String>,
+        authorization: Bytes
+    ) -> Self {
+        Referral { referrer, contact_uri, authorization }
+    }
+
+    /// Returns the handle of the referring party.
+    pub fn referrer(&self) -> &Handle {
+        &self.referrer
+    }
+
+    /// Returns the contact URI if present.
+    pub fn contact_uri(&self) -> Option<&str> {
+        self.contact_uri.as_ref().map(AsRef::as_ref)
+    }
+
+    /// Returns the authorization token.
+    pub fn authorization(&self) -> &Bytes {
+        &self.authorization
+    }
+
+    /// Returns the authorization to be included in a publisher request.
+    pub fn to_authorization(&self) -> Authorization {
+        Authorization::new(
+            self.referrer.clone(), self.authorization.clone()
+        )
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "  <referral")?;
+        write_attr(target, "referrer", &self.referrer)?;
+        write_opt_attr(target, "contact_uri", self.contact_uri.as_ref())?;
+        writeln!(target, ">")?;
+        write_base64(target, &self.authorization)?;
+        writeln!(target, "  </referral>")
+    }
+}
+
+
+//------------ Authorization -------------------------------------------------
+
+/// The authorization from a referral included in a publisher request.
+///
+/// When a parent refers a child to another repository, the child passes
+/// the authorization token on to that repository as part of its publisher
+/// request. Like in a [`Referral`], the token is kept as is.
+///
+/// [`Referral`]: struct.Referral.html
+#[derive(Clone, Debug)]
+pub struct Authorization {
+    /// The handle of the referring party.
+    referrer: Handle,
+
+    /// The authorization token.
+    token: Bytes,
+}
+
+impl Authorization {
+    /// Creates a new authorization.
+    pub fn new(referrer: Handle, token: Bytes) -> Self {
+        Authorization { referrer, token }
+    }
+
+    /// Returns the handle of the referring party.
+    pub fn referrer(&self) -> &Handle {
+        &self.referrer
+    }
+
+    /// Returns the authorization token.
+    pub fn token(&self) -> &Bytes {
+        &self.token
+    }
+
+    /// Takes an optional referral element from the content of a request.
+    fn take_opt_from<R: io::BufRead>(
+        content: &mut Content,
+        reader: &mut Reader<R>
+    ) -> Result<Option<Self>, Error> {
+        let mut referrer = None;
+        let inner = content.take_opt_element(reader, |element| {
+            if element.name() != REFERRAL {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"referrer" => {
+                    referrer = Some(value.ascii_into::<Handle>()?);
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        let mut inner = match inner {
+            Some(inner) => inner,
+            None => return Ok(None)
+        };
+        let token = inner.take_base64(reader, REFERRAL)?;
+        inner.take_end(reader)?;
+        Ok(Some(Authorization::new(
+            referrer.ok_or(Error::Malformed)?, token
+        )))
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "  <referral")?;
+        write_attr(target, "referrer", &self.referrer)?;
+        writeln!(target, ">")?;
+        write_base64(target, &self.token)?;
+        writeln!(target, "  </referral>")
+    }
+}
+
+
+//------------ Helpers -------------------------------------------------------
+
+/// Returns whether a certificate can be used as a BPKI trust anchor.
+fn is_bpki_ta(cert: &Cert) -> bool {
+    cert.basic_ca() == Some(true) && cert.is_self_signed()
+}
+
+/// Checks the value of a version attribute.
+fn take_version(value: AttrValue) -> Result<(), Error> {
+    let value = value.ascii_into::<String>()?;
+    if value != VERSION {
+        return Err(Error::UnsupportedVersion(value))
+    }
+    Ok(())
+}
+
+/// Takes an element with a base64 encoded BPKI trust anchor certificate.
+fn take_bpki_ta<R: io::BufRead>(
+    content: &mut Content,
+    reader: &mut Reader<R>,
+    name: Name
+) -> Result<Cert, Error> {
+    let mut inner = content.take_element(reader, |element| {
+        if element.name() != name {
+            return Err(Error::Malformed)
+        }
+        element.attributes(|_, _| Err(Error::Malformed))
+    })?;
+    let cert = Cert::decode(
+        inner.take_base64(reader, name)?
+    ).map_err(|_| Error::Malformed)?;
+    inner.take_end(reader)?;
+    Ok(cert)
+}
+
+/// Parses a publisher or child request.
+///
+/// Returns the tag, the handle, the certificate, and the authorization of
+/// an optional referral element which is only allowed if `referral` is
+/// true. The certificate is not checked beyond being present.
+fn parse_request<R: io::BufRead>(
+    reader: R,
+    root: Name,
+    handle_attr: &[u8],
+    bpki_ta: Name,
+    referral: bool
+) -> Result<(Option<String>, Handle, Cert, Option<Authorization>), Error> {
+    let mut reader = Reader::new(reader);
+
+    let mut tag = None;
+    let mut handle = None;
+    let mut outer = reader.start(|element| {
+        if element.name() != root {
+            return Err(Error::Malformed)
+        }
+        let mut version = false;
+        element.attributes(|name, value| {
+            if name == b"version" {
+                take_version(value)?;
+                version = true;
+            }
+            else if name == handle_attr {
+                handle = Some(value.ascii_into::<Handle>()?);
+            }
+            else if name == b"tag" {
+                tag = Some(value.ascii_into::<String>()?);
+            }
+            else {
+                return Err(Error::Malformed)
+            }
+            Ok(())
+        })?;
+        if version { Ok(()) } else { Err(Error::Malformed) }
+    })?;
+
+    let id_cert = take_bpki_ta(&mut outer, &mut reader, bpki_ta)?;
+    let referral = if referral {
+        Authorization::take_opt_from(&mut outer, &mut reader)?
+    }
+    else {
+        None
+    };
+    outer.take_end(&mut reader)?;
+    reader.end()?;
+
+    Ok((tag, handle.ok_or(Error::Malformed)?, id_cert, referral))
+}
+
+/// Writes a publisher or child request.
+fn write_request<W: io::Write>(
+    target: &mut W,
+    root: &str,
+    handle_attr: &str,
+    handle: &Handle,
+    tag: Option<&str>,
+    bpki_ta: &str,
+    id_cert: &Cert,
+    referral: Option<&Authorization>
+) -> io::Result<()> {
+    write!(target, "<{}", root)?;
+    write_attr(target, "xmlns", str::from_utf8(NS).unwrap())?;
+    write_attr(target, "version", VERSION)?;
+    write_attr(target, handle_attr, handle)?;
+    write_opt_attr(target, "tag", tag)?;
+    writeln!(target, ">")?;
+    writeln!(target, "  <{}>", bpki_ta)?;
+    write_base64(target, &id_cert.to_captured().into_bytes())?;
+    writeln!(target, "  </{}>", bpki_ta)?;
+    if let Some(referral) = referral {
+        referral.write_xml(target)?;
+    }
+    writeln!(target, "</{}>", root)
+}
+
+/// Writes base64 encoded data in indented lines of 64 characters.
+fn write_base64<W: io::Write>(target: &mut W, data: &[u8]) -> io::Result<()> {
+    let data = base64::encode(data);
+    for line in data.as_bytes().chunks(64) {
+        // Base64 is all ASCII, so this can’t fail.
+        writeln!(target, "    {}", str::from_utf8(line).unwrap())?;
+    }
+    Ok(())
+}
+
+
+//------------ Xml Names -----------------------------------------------------
+
+const NS: &[u8] = b"http://www.hactrn.net/uris/rpki/rpki-setup/";
+const PUBLISHER_REQUEST: Name = Name::qualified(NS, b"publisher_request");
+const PUBLISHER_BPKI_TA: Name = Name::qualified(NS, b"publisher_bpki_ta");
+const CHILD_REQUEST: Name = Name::qualified(NS, b"child_request");
+const CHILD_BPKI_TA: Name = Name::qualified(NS, b"child_bpki_ta");
+const PARENT_RESPONSE: Name = Name::qualified(NS, b"parent_response");
+const PARENT_BPKI_TA: Name = Name::qualified(NS, b"parent_bpki_ta");
+const OFFER: Name = Name::qualified(NS, b"offer");
+const REFERRAL: Name = Name::qualified(NS, b"referral");
+
+const VERSION: &str = "1";
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ta_cert() -> Cert {
+        Cert::decode(include_bytes!("../test-data/ta.cer").as_ref()).unwrap()
+    }
+
+    fn handle(s: &str) -> Handle {
+        s.parse().unwrap()
+    }
+
+    fn same_cert(left: &Cert, right: &Cert) -> bool {
+        left.to_captured().as_slice() == right.to_captured().as_slice()
+    }
+
+    /// Returns data base64 encoded in lines like in the RFC 8183 examples.
+    fn wrapped(data: &[u8]) -> String {
+        let mut res = String::new();
+        for line in base64::encode(data).as_bytes().chunks(64) {
+            res.push_str("    ");
+            res.push_str(str::from_utf8(line).unwrap());
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Returns a document in the form of the examples in RFC 8183.
+    ///
+    /// The test certificate is included in the `ta` element, `rest` is
+    /// added after that.
+    fn document(root: &str, attrs: &str, ta: &str, rest: &str) -> String {
+        format!(
+            "<{}\n    \
+             xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\"\n    \
+             {}>\n  \
+             <{}>\n{}  </{}>\n{}</{}>\n",
+            root, attrs,
+            ta, wrapped(include_bytes!("../test-data/ta.cer").as_ref()), ta,
+            rest, root
+        )
+    }
+
+    fn rfc_request(attrs: &str) -> String {
+        document("publisher_request", attrs, "publisher_bpki_ta", "")
+    }
+
+    fn round_trip(req: &PublisherRequest) -> PublisherRequest {
+        let mut xml = Vec::new();
+        req.write_xml(&mut xml).unwrap();
+        PublisherRequest::parse(xml.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn parse_rfc_example() {
+        let xml = rfc_request(
+            "version=\"1\"\n    \
+             publisher_handle=\"Bob\"\n    \
+             tag=\"A0001\""
+        );
+        let req = PublisherRequest::parse(xml.as_bytes()).unwrap();
+        assert_eq!(req.publisher_handle(), "Bob");
+        assert_eq!(req.tag(), Some("A0001"));
+        assert!(same_cert(req.id_cert(), &ta_cert()));
+
+        let req2 = round_trip(&req);
+        assert_eq!(req2.publisher_handle(), "Bob");
+        assert_eq!(req2.tag(), Some("A0001"));
+        assert!(same_cert(req2.id_cert(), &ta_cert()));
+    }
+
+    #[test]
+    fn parse_unwrapped_cert() {
+        let xml = format!(
+            "<publisher_request \
+              xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+              version=\"1\" publisher_handle=\"Bob\">\
+              <publisher_bpki_ta>{}</publisher_bpki_ta>\
+             </publisher_request>",
+            base64::encode(include_bytes!("../test-data/ta.cer").as_ref())
+        );
+        let req = PublisherRequest::parse(xml.as_bytes()).unwrap();
+        assert_eq!(req.tag(), None);
+        assert!(same_cert(req.id_cert(), &ta_cert()));
+    }
+
+    #[test]
+    fn parse_bad_requests() {
+        // Wrong version.
+        assert!(matches_unsupported(PublisherRequest::parse(
+            rfc_request("version=\"2\" publisher_handle=\"Bob\"").as_bytes()
+        )));
+
+        // Missing version or handle.
+        assert!(PublisherRequest::parse(
+            rfc_request("publisher_handle=\"Bob\"").as_bytes()
+        ).is_err());
+        assert!(PublisherRequest::parse(
+            rfc_request("version=\"1\"").as_bytes()
+        ).is_err());
+
+        // Invalid handle.
+        assert!(PublisherRequest::parse(
+            rfc_request("version=\"1\" publisher_handle=\"B b\"").as_bytes()
+        ).is_err());
+
+        // Unknown attribute.
+        assert!(PublisherRequest::parse(
+            rfc_request(
+                "version=\"1\" publisher_handle=\"Bob\" foo=\"bar\""
+            ).as_bytes()
+        ).is_err());
+
+        // Wrong namespace.
+        assert!(PublisherRequest::parse(
+            rfc_request("version=\"1\" publisher_handle=\"Bob\"").replace(
+                "http://www.hactrn.net/uris/rpki/rpki-setup/",
+                "urn:example:wrong"
+            ).as_bytes()
+        ).is_err());
+
+        // Broken base64.
+        match PublisherRequest::parse(
+            b"<publisher_request \
+               xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+               version=\"1\" publisher_handle=\"Bob\">\
+               <publisher_bpki_ta>MII!</publisher_bpki_ta>\
+              </publisher_request>".as_ref()
+        ) {
+            Err(Error::InvalidBase64(ref name)) => {
+                assert_eq!(name, "publisher_bpki_ta")
+            }
+            _ => panic!("broken base64 accepted")
+        }
+
+        // Not a self-signed certificate.
+        let xml = format!(
+            "<publisher_request \
+              xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+              version=\"1\" publisher_handle=\"Bob\">\
+              <publisher_bpki_ta>{}</publisher_bpki_ta>\
+             </publisher_request>",
+            base64::encode(include_bytes!("../test-data/ca1.cer").as_ref())
+        );
+        assert!(PublisherRequest::parse(xml.as_bytes()).is_err());
+    }
+
+    fn matches_unsupported(res: Result<PublisherRequest, Error>) -> bool {
+        match res {
+            Err(Error::UnsupportedVersion(ref version)) => version == "2",
+            _ => false
+        }
+    }
+
+    #[test]
+    fn write_request() {
+        let req = PublisherRequest::new(
+            None, handle("Bob"), ta_cert()
+        ).unwrap();
+        let mut xml = Vec::new();
+        req.write_xml(&mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.starts_with(
+            "<publisher_request \
+             xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+             version=\"1\" publisher_handle=\"Bob\">\n"
+        ));
+        assert!(xml.lines().skip(2).take_while(|line| {
+            line.starts_with("    ")
+        }).all(|line| line.len() <= 68));
+
+        let req = round_trip(&req);
+        assert_eq!(req.tag(), None);
+        assert_eq!(req.publisher_handle(), "Bob");
+        assert!(same_cert(req.id_cert(), &ta_cert()));
+    }
+
+    #[test]
+    fn new_request() {
+        assert!(PublisherRequest::new(
+            None, handle("Bob"),
+            Cert::decode(
+                include_bytes!("../test-data/ca1.cer").as_ref()
+            ).unwrap()
+        ).is_err());
+    }
+
+    #[test]
+    fn publisher_request_referral() {
+        let token = b"\x30\x03\x02\x01\x2a some opaque token";
+        let xml = document(
+            "publisher_request",
+            "version=\"1\"\n    \
+             publisher_handle=\"Bob\"\n    \
+             tag=\"A0001\"",
+            "publisher_bpki_ta",
+            &format!(
+                "  <referral referrer=\"Alice/Bob-42\">\n{}  </referral>\n",
+                wrapped(token.as_ref())
+            )
+        );
+        let req = PublisherRequest::parse(xml.as_bytes()).unwrap();
+        assert_eq!(req.tag(), Some("A0001"));
+        let referral = req.referral().unwrap();
+        assert_eq!(referral.referrer(), "Alice/Bob-42");
+        assert_eq!(referral.token().as_ref(), token.as_ref());
+
+        let req = round_trip(&req);
+        assert_eq!(req.tag(), Some("A0001"));
+        let referral = req.referral().unwrap();
+        assert_eq!(referral.referrer(), "Alice/Bob-42");
+        assert_eq!(referral.token().as_ref(), token.as_ref());
+
+        let (_, _, _, referral) = req.unpack();
+        assert!(referral.is_some());
+
+        // Without a referrer.
+        assert!(PublisherRequest::parse(
+            rfc_request("version=\"1\" publisher_handle=\"Bob\"").replace(
+                "</publisher_request>",
+                "<referral>AAAA</referral></publisher_request>"
+            ).as_bytes()
+        ).is_err());
+
+        // Two referrals.
+        assert!(PublisherRequest::parse(
+            rfc_request("version=\"1\" publisher_handle=\"Bob\"").replace(
+                "</publisher_request>",
+                "<referral referrer=\"Alice\">AAAA</referral>\
+                 <referral referrer=\"Alice\">AAAA</referral>\
+                 </publisher_request>"
+            ).as_bytes()
+        ).is_err());
+    }
+
+    #[test]
+    fn child_request() {
+        let xml = document(
+            "child_request", "version=\"1\"\n    child_handle=\"Bob\"",
+            "child_bpki_ta", ""
+        );
+        let req = ChildRequest::parse(xml.as_bytes()).unwrap();
+        assert_eq!(req.child_handle(), "Bob");
+        assert_eq!(req.tag(), None);
+        assert!(same_cert(req.id_cert(), &ta_cert()));
+
+        let mut xml = Vec::new();
+        req.write_xml(&mut xml).unwrap();
+        let req = ChildRequest::parse(xml.as_slice()).unwrap();
+        assert_eq!(req.child_handle(), "Bob");
+        assert_eq!(req.tag(), None);
+        assert!(same_cert(req.id_cert(), &ta_cert()));
+
+        // With a tag.
+        let xml = document(
+            "child_request",
+            "version=\"1\" child_handle=\"Bob\" tag=\"A0001\"",
+            "child_bpki_ta", ""
+        );
+        let req = ChildRequest::parse(xml.as_bytes()).unwrap();
+        assert_eq!(req.tag(), Some("A0001"));
+        let mut xml = Vec::new();
+        req.write_xml(&mut xml).unwrap();
+        let req = ChildRequest::parse(xml.as_slice()).unwrap();
+        assert_eq!(req.tag(), Some("A0001"));
+
+        // Child requests can’t contain referrals.
+        assert!(ChildRequest::parse(
+            document(
+                "child_request", "version=\"1\" child_handle=\"Bob\"",
+                "child_bpki_ta",
+                "  <referral referrer=\"Alice\">AAAA</referral>\n"
+            ).as_bytes()
+        ).is_err());
+
+        // Handles with illegal characters.
+        assert!(ChildRequest::parse(
+            document(
+                "child_request", "version=\"1\" child_handle=\"B.b\"",
+                "child_bpki_ta", ""
+            ).as_bytes()
+        ).is_err());
+
+        // A publisher request is not a child request.
+        assert!(ChildRequest::parse(
+            rfc_request("version=\"1\" publisher_handle=\"Bob\"").as_bytes()
+        ).is_err());
+        assert!(ChildRequest::parse(
+            document(
+                "child_request", "version=\"2\" child_handle=\"Bob\"",
+                "child_bpki_ta", ""
+            ).as_bytes()
+        ).is_err());
+    }
+
+    fn parent_response(rest: &str) -> String {
+        document(
+            "parent_response",
+            "version=\"1\"\n    \
+             service_uri=\"http://a.example/up-down/Alice/Bob-42\"\n    \
+             child_handle=\"Bob-42\"\n    \
+             parent_handle=\"Alice\"",
+            "parent_bpki_ta", rest
+        )
+    }
+
+    fn response_round_trip(res: &ParentResponse) -> ParentResponse {
+        let mut xml = Vec::new();
+        res.write_xml(&mut xml).unwrap();
+        ParentResponse::parse(xml.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn child_request_crlf() {
+        // Base64 wrapped with CRLF line endings and surrounded by extra
+        // white space as produced by some implementations.
+        let req = ChildRequest::parse(
+            include_bytes!("../test-data/oob-child-request-crlf.xml").as_ref()
+        ).unwrap();
+        assert_eq!(req.child_handle(), "Bob");
+        assert!(same_cert(req.id_cert(), &ta_cert()));
+    }
+
+    #[test]
+    fn parent_response_offer() {
+        let xml = parent_response("  <offer/>\n");
+        let res = ParentResponse::parse(xml.as_bytes()).unwrap();
+        assert_eq!(
+            res.service_uri(), "http://a.example/up-down/Alice/Bob-42"
+        );
+        assert_eq!(res.child_handle(), "Bob-42");
+        assert_eq!(res.parent_handle(), "Alice");
+        assert_eq!(res.tag(), None);
+        assert!(res.offer());
+        assert!(res.referrals().is_empty());
+        assert!(same_cert(res.id_cert(), &ta_cert()));
+
+        let res = response_round_trip(&res);
+        assert_eq!(
+            res.service_uri(), "http://a.example/up-down/Alice/Bob-42"
+        );
+        assert_eq!(res.child_handle(), "Bob-42");
+        assert_eq!(res.parent_handle(), "Alice");
+        assert!(res.offer());
+        assert!(res.referrals().is_empty());
+        assert!(same_cert(res.id_cert(), &ta_cert()));
+    }
+
+    #[test]
+    fn parent_response_referral() {
+        // The authorization is opaque, so any data will do.
+        let token = b"\x30\x03\x02\x01\x2a some opaque token";
+        let xml = parent_response(&format!(
+            "  <referral referrer=\"Alice/Bob-42\">\n{}  </referral>\n\
+               <referral referrer=\"Carol\" \
+                 contact_uri=\"https://c.example/\">{}</referral>\n",
+            wrapped(token.as_ref()),
+            base64::encode_config(b"other".as_ref(), base64::STANDARD_NO_PAD)
+        ));
+        let res = ParentResponse::parse(xml.as_bytes()).unwrap();
+        assert!(!res.offer());
+        assert_eq!(res.referrals().len(), 2);
+        assert_eq!(res.referrals()[0].referrer(), "Alice/Bob-42");
+        assert_eq!(res.referrals()[0].contact_uri(), None);
+        assert_eq!(
+            res.referrals()[0].authorization().as_ref(), token.as_ref()
+        );
+        assert_eq!(res.referrals()[1].referrer(), "Carol");
+        assert_eq!(
+            res.referrals()[1].contact_uri(), Some("https://c.example/")
+        );
+        assert_eq!(
+            res.referrals()[1].authorization().as_ref(), b"other".as_ref()
+        );
+
+        let res = response_round_trip(&res);
+        assert_eq!(res.referrals().len(), 2);
+        assert_eq!(
+            res.referrals()[0].authorization().as_ref(), token.as_ref()
+        );
+        assert_eq!(
+            res.referrals()[1].contact_uri(), Some("https://c.example/")
+        );
+    }
+
+    #[test]
+    fn parse_bad_responses() {
+        // Wrong version.
+        let xml = parent_response("").replace(
+            "version=\"1\"", "version=\"2\""
+        );
+        match ParentResponse::parse(xml.as_bytes()) {
+            Err(Error::UnsupportedVersion(ref version)) => {
+                assert_eq!(version, "2")
+            }
+            _ => panic!("wrong version accepted")
+        }
+
+        // Missing version.
+        assert!(ParentResponse::parse(
+            parent_response("").replace("version=\"1\"", "").as_bytes()
+        ).is_err());
+
+        // Missing service URI.
+        assert!(ParentResponse::parse(
+            parent_response("").replace(
+                "service_uri=\"http://a.example/up-down/Alice/Bob-42\"", ""
+            ).as_bytes()
+        ).is_err());
+
+        // Offer and referral.
+        assert!(ParentResponse::parse(
+            parent_response(
+                "<offer/><referral referrer=\"Alice\">AAAA</referral>"
+            ).as_bytes()
+        ).is_err());
+        assert!(ParentResponse::parse(
+            parent_response(
+                "<referral referrer=\"Alice\">AAAA</referral><offer/>"
+            ).as_bytes()
+        ).is_err());
+
+        // Two offers.
+        assert!(ParentResponse::parse(
+            parent_response("<offer/><offer/>").as_bytes()
+        ).is_err());
+
+        // Referral without referrer.
+        assert!(ParentResponse::parse(
+            parent_response("<referral>AAAA</referral>").as_bytes()
+        ).is_err());
+
+        // Unknown element.
+        assert!(ParentResponse::parse(
+            parent_response("<foo/>").as_bytes()
+        ).is_err());
+    }
+
+    #[test]
+    fn derive_publisher_request() {
+        let req = ChildRequest::new(
+            Some("A0001".into()), handle("Bob"), ta_cert()
+        ).unwrap();
+        let mut res = req.to_parent_response(
+            handle("Alice"), ta_cert(),
+            "https://a.example/up-down/Alice/Bob".into()
+        ).unwrap();
+        res.push_referral(Referral::new(
+            handle("Alice/Bob"), Some("https://c.example/".into()),
+            Bytes::from("token")
+        ));
+        let res = response_round_trip(&res);
+
+        // Request to the referred repository.
+        let publ = res.to_publisher_request(
+            Some(&res.referrals()[0]), handle("Bob"), ta_cert()
+        ).unwrap();
+        assert_eq!(publ.tag(), Some("A0001"));
+        assert_eq!(publ.publisher_handle(), "Bob");
+        let publ = round_trip(&publ);
+        assert_eq!(publ.tag(), Some("A0001"));
+        let referral = publ.referral().unwrap();
+        assert_eq!(referral.referrer(), "Alice/Bob");
+        assert_eq!(referral.token().as_ref(), b"token".as_ref());
+
+        // Request to the parent’s repository without a tag.
+        let res = ChildRequest::new(
+            None, handle("Bob"), ta_cert()
+        ).unwrap().to_parent_response(
+            handle("Alice"), ta_cert(),
+            "https://a.example/up-down/Alice/Bob".into()
+        ).unwrap();
+        let publ = round_trip(
+            &res.to_publisher_request(None, handle("Bob"), ta_cert()).unwrap()
+        );
+        assert_eq!(publ.tag(), None);
+        assert!(publ.referral().is_none());
+    }
+
+    #[test]
+    fn derive_parent_response() {
+        let req = ChildRequest::new(
+            Some("A0001".into()), handle("Bob"), ta_cert()
+        ).unwrap();
+        let mut res = req.to_parent_response(
+            handle("Alice"), ta_cert(),
+            "https://a.example/up-down/Alice/Bob".into()
+        ).unwrap();
+        assert_eq!(res.tag(), Some("A0001"));
+        assert_eq!(res.child_handle(), "Bob");
+        assert_eq!(res.parent_handle(), "Alice");
+        assert!(!res.offer());
+
+        res.set_offer(true);
+        assert!(res.offer());
+        res.push_referral(
+            Referral::new(handle("Alice"), None, Bytes::from("x"))
+        );
+        assert!(!res.offer());
+        assert_eq!(res.referrals().len(), 1);
+        res.set_offer(true);
+        assert!(res.referrals().is_empty());
+
+        let res = response_round_trip(&res);
+        assert_eq!(res.tag(), Some("A0001"));
+        assert!(res.offer());
+
+        assert!(req.to_parent_response(
+            handle("Alice"), ta_cert(), "".into()
+        ).is_err());
+    }
+}
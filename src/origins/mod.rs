@@ -0,0 +1,461 @@
+//! Route origins.
+//!
+//! Route origins, often also called _validated ROA payload_ or VRPs, are
+//! the final product of validating ROAs. Each route origin states that a
+//! certain AS is allowed to originate routes for a prefix and all its more
+//! specific prefixes up to a maximum length.
+//!
+//! The type [`RouteOrigin`] represents a single such statement while
+//! [`RouteOrigins`] is an ordered set of them, typically collected from
+//! many ROAs. The [`output`] module provides functions for writing route
+//! origins in various formats.
+//!
+//! [`RouteOrigin`]: struct.RouteOrigin.html
+//! [`RouteOrigins`]: struct.RouteOrigins.html
+//! [`output`]: output/index.html
+
+use std::{error, fmt};
+use std::collections::{btree_set, BTreeSet};
+use std::iter::FromIterator;
+use std::net::IpAddr;
+use std::str::FromStr;
+use crate::resources::AsId;
+use crate::roa::{FriendlyRoaIpAddress, RouteOriginAttestation};
+
+pub use self::validity::{Announcement, RouteValidity, validate};
+
+pub mod output;
+mod validity;
+
+
+//------------ RouteOrigin ---------------------------------------------------
+
+/// A single route origin.
+///
+/// Route origins are ordered by their address first, which places all IPv4
+/// prefixes before all IPv6 prefixes, then by prefix length, so that
+/// shorter prefixes come first, followed by max length and AS number.
+///
+/// The textual representation of a route origin is the prefix followed by
+/// a hyphen and the max length and finally an arrow and the AS number,
+/// e.g., `10.0.0.0/8-16 => AS64511`. When parsing, the max length can be
+/// omitted in which case it is the prefix length.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RouteOrigin {
+    /// The address of the prefix.
+    ///
+    /// All bits beyond the prefix length are zero.
+    address: IpAddr,
+
+    /// The length of the prefix.
+    address_length: u8,
+
+    /// The maximum length of more specific prefixes.
+    max_length: u8,
+
+    /// The AS number allowed to originate the prefix.
+    as_id: AsId,
+}
+
+impl RouteOrigin {
+    /// Creates a new route origin.
+    ///
+    /// Returns an error if the prefix length is too long for the address
+    /// family, if the max length is shorter than the prefix length or too
+    /// long for the family, or if there are bits set in `address` beyond
+    /// the prefix length.
+    pub fn new(
+        address: IpAddr,
+        address_length: u8,
+        max_length: u8,
+        as_id: AsId,
+    ) -> Result<Self, FromStrError> {
+        let family_len = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if address_length > family_len {
+            return Err(FromStrError::BadPrefixLength)
+        }
+        if max_length < address_length || max_length > family_len {
+            return Err(FromStrError::BadMaxLength)
+        }
+        if mask_address(address, address_length) != address {
+            return Err(FromStrError::HostBits)
+        }
+        Ok(RouteOrigin { address, address_length, max_length, as_id })
+    }
+
+    /// Creates a new route origin from a ROA’s prefix.
+    pub fn from_roa_address(
+        addr: &FriendlyRoaIpAddress,
+        as_id: AsId
+    ) -> Self {
+        RouteOrigin {
+            address: addr.address(),
+            address_length: addr.address_length(),
+            max_length: addr.max_length(),
+            as_id
+        }
+    }
+
+    /// Returns the address of the prefix.
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    /// Returns the length of the prefix.
+    pub fn address_length(&self) -> u8 {
+        self.address_length
+    }
+
+    /// Returns the max length.
+    pub fn max_length(&self) -> u8 {
+        self.max_length
+    }
+
+    /// Returns the AS number.
+    pub fn as_id(&self) -> AsId {
+        self.as_id
+    }
+}
+
+
+//--- FromStr
+
+impl FromStr for RouteOrigin {
+    type Err = FromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, "=>");
+        let prefix = parts.next().unwrap().trim();
+        let as_id = match parts.next() {
+            Some(as_id) => as_id.trim(),
+            None => return Err(FromStrError::MissingAsn),
+        };
+        let as_id = AsId::from_str(as_id).map_err(|_| FromStrError::BadAsn)?;
+
+        let (prefix, max_length) = match prefix.find('-') {
+            Some(pos) => (&prefix[..pos], Some(&prefix[pos + 1..])),
+            None => (prefix, None)
+        };
+        let pos = prefix.find('/').ok_or(FromStrError::MissingSeparator)?;
+        let address = IpAddr::from_str(&prefix[..pos]).map_err(|_| {
+            FromStrError::BadAddress
+        })?;
+        let address_length = u8::from_str(&prefix[pos + 1..]).map_err(|_| {
+            FromStrError::BadPrefixLength
+        })?;
+        let max_length = match max_length {
+            Some(max_length) => {
+                u8::from_str(max_length).map_err(|_| {
+                    FromStrError::BadMaxLength
+                })?
+            }
+            None => address_length
+        };
+        Self::new(address, address_length, max_length, as_id)
+    }
+}
+
+
+//--- Display
+
+impl fmt::Display for RouteOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "{}/{}-{} => {}",
+            self.address, self.address_length, self.max_length, self.as_id
+        )
+    }
+}
+
+
+//------------ RouteOrigins --------------------------------------------------
+
+/// An ordered set of route origins.
+///
+/// Route origins are kept in the order defined by [`RouteOrigin`]. Adding
+/// a route origin that is already present has no effect, so the set can be
+/// used to merge the route origins of many ROAs.
+///
+/// [`RouteOrigin`]: struct.RouteOrigin.html
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RouteOrigins {
+    origins: BTreeSet<RouteOrigin>,
+}
+
+impl RouteOrigins {
+    /// Creates a new, empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a route origin to the set.
+    ///
+    /// Returns whether the route origin was not yet present.
+    pub fn insert(&mut self, origin: RouteOrigin) -> bool {
+        self.origins.insert(origin)
+    }
+
+    /// Adds all route origins of a ROA to the set.
+    ///
+    /// The ROA should have been validated before.
+    pub fn push_roa(&mut self, roa: &RouteOriginAttestation) {
+        let as_id = roa.as_id();
+        self.origins.extend(
+            roa.iter().map(|addr| RouteOrigin::from_roa_address(&addr, as_id))
+        )
+    }
+
+    /// Returns whether the set contains the given route origin.
+    pub fn contains(&self, origin: &RouteOrigin) -> bool {
+        self.origins.contains(origin)
+    }
+
+    /// Returns an iterator over all route origins covering a prefix.
+    ///
+    /// A route origin covers a prefix if its own prefix is equal to or
+    /// less specific than the given prefix. The route origins are returned
+    /// starting with the least specific ones.
+    ///
+    /// Rather than scanning the whole set, this looks up each possible
+    /// covering prefix, so it only takes as many lookups as the prefix
+    /// length plus one.
+    pub fn iter_covering<'a>(
+        &'a self,
+        address: IpAddr,
+        address_length: u8
+    ) -> impl Iterator<Item = &'a RouteOrigin> + 'a {
+        (0..=address_length).flat_map(move |len| {
+            let address = mask_address(address, len);
+            self.origins.range(
+                RouteOrigin {
+                    address,
+                    address_length: len,
+                    max_length: 0,
+                    as_id: AsId::MIN,
+                }
+                ..=
+                RouteOrigin {
+                    address,
+                    address_length: len,
+                    max_length: std::u8::MAX,
+                    as_id: AsId::MAX,
+                }
+            )
+        })
+    }
+
+    /// Returns the number of route origins in the set.
+    pub fn len(&self) -> usize {
+        self.origins.len()
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+
+    /// Returns an iterator over the route origins in order.
+    pub fn iter(&self) -> btree_set::Iter<RouteOrigin> {
+        self.origins.iter()
+    }
+}
+
+
+//--- From
+
+impl<'a> From<&'a RouteOriginAttestation> for RouteOrigins {
+    fn from(roa: &'a RouteOriginAttestation) -> Self {
+        let mut res = Self::new();
+        res.push_roa(roa);
+        res
+    }
+}
+
+
+//--- FromIterator and Extend
+
+impl FromIterator<RouteOrigin> for RouteOrigins {
+    fn from_iter<I: IntoIterator<Item = RouteOrigin>>(iter: I) -> Self {
+        RouteOrigins { origins: BTreeSet::from_iter(iter) }
+    }
+}
+
+impl Extend<RouteOrigin> for RouteOrigins {
+    fn extend<I: IntoIterator<Item = RouteOrigin>>(&mut self, iter: I) {
+        self.origins.extend(iter)
+    }
+}
+
+
+//--- IntoIterator
+
+impl IntoIterator for RouteOrigins {
+    type Item = RouteOrigin;
+    type IntoIter = btree_set::IntoIter<RouteOrigin>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.origins.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a RouteOrigins {
+    type Item = &'a RouteOrigin;
+    type IntoIter = btree_set::Iter<'a, RouteOrigin>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.origins.iter()
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Returns the address with all bits beyond `len` cleared.
+fn mask_address(address: IpAddr, len: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(addr) => {
+            let mask = match len {
+                0 => 0,
+                len if len >= 32 => !0,
+                len => !0u32 << (32 - len)
+            };
+            IpAddr::V4((u32::from(addr) & mask).into())
+        }
+        IpAddr::V6(addr) => {
+            let mask = match len {
+                0 => 0,
+                len if len >= 128 => !0,
+                len => !0u128 << (128 - len)
+            };
+            IpAddr::V6((u128::from(addr) & mask).into())
+        }
+    }
+}
+
+
+//------------ FromStrError --------------------------------------------------
+
+/// An error happened while parsing a route origin.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FromStrError {
+    MissingSeparator,
+    MissingAsn,
+    BadAddress,
+    BadPrefixLength,
+    BadMaxLength,
+    HostBits,
+    BadAsn,
+}
+
+impl fmt::Display for FromStrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            FromStrError::MissingSeparator => "missing prefix length",
+            FromStrError::MissingAsn => "missing AS number",
+            FromStrError::BadAddress => "invalid address",
+            FromStrError::BadPrefixLength => "invalid prefix length",
+            FromStrError::BadMaxLength => "invalid max length",
+            FromStrError::HostBits => "bits set beyond prefix length",
+            FromStrError::BadAsn => "invalid AS number",
+        })
+    }
+}
+
+impl error::Error for FromStrError { }
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use crate::roa::RoaBuilder;
+    use super::*;
+
+    fn origin(s: &str) -> RouteOrigin {
+        RouteOrigin::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn from_str_and_display() {
+        let vrp = origin("10.0.0.0/8-16 => AS64511");
+        assert_eq!(vrp.address(), IpAddr::from(Ipv4Addr::new(10, 0, 0, 0)));
+        assert_eq!(vrp.address_length(), 8);
+        assert_eq!(vrp.max_length(), 16);
+        assert_eq!(vrp.as_id(), AsId::from(64511));
+        assert_eq!(vrp.to_string(), "10.0.0.0/8-16 => AS64511");
+
+        assert_eq!(
+            origin("2001:db8::/32=>AS64511").to_string(),
+            "2001:db8::/32-32 => AS64511"
+        );
+
+        assert_eq!(
+            RouteOrigin::from_str("10.0.0.0/8-16"),
+            Err(FromStrError::MissingAsn)
+        );
+        assert_eq!(
+            RouteOrigin::from_str("10.0.0.0/8-7 => AS1"),
+            Err(FromStrError::BadMaxLength)
+        );
+        assert_eq!(
+            RouteOrigin::from_str("10.0.0.0/8-33 => AS1"),
+            Err(FromStrError::BadMaxLength)
+        );
+        assert_eq!(
+            RouteOrigin::from_str("10.0.0.1/8 => AS1"),
+            Err(FromStrError::HostBits)
+        );
+        assert_eq!(
+            RouteOrigin::from_str("10.0.0.0/33 => AS1"),
+            Err(FromStrError::BadPrefixLength)
+        );
+    }
+
+    #[test]
+    fn from_roa() {
+        let mut roa = RoaBuilder::new(64511.into());
+        roa.push_v6_addr(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32, None
+        );
+        roa.push_v4_addr(Ipv4Addr::new(10, 0, 0, 0), 16, None);
+        roa.push_v4_addr(Ipv4Addr::new(10, 0, 0, 0), 8, Some(16));
+        let origins = RouteOrigins::from(&roa.to_attestation());
+        assert_eq!(
+            origins.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "10.0.0.0/8-16 => AS64511",
+                "10.0.0.0/16-16 => AS64511",
+                "2001:db8::/32-32 => AS64511",
+            ]
+        );
+    }
+
+    #[test]
+    fn ordering_and_dedup() {
+        let mut origins: RouteOrigins = vec![
+            origin("2001:db8::/32 => AS64511"),
+            origin("192.0.2.0/24 => AS64511"),
+            origin("10.0.0.0/16 => AS64511"),
+            origin("10.0.0.0/8-16 => AS64511"),
+        ].into_iter().collect();
+        origins.extend(vec![
+            origin("10.0.0.0/8-16 => AS64511"),
+            origin("10.0.0.0/8-16 => AS64496"),
+        ]);
+        assert_eq!(origins.len(), 5);
+        assert_eq!(
+            origins.into_iter().map(|o| o.to_string()).collect::<Vec<_>>(),
+            [
+                "10.0.0.0/8-16 => AS64496",
+                "10.0.0.0/8-16 => AS64511",
+                "10.0.0.0/16-16 => AS64511",
+                "192.0.2.0/24-24 => AS64511",
+                "2001:db8::/32-32 => AS64511",
+            ]
+        );
+    }
+}
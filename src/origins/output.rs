@@ -0,0 +1,241 @@
+//! Writing route origins in common output formats.
+//!
+//! This module provides functions that write a sequence of route origins
+//! to an [`io::Write`] in a number of formats commonly used by relying
+//! party software. Each route origin is accompanied by the name of the
+//! trust anchor it was derived from. The functions take an iterator over
+//! pairs of a route origin and a trust anchor name and write the items in
+//! the order given by the iterator. Sorted input, such as produced by
+//! iterating over [`RouteOrigins`], therefore results in deterministic
+//! output.
+//!
+//! All functions write their output item by item and don’t keep the whole
+//! document in memory.
+//!
+//! [`io::Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`RouteOrigins`]: ../struct.RouteOrigins.html
+
+use std::io;
+use super::RouteOrigin;
+
+
+//------------ write_csv -----------------------------------------------------
+
+/// Writes route origins as comma-separated values.
+///
+/// The output starts with a header line `ASN,IP Prefix,Max Length,Trust
+/// Anchor` followed by one line per route origin, e.g.,
+/// `AS65000,10.0.0.0/8,16,ripe`. Trust anchor names containing commas,
+/// quotes, or line breaks are quoted as described in RFC 4180.
+pub fn write_csv<'a, I, W>(iter: I, target: &mut W) -> Result<(), io::Error>
+where
+    I: IntoIterator<Item = (&'a RouteOrigin, &'a str)>,
+    W: io::Write
+{
+    writeln!(target, "ASN,IP Prefix,Max Length,Trust Anchor")?;
+    for (origin, ta) in iter {
+        write!(
+            target, "{},{}/{},{},",
+            origin.as_id(), origin.address(), origin.address_length(),
+            origin.max_length()
+        )?;
+        write_csv_field(ta, target)?;
+        writeln!(target)?;
+    }
+    Ok(())
+}
+
+/// Writes a single CSV field, quoting it if necessary.
+fn write_csv_field<W: io::Write>(
+    field: &str, target: &mut W
+) -> Result<(), io::Error> {
+    if !field.contains(&[',', '"', '\r', '\n'][..]) {
+        return target.write_all(field.as_bytes())
+    }
+    write!(target, "\"{}\"", field.replace('"', "\"\""))
+}
+
+
+//------------ write_json ----------------------------------------------------
+
+/// Writes route origins in the JSON format of the RIPE NCC RPKI Validator.
+///
+/// The output is a JSON object with a single member `"roas"` whose value
+/// is an array containing an object for each route origin:
+///
+/// ```txt
+/// {
+///   "roas": [
+///     { "asn": "AS65000", "prefix": "10.0.0.0/8", "maxLength": 16, "ta": "ripe" }
+///   ]
+/// }
+/// ```
+pub fn write_json<'a, I, W>(iter: I, target: &mut W) -> Result<(), io::Error>
+where
+    I: IntoIterator<Item = (&'a RouteOrigin, &'a str)>,
+    W: io::Write
+{
+    write!(target, "{{\n  \"roas\": [")?;
+    let mut first = true;
+    for (origin, ta) in iter {
+        if first {
+            first = false;
+        }
+        else {
+            write!(target, ",")?;
+        }
+        write!(
+            target,
+            "\n    {{ \"asn\": \"{}\", \"prefix\": \"{}/{}\", \
+             \"maxLength\": {}, \"ta\": \"",
+            origin.as_id(), origin.address(), origin.address_length(),
+            origin.max_length()
+        )?;
+        write_json_str(ta, target)?;
+        write!(target, "\" }}")?;
+    }
+    writeln!(target, "\n  ]\n}}")
+}
+
+/// Writes the content of a JSON string with all necessary escapes.
+fn write_json_str<W: io::Write>(
+    s: &str, target: &mut W
+) -> Result<(), io::Error> {
+    for ch in s.chars() {
+        match ch {
+            '"' => target.write_all(b"\\\"")?,
+            '\\' => target.write_all(b"\\\\")?,
+            '\n' => target.write_all(b"\\n")?,
+            '\r' => target.write_all(b"\\r")?,
+            '\t' => target.write_all(b"\\t")?,
+            ch if (ch as u32) < 0x20 => {
+                write!(target, "\\u{:04x}", ch as u32)?
+            }
+            ch => write!(target, "{}", ch)?,
+        }
+    }
+    Ok(())
+}
+
+
+//------------ write_rpsl ----------------------------------------------------
+
+/// Writes route origins as RPSL route objects.
+///
+/// Each route origin results in a `route` object for IPv4 prefixes or a
+/// `route6` object for IPv6 prefixes as defined in RFC 2622 and RFC 4012,
+/// respectively. Objects are separated by empty lines. The source of each
+/// object is derived from the trust anchor name, e.g., `ROA-RIPE-RPKI-ROOT`
+/// for a trust anchor named `ripe`.
+///
+/// Since RPSL route objects have no notion of a max length, only the
+/// prefix itself is included.
+pub fn write_rpsl<'a, I, W>(iter: I, target: &mut W) -> Result<(), io::Error>
+where
+    I: IntoIterator<Item = (&'a RouteOrigin, &'a str)>,
+    W: io::Write
+{
+    for (origin, ta) in iter {
+        writeln!(
+            target,
+            "{}: {}/{}\norigin: {}\ndescr: RPKI attestation\nmnt-by: NA\n\
+             source: ROA-{}-RPKI-ROOT\n",
+            if origin.address().is_ipv4() { "route" } else { "route6" },
+            origin.address(), origin.address_length(), origin.as_id(),
+            ta.to_uppercase()
+        )?;
+    }
+    Ok(())
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use super::*;
+
+    fn origins() -> Vec<(RouteOrigin, &'static str)> {
+        vec![
+            ("10.0.0.0/8-16 => AS65000", "ripe"),
+            ("192.0.2.0/24 => AS64496", "arin"),
+            ("2001:db8::/32-48 => AS64496", "ripe"),
+        ].into_iter().map(|(origin, ta)| {
+            (RouteOrigin::from_str(origin).unwrap(), ta)
+        }).collect()
+    }
+
+    fn write<F>(op: F) -> String
+    where
+        F: FnOnce(&[(RouteOrigin, &str)], &mut Vec<u8>) -> io::Result<()>
+    {
+        let mut target = Vec::new();
+        op(&origins(), &mut target).unwrap();
+        String::from_utf8(target).unwrap()
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            write(|origins, target| write_csv(
+                origins.iter().map(|(origin, ta)| (origin, *ta)), target
+            )),
+            include_str!("../../test-data/vrps.csv")
+        );
+    }
+
+    #[test]
+    fn csv_quoting() {
+        let origin = RouteOrigin::from_str(
+            "10.0.0.0/8 => AS65000"
+        ).unwrap();
+        let mut target = Vec::new();
+        write_csv(Some((&origin, "a \"b\", c")), &mut target).unwrap();
+        assert_eq!(
+            String::from_utf8(target).unwrap(),
+            "ASN,IP Prefix,Max Length,Trust Anchor\n\
+             AS65000,10.0.0.0/8,8,\"a \"\"b\"\", c\"\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            write(|origins, target| write_json(
+                origins.iter().map(|(origin, ta)| (origin, *ta)), target
+            )),
+            include_str!("../../test-data/vrps.json")
+        );
+    }
+
+    #[test]
+    fn json_empty_and_escaped() {
+        let mut target = Vec::new();
+        write_json(None, &mut target).unwrap();
+        assert_eq!(
+            String::from_utf8(target).unwrap(),
+            "{\n  \"roas\": [\n  ]\n}\n"
+        );
+
+        let origin = RouteOrigin::from_str(
+            "10.0.0.0/8 => AS65000"
+        ).unwrap();
+        let mut target = Vec::new();
+        write_json(Some((&origin, "a\"b\\c\u{1}")), &mut target).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(
+            &target
+        ).unwrap();
+        assert_eq!(json["roas"][0]["ta"], "a\"b\\c\u{1}");
+    }
+
+    #[test]
+    fn rpsl() {
+        assert_eq!(
+            write(|origins, target| write_rpsl(
+                origins.iter().map(|(origin, ta)| (origin, *ta)), target
+            )),
+            include_str!("../../test-data/vrps.rpsl")
+        );
+    }
+}
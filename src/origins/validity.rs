@@ -0,0 +1,260 @@
+//! Route origin validation.
+//!
+//! This module implements the procedure for determining the validity state
+//! of a BGP route from a set of route origins as defined in [RFC 6811].
+//!
+//! [RFC 6811]: https://tools.ietf.org/html/rfc6811
+
+use std::fmt;
+use std::net::IpAddr;
+use crate::resources::AsId;
+use super::{FromStrError, RouteOrigin, RouteOrigins, mask_address};
+
+
+//------------ Announcement --------------------------------------------------
+
+/// A BGP route announcement to be validated.
+///
+/// An announcement consists of the announced prefix and the origin AS of
+/// the route, i.e., the last AS in its AS path.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Announcement {
+    /// The address of the announced prefix.
+    address: IpAddr,
+
+    /// The length of the announced prefix.
+    address_length: u8,
+
+    /// The origin AS of the route.
+    origin: AsId,
+}
+
+impl Announcement {
+    /// Creates a new announcement.
+    ///
+    /// Returns an error if the prefix length is too long for the address
+    /// family or if bits beyond the prefix length are set in `address`.
+    pub fn new(
+        address: IpAddr,
+        address_length: u8,
+        origin: AsId
+    ) -> Result<Self, FromStrError> {
+        let family_len = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if address_length > family_len {
+            return Err(FromStrError::BadPrefixLength)
+        }
+        if mask_address(address, address_length) != address {
+            return Err(FromStrError::HostBits)
+        }
+        Ok(Announcement { address, address_length, origin })
+    }
+
+    /// Returns the address of the announced prefix.
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    /// Returns the length of the announced prefix.
+    pub fn address_length(&self) -> u8 {
+        self.address_length
+    }
+
+    /// Returns the origin AS of the route.
+    pub fn origin(&self) -> AsId {
+        self.origin
+    }
+
+    /// Returns whether a route origin matches the announcement.
+    ///
+    /// The route origin must cover the announcement, the announced prefix
+    /// must not be longer than the max length, and the AS numbers must be
+    /// equal. A route origin for AS 0 never matches as specified in
+    /// RFC 7607.
+    fn is_matched_by(&self, origin: &RouteOrigin) -> bool {
+        origin.as_id() != AsId::from(0)
+            && origin.as_id() == self.origin
+            && self.address_length <= origin.max_length()
+    }
+}
+
+
+//--- Display
+
+impl fmt::Display for Announcement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "{}/{} => {}", self.address, self.address_length, self.origin
+        )
+    }
+}
+
+
+//------------ RouteValidity -------------------------------------------------
+
+/// The validation state of a route as defined in RFC 6811.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RouteValidity {
+    /// At least one route origin matches the route.
+    Valid,
+
+    /// The route is covered by at least one route origin but none matches.
+    Invalid,
+
+    /// No route origin covers the route.
+    NotFound,
+}
+
+impl RouteValidity {
+    /// Returns a string slice with the conventional name of the state.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RouteValidity::Valid => "valid",
+            RouteValidity::Invalid => "invalid",
+            RouteValidity::NotFound => "not-found",
+        }
+    }
+}
+
+
+//--- Display
+
+impl fmt::Display for RouteValidity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+//------------ validate ------------------------------------------------------
+
+/// Determines the validity state of an announcement.
+///
+/// This follows section 2 of RFC 6811: If no route origin covers the
+/// announced prefix, the route is ‘not found.’ If there is at least one
+/// covering route origin with the same AS number as the route’s origin
+/// and a max length not shorter than the announced prefix, the route is
+/// ‘valid.’ Otherwise, it is ‘invalid.’ Route origins for AS 0 cover
+/// prefixes but never match, thus making all routes they cover invalid
+/// unless another route origin matches.
+///
+/// Only route origins that cover the announcement are looked at, so the
+/// effort depends on the length of the prefix rather than the size of the
+/// set.
+pub fn validate(
+    announcement: &Announcement,
+    origins: &RouteOrigins
+) -> RouteValidity {
+    let mut res = RouteValidity::NotFound;
+    for origin in origins.iter_covering(
+        announcement.address, announcement.address_length
+    ) {
+        if announcement.is_matched_by(origin) {
+            return RouteValidity::Valid
+        }
+        res = RouteValidity::Invalid;
+    }
+    res
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use super::*;
+
+    fn origins(list: &[&str]) -> RouteOrigins {
+        list.iter().map(|s| RouteOrigin::from_str(s).unwrap()).collect()
+    }
+
+    fn announcement(prefix: &str, origin: u32) -> Announcement {
+        let pos = prefix.find('/').unwrap();
+        Announcement::new(
+            IpAddr::from_str(&prefix[..pos]).unwrap(),
+            u8::from_str(&prefix[pos + 1..]).unwrap(),
+            origin.into()
+        ).unwrap()
+    }
+
+    fn check(
+        origins: &RouteOrigins, prefix: &str, origin: u32
+    ) -> RouteValidity {
+        validate(&announcement(prefix, origin), origins)
+    }
+
+    #[test]
+    fn rfc6811_states() {
+        let vrps = origins(&[
+            "10.0.0.0/16-24 => AS64496",
+            "10.0.0.0/16 => AS64497",
+            "2001:db8::/32-48 => AS64496",
+        ]);
+
+        // Covered and matched.
+        assert_eq!(check(&vrps, "10.0.0.0/16", 64496), RouteValidity::Valid);
+        assert_eq!(check(&vrps, "10.0.1.0/24", 64496), RouteValidity::Valid);
+        assert_eq!(check(&vrps, "10.0.0.0/16", 64497), RouteValidity::Valid);
+        assert_eq!(
+            check(&vrps, "2001:db8:1::/48", 64496), RouteValidity::Valid
+        );
+
+        // Covered but longer than max length.
+        assert_eq!(
+            check(&vrps, "10.0.0.0/25", 64496), RouteValidity::Invalid
+        );
+        assert_eq!(
+            check(&vrps, "10.0.1.0/24", 64497), RouteValidity::Invalid
+        );
+
+        // Covered but the wrong origin.
+        assert_eq!(
+            check(&vrps, "10.0.0.0/16", 64498), RouteValidity::Invalid
+        );
+        assert_eq!(
+            check(&vrps, "2001:db8::/32", 64497), RouteValidity::Invalid
+        );
+
+        // Not covered: different prefix, less specific prefix, other family.
+        assert_eq!(
+            check(&vrps, "10.1.0.0/16", 64496), RouteValidity::NotFound
+        );
+        assert_eq!(
+            check(&vrps, "10.0.0.0/8", 64496), RouteValidity::NotFound
+        );
+        assert_eq!(
+            check(&vrps, "2001:db9::/32", 64496), RouteValidity::NotFound
+        );
+        assert_eq!(check(&vrps, "0.0.0.0/0", 64496), RouteValidity::NotFound);
+    }
+
+    #[test]
+    fn as0() {
+        let vrps = origins(&[
+            "192.0.2.0/24-32 => AS0",
+        ]);
+        assert_eq!(check(&vrps, "192.0.2.0/24", 0), RouteValidity::Invalid);
+        assert_eq!(
+            check(&vrps, "192.0.2.0/24", 64496), RouteValidity::Invalid
+        );
+        assert_eq!(
+            check(&vrps, "192.0.2.128/25", 64496), RouteValidity::Invalid
+        );
+        assert_eq!(
+            check(&vrps, "198.51.100.0/24", 64496), RouteValidity::NotFound
+        );
+
+        // An additional matching route origin still makes the route valid.
+        let vrps = origins(&[
+            "192.0.2.0/24-32 => AS0",
+            "192.0.2.0/24 => AS64496",
+        ]);
+        assert_eq!(check(&vrps, "192.0.2.0/24", 64496), RouteValidity::Valid);
+        assert_eq!(
+            check(&vrps, "192.0.2.0/25", 64496), RouteValidity::Invalid
+        );
+    }
+}
@@ -0,0 +1,2291 @@
+//! The RPKI provisioning protocol.
+//!
+//! This module contains the messages exchanged between a child CA and its
+//! parent CA as defined in [RFC 6492]. A child uses them to learn which
+//! resources it is entitled to, to request certificates for its keys, and
+//! to have certificates for retired keys revoked.
+//!
+//! All messages are represented by [`Message`] which carries the sender and
+//! recipient handles as well as the actual [`ProvisioningMessage`].
+//!
+//! The messages are exchanged wrapped into CMS signed messages as provided
+//! by the [`sigmsg`] module. [`ProvisioningCms`] combines the two steps when
+//! sending and receiving messages and also checks that the sender and
+//! recipient handles are the expected ones.
+//!
+//! [RFC 6492]: https://tools.ietf.org/html/rfc6492
+//! [`Message`]: struct.Message.html
+//! [`ProvisioningMessage`]: enum.ProvisioningMessage.html
+//! [`sigmsg`]: ../sigmsg/index.html
+//! [`ProvisioningCms`]: struct.ProvisioningCms.html
+
+use std::{error, fmt, io};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use bytes::Bytes;
+use chrono::SecondsFormat;
+use crate::uri;
+use crate::cert::Cert;
+use crate::crypto::{KeyIdentifier, Signer, SigningError};
+use crate::csr::Csr;
+use crate::handle::Handle;
+use crate::resources::{AddressFamily, AsBlocks, IpBlocks};
+use crate::sigmsg::{SignedMessage, SignedXmlError};
+use crate::x509::{Time, Validity};
+use crate::xml::decode::{Content, Element, Error, Name, Reader};
+use crate::xml::encode::{Escaped, write_attr, write_opt_attr};
+
+
+//------------ Message -------------------------------------------------------
+
+/// A provisioning protocol message.
+#[derive(Clone, Debug)]
+pub struct Message {
+    /// The handle of the sender of the message.
+    sender: Handle,
+
+    /// The handle of the intended recipient of the message.
+    recipient: Handle,
+
+    /// The actual content of the message.
+    payload: ProvisioningMessage,
+}
+
+impl Message {
+    pub fn new(
+        sender: Handle,
+        recipient: Handle,
+        payload: ProvisioningMessage
+    ) -> Self {
+        Message { sender, recipient, payload }
+    }
+
+    pub fn sender(&self) -> &Handle {
+        &self.sender
+    }
+
+    pub fn recipient(&self) -> &Handle {
+        &self.recipient
+    }
+
+    pub fn payload(&self) -> &ProvisioningMessage {
+        &self.payload
+    }
+
+    pub fn into_payload(self) -> ProvisioningMessage {
+        self.payload
+    }
+
+    /// Converts the message into its payload, turning errors into errors.
+    ///
+    /// If the message is an error response, returns the response as the
+    /// error. Otherwise returns the payload.
+    pub fn into_reply(
+        self
+    ) -> Result<ProvisioningMessage, NotPerformedResponse> {
+        match self.payload {
+            ProvisioningMessage::ErrorResponse(err) => Err(err),
+            payload => Ok(payload)
+        }
+    }
+
+    /// Parses a message from its XML representation.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+
+        let mut attrs = None;
+        let mut outer = reader.start(|element| {
+            if element.name() != MESSAGE {
+                return Err(Error::Malformed)
+            }
+            attrs = Some(Attributes::from_element(&element)?);
+            Ok(())
+        })?;
+        let mut attrs = attrs.unwrap(); // Or we'd have exited already.
+        if attrs.take::<u8>(b"version")? != 1 {
+            return Err(Error::Malformed)
+        }
+        let sender = attrs.take(b"sender")?;
+        let recipient = attrs.take(b"recipient")?;
+        let msg_type = attrs.take::<String>(b"type")?;
+        attrs.check_empty()?;
+
+        let payload = match msg_type.as_str() {
+            LIST_TYPE => ProvisioningMessage::List,
+            LIST_RESPONSE_TYPE => {
+                ProvisioningMessage::ListResponse(
+                    ListResponse::take_from(&mut outer, &mut reader)?
+                )
+            }
+            ISSUE_TYPE => {
+                ProvisioningMessage::Issue(
+                    IssueRequest::take_from(&mut outer, &mut reader)?
+                )
+            }
+            ISSUE_RESPONSE_TYPE => {
+                ProvisioningMessage::IssueResponse(
+                    IssueResponse::take_from(&mut outer, &mut reader)?
+                )
+            }
+            REVOKE_TYPE => {
+                ProvisioningMessage::Revoke(
+                    RevocationRequest::take_from(&mut outer, &mut reader)?
+                )
+            }
+            REVOKE_RESPONSE_TYPE => {
+                ProvisioningMessage::RevokeResponse(RevocationResponse(
+                    RevocationRequest::take_from(&mut outer, &mut reader)?
+                ))
+            }
+            ERROR_RESPONSE_TYPE => {
+                ProvisioningMessage::ErrorResponse(
+                    NotPerformedResponse::take_from(&mut outer, &mut reader)?
+                )
+            }
+            _ => return Err(Error::Malformed)
+        };
+
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+
+        Ok(Message { sender, recipient, payload })
+    }
+
+    /// Writes the XML representation of the message to a target.
+    pub fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "<message")?;
+        write_attr(target, "xmlns", NS)?;
+        write_attr(target, "version", 1)?;
+        write_attr(target, "sender", &self.sender)?;
+        write_attr(target, "recipient", &self.recipient)?;
+        write_attr(target, "type", self.payload.msg_type())?;
+        writeln!(target, ">")?;
+        self.payload.write_xml(target)?;
+        writeln!(target, "</message>")
+    }
+}
+
+
+//------------ ProvisioningMessage -------------------------------------------
+
+/// The content of a provisioning protocol message.
+#[derive(Clone, Debug)]
+pub enum ProvisioningMessage {
+    /// A child asks for the resource classes it is entitled to.
+    List,
+
+    /// The parent answers with the child’s resource classes.
+    ListResponse(ListResponse),
+
+    /// A child requests a certificate in a resource class.
+    Issue(IssueRequest),
+
+    /// The parent answers with the issued certificate.
+    IssueResponse(IssueResponse),
+
+    /// A child requests revocation of all certificates for a key.
+    Revoke(RevocationRequest),
+
+    /// The parent confirms the revocation.
+    RevokeResponse(RevocationResponse),
+
+    /// The parent was unable to perform the request.
+    ErrorResponse(NotPerformedResponse),
+}
+
+impl ProvisioningMessage {
+    /// Returns the value of the type attribute for the message.
+    pub fn msg_type(&self) -> &'static str {
+        match *self {
+            ProvisioningMessage::List => LIST_TYPE,
+            ProvisioningMessage::ListResponse(_) => LIST_RESPONSE_TYPE,
+            ProvisioningMessage::Issue(_) => ISSUE_TYPE,
+            ProvisioningMessage::IssueResponse(_) => ISSUE_RESPONSE_TYPE,
+            ProvisioningMessage::Revoke(_) => REVOKE_TYPE,
+            ProvisioningMessage::RevokeResponse(_) => REVOKE_RESPONSE_TYPE,
+            ProvisioningMessage::ErrorResponse(_) => ERROR_RESPONSE_TYPE,
+        }
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        match *self {
+            ProvisioningMessage::List => Ok(()),
+            ProvisioningMessage::ListResponse(ref list) => {
+                list.write_xml(target)
+            }
+            ProvisioningMessage::Issue(ref issue) => issue.write_xml(target),
+            ProvisioningMessage::IssueResponse(ref issue) => {
+                issue.write_xml(target)
+            }
+            ProvisioningMessage::Revoke(ref revoke) => {
+                revoke.write_xml(target)
+            }
+            ProvisioningMessage::RevokeResponse(ref revoke) => {
+                revoke.write_xml(target)
+            }
+            ProvisioningMessage::ErrorResponse(ref err) => {
+                err.write_xml(target)
+            }
+        }
+    }
+}
+
+
+//------------ ListResponse --------------------------------------------------
+
+/// The resource classes a child is entitled to.
+#[derive(Clone, Debug)]
+pub struct ListResponse {
+    classes: Vec<ResourceClass>,
+}
+
+impl ListResponse {
+    pub fn new(classes: Vec<ResourceClass>) -> Self {
+        ListResponse { classes }
+    }
+
+    pub fn classes(&self) -> &[ResourceClass] {
+        &self.classes
+    }
+
+    pub fn into_classes(self) -> Vec<ResourceClass> {
+        self.classes
+    }
+
+    fn take_from<R: io::BufRead>(
+        content: &mut Content,
+        reader: &mut Reader<R>
+    ) -> Result<Self, Error> {
+        let mut classes = Vec::new();
+        while let Some(class) = ResourceClass::take_opt_from(
+            content, reader
+        )? {
+            classes.push(class)
+        }
+        Ok(ListResponse { classes })
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        for class in &self.classes {
+            class.write_xml(target)?;
+        }
+        Ok(())
+    }
+}
+
+
+//------------ IssueRequest --------------------------------------------------
+
+/// A request for a certificate in a resource class.
+#[derive(Clone, Debug)]
+pub struct IssueRequest {
+    /// The name of the resource class.
+    class_name: String,
+
+    /// The resources to be included in the certificate if limited.
+    limit: RequestResourceLimit,
+
+    /// The certificate signing request.
+    csr: Csr,
+}
+
+impl IssueRequest {
+    pub fn new(
+        class_name: String,
+        limit: RequestResourceLimit,
+        csr: Csr
+    ) -> Self {
+        IssueRequest { class_name, limit, csr }
+    }
+
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    pub fn limit(&self) -> &RequestResourceLimit {
+        &self.limit
+    }
+
+    pub fn csr(&self) -> &Csr {
+        &self.csr
+    }
+
+    fn take_from<R: io::BufRead>(
+        content: &mut Content,
+        reader: &mut Reader<R>
+    ) -> Result<Self, Error> {
+        let mut attrs = None;
+        let inner = content.take_opt_element(reader, |element| {
+            if element.name() != REQUEST {
+                return Err(Error::Malformed)
+            }
+            attrs = Some(Attributes::from_element(&element)?);
+            Ok(())
+        })?;
+        let mut inner = match inner {
+            Some(inner) => inner,
+            None => return Err(Error::Malformed)
+        };
+        let mut attrs = attrs.unwrap(); // Or we'd have exited already.
+        let class_name = attrs.take(b"class_name")?;
+        let limit = RequestResourceLimit::take_from(&mut attrs)?;
+        attrs.check_empty()?;
+        let csr = Csr::decode(inner.take_base64(reader, REQUEST)?)
+            .map_err(|_| Error::Malformed)?;
+        inner.take_end(reader)?;
+        Ok(IssueRequest { class_name, limit, csr })
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "  <request")?;
+        write_attr(target, "class_name", &self.class_name)?;
+        self.limit.write_xml(target)?;
+        writeln!(
+            target, ">{}</request>",
+            base64::encode(&self.csr.to_captured().into_bytes())
+        )
+    }
+}
+
+
+//------------ IssueResponse -------------------------------------------------
+
+/// The answer to an issue request.
+///
+/// This is a resource class with exactly one certificate, the newly issued
+/// one.
+#[derive(Clone, Debug)]
+pub struct IssueResponse {
+    class: ResourceClass,
+}
+
+impl IssueResponse {
+    /// Creates a new issue response.
+    ///
+    /// Returns the class as an error if it doesn’t contain exactly one
+    /// certificate.
+    pub fn new(class: ResourceClass) -> Result<Self, ResourceClass> {
+        if class.certificates.len() == 1 {
+            Ok(IssueResponse { class })
+        }
+        else {
+            Err(class)
+        }
+    }
+
+    pub fn class(&self) -> &ResourceClass {
+        &self.class
+    }
+
+    /// Returns the issued certificate.
+    pub fn issued(&self) -> &IssuedCert {
+        &self.class.certificates[0]
+    }
+
+    pub fn into_class(self) -> ResourceClass {
+        self.class
+    }
+
+    fn take_from<R: io::BufRead>(
+        content: &mut Content,
+        reader: &mut Reader<R>
+    ) -> Result<Self, Error> {
+        match ResourceClass::take_opt_from(content, reader)? {
+            Some(class) => {
+                IssueResponse::new(class).map_err(|_| Error::Malformed)
+            }
+            None => Err(Error::Malformed)
+        }
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        self.class.write_xml(target)
+    }
+}
+
+
+//------------ RevocationRequest ---------------------------------------------
+
+/// A request to revoke all certificates for a key.
+///
+/// The key is identified by its key identifier which is transmitted in
+/// base64url encoding. It is written without padding but accepted with
+/// padding, too.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevocationRequest {
+    /// The name of the resource class.
+    class_name: String,
+
+    /// The key identifier of the key.
+    key: KeyIdentifier,
+}
+
+impl RevocationRequest {
+    pub fn new(class_name: String, key: KeyIdentifier) -> Self {
+        RevocationRequest { class_name, key }
+    }
+
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    pub fn key(&self) -> KeyIdentifier {
+        self.key
+    }
+
+    /// Creates the response confirming this request.
+    pub fn to_response(&self) -> RevocationResponse {
+        RevocationResponse(self.clone())
+    }
+
+    fn take_from<R: io::BufRead>(
+        content: &mut Content,
+        reader: &mut Reader<R>
+    ) -> Result<Self, Error> {
+        let mut attrs = None;
+        let inner = content.take_opt_element(reader, |element| {
+            if element.name() != KEY {
+                return Err(Error::Malformed)
+            }
+            attrs = Some(Attributes::from_element(&element)?);
+            Ok(())
+        })?;
+        let mut inner = match inner {
+            Some(inner) => inner,
+            None => return Err(Error::Malformed)
+        };
+        let mut attrs = attrs.unwrap(); // Or we'd have exited already.
+        let class_name = attrs.take(b"class_name")?;
+        let key = attrs.take::<String>(b"ski")?;
+        attrs.check_empty()?;
+        let key = base64::decode_config(
+            key.trim_end_matches('='), base64::URL_SAFE_NO_PAD
+        ).map_err(|_| Error::Malformed)?;
+        let key = KeyIdentifier::try_from(key.as_slice())
+            .map_err(|_| Error::Malformed)?;
+        inner.take_end(reader)?;
+        Ok(RevocationRequest { class_name, key })
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "  <key")?;
+        write_attr(target, "class_name", &self.class_name)?;
+        write_attr(
+            target, "ski",
+            base64::encode_config(&self.key, base64::URL_SAFE_NO_PAD)
+        )?;
+        writeln!(target, "/>")
+    }
+}
+
+
+//------------ RevocationResponse --------------------------------------------
+
+/// The parent’s confirmation of a revocation request.
+///
+/// The response echoes the class name and key of the request. In order to
+/// avoid mismatches, it can only be created from the request via
+/// [`RevocationRequest::to_response`] or by parsing a message.
+///
+/// [`RevocationRequest::to_response`]: struct.RevocationRequest.html#method.to_response
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevocationResponse(RevocationRequest);
+
+impl RevocationResponse {
+    pub fn class_name(&self) -> &str {
+        self.0.class_name()
+    }
+
+    pub fn key(&self) -> KeyIdentifier {
+        self.0.key()
+    }
+
+    /// Returns whether this response confirms the given request.
+    pub fn confirms(&self, request: &RevocationRequest) -> bool {
+        self.0 == *request
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        self.0.write_xml(target)
+    }
+}
+
+impl From<RevocationRequest> for RevocationResponse {
+    fn from(request: RevocationRequest) -> Self {
+        RevocationResponse(request)
+    }
+}
+
+
+//------------ NotPerformedResponse ------------------------------------------
+
+/// The parent was unable to perform a request.
+///
+/// The response contains a status code and, optionally, a human readable
+/// description of the error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NotPerformedResponse {
+    /// The status code.
+    status: NotPerformedCode,
+
+    /// The description of the error.
+    description: Option<String>,
+}
+
+impl NotPerformedResponse {
+    pub fn new(
+        status: NotPerformedCode,
+        description: Option<String>
+    ) -> Self {
+        NotPerformedResponse { status, description }
+    }
+
+    /// Creates a response with the description of the code from RFC 6492.
+    ///
+    /// For unknown codes, the response will not have a description.
+    pub fn from_code(status: NotPerformedCode) -> Self {
+        NotPerformedResponse::new(
+            status, status.description().map(Into::into)
+        )
+    }
+
+    pub fn status(&self) -> NotPerformedCode {
+        self.status
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn take_from<R: io::BufRead>(
+        content: &mut Content,
+        reader: &mut Reader<R>
+    ) -> Result<Self, Error> {
+        let mut status = content.take_element(reader, |element| {
+            if element.name() != STATUS {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|_, _| Err(Error::Malformed))
+        })?;
+        let code = status.take_text(reader, |text| {
+            u64::from_str(text.to_ascii()?.trim())
+                .map_err(|_| Error::Malformed)
+        })?;
+        status.take_end(reader)?;
+
+        let description = content.take_opt_element(reader, |element| {
+            if element.name() != DESCRIPTION {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, _| {
+                if name == b"xml:lang" { Ok(()) }
+                else { Err(Error::Malformed) }
+            })
+        })?;
+        let description = match description {
+            Some(mut description) => {
+                let text = description.take_text(reader, |text| {
+                    text.to_ascii().map(|text| text.into_owned())
+                })?;
+                description.take_end(reader)?;
+                Some(text)
+            }
+            None => None
+        };
+
+        Ok(NotPerformedResponse::new(
+            NotPerformedCode::from_code(code), description
+        ))
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        writeln!(target, "  <status>{}</status>", self.status)?;
+        if let Some(ref text) = self.description {
+            writeln!(
+                target,
+                "  <description xml:lang=\"en-US\">{}</description>",
+                Escaped(text)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for NotPerformedResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.description {
+            Some(ref text) => write!(f, "{}: {}", self.status, text),
+            None => write!(f, "{}", self.status)
+        }
+    }
+}
+
+impl error::Error for NotPerformedResponse { }
+
+
+//------------ NotPerformedCode ----------------------------------------------
+
+/// The status code of a not performed response.
+///
+/// The codes are defined in section 3.6 of RFC 6492. Any other code is
+/// kept as `Unknown`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NotPerformedCode {
+    /// 1101: Already processing request.
+    AlreadyProcessing,
+
+    /// 1102: Version number error.
+    VersionError,
+
+    /// 1103: Unrecognized request type.
+    UnrecognizedRequestType,
+
+    /// 1104: Request scheduled for processing.
+    RequestScheduled,
+
+    /// 1201: Request – no such resource class.
+    NoSuchResourceClass,
+
+    /// 1202: Request – no resources allocated in resource class.
+    NoResources,
+
+    /// 1203: Request – badly formed certificate request.
+    BadlyFormedRequest,
+
+    /// 1204: Request – already used key in request.
+    AlreadyUsedKey,
+
+    /// 1301: Revoke – no such resource class.
+    RevokeNoSuchResourceClass,
+
+    /// 1302: Revoke – no such key.
+    RevokeNoSuchKey,
+
+    /// 2001: Internal server error – request not performed.
+    InternalServerError,
+
+    /// Any other code.
+    Unknown(u64),
+}
+
+impl NotPerformedCode {
+    /// Creates a status from its numeric code.
+    pub fn from_code(code: u64) -> Self {
+        match code {
+            1101 => NotPerformedCode::AlreadyProcessing,
+            1102 => NotPerformedCode::VersionError,
+            1103 => NotPerformedCode::UnrecognizedRequestType,
+            1104 => NotPerformedCode::RequestScheduled,
+            1201 => NotPerformedCode::NoSuchResourceClass,
+            1202 => NotPerformedCode::NoResources,
+            1203 => NotPerformedCode::BadlyFormedRequest,
+            1204 => NotPerformedCode::AlreadyUsedKey,
+            1301 => NotPerformedCode::RevokeNoSuchResourceClass,
+            1302 => NotPerformedCode::RevokeNoSuchKey,
+            2001 => NotPerformedCode::InternalServerError,
+            code => NotPerformedCode::Unknown(code)
+        }
+    }
+
+    /// Returns the numeric code.
+    pub fn code(self) -> u64 {
+        match self {
+            NotPerformedCode::AlreadyProcessing => 1101,
+            NotPerformedCode::VersionError => 1102,
+            NotPerformedCode::UnrecognizedRequestType => 1103,
+            NotPerformedCode::RequestScheduled => 1104,
+            NotPerformedCode::NoSuchResourceClass => 1201,
+            NotPerformedCode::NoResources => 1202,
+            NotPerformedCode::BadlyFormedRequest => 1203,
+            NotPerformedCode::AlreadyUsedKey => 1204,
+            NotPerformedCode::RevokeNoSuchResourceClass => 1301,
+            NotPerformedCode::RevokeNoSuchKey => 1302,
+            NotPerformedCode::InternalServerError => 2001,
+            NotPerformedCode::Unknown(code) => code,
+        }
+    }
+
+    /// Returns the description of the code given in RFC 6492.
+    pub fn description(self) -> Option<&'static str> {
+        match self {
+            NotPerformedCode::AlreadyProcessing => {
+                Some("already processing request")
+            }
+            NotPerformedCode::VersionError => Some("version number error"),
+            NotPerformedCode::UnrecognizedRequestType => {
+                Some("unrecognised request type")
+            }
+            NotPerformedCode::RequestScheduled => {
+                Some("request scheduled for processing")
+            }
+            NotPerformedCode::NoSuchResourceClass => {
+                Some("request - no such resource class")
+            }
+            NotPerformedCode::NoResources => {
+                Some("request - no resources allocated in resource class")
+            }
+            NotPerformedCode::BadlyFormedRequest => {
+                Some("request - badly formed certificate request")
+            }
+            NotPerformedCode::AlreadyUsedKey => {
+                Some("request - already used key in request")
+            }
+            NotPerformedCode::RevokeNoSuchResourceClass => {
+                Some("revoke - no such resource class")
+            }
+            NotPerformedCode::RevokeNoSuchKey => {
+                Some("revoke - no such key")
+            }
+            NotPerformedCode::InternalServerError => {
+                Some("Internal Server Error - Request not performed")
+            }
+            NotPerformedCode::Unknown(_) => None,
+        }
+    }
+}
+
+impl From<u64> for NotPerformedCode {
+    fn from(code: u64) -> Self {
+        NotPerformedCode::from_code(code)
+    }
+}
+
+impl From<NotPerformedCode> for u64 {
+    fn from(code: NotPerformedCode) -> Self {
+        code.code()
+    }
+}
+
+impl fmt::Display for NotPerformedCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+
+//------------ ResourceClass -------------------------------------------------
+
+/// A resource class of a child.
+///
+/// A class describes the resources a child is entitled to under a certain
+/// certificate of the parent, the certificates the parent has issued to
+/// the child for these resources, and the parent’s certificate.
+#[derive(Clone, Debug)]
+pub struct ResourceClass {
+    /// The name of the class.
+    class_name: String,
+
+    /// The rsync URIs of the issuer certificate.
+    ///
+    /// There always is at least one.
+    cert_urls: Vec<uri::Rsync>,
+
+    /// The resources the child is entitled to.
+    resource_set: ResourceSet,
+
+    /// The time when the entitlement ends.
+    not_after: Time,
+
+    /// The suggested suffix for the child’s repository.
+    suffix: Option<String>,
+
+    /// The certificates currently issued to the child.
+    certificates: Vec<IssuedCert>,
+
+    /// The issuer certificate.
+    issuer: Cert,
+}
+
+impl ResourceClass {
+    pub fn new(
+        class_name: String,
+        cert_url: uri::Rsync,
+        resource_set: ResourceSet,
+        not_after: Time,
+        suffix: Option<String>,
+        certificates: Vec<IssuedCert>,
+        issuer: Cert,
+    ) -> Self {
+        ResourceClass {
+            class_name, cert_urls: vec![cert_url], resource_set, not_after,
+            suffix, certificates, issuer
+        }
+    }
+
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Returns the first URI of the issuer certificate.
+    pub fn cert_url(&self) -> &uri::Rsync {
+        &self.cert_urls[0]
+    }
+
+    /// Returns all URIs of the issuer certificate.
+    ///
+    /// The `cert_url` attribute may contain a comma-separated list of URIs
+    /// for the issuer certificate. They are returned in order.
+    pub fn cert_urls(&self) -> &[uri::Rsync] {
+        &self.cert_urls
+    }
+
+    /// Adds another URI of the issuer certificate.
+    pub fn push_cert_url(&mut self, uri: uri::Rsync) {
+        self.cert_urls.push(uri)
+    }
+
+    pub fn resource_set(&self) -> &ResourceSet {
+        &self.resource_set
+    }
+
+    pub fn not_after(&self) -> Time {
+        self.not_after
+    }
+
+    pub fn suffix(&self) -> Option<&str> {
+        self.suffix.as_deref()
+    }
+
+    pub fn certificates(&self) -> &[IssuedCert] {
+        &self.certificates
+    }
+
+    pub fn issuer(&self) -> &Cert {
+        &self.issuer
+    }
+
+    /// Takes an optional class element from the content of a message.
+    fn take_opt_from<R: io::BufRead>(
+        content: &mut Content,
+        reader: &mut Reader<R>
+    ) -> Result<Option<Self>, Error> {
+        let mut attrs = None;
+        let inner = content.take_opt_element(reader, |element| {
+            if element.name() != CLASS {
+                return Err(Error::Malformed)
+            }
+            attrs = Some(Attributes::from_element(&element)?);
+            Ok(())
+        })?;
+        let mut inner = match inner {
+            Some(inner) => inner,
+            None => return Ok(None)
+        };
+        let mut attrs = attrs.unwrap(); // Or we'd have exited already.
+        let class_name = attrs.take(b"class_name")?;
+        let cert_urls = take_uri_list(attrs.take(b"cert_url")?)?;
+        let resource_set = ResourceSet::take_from(&mut attrs)?;
+        let not_after = attrs.take(b"resource_set_notafter")?;
+        let suffix = attrs.take_opt(b"suffix")?;
+        attrs.check_empty()?;
+
+        // Any number of certificate elements followed by exactly one
+        // issuer element.
+        let mut certificates = Vec::new();
+        let issuer = loop {
+            let mut attrs = None;
+            let el = inner.take_opt_element(reader, |element| {
+                match element.name() {
+                    CERTIFICATE => {
+                        attrs = Some(Attributes::from_element(&element)?);
+                        Ok(())
+                    }
+                    ISSUER => {
+                        element.attributes(|_, _| Err(Error::Malformed))
+                    }
+                    _ => Err(Error::Malformed)
+                }
+            })?;
+            let mut el = match el {
+                Some(el) => el,
+                None => return Err(Error::Malformed)
+            };
+            let name = if attrs.is_some() { CERTIFICATE } else { ISSUER };
+            let cert = take_cert(&mut el, reader, name)?;
+            el.take_end(reader)?;
+            match attrs {
+                Some(mut attrs) => {
+                    let cert_url = attrs.take(b"cert_url")?;
+                    let limit = RequestResourceLimit::take_from(&mut attrs)?;
+                    attrs.check_empty()?;
+                    certificates.push(IssuedCert::new(cert_url, limit, cert));
+                }
+                None => break cert
+            }
+        };
+        inner.take_end(reader)?;
+
+        Ok(Some(ResourceClass {
+            class_name, cert_urls, resource_set, not_after, suffix,
+            certificates, issuer
+        }))
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "  <class")?;
+        write_attr(target, "class_name", &self.class_name)?;
+        write_attr(target, "cert_url", &UriList(&self.cert_urls))?;
+        self.resource_set.write_xml(target)?;
+        write_attr(
+            target, "resource_set_notafter",
+            self.not_after.to_rfc3339_opts(SecondsFormat::Secs, true)
+        )?;
+        write_opt_attr(target, "suffix", self.suffix.as_ref())?;
+        writeln!(target, ">")?;
+        for cert in &self.certificates {
+            cert.write_xml(target)?;
+        }
+        writeln!(
+            target, "    <issuer>{}</issuer>",
+            base64::encode(&self.issuer.to_captured().into_bytes())
+        )?;
+        writeln!(target, "  </class>")
+    }
+}
+
+
+//------------ IssuedCert ----------------------------------------------------
+
+/// A certificate issued by the parent to the child.
+#[derive(Clone, Debug)]
+pub struct IssuedCert {
+    /// The rsync URI where the certificate is published.
+    cert_url: uri::Rsync,
+
+    /// The resource limit requested by the child.
+    limit: RequestResourceLimit,
+
+    /// The certificate itself.
+    cert: Cert,
+}
+
+impl IssuedCert {
+    pub fn new(
+        cert_url: uri::Rsync,
+        limit: RequestResourceLimit,
+        cert: Cert
+    ) -> Self {
+        IssuedCert { cert_url, limit, cert }
+    }
+
+    pub fn cert_url(&self) -> &uri::Rsync {
+        &self.cert_url
+    }
+
+    pub fn limit(&self) -> &RequestResourceLimit {
+        &self.limit
+    }
+
+    pub fn cert(&self) -> &Cert {
+        &self.cert
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "    <certificate")?;
+        write_attr(target, "cert_url", &self.cert_url)?;
+        self.limit.write_xml(target)?;
+        writeln!(
+            target, ">{}</certificate>",
+            base64::encode(&self.cert.to_captured().into_bytes())
+        )
+    }
+}
+
+
+//------------ ResourceSet ---------------------------------------------------
+
+/// The resources a child is entitled to in a resource class.
+///
+/// Each resource type is given as a comma-separated list in the RFC 3779
+/// text syntax, which may be empty. Unlike in certificates, resources can
+/// not be inherited, so the value "inherit" is rejected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResourceSet {
+    asn: AsBlocks,
+    v4: IpBlocks,
+    v6: IpBlocks,
+}
+
+impl ResourceSet {
+    pub fn new(asn: AsBlocks, v4: IpBlocks, v6: IpBlocks) -> Self {
+        ResourceSet { asn, v4, v6 }
+    }
+
+    pub fn asn(&self) -> &AsBlocks {
+        &self.asn
+    }
+
+    pub fn v4(&self) -> &IpBlocks {
+        &self.v4
+    }
+
+    pub fn v6(&self) -> &IpBlocks {
+        &self.v6
+    }
+
+    fn take_from(attrs: &mut Attributes) -> Result<Self, Error> {
+        Ok(ResourceSet {
+            asn: attrs.take(b"resource_set_as")?,
+            v4: attrs.take_ip_blocks(
+                b"resource_set_ipv4", AddressFamily::Ipv4
+            )?.ok_or(Error::Malformed)?,
+            v6: attrs.take_ip_blocks(
+                b"resource_set_ipv6", AddressFamily::Ipv6
+            )?.ok_or(Error::Malformed)?,
+        })
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write_attr(target, "resource_set_as", AsSet(&self.asn))?;
+        write_attr(
+            target, "resource_set_ipv4", IpSet(&self.v4, AddressFamily::Ipv4)
+        )?;
+        write_attr(
+            target, "resource_set_ipv6", IpSet(&self.v6, AddressFamily::Ipv6)
+        )
+    }
+}
+
+
+//------------ RequestResourceLimit ------------------------------------------
+
+/// The resources a child wants included in a certificate.
+///
+/// For each resource type, the child may limit the resources to be
+/// included to a subset of what it is entitled to. If there is no limit for
+/// a type, all resources of the type are included.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RequestResourceLimit {
+    asn: Option<AsBlocks>,
+    v4: Option<IpBlocks>,
+    v6: Option<IpBlocks>,
+}
+
+impl RequestResourceLimit {
+    pub fn new(
+        asn: Option<AsBlocks>,
+        v4: Option<IpBlocks>,
+        v6: Option<IpBlocks>
+    ) -> Self {
+        RequestResourceLimit { asn, v4, v6 }
+    }
+
+    pub fn asn(&self) -> Option<&AsBlocks> {
+        self.asn.as_ref()
+    }
+
+    pub fn v4(&self) -> Option<&IpBlocks> {
+        self.v4.as_ref()
+    }
+
+    pub fn v6(&self) -> Option<&IpBlocks> {
+        self.v6.as_ref()
+    }
+
+    /// Returns whether there are no limits at all.
+    pub fn is_empty(&self) -> bool {
+        self.asn.is_none() && self.v4.is_none() && self.v6.is_none()
+    }
+
+    fn take_from(attrs: &mut Attributes) -> Result<Self, Error> {
+        Ok(RequestResourceLimit {
+            asn: attrs.take_opt(b"req_resource_set_as")?,
+            v4: attrs.take_ip_blocks(
+                b"req_resource_set_ipv4", AddressFamily::Ipv4
+            )?,
+            v6: attrs.take_ip_blocks(
+                b"req_resource_set_ipv6", AddressFamily::Ipv6
+            )?,
+        })
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write_opt_attr(
+            target, "req_resource_set_as", self.asn.as_ref().map(AsSet)
+        )?;
+        write_opt_attr(
+            target, "req_resource_set_ipv4",
+            self.v4.as_ref().map(|v4| IpSet(v4, AddressFamily::Ipv4))
+        )?;
+        write_opt_attr(
+            target, "req_resource_set_ipv6",
+            self.v6.as_ref().map(|v6| IpSet(v6, AddressFamily::Ipv6))
+        )
+    }
+}
+
+
+//------------ ProvisioningCms -----------------------------------------------
+
+/// Provisioning messages wrapped into CMS signed messages.
+///
+/// The methods of this type take care of all the steps necessary to send
+/// and receive provisioning messages: encoding the XML, wrapping it into a
+/// signed message with the right content type, and, on the receiving end,
+/// validating the signed message against the identity certificate of the
+/// peer, parsing the XML, and checking that the message was sent by the
+/// expected sender to the expected recipient.
+///
+/// Warnings produced by validating the signed message are dropped.
+#[derive(Clone, Copy, Debug)]
+pub struct ProvisioningCms;
+
+impl ProvisioningCms {
+    /// Signs a message.
+    ///
+    /// The EE certificate of the signed message is issued by `issuer_key`
+    /// which should be the key of the sender’s identity certificate. It
+    /// will have a random serial number and the given validity.
+    ///
+    /// Returns the DER encoded signed message.
+    pub fn sign<S: Signer + ?Sized>(
+        message: &Message,
+        signer: &S,
+        issuer_key: &S::KeyId,
+        ee_validity: Validity,
+    ) -> Result<Bytes, SigningError<S::Error>> {
+        // Writing to a vec can’t fail.
+        let mut xml = Vec::new();
+        message.write_xml(&mut xml).unwrap();
+        SignedMessage::sign_xml(xml.into(), signer, issuer_key, ee_validity)
+    }
+
+    /// Validates a signed message and parses its content.
+    ///
+    /// The signed message must have been issued by the identity certificate
+    /// of the peer, `peer`. The message must have been sent by
+    /// `expected_sender` to `expected_recipient`.
+    pub fn validate_and_parse(
+        der: Bytes,
+        peer: &Cert,
+        expected_sender: &str,
+        expected_recipient: &str,
+    ) -> Result<Message, ProvisioningCmsError> {
+        Self::validate_and_parse_at(
+            der, peer, expected_sender, expected_recipient, Time::now()
+        )
+    }
+
+    /// Validates a signed message at the given time and parses its content.
+    ///
+    /// Problems with the signed message itself are reported via the
+    /// `Signed` variant of [`ProvisioningCmsError`]. If the EE certificate
+    /// wasn’t issued by `peer` at all, it contains
+    /// [`SignedXmlError::WrongSigner`].
+    ///
+    /// [`ProvisioningCmsError`]: enum.ProvisioningCmsError.html
+    /// [`SignedXmlError::WrongSigner`]: ../sigmsg/enum.SignedXmlError.html
+    pub fn validate_and_parse_at(
+        der: Bytes,
+        peer: &Cert,
+        expected_sender: &str,
+        expected_recipient: &str,
+        now: Time,
+    ) -> Result<Message, ProvisioningCmsError> {
+        let xml = SignedMessage::validate_xml_at(der, peer, now)?;
+        let message = Message::parse(xml.as_ref()).map_err(
+            ProvisioningCmsError::Xml
+        )?;
+        if message.sender() != expected_sender {
+            return Err(ProvisioningCmsError::Sender(message.sender))
+        }
+        if message.recipient() != expected_recipient {
+            return Err(ProvisioningCmsError::Recipient(message.recipient))
+        }
+        Ok(message)
+    }
+}
+
+
+//------------ ProvisioningCmsError ------------------------------------------
+
+/// An error happened while receiving a CMS wrapped provisioning message.
+#[derive(Debug)]
+pub enum ProvisioningCmsError {
+    /// The signed message is not acceptable.
+    Signed(SignedXmlError),
+
+    /// The XML content is not a valid provisioning message.
+    Xml(Error),
+
+    /// The message was sent by someone else.
+    ///
+    /// Contains the sender handle given in the message.
+    Sender(Handle),
+
+    /// The message was meant for someone else.
+    ///
+    /// Contains the recipient handle given in the message.
+    Recipient(Handle),
+}
+
+impl From<SignedXmlError> for ProvisioningCmsError {
+    fn from(err: SignedXmlError) -> Self {
+        ProvisioningCmsError::Signed(err)
+    }
+}
+
+impl fmt::Display for ProvisioningCmsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProvisioningCmsError::Signed(ref err) => err.fmt(f),
+            ProvisioningCmsError::Xml(ref err) => {
+                write!(f, "invalid provisioning message: {}", err)
+            }
+            ProvisioningCmsError::Sender(ref sender) => {
+                write!(f, "unexpected sender '{}'", sender)
+            }
+            ProvisioningCmsError::Recipient(ref recipient) => {
+                write!(f, "unexpected recipient '{}'", recipient)
+            }
+        }
+    }
+}
+
+impl error::Error for ProvisioningCmsError { }
+
+
+//------------ Attributes ----------------------------------------------------
+
+/// The attributes of an element.
+///
+/// Elements in the provisioning protocol have quite a few attributes, some
+/// of them optional. We collect them all first and then take out the ones
+/// we need. Whatever is left is an unknown attribute.
+struct Attributes(Vec<(Vec<u8>, String)>);
+
+impl Attributes {
+    fn from_element(element: &Element) -> Result<Self, Error> {
+        let mut res = Vec::new();
+        element.attributes(|name, value| -> Result<(), Error> {
+            res.push((name.into(), value.ascii_into()?));
+            Ok(())
+        })?;
+        Ok(Attributes(res))
+    }
+
+    fn take_opt<T: FromStr>(
+        &mut self,
+        name: &[u8]
+    ) -> Result<Option<T>, Error> {
+        match self.0.iter().position(|item| item.0 == name) {
+            Some(pos) => {
+                T::from_str(&self.0.swap_remove(pos).1)
+                    .map(Some).map_err(|_| Error::Malformed)
+            }
+            None => Ok(None)
+        }
+    }
+
+    fn take<T: FromStr>(&mut self, name: &[u8]) -> Result<T, Error> {
+        self.take_opt(name)?.ok_or(Error::Malformed)
+    }
+
+    /// Takes an attribute with IP resources of the given family.
+    ///
+    /// `IpBlocks::from_str` guesses the family, so we need to make sure it
+    /// guesses right.
+    fn take_ip_blocks(
+        &mut self,
+        name: &[u8],
+        family: AddressFamily
+    ) -> Result<Option<IpBlocks>, Error> {
+        let value = match self.take_opt::<String>(name)? {
+            Some(value) => value,
+            None => return Ok(None)
+        };
+        let wrong = match family {
+            AddressFamily::Ipv4 => ':',
+            AddressFamily::Ipv6 => '.',
+        };
+        if value.contains(wrong) {
+            return Err(Error::Malformed)
+        }
+        IpBlocks::from_str(&value).map(Some).map_err(|_| Error::Malformed)
+    }
+
+    fn check_empty(&self) -> Result<(), Error> {
+        if self.0.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(Error::Malformed)
+        }
+    }
+}
+
+
+//------------ AsSet and IpSet -----------------------------------------------
+
+/// A helper type for writing AS resources as an attribute value.
+struct AsSet<'a>(&'a AsBlocks);
+
+impl<'a> fmt::Display for AsSet<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, block) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            let (min, max) = (u32::from(block.min()), u32::from(block.max()));
+            if min == max {
+                write!(f, "{}", min)?;
+            }
+            else {
+                write!(f, "{}-{}", min, max)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A helper type for writing IP resources as an attribute value.
+struct IpSet<'a>(&'a IpBlocks, AddressFamily);
+
+impl<'a> fmt::Display for IpSet<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, block) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            match self.1 {
+                AddressFamily::Ipv4 => block.fmt_v4(f)?,
+                AddressFamily::Ipv6 => block.fmt_v6(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+
+//------------ UriList -------------------------------------------------------
+
+/// A helper type for writing a list of URIs as an attribute value.
+struct UriList<'a>(&'a [uri::Rsync]);
+
+impl<'a> fmt::Display for UriList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, uri) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", uri)?;
+        }
+        Ok(())
+    }
+}
+
+
+//------------ Helpers -------------------------------------------------------
+
+/// Parses the comma-separated list of URIs of a `cert_url` attribute.
+///
+/// The list must contain at least one URI.
+fn take_uri_list(value: String) -> Result<Vec<uri::Rsync>, Error> {
+    value.split(',').map(|item| {
+        uri::Rsync::from_str(item).map_err(|_| Error::Malformed)
+    }).collect()
+}
+
+/// Takes a base64 encoded certificate from the content of an element.
+fn take_cert<R: io::BufRead>(
+    content: &mut Content,
+    reader: &mut Reader<R>,
+    name: Name
+) -> Result<Cert, Error> {
+    Cert::decode(
+        content.take_base64(reader, name)?
+    ).map_err(|_| Error::Malformed)
+}
+
+
+//------------ Xml Names -----------------------------------------------------
+
+const NS: &str = "http://www.apnic.net/specs/rescerts/up-down/";
+const MESSAGE: Name = Name::qualified(NS.as_bytes(), b"message");
+const CLASS: Name = Name::qualified(NS.as_bytes(), b"class");
+const CERTIFICATE: Name = Name::qualified(NS.as_bytes(), b"certificate");
+const ISSUER: Name = Name::qualified(NS.as_bytes(), b"issuer");
+const REQUEST: Name = Name::qualified(NS.as_bytes(), b"request");
+const KEY: Name = Name::qualified(NS.as_bytes(), b"key");
+const STATUS: Name = Name::qualified(NS.as_bytes(), b"status");
+const DESCRIPTION: Name = Name::qualified(NS.as_bytes(), b"description");
+
+const LIST_TYPE: &str = "list";
+const LIST_RESPONSE_TYPE: &str = "list_response";
+const ISSUE_TYPE: &str = "issue";
+const ISSUE_RESPONSE_TYPE: &str = "issue_response";
+const REVOKE_TYPE: &str = "revoke";
+const REVOKE_RESPONSE_TYPE: &str = "revoke_response";
+const ERROR_RESPONSE_TYPE: &str = "error_response";
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn handle(s: &str) -> Handle {
+        s.parse().unwrap()
+    }
+
+    fn roundtrip(message: &Message) -> Message {
+        let mut xml = Vec::new();
+        message.write_xml(&mut xml).unwrap();
+        Message::parse(xml.as_slice()).unwrap()
+    }
+
+    fn ta_cert() -> Cert {
+        Cert::decode(include_bytes!("../test-data/ta.cer").as_ref()).unwrap()
+    }
+
+    fn ca_cert() -> Cert {
+        Cert::decode(include_bytes!("../test-data/ca1.cer").as_ref()).unwrap()
+    }
+
+    fn same_cert(left: &Cert, right: &Cert) -> bool {
+        left.to_captured().as_slice() == right.to_captured().as_slice()
+    }
+
+    fn assert_same_class(left: &ResourceClass, right: &ResourceClass) {
+        assert_eq!(left.class_name(), right.class_name());
+        assert_eq!(left.cert_urls(), right.cert_urls());
+        assert_eq!(left.resource_set(), right.resource_set());
+        assert_eq!(left.not_after(), right.not_after());
+        assert_eq!(left.suffix(), right.suffix());
+        assert_eq!(left.certificates().len(), right.certificates().len());
+        for (left, right) in left.certificates().iter().zip(
+            right.certificates()
+        ) {
+            assert_eq!(left.cert_url(), right.cert_url());
+            assert_eq!(left.limit(), right.limit());
+            assert!(same_cert(left.cert(), right.cert()));
+        }
+        assert!(same_cert(left.issuer(), right.issuer()));
+    }
+
+    #[test]
+    fn parse_list() {
+        let msg = Message::parse(
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"child\" \
+              recipient=\"parent\" type=\"list\"/>".as_ref()
+        ).unwrap();
+        assert_eq!(msg.sender(), "child");
+        assert_eq!(msg.recipient(), "parent");
+        match msg.payload() {
+            ProvisioningMessage::List => { }
+            _ => panic!("expected list")
+        }
+    }
+
+    #[test]
+    fn parse_list_response() {
+        let msg = Message::parse(
+            include_bytes!("../test-data/provisioning-list-response.xml")
+                .as_ref()
+        ).unwrap();
+        assert_eq!(msg.sender(), "parent");
+        assert_eq!(msg.recipient(), "child");
+        let list = match msg.into_payload() {
+            ProvisioningMessage::ListResponse(list) => list,
+            _ => panic!("expected list response")
+        };
+        assert_eq!(list.classes().len(), 1);
+        let class = &list.classes()[0];
+        assert_eq!(class.class_name(), "class-1");
+        assert_eq!(
+            class.cert_url().to_string(), "rsync://example.com/repo/ta.cer"
+        );
+        assert_eq!(
+            class.resource_set().asn(),
+            &AsBlocks::from_str("64496-64511, 65000").unwrap()
+        );
+        assert_eq!(
+            class.resource_set().v4(),
+            &IpBlocks::from_str("192.0.2.0/24, 198.51.100.0-198.51.100.10")
+                .unwrap()
+        );
+        assert!(class.resource_set().v6().is_empty());
+        assert_eq!(
+            class.not_after(), Time::from_str("2021-10-14T00:00:00Z").unwrap()
+        );
+        assert_eq!(class.suffix(), Some("child"));
+        assert_eq!(class.certificates().len(), 1);
+        let issued = &class.certificates()[0];
+        assert_eq!(
+            issued.cert_url().to_string(), "rsync://example.com/repo/ca1.cer"
+        );
+        assert_eq!(
+            issued.limit().v4(),
+            Some(&IpBlocks::from_str("192.0.2.0/24").unwrap())
+        );
+        assert!(issued.limit().asn().is_none());
+        assert!(issued.limit().v6().is_none());
+        assert!(same_cert(issued.cert(), &ca_cert()));
+        assert!(same_cert(class.issuer(), &ta_cert()));
+    }
+
+    #[test]
+    fn list_response_round_trip() {
+        let xml = include_str!("../test-data/provisioning-list-response.xml");
+        let check = |xml: &str| {
+            let msg = Message::parse(xml.as_bytes()).unwrap();
+            let parsed = roundtrip(&msg);
+            assert_eq!(parsed.sender(), msg.sender());
+            assert_eq!(parsed.recipient(), msg.recipient());
+            let (left, right) = match (msg.payload(), parsed.payload()) {
+                (
+                    ProvisioningMessage::ListResponse(left),
+                    ProvisioningMessage::ListResponse(right)
+                ) => (left, right),
+                _ => panic!("expected list responses")
+            };
+            assert_eq!(left.classes().len(), right.classes().len());
+            for (left, right) in left.classes().iter().zip(right.classes()) {
+                assert_same_class(left, right);
+            }
+            msg
+        };
+
+        let msg = check(xml);
+        match msg.payload() {
+            ProvisioningMessage::ListResponse(list) => {
+                assert_eq!(list.classes()[0].cert_urls().len(), 1);
+            }
+            _ => unreachable!()
+        }
+
+        // The issuer certificate with two URIs.
+        let msg = check(&xml.replace(
+            "cert_url=\"rsync://example.com/repo/ta.cer\"",
+            "cert_url=\"rsync://example.com/repo/ta.cer,\
+             rsync://example.net/mirror/ta.cer\""
+        ));
+        match msg.payload() {
+            ProvisioningMessage::ListResponse(list) => {
+                let class = &list.classes()[0];
+                assert_eq!(class.cert_urls().len(), 2);
+                assert_eq!(
+                    class.cert_url().to_string(),
+                    "rsync://example.com/repo/ta.cer"
+                );
+                assert_eq!(
+                    class.cert_urls()[1].to_string(),
+                    "rsync://example.net/mirror/ta.cer"
+                );
+            }
+            _ => unreachable!()
+        }
+
+        // An empty entry in the list is an error.
+        assert!(Message::parse(xml.replace(
+            "cert_url=\"rsync://example.com/repo/ta.cer\"",
+            "cert_url=\"rsync://example.com/repo/ta.cer,\""
+        ).as_bytes()).is_err());
+    }
+
+    #[test]
+    fn parse_issue() {
+        let msg = Message::parse(
+            include_bytes!("../test-data/provisioning-issue.xml").as_ref()
+        ).unwrap();
+        let issue = match msg.into_payload() {
+            ProvisioningMessage::Issue(issue) => issue,
+            _ => panic!("expected issue")
+        };
+        assert_eq!(issue.class_name(), "class-1");
+        assert_eq!(
+            issue.limit().v6(),
+            Some(&IpBlocks::from_str("2001:db8::/32").unwrap())
+        );
+        assert!(issue.limit().asn().is_none());
+        issue.csr().validate().unwrap();
+        assert_eq!(
+            issue.csr().to_captured().as_slice(),
+            include_bytes!("../test-data/drl-csr.der").as_ref()
+        );
+    }
+
+    #[test]
+    fn issue_round_trip() {
+        let msg = Message::parse(
+            include_bytes!("../test-data/provisioning-issue.xml").as_ref()
+        ).unwrap();
+        let parsed = roundtrip(&msg);
+        match (msg.payload(), parsed.payload()) {
+            (
+                ProvisioningMessage::Issue(left),
+                ProvisioningMessage::Issue(right)
+            ) => {
+                assert_eq!(left.class_name(), right.class_name());
+                assert_eq!(left.limit(), right.limit());
+                assert_eq!(
+                    left.csr().to_captured().as_slice(),
+                    right.csr().to_captured().as_slice()
+                );
+                right.csr().validate_rpki_ca().unwrap();
+            }
+            _ => panic!("expected issue")
+        }
+
+        // A request with a broken signature parses but doesn’t validate.
+        let csr = Csr::decode(
+            include_bytes!("../test-data/drl-csr-bad-signature.der").as_ref()
+        ).unwrap();
+        let msg = roundtrip(&Message::new(
+            handle("child"), handle("parent"),
+            ProvisioningMessage::Issue(IssueRequest::new(
+                "class-1".into(), RequestResourceLimit::default(), csr
+            ))
+        ));
+        match msg.payload() {
+            ProvisioningMessage::Issue(issue) => {
+                assert!(issue.csr().validate_rpki_ca().is_err());
+            }
+            _ => panic!("expected issue")
+        }
+    }
+
+    #[test]
+    fn issue_response_round_trip() {
+        let msg = Message::parse(
+            include_bytes!("../test-data/provisioning-issue-response.xml")
+                .as_ref()
+        ).unwrap();
+        let parsed = roundtrip(&msg);
+        assert_eq!(parsed.sender(), "parent");
+        assert_eq!(parsed.recipient(), "child");
+        match (msg.payload(), parsed.payload()) {
+            (
+                ProvisioningMessage::IssueResponse(left),
+                ProvisioningMessage::IssueResponse(right)
+            ) => {
+                assert!(same_cert(left.issued().cert(), &ca_cert()));
+                assert!(same_cert(
+                    left.issued().cert(), right.issued().cert()
+                ));
+                assert_eq!(
+                    left.issued().cert_url(), right.issued().cert_url()
+                );
+                assert_eq!(left.issued().limit(), right.issued().limit());
+                assert_same_class(left.class(), right.class());
+            }
+            _ => panic!("expected issue response")
+        }
+    }
+
+    #[test]
+    fn parse_bad() {
+        // Wrong version.
+        assert!(Message::parse(
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"2\" sender=\"child\" \
+              recipient=\"parent\" type=\"list\"/>".as_ref()
+        ).is_err());
+        // Unknown type.
+        assert!(Message::parse(
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"child\" \
+              recipient=\"parent\" type=\"error_response\"/>".as_ref()
+        ).is_err());
+        // Missing recipient.
+        assert!(Message::parse(
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"child\" \
+              type=\"list\"/>".as_ref()
+        ).is_err());
+        // List with content.
+        assert!(Message::parse(
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"child\" \
+              recipient=\"parent\" type=\"list\">\
+              <key class_name=\"a\" ski=\"AAAAAAAAAAAAAAAAAAAAAAAAAAA\"/>\
+              </message>".as_ref()
+        ).is_err());
+        // IPv6 resources in the IPv4 attribute.
+        assert!(Message::parse(
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"child\" \
+              recipient=\"parent\" type=\"issue\">\
+              <request class_name=\"a\" \
+              req_resource_set_ipv4=\"2001:db8::/32\">AAAA</request>\
+              </message>".as_ref()
+        ).is_err());
+    }
+
+    /// A simple xorshift generator for the resource set property tests.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    fn random_as_set(rng: &mut Rng) -> String {
+        let mut res = Vec::new();
+        for _ in 0..rng.below(6) {
+            let min = rng.below(70000) as u32;
+            if rng.below(2) == 0 {
+                res.push(min.to_string())
+            }
+            else {
+                let max = min + rng.below(1000) as u32;
+                res.push(format!("{}-{}", min, max))
+            }
+        }
+        res.join(",")
+    }
+
+    fn random_ip_set(rng: &mut Rng, family: AddressFamily) -> String {
+        use std::net::{Ipv4Addr, Ipv6Addr};
+
+        let bits = match family {
+            AddressFamily::Ipv4 => 32,
+            AddressFamily::Ipv6 => 128,
+        };
+        let addr = |value: u128| match family {
+            AddressFamily::Ipv4 => Ipv4Addr::from(value as u32).to_string(),
+            AddressFamily::Ipv6 => Ipv6Addr::from(value).to_string(),
+        };
+        let mut res = Vec::new();
+        for _ in 0..rng.below(6) {
+            let value = u128::from(rng.next()) << 64;
+            let value = (value | u128::from(rng.next())) >> (128 - bits);
+            match rng.below(3) {
+                0 => res.push(addr(value)),
+                1 => {
+                    let len = rng.below(bits + 1) as u32;
+                    let mask = if len == 0 {
+                        0
+                    }
+                    else {
+                        !0u128 << (bits as u32 - len)
+                    };
+                    res.push(format!("{}/{}", addr(value & mask), len))
+                }
+                _ => {
+                    let max = value.saturating_add(
+                        u128::from(rng.below(1 << 16))
+                    );
+                    let max = if bits == 32 {
+                        max.min(u128::from(std::u32::MAX))
+                    }
+                    else {
+                        max
+                    };
+                    res.push(format!("{}-{}", addr(value), addr(max)))
+                }
+            }
+        }
+        res.join(",")
+    }
+
+    fn parse_as_set(value: &str) -> AsBlocks {
+        AsBlocks::from_str(value).unwrap()
+    }
+
+    fn parse_ip_set(value: &str, family: AddressFamily) -> IpBlocks {
+        let mut attrs = Attributes(vec![(b"set".to_vec(), value.into())]);
+        attrs.take_ip_blocks(b"set", family).unwrap().unwrap()
+    }
+
+    #[test]
+    fn resource_set_text_round_trip() {
+        let mut rng = Rng(0x5eed_1234_abcd_0001);
+        for _ in 0..500 {
+            let text = random_as_set(&mut rng);
+            let first = parse_as_set(&text);
+            let first_text = AsSet(&first).to_string();
+            let second = parse_as_set(&first_text);
+            assert_eq!(
+                AsSet(&second).to_string(), first_text, "{}", text
+            );
+            assert!(first.contains(&second) && second.contains(&first));
+
+            for &family in &[AddressFamily::Ipv4, AddressFamily::Ipv6] {
+                let text = random_ip_set(&mut rng, family);
+                let first = parse_ip_set(&text, family);
+                let first_text = IpSet(&first, family).to_string();
+                let second = parse_ip_set(&first_text, family);
+                assert_eq!(
+                    IpSet(&second, family).to_string(), first_text,
+                    "{}", text
+                );
+                assert!(first.contains(&second) && second.contains(&first));
+            }
+        }
+    }
+
+    #[test]
+    fn resource_set_empty_and_inherit() {
+        let mut attrs = Attributes(vec![
+            (b"resource_set_as".to_vec(), String::new()),
+            (b"resource_set_ipv4".to_vec(), String::new()),
+            (b"resource_set_ipv6".to_vec(), String::new()),
+        ]);
+        let set = ResourceSet::take_from(&mut attrs).unwrap();
+        assert!(set.asn().is_empty());
+        assert!(set.v4().is_empty());
+        assert!(set.v6().is_empty());
+        attrs.check_empty().unwrap();
+
+        let mut xml = Vec::new();
+        set.write_xml(&mut xml).unwrap();
+        assert_eq!(
+            String::from_utf8(xml).unwrap(),
+            " resource_set_as=\"\" resource_set_ipv4=\"\" \
+             resource_set_ipv6=\"\""
+        );
+
+        for name in &[
+            "resource_set_as", "resource_set_ipv4", "resource_set_ipv6"
+        ] {
+            let mut attrs = Attributes(vec![
+                (b"resource_set_as".to_vec(), String::new()),
+                (b"resource_set_ipv4".to_vec(), String::new()),
+                (b"resource_set_ipv6".to_vec(), String::new()),
+            ]);
+            for item in attrs.0.iter_mut() {
+                if item.0 == name.as_bytes() {
+                    item.1 = "inherit".into()
+                }
+            }
+            assert!(ResourceSet::take_from(&mut attrs).is_err());
+        }
+    }
+
+    #[test]
+    fn write_list_response() {
+        let class = ResourceClass::new(
+            "class-1".into(),
+            uri::Rsync::from_str("rsync://example.com/repo/ta.cer").unwrap(),
+            ResourceSet::new(
+                AsBlocks::from_str("AS64496-AS64511, AS65000").unwrap(),
+                IpBlocks::from_str("192.0.2.0/24").unwrap(),
+                IpBlocks::from_str("2001:db8::/32, 2001:db9::1").unwrap(),
+            ),
+            Time::from_str("2021-10-14T00:00:00Z").unwrap(),
+            None,
+            vec![IssuedCert::new(
+                uri::Rsync::from_str(
+                    "rsync://example.com/repo/ca1.cer"
+                ).unwrap(),
+                RequestResourceLimit::new(
+                    Some(AsBlocks::from_str("65000").unwrap()), None, None
+                ),
+                ca_cert()
+            )],
+            ta_cert()
+        );
+        let msg = roundtrip(&Message::new(
+            handle("parent"), handle("child"),
+            ProvisioningMessage::ListResponse(
+                ListResponse::new(vec![class.clone()])
+            )
+        ));
+        let list = match msg.into_payload() {
+            ProvisioningMessage::ListResponse(list) => list,
+            _ => panic!("expected list response")
+        };
+        assert_eq!(list.classes().len(), 1);
+        assert_same_class(&list.classes()[0], &class);
+
+        // The same class works as an issue response.
+        let msg = roundtrip(&Message::new(
+            handle("parent"), handle("child"),
+            ProvisioningMessage::IssueResponse(
+                IssueResponse::new(class.clone()).unwrap()
+            )
+        ));
+        match msg.into_payload() {
+            ProvisioningMessage::IssueResponse(issue) => {
+                assert_eq!(issue.class().class_name(), "class-1");
+                assert!(same_cert(issue.issued().cert(), &ca_cert()));
+            }
+            _ => panic!("expected issue response")
+        }
+
+        // But not without a certificate.
+        let mut class = class;
+        class.certificates.clear();
+        assert!(IssueResponse::new(class).is_err());
+    }
+
+    #[test]
+    fn write_issue() {
+        let csr = Csr::decode(
+            include_bytes!("../test-data/drl-csr.der").as_ref()
+        ).unwrap();
+        let msg = roundtrip(&Message::new(
+            handle("child"), handle("parent"),
+            ProvisioningMessage::Issue(IssueRequest::new(
+                "class-1".into(),
+                RequestResourceLimit::new(
+                    None, Some(IpBlocks::from_str("10.0.0.0/8").unwrap()),
+                    None
+                ),
+                csr.clone()
+            ))
+        ));
+        assert_eq!(msg.sender(), "child");
+        match msg.into_payload() {
+            ProvisioningMessage::Issue(issue) => {
+                assert_eq!(issue.class_name(), "class-1");
+                assert_eq!(
+                    issue.limit().v4(),
+                    Some(&IpBlocks::from_str("10.0.0.0/8").unwrap())
+                );
+                assert_eq!(
+                    issue.csr().to_captured().as_slice(),
+                    csr.to_captured().as_slice()
+                );
+            }
+            _ => panic!("expected issue")
+        }
+    }
+
+    #[test]
+    fn parse_revoke() {
+        let msg = |msg_type: &str, ski: &str| {
+            format!(
+                "<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+                 up-down/\" version=\"1\" sender=\"child\" \
+                 recipient=\"parent\" type=\"{}\">\
+                 <key class_name=\"class-1\" ski=\"{}\"/></message>",
+                msg_type, ski
+            )
+        };
+        let key = ta_cert().subject_key_identifier();
+        let ski = base64::encode_config(&key, base64::URL_SAFE_NO_PAD);
+        assert!(!ski.contains('='));
+        let padded = base64::encode_config(&key, base64::URL_SAFE);
+        assert!(padded.ends_with('='));
+
+        for ski in &[ski.as_str(), padded.as_str()] {
+            match Message::parse(msg("revoke", ski).as_bytes()).unwrap()
+                .into_payload()
+            {
+                ProvisioningMessage::Revoke(revoke) => {
+                    assert_eq!(revoke.class_name(), "class-1");
+                    assert_eq!(revoke.key(), key);
+                }
+                _ => panic!("expected revoke")
+            }
+            match Message::parse(
+                msg("revoke_response", ski).as_bytes()
+            ).unwrap().into_payload() {
+                ProvisioningMessage::RevokeResponse(revoke) => {
+                    assert_eq!(revoke.key(), key);
+                }
+                _ => panic!("expected revoke response")
+            }
+        }
+
+        // Written without padding.
+        let mut xml = Vec::new();
+        Message::new(
+            handle("child"), handle("parent"),
+            ProvisioningMessage::Revoke(
+                RevocationRequest::new("class-1".into(), key)
+            )
+        ).write_xml(&mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains(&format!("ski=\"{}\"", ski)));
+
+        // Key identifiers must be 20 octets.
+        for len in &[19, 21] {
+            let ski = base64::encode_config(
+                &vec![0u8; *len], base64::URL_SAFE_NO_PAD
+            );
+            assert!(Message::parse(msg("revoke", &ski).as_bytes()).is_err());
+        }
+        assert!(Message::parse(msg("revoke", "!!").as_bytes()).is_err());
+    }
+
+    #[test]
+    fn error_response() {
+        let codes = [
+            (1101, NotPerformedCode::AlreadyProcessing),
+            (1102, NotPerformedCode::VersionError),
+            (1103, NotPerformedCode::UnrecognizedRequestType),
+            (1104, NotPerformedCode::RequestScheduled),
+            (1201, NotPerformedCode::NoSuchResourceClass),
+            (1202, NotPerformedCode::NoResources),
+            (1203, NotPerformedCode::BadlyFormedRequest),
+            (1204, NotPerformedCode::AlreadyUsedKey),
+            (1301, NotPerformedCode::RevokeNoSuchResourceClass),
+            (1302, NotPerformedCode::RevokeNoSuchKey),
+            (2001, NotPerformedCode::InternalServerError),
+            (9999, NotPerformedCode::Unknown(9999)),
+        ];
+        for &(code, status) in codes.iter() {
+            assert_eq!(NotPerformedCode::from_code(code), status);
+            assert_eq!(status.code(), code);
+            assert_eq!(status.description().is_none(), code == 9999);
+
+            let response = NotPerformedResponse::from_code(status);
+            let msg = roundtrip(&Message::new(
+                handle("parent"), handle("child"),
+                ProvisioningMessage::ErrorResponse(response.clone())
+            ));
+            match msg.into_reply() {
+                Err(err) => assert_eq!(err, response),
+                Ok(_) => panic!("expected error response")
+            }
+        }
+
+        let msg = Message::parse(
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"parent\" \
+              recipient=\"child\" type=\"error_response\">\
+              <status>1201</status>\
+              <description xml:lang=\"en-US\">No &amp; class</description>\
+              </message>".as_ref()
+        ).unwrap();
+        let err = msg.into_reply().unwrap_err();
+        assert_eq!(err.status(), NotPerformedCode::NoSuchResourceClass);
+        assert_eq!(err.description(), Some("No & class"));
+        assert_eq!(err.to_string(), "1201: No & class");
+
+        let msg = Message::parse(
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"parent\" \
+              recipient=\"child\" type=\"error_response\">\
+              <status>4711</status></message>".as_ref()
+        ).unwrap();
+        let err = msg.into_reply().unwrap_err();
+        assert_eq!(err.status(), NotPerformedCode::Unknown(4711));
+        assert_eq!(err.description(), None);
+
+        // Missing or bad status, description that isn’t UTF-8.
+        for xml in &[
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"parent\" \
+              recipient=\"child\" type=\"error_response\">\
+              </message>".as_ref(),
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"parent\" \
+              recipient=\"child\" type=\"error_response\">\
+              <status>bad</status></message>".as_ref(),
+            b"<message xmlns=\"http://www.apnic.net/specs/rescerts/\
+              up-down/\" version=\"1\" sender=\"parent\" \
+              recipient=\"child\" type=\"error_response\">\
+              <status>1201</status>\
+              <description xml:lang=\"en-US\">No \xff class</description>\
+              </message>".as_ref(),
+        ] {
+            assert!(Message::parse(*xml).is_err());
+        }
+
+        // Other replies are passed through.
+        let msg = roundtrip(&Message::new(
+            handle("child"), handle("parent"), ProvisioningMessage::List
+        ));
+        match msg.into_reply() {
+            Ok(ProvisioningMessage::List) => { }
+            _ => panic!("expected list")
+        }
+    }
+
+    #[test]
+    fn write_revoke() {
+        let key = ta_cert().subject_key_identifier();
+        let msg = roundtrip(&Message::new(
+            handle("child"), handle("parent"),
+            ProvisioningMessage::Revoke(
+                RevocationRequest::new("class-1".into(), key)
+            )
+        ));
+        match msg.into_payload() {
+            ProvisioningMessage::Revoke(revoke) => {
+                assert_eq!(revoke.class_name(), "class-1");
+                assert_eq!(revoke.key(), key);
+            }
+            _ => panic!("expected revoke")
+        }
+
+        let request = RevocationRequest::new("class-1".into(), key);
+        let msg = roundtrip(&Message::new(
+            handle("parent"), handle("child"),
+            ProvisioningMessage::RevokeResponse(request.to_response())
+        ));
+        match msg.into_payload() {
+            ProvisioningMessage::RevokeResponse(revoke) => {
+                assert_eq!(revoke.class_name(), "class-1");
+                assert_eq!(revoke.key(), key);
+                assert!(revoke.confirms(&request));
+                assert!(!revoke.confirms(
+                    &RevocationRequest::new("class-2".into(), key)
+                ));
+            }
+            _ => panic!("expected revoke response")
+        }
+
+        let msg = roundtrip(&Message::new(
+            handle("child"), handle("parent"), ProvisioningMessage::List
+        ));
+        match msg.into_payload() {
+            ProvisioningMessage::List => { }
+            _ => panic!("expected list")
+        }
+    }
+}
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use bcder::Oid;
+    use crate::oid;
+    use crate::sigmsg::SignedMessageBuilder;
+    use super::*;
+
+    fn handle(s: &str) -> Handle {
+        s.parse().unwrap()
+    }
+
+    fn list() -> Message {
+        Message::new(
+            handle("child"), handle("parent"), ProvisioningMessage::List
+        )
+    }
+
+    #[test]
+    fn sign_and_validate() {
+        let (child, signer, child_key) = Cert::generate_test_ta().unwrap();
+        let der = ProvisioningCms::sign(
+            &list(), &signer, &child_key, Validity::from_secs(300)
+        ).unwrap();
+        let msg = ProvisioningCms::validate_and_parse(
+            der, &child, "child", "parent"
+        ).unwrap();
+        assert_eq!(msg.sender(), "child");
+        assert_eq!(msg.recipient(), "parent");
+        match msg.payload() {
+            ProvisioningMessage::List => { }
+            _ => panic!("expected list")
+        }
+
+        let reply = Message::new(
+            handle("parent"), handle("child"),
+            ProvisioningMessage::ErrorResponse(
+                NotPerformedResponse::from_code(
+                    NotPerformedCode::NoSuchResourceClass
+                )
+            )
+        );
+        let (parent, signer, parent_key) = Cert::generate_test_ta().unwrap();
+        let der = ProvisioningCms::sign(
+            &reply, &signer, &parent_key, Validity::from_secs(300)
+        ).unwrap();
+        match ProvisioningCms::validate_and_parse(
+            der, &parent, "parent", "child"
+        ).unwrap().into_reply() {
+            Err(err) => {
+                assert_eq!(
+                    err.status(), NotPerformedCode::NoSuchResourceClass
+                )
+            }
+            _ => panic!("expected error response")
+        }
+    }
+
+    #[test]
+    fn wrong_signer() {
+        let (_, signer, mallory_key) = Cert::generate_test_ta().unwrap();
+        let (child, _, _) = Cert::generate_test_ta().unwrap();
+        let der = ProvisioningCms::sign(
+            &list(), &signer, &mallory_key, Validity::from_secs(300)
+        ).unwrap();
+        match ProvisioningCms::validate_and_parse(
+            der, &child, "child", "parent"
+        ) {
+            Err(ProvisioningCmsError::Signed(
+                SignedXmlError::WrongSigner
+            )) => { }
+            res => panic!("expected wrong signer, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn wrong_handles() {
+        let (child, signer, child_key) = Cert::generate_test_ta().unwrap();
+        let der = ProvisioningCms::sign(
+            &list(), &signer, &child_key, Validity::from_secs(300)
+        ).unwrap();
+
+        match ProvisioningCms::validate_and_parse(
+            der.clone(), &child, "other", "parent"
+        ) {
+            Err(ProvisioningCmsError::Sender(ref sender))
+                if sender == "child" => { }
+            res => panic!("expected sender mismatch, got {:?}", res)
+        }
+        match ProvisioningCms::validate_and_parse(
+            der, &child, "child", "other"
+        ) {
+            Err(ProvisioningCmsError::Recipient(ref recipient))
+                if recipient == "parent" => { }
+            res => panic!("expected recipient mismatch, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn invalid_message() {
+        let (child, signer, child_key) = Cert::generate_test_ta().unwrap();
+
+        // Expired EE certificate.
+        let der = ProvisioningCms::sign(
+            &list(), &signer, &child_key, Validity::from_secs(300)
+        ).unwrap();
+        match ProvisioningCms::validate_and_parse_at(
+            der, &child, "child", "parent",
+            Time::now() + chrono::Duration::hours(1)
+        ) {
+            Err(ProvisioningCmsError::Signed(
+                SignedXmlError::Invalid(_)
+            )) => { }
+            res => panic!("expected invalid message, got {:?}", res)
+        }
+
+        let der = |content_type: Oid<Bytes>, content: &'static [u8]| {
+            SignedMessageBuilder::new(
+                1u64.into(), Validity::from_secs(300)
+            ).finalize(
+                content_type, Bytes::from_static(content),
+                &signer, &child_key
+            ).unwrap().to_captured().into_bytes()
+        };
+        match ProvisioningCms::validate_and_parse(
+            der(Oid(oid::CT_RPKI_MANIFEST.0.into()), b"<msg/>"),
+            &child, "child", "parent"
+        ) {
+            Err(ProvisioningCmsError::Signed(
+                SignedXmlError::ContentType
+            )) => { }
+            res => panic!("expected content type error, got {:?}", res)
+        }
+        match ProvisioningCms::validate_and_parse(
+            der(Oid(oid::PROTOCOL_CONTENT_TYPE.0.into()), b"<msg/>"),
+            &child, "child", "parent"
+        ) {
+            Err(ProvisioningCmsError::Xml(_)) => { }
+            res => panic!("expected XML error, got {:?}", res)
+        }
+        match ProvisioningCms::validate_and_parse(
+            Bytes::from_static(b"\x30\x00"), &child, "child", "parent"
+        ) {
+            Err(ProvisioningCmsError::Signed(
+                SignedXmlError::Decode
+            )) => { }
+            res => panic!("expected decode error, got {:?}", res)
+        }
+    }
+}
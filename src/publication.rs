@@ -0,0 +1,2045 @@
+//! The RPKI publication protocol.
+//!
+//! This module contains the messages exchanged between a publication client
+//! and a publication server as defined in [RFC 8181]. A client sends a
+//! [`PublicationQuery`] asking the server to publish or withdraw objects or
+//! to list the objects currently published. The server answers with a
+//! [`PublicationReply`].
+//!
+//! The messages are exchanged wrapped into CMS signed messages as provided
+//! by the [`sigmsg`] module. [`PublicationCms`] combines the two steps when
+//! sending and receiving messages.
+//!
+//! [RFC 8181]: https://tools.ietf.org/html/rfc8181
+//! [`PublicationQuery`]: enum.PublicationQuery.html
+//! [`PublicationReply`]: enum.PublicationReply.html
+//! [`sigmsg`]: ../sigmsg/index.html
+//! [`PublicationCms`]: struct.PublicationCms.html
+
+use std::{error, fmt, io, slice, str, vec};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::iter::FromIterator;
+use bytes::Bytes;
+use crate::uri;
+use crate::cert::Cert;
+use crate::crypto::{Sha256Hash, Signer, SigningError};
+use crate::sigmsg::{SignedMessage, SignedXmlError};
+use crate::x509::{Time, Validity};
+use crate::xml::decode::{Content, Element, Error, Name, Reader};
+use crate::xml::encode::{Escaped, write_attr, write_opt_attr};
+
+
+//------------ PublicationQuery ----------------------------------------------
+
+/// A query message sent by a publication client.
+#[derive(Clone, Debug)]
+pub enum PublicationQuery {
+    /// A list of objects to publish or withdraw.
+    Update(PublishDelta),
+
+    /// A request for the list of objects currently published.
+    ///
+    /// The value is the optional tag of the request.
+    List(Option<String>),
+}
+
+impl PublicationQuery {
+    /// Parses a query message from its XML representation.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        Self::parse_with(reader, false)
+    }
+
+    /// Parses a query message, rejecting tags not allowed by RFC 8181.
+    ///
+    /// Tags must be at most 1024 characters long and can’t contain
+    /// tabs or line breaks, leading or trailing spaces, or consecutive
+    /// spaces.
+    pub fn parse_strict<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        Self::parse_with(reader, true)
+    }
+
+    fn parse_with<R: io::BufRead>(
+        reader: R,
+        strict: bool
+    ) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+        let mut outer = reader.start(|element| {
+            take_msg_start(element, QUERY_TYPE)
+        })?;
+
+        let mut pdus = PublishDelta::new();
+        let mut list = None;
+        loop {
+            let mut start = None;
+            let inner = outer.take_opt_element(&mut reader, |element| {
+                start = Some(PduStart::from_element(element, strict)?);
+                Ok::<_, Error>(())
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            let start = start.unwrap(); // Or we'd have exited already.
+            if let Kind::List = start.kind {
+                if list.is_some() || !start.is_list_query() {
+                    return Err(Error::Malformed)
+                }
+                list = Some(start.tag);
+            }
+            else {
+                pdus.push(
+                    QueryPdu::take_from(start, &mut inner, &mut reader)?
+                );
+            }
+            inner.take_end(&mut reader)?;
+        }
+
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+
+        match list {
+            Some(tag) => {
+                if !pdus.is_empty() {
+                    return Err(Error::Malformed)
+                }
+                Ok(PublicationQuery::List(tag))
+            }
+            None => Ok(PublicationQuery::Update(pdus))
+        }
+    }
+
+    /// Writes the XML representation of the query to a target.
+    pub fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write_msg_start(target, QUERY_TYPE)?;
+        match *self {
+            PublicationQuery::Update(ref delta) => {
+                for pdu in delta {
+                    pdu.write_xml(target, "  ")?;
+                }
+            }
+            PublicationQuery::List(ref tag) => {
+                write!(target, "  <list")?;
+                write_opt_attr(target, "tag", tag.as_ref())?;
+                writeln!(target, "/>")?;
+            }
+        }
+        write_msg_end(target)
+    }
+}
+
+
+//------------ PublishDelta --------------------------------------------------
+
+/// A list of publish and withdraw requests.
+///
+/// The server applies all requests of a query atomically: either all of
+/// them succeed or none does. This makes it possible to update a manifest,
+/// its CRL, and the objects listed on it in one go. The requests are kept
+/// in the order they were added which is also the order they are written
+/// in.
+#[derive(Clone, Debug, Default)]
+pub struct PublishDelta(Vec<QueryPdu>);
+
+impl PublishDelta {
+    /// Creates a new, empty delta.
+    pub fn new() -> Self {
+        PublishDelta(Vec::new())
+    }
+
+    /// Adds a request to publish a new object.
+    pub fn add_publish(&mut self, uri: uri::Rsync, content: Bytes) {
+        self.push(Publish::new(None, uri, None, content))
+    }
+
+    /// Adds a request to replace an object with the given hash.
+    pub fn add_update(
+        &mut self,
+        uri: uri::Rsync,
+        content: Bytes,
+        old_hash: Sha256Hash
+    ) {
+        self.push(Publish::new(None, uri, Some(old_hash), content))
+    }
+
+    /// Adds a request to withdraw an object with the given hash.
+    pub fn add_withdraw(&mut self, uri: uri::Rsync, hash: Sha256Hash) {
+        self.push(Withdraw::new(None, uri, hash))
+    }
+
+    /// Adds a request.
+    ///
+    /// Use this method to add requests with a tag.
+    pub fn push<T: Into<QueryPdu>>(&mut self, pdu: T) {
+        self.0.push(pdu.into())
+    }
+
+    /// Returns the number of requests.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether there are no requests.
+    ///
+    /// An empty delta is allowed and doesn’t change anything.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the requests in order.
+    pub fn iter(&self) -> slice::Iter<QueryPdu> {
+        self.0.iter()
+    }
+
+    /// Returns a slice of the requests.
+    pub fn as_slice(&self) -> &[QueryPdu] {
+        self.0.as_slice()
+    }
+
+    /// Converts the delta into a vec of the requests.
+    pub fn into_vec(self) -> Vec<QueryPdu> {
+        self.0
+    }
+
+    /// Checks whether the delta can be applied to the current objects.
+    ///
+    /// The closure `current` is called with the URI of an object and must
+    /// return the hash of the object currently published under that URI or
+    /// `None` if there is no such object. The requests are checked in
+    /// order, taking the changes of earlier requests into account.
+    ///
+    /// If any request cannot be applied, returns an error report for each
+    /// such request, suitable for an error reply to the query.
+    pub fn check<F>(&self, current: F) -> Result<(), Vec<ReportError>>
+    where F: Fn(&uri::Rsync) -> Option<Sha256Hash> {
+        let mut changed: HashMap<uri::Rsync, Option<Sha256Hash>>
+            = HashMap::new();
+        let mut errors = Vec::new();
+        for pdu in &self.0 {
+            let (uri, expected, new) = match *pdu {
+                QueryPdu::Publish(ref publish) => (
+                    publish.uri(), publish.hash(),
+                    Some(Publish::hash_of(publish.content()))
+                ),
+                QueryPdu::Withdraw(ref withdraw) => (
+                    withdraw.uri(), Some(withdraw.hash()), None
+                )
+            };
+            let existing = match changed.get(uri) {
+                Some(hash) => *hash,
+                None => current(uri)
+            };
+            let code = match (existing, expected) {
+                (None, None) => None,
+                (Some(_), None) => {
+                    Some(ReportErrorCode::ObjectAlreadyPresent)
+                }
+                (None, Some(_)) => Some(ReportErrorCode::NoObjectPresent),
+                (Some(existing), Some(expected)) => {
+                    if existing == *expected {
+                        None
+                    }
+                    else {
+                        Some(ReportErrorCode::NoObjectMatchingHash)
+                    }
+                }
+            };
+            match code {
+                Some(code) => {
+                    errors.push(ReportError::for_pdu(code, pdu, None))
+                }
+                None => {
+                    changed.insert(uri.clone(), new);
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(errors)
+        }
+    }
+}
+
+impl From<Vec<QueryPdu>> for PublishDelta {
+    fn from(pdus: Vec<QueryPdu>) -> Self {
+        PublishDelta(pdus)
+    }
+}
+
+impl FromIterator<QueryPdu> for PublishDelta {
+    fn from_iter<I: IntoIterator<Item = QueryPdu>>(iter: I) -> Self {
+        PublishDelta(Vec::from_iter(iter))
+    }
+}
+
+impl IntoIterator for PublishDelta {
+    type Item = QueryPdu;
+    type IntoIter = vec::IntoIter<QueryPdu>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a PublishDelta {
+    type Item = &'a QueryPdu;
+    type IntoIter = slice::Iter<'a, QueryPdu>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+
+//------------ PublicationReply ----------------------------------------------
+
+/// A reply message sent by a publication server.
+#[derive(Clone, Debug)]
+pub enum PublicationReply {
+    /// All publish and withdraw requests of the query were successful.
+    Success,
+
+    /// The list of objects currently published.
+    List(Vec<ListElement>),
+
+    /// Processing the query failed.
+    ErrorReport(Vec<ReportError>),
+}
+
+impl PublicationReply {
+    /// Parses a reply message from its XML representation.
+    ///
+    /// A reply without any elements is interpreted as an empty list.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        Self::parse_with(reader, false)
+    }
+
+    /// Parses a reply message, rejecting tags not allowed by RFC 8181.
+    ///
+    /// See [`PublicationQuery::parse_strict`] for the rules for tags.
+    ///
+    /// [`PublicationQuery::parse_strict`]: enum.PublicationQuery.html#method.parse_strict
+    pub fn parse_strict<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        Self::parse_with(reader, true)
+    }
+
+    fn parse_with<R: io::BufRead>(
+        reader: R,
+        strict: bool
+    ) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+        let mut outer = reader.start(|element| {
+            take_msg_start(element, REPLY_TYPE)
+        })?;
+
+        let mut success = false;
+        let mut list = Vec::new();
+        let mut errors = Vec::new();
+        loop {
+            let mut start = None;
+            let inner = outer.take_opt_element(&mut reader, |element| {
+                start = Some(PduStart::from_element(element, strict)?);
+                Ok::<_, Error>(())
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            let start = start.unwrap(); // Or we'd have exited already.
+            match start.kind {
+                Kind::Success => {
+                    if success || !start.is_success() {
+                        return Err(Error::Malformed)
+                    }
+                    success = true;
+                }
+                Kind::List => list.push(ListElement::from_start(start)?),
+                Kind::ReportError => {
+                    let error = ReportError::take_from(
+                        start, &mut inner, &mut reader, strict
+                    )?;
+                    errors.push(error);
+                }
+                _ => return Err(Error::Malformed)
+            }
+            inner.take_end(&mut reader)?;
+        }
+
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+
+        match (success, list.is_empty(), errors.is_empty()) {
+            (true, true, true) => Ok(PublicationReply::Success),
+            (false, _, true) => Ok(PublicationReply::List(list)),
+            (false, true, false) => Ok(PublicationReply::ErrorReport(errors)),
+            _ => Err(Error::Malformed)
+        }
+    }
+
+    /// Creates a list reply for a list query with the given tag.
+    ///
+    /// The tag of the query is echoed in every list element.
+    pub fn list_for<I>(tag: Option<&str>, objects: I) -> Self
+    where I: IntoIterator<Item = (uri::Rsync, Sha256Hash)> {
+        PublicationReply::List(
+            objects.into_iter().map(|(uri, hash)| {
+                ListElement::new(tag.map(Into::into), uri, hash)
+            }).collect()
+        )
+    }
+
+    /// Writes the XML representation of the reply to a target.
+    pub fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write_msg_start(target, REPLY_TYPE)?;
+        match *self {
+            PublicationReply::Success => {
+                writeln!(target, "  <success/>")?;
+            }
+            PublicationReply::List(ref list) => {
+                for item in list {
+                    item.write_xml(target)?;
+                }
+            }
+            PublicationReply::ErrorReport(ref errors) => {
+                for error in errors {
+                    error.write_xml(target)?;
+                }
+            }
+        }
+        write_msg_end(target)
+    }
+}
+
+
+//------------ QueryPdu ------------------------------------------------------
+
+/// A single request to change the published objects.
+#[derive(Clone, Debug)]
+pub enum QueryPdu {
+    Publish(Publish),
+    Withdraw(Withdraw),
+}
+
+impl QueryPdu {
+    /// Takes the remainder of a PDU whose start has already been parsed.
+    fn take_from<R: io::BufRead>(
+        start: PduStart,
+        content: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<Self, Error> {
+        if start.error_code.is_some() {
+            return Err(Error::Malformed)
+        }
+        let uri = match start.uri {
+            Some(uri) => uri,
+            None => return Err(Error::Malformed)
+        };
+        match start.kind {
+            Kind::Publish => {
+                let data = content.take_base64(reader, PUBLISH)?;
+                Ok(QueryPdu::Publish(Publish::new(
+                    start.tag, uri, start.hash, data
+                )))
+            }
+            Kind::Withdraw => {
+                let hash = match start.hash {
+                    Some(hash) => hash,
+                    None => return Err(Error::Malformed)
+                };
+                Ok(QueryPdu::Withdraw(Withdraw::new(start.tag, uri, hash)))
+            }
+            _ => Err(Error::Malformed)
+        }
+    }
+
+    /// Writes the XML representation of the PDU.
+    fn write_xml<W: io::Write>(
+        &self,
+        target: &mut W,
+        indent: &str
+    ) -> io::Result<()> {
+        match *self {
+            QueryPdu::Publish(ref publish) => {
+                write!(target, "{}<publish", indent)?;
+                write_opt_attr(target, "tag", publish.tag.as_ref())?;
+                write_attr(target, "uri", &publish.uri)?;
+                write_opt_attr(target, "hash", publish.hash.as_ref())?;
+                writeln!(
+                    target, ">{}</publish>", base64::encode(&publish.content)
+                )
+            }
+            QueryPdu::Withdraw(ref withdraw) => {
+                write!(target, "{}<withdraw", indent)?;
+                write_opt_attr(target, "tag", withdraw.tag.as_ref())?;
+                write_attr(target, "uri", &withdraw.uri)?;
+                write_attr(target, "hash", &withdraw.hash)?;
+                writeln!(target, "/>")
+            }
+        }
+    }
+}
+
+impl QueryPdu {
+    /// Returns the tag of the PDU if present.
+    pub fn tag(&self) -> Option<&str> {
+        match *self {
+            QueryPdu::Publish(ref publish) => publish.tag(),
+            QueryPdu::Withdraw(ref withdraw) => withdraw.tag(),
+        }
+    }
+}
+
+impl From<Publish> for QueryPdu {
+    fn from(publish: Publish) -> Self {
+        QueryPdu::Publish(publish)
+    }
+}
+
+impl From<Withdraw> for QueryPdu {
+    fn from(withdraw: Withdraw) -> Self {
+        QueryPdu::Withdraw(withdraw)
+    }
+}
+
+
+//------------ Publish -------------------------------------------------------
+
+/// A request to publish an object.
+///
+/// If the object replaces an object already published under the same URI,
+/// the request has to contain the hash of the replaced object.
+#[derive(Clone, Debug)]
+pub struct Publish {
+    tag: Option<String>,
+    uri: uri::Rsync,
+    hash: Option<Sha256Hash>,
+    content: Bytes,
+}
+
+impl Publish {
+    pub fn new(
+        tag: Option<String>,
+        uri: uri::Rsync,
+        hash: Option<Sha256Hash>,
+        content: Bytes,
+    ) -> Self {
+        Publish { tag, uri, hash, content }
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    /// Returns the SHA-256 hash of the object to be replaced if present.
+    pub fn hash(&self) -> Option<&Sha256Hash> {
+        self.hash.as_ref()
+    }
+
+    /// Returns the hash of the given object content.
+    ///
+    /// This is the hash to be used in a request replacing or withdrawing
+    /// an object with this content.
+    pub fn hash_of(content: &[u8]) -> Sha256Hash {
+        Sha256Hash::from_content(content)
+    }
+
+    pub fn content(&self) -> &Bytes {
+        &self.content
+    }
+}
+
+
+//------------ Withdraw ------------------------------------------------------
+
+/// A request to withdraw a published object.
+#[derive(Clone, Debug)]
+pub struct Withdraw {
+    tag: Option<String>,
+    uri: uri::Rsync,
+    hash: Sha256Hash,
+}
+
+impl Withdraw {
+    pub fn new(
+        tag: Option<String>,
+        uri: uri::Rsync,
+        hash: Sha256Hash
+    ) -> Self {
+        Withdraw { tag, uri, hash }
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    /// Returns the SHA-256 hash of the object to be withdrawn.
+    pub fn hash(&self) -> &Sha256Hash {
+        &self.hash
+    }
+}
+
+
+//------------ ListElement ---------------------------------------------------
+
+/// A currently published object as included in a list reply.
+#[derive(Clone, Debug)]
+pub struct ListElement {
+    tag: Option<String>,
+    uri: uri::Rsync,
+    hash: Sha256Hash,
+}
+
+impl ListElement {
+    pub fn new(
+        tag: Option<String>,
+        uri: uri::Rsync,
+        hash: Sha256Hash
+    ) -> Self {
+        ListElement { tag, uri, hash }
+    }
+
+    fn from_start(start: PduStart) -> Result<Self, Error> {
+        match (start.uri, start.hash, start.error_code) {
+            (Some(uri), Some(hash), None) => {
+                Ok(ListElement::new(start.tag, uri, hash))
+            }
+            _ => Err(Error::Malformed)
+        }
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    /// Returns the SHA-256 hash of the published object.
+    pub fn hash(&self) -> &Sha256Hash {
+        &self.hash
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "  <list")?;
+        write_opt_attr(target, "tag", self.tag.as_ref())?;
+        write_attr(target, "uri", &self.uri)?;
+        write_attr(target, "hash", &self.hash)?;
+        writeln!(target, "/>")
+    }
+}
+
+
+//------------ ReportError ---------------------------------------------------
+
+/// An error reported by the publication server.
+///
+/// The report can contain a human readable error text as well as the PDU of
+/// the query that caused the error. The latter is limited to publish and
+/// withdraw PDUs.
+#[derive(Clone, Debug)]
+pub struct ReportError {
+    error_code: ReportErrorCode,
+    tag: Option<String>,
+    error_text: Option<String>,
+    failed_pdu: Option<QueryPdu>,
+}
+
+impl ReportError {
+    pub fn new(
+        error_code: ReportErrorCode,
+        tag: Option<String>,
+        error_text: Option<String>,
+        failed_pdu: Option<QueryPdu>,
+    ) -> Self {
+        ReportError { error_code, tag, error_text, failed_pdu }
+    }
+
+    /// Creates an error report for a PDU of a query.
+    ///
+    /// The report echoes the tag of the PDU and includes the PDU as the
+    /// failed PDU.
+    pub fn for_pdu(
+        error_code: ReportErrorCode,
+        pdu: &QueryPdu,
+        error_text: Option<String>,
+    ) -> Self {
+        ReportError::new(
+            error_code, pdu.tag().map(Into::into), error_text,
+            Some(pdu.clone())
+        )
+    }
+
+    /// Takes the content of a report whose start has already been parsed.
+    fn take_from<R: io::BufRead>(
+        start: PduStart,
+        content: &mut Content,
+        reader: &mut Reader<R>,
+        strict: bool,
+    ) -> Result<Self, Error> {
+        if start.uri.is_some() || start.hash.is_some() {
+            return Err(Error::Malformed)
+        }
+        let error_code = match start.error_code {
+            Some(code) => code,
+            None => return Err(Error::Malformed)
+        };
+
+        let mut error_text = None;
+        let mut failed_pdu = None;
+        loop {
+            let mut is_text = false;
+            let inner = content.take_opt_element(reader, |element| {
+                match element.name() {
+                    ERROR_TEXT if error_text.is_none() => is_text = true,
+                    FAILED_PDU if failed_pdu.is_none() => { }
+                    _ => return Err(Error::Malformed)
+                }
+                element.attributes(|_, _| Err(Error::Malformed))
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            if is_text {
+                error_text = Some(inner.take_text(reader, |text| {
+                    text.to_ascii().map(|text| text.into_owned())
+                })?);
+            }
+            else {
+                let mut start = None;
+                let pdu = inner.take_opt_element(reader, |element| {
+                    start = Some(PduStart::from_element(element, strict)?);
+                    Ok::<_, Error>(())
+                })?;
+                let mut pdu = match pdu {
+                    Some(pdu) => pdu,
+                    None => return Err(Error::Malformed)
+                };
+                failed_pdu = Some(QueryPdu::take_from(
+                    start.unwrap(), &mut pdu, reader
+                )?);
+                pdu.take_end(reader)?;
+            }
+            inner.take_end(reader)?;
+        }
+
+        Ok(ReportError::new(error_code, start.tag, error_text, failed_pdu))
+    }
+
+    pub fn error_code(&self) -> &ReportErrorCode {
+        &self.error_code
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub fn error_text(&self) -> Option<&str> {
+        self.error_text.as_deref()
+    }
+
+    pub fn failed_pdu(&self) -> Option<&QueryPdu> {
+        self.failed_pdu.as_ref()
+    }
+
+    fn write_xml<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        write!(target, "  <report_error")?;
+        write_opt_attr(target, "tag", self.tag.as_ref())?;
+        write_attr(target, "error_code", &self.error_code)?;
+        if self.error_text.is_none() && self.failed_pdu.is_none() {
+            return writeln!(target, "/>")
+        }
+        writeln!(target, ">")?;
+        if let Some(ref text) = self.error_text {
+            writeln!(
+                target, "    <error_text>{}</error_text>", Escaped(text)
+            )?;
+        }
+        if let Some(ref pdu) = self.failed_pdu {
+            writeln!(target, "    <failed_pdu>")?;
+            pdu.write_xml(target, "      ")?;
+            writeln!(target, "    </failed_pdu>")?;
+        }
+        writeln!(target, "  </report_error>")
+    }
+}
+
+
+//------------ ReportErrorCode -----------------------------------------------
+
+/// The error codes defined in section 2.5 of RFC 8181.
+///
+/// Error codes not defined by the RFC are kept as they are in the
+/// `Unknown` variant so that a client can still report them.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ReportErrorCode {
+    XmlError,
+    PermissionFailure,
+    BadCmsSignature,
+    ObjectAlreadyPresent,
+    NoObjectPresent,
+    NoObjectMatchingHash,
+    ConsistencyProblem,
+    OtherError,
+    Unknown(String),
+}
+
+impl ReportErrorCode {
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ReportErrorCode::XmlError => "xml_error",
+            ReportErrorCode::PermissionFailure => "permission_failure",
+            ReportErrorCode::BadCmsSignature => "bad_cms_signature",
+            ReportErrorCode::ObjectAlreadyPresent => "object_already_present",
+            ReportErrorCode::NoObjectPresent => "no_object_present",
+            ReportErrorCode::NoObjectMatchingHash => {
+                "no_object_matching_hash"
+            }
+            ReportErrorCode::ConsistencyProblem => "consistency_problem",
+            ReportErrorCode::OtherError => "other_error",
+            ReportErrorCode::Unknown(ref code) => code.as_str(),
+        }
+    }
+}
+
+impl str::FromStr for ReportErrorCode {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xml_error" => Ok(ReportErrorCode::XmlError),
+            "permission_failure" => Ok(ReportErrorCode::PermissionFailure),
+            "bad_cms_signature" => Ok(ReportErrorCode::BadCmsSignature),
+            "object_already_present" => {
+                Ok(ReportErrorCode::ObjectAlreadyPresent)
+            }
+            "no_object_present" => Ok(ReportErrorCode::NoObjectPresent),
+            "no_object_matching_hash" => {
+                Ok(ReportErrorCode::NoObjectMatchingHash)
+            }
+            "consistency_problem" => Ok(ReportErrorCode::ConsistencyProblem),
+            "other_error" => Ok(ReportErrorCode::OtherError),
+            _ => Ok(ReportErrorCode::Unknown(s.into()))
+        }
+    }
+}
+
+impl fmt::Display for ReportErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+//------------ PublicationCms ------------------------------------------------
+
+/// Publication messages wrapped into CMS signed messages.
+///
+/// The methods of this type take care of all the steps necessary to send
+/// and receive publication messages: encoding the XML, wrapping it into a
+/// signed message with the right content type, and, on the receiving end,
+/// validating the signed message against the identity certificate of the
+/// sender before parsing the XML.
+///
+/// Warnings produced by validating the signed message are dropped.
+#[derive(Clone, Copy, Debug)]
+pub struct PublicationCms;
+
+impl PublicationCms {
+    /// Signs a query.
+    ///
+    /// The EE certificate of the signed message is issued by `issuer_key`
+    /// which should be the key of the publisher’s identity certificate. It
+    /// will have a random serial number and the given validity.
+    ///
+    /// Returns the DER encoded signed message.
+    pub fn sign<S: Signer + ?Sized>(
+        query: &PublicationQuery,
+        signer: &S,
+        issuer_key: &S::KeyId,
+        ee_validity: Validity,
+    ) -> Result<Bytes, SigningError<S::Error>> {
+        let mut xml = Vec::new();
+        query.write_xml(&mut xml).unwrap(); // Writing to a vec can’t fail.
+        Self::sign_xml(xml, signer, issuer_key, ee_validity)
+    }
+
+    /// Signs a reply.
+    ///
+    /// This is the same as [`sign`] but for replies. The EE certificate is
+    /// issued by the key of the server’s identity certificate.
+    ///
+    /// [`sign`]: #method.sign
+    pub fn sign_reply<S: Signer + ?Sized>(
+        reply: &PublicationReply,
+        signer: &S,
+        issuer_key: &S::KeyId,
+        ee_validity: Validity,
+    ) -> Result<Bytes, SigningError<S::Error>> {
+        let mut xml = Vec::new();
+        reply.write_xml(&mut xml).unwrap(); // Writing to a vec can’t fail.
+        Self::sign_xml(xml, signer, issuer_key, ee_validity)
+    }
+
+    /// Validates a signed query and parses its content.
+    ///
+    /// The signed message must have been issued by the identity certificate
+    /// of the publisher, `publisher`.
+    pub fn validate_and_parse(
+        der: Bytes,
+        publisher: &Cert,
+    ) -> Result<PublicationQuery, PublicationCmsError> {
+        Self::validate_and_parse_at(der, publisher, Time::now())
+    }
+
+    /// Validates a signed query at the given time and parses its content.
+    pub fn validate_and_parse_at(
+        der: Bytes,
+        publisher: &Cert,
+        now: Time,
+    ) -> Result<PublicationQuery, PublicationCmsError> {
+        let xml = Self::validate_xml(der, publisher, now)?;
+        PublicationQuery::parse(xml.as_ref()).map_err(
+            PublicationCmsError::Xml
+        )
+    }
+
+    /// Validates a signed reply and parses its content.
+    ///
+    /// The signed message must have been issued by the identity certificate
+    /// of the server, `server`.
+    pub fn validate_and_parse_reply(
+        der: Bytes,
+        server: &Cert,
+    ) -> Result<PublicationReply, PublicationCmsError> {
+        Self::validate_and_parse_reply_at(der, server, Time::now())
+    }
+
+    /// Validates a signed reply at the given time and parses its content.
+    pub fn validate_and_parse_reply_at(
+        der: Bytes,
+        server: &Cert,
+        now: Time,
+    ) -> Result<PublicationReply, PublicationCmsError> {
+        let xml = Self::validate_xml(der, server, now)?;
+        PublicationReply::parse(xml.as_ref()).map_err(
+            PublicationCmsError::Xml
+        )
+    }
+
+    /// Wraps XML content into a signed message.
+    fn sign_xml<S: Signer + ?Sized>(
+        xml: Vec<u8>,
+        signer: &S,
+        issuer_key: &S::KeyId,
+        ee_validity: Validity,
+    ) -> Result<Bytes, SigningError<S::Error>> {
+        SignedMessage::sign_xml(xml.into(), signer, issuer_key, ee_validity)
+    }
+
+    /// Validates a signed message and returns its XML content.
+    fn validate_xml(
+        der: Bytes,
+        issuer: &Cert,
+        now: Time,
+    ) -> Result<Bytes, PublicationCmsError> {
+        SignedMessage::validate_xml_at(der, issuer, now).map_err(Into::into)
+    }
+}
+
+
+//------------ PublicationCmsError -------------------------------------------
+
+/// An error happened while receiving a CMS wrapped publication message.
+#[derive(Debug)]
+pub enum PublicationCmsError {
+    /// The signed message is not acceptable.
+    Signed(SignedXmlError),
+
+    /// The XML content is not a valid publication message.
+    Xml(Error),
+}
+
+impl From<SignedXmlError> for PublicationCmsError {
+    fn from(err: SignedXmlError) -> Self {
+        PublicationCmsError::Signed(err)
+    }
+}
+
+impl fmt::Display for PublicationCmsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PublicationCmsError::Signed(ref err) => err.fmt(f),
+            PublicationCmsError::Xml(ref err) => {
+                write!(f, "invalid publication message: {}", err)
+            }
+        }
+    }
+}
+
+impl error::Error for PublicationCmsError { }
+
+
+//------------ PduStart ------------------------------------------------------
+
+/// The start tag of a PDU with all the attributes we know of.
+///
+/// Which attributes are actually allowed depends on the kind of PDU and is
+/// checked when processing the rest of the PDU.
+struct PduStart {
+    kind: Kind,
+    tag: Option<String>,
+    uri: Option<uri::Rsync>,
+    hash: Option<Sha256Hash>,
+    error_code: Option<ReportErrorCode>,
+}
+
+impl PduStart {
+    fn from_element(element: Element, strict: bool) -> Result<Self, Error> {
+        let kind = match element.name() {
+            PUBLISH => Kind::Publish,
+            WITHDRAW => Kind::Withdraw,
+            LIST => Kind::List,
+            SUCCESS => Kind::Success,
+            REPORT_ERROR => Kind::ReportError,
+            _ => return Err(Error::Malformed)
+        };
+        let mut res = PduStart {
+            kind, tag: None, uri: None, hash: None, error_code: None
+        };
+        element.attributes(|name, value| match name {
+            b"tag" => {
+                let tag = value.into_string()?;
+                if strict && !is_valid_tag(&tag) {
+                    return Err(Error::Malformed)
+                }
+                res.tag = Some(tag);
+                Ok(())
+            }
+            b"uri" => {
+                res.uri = Some(value.ascii_into()?);
+                Ok(())
+            }
+            b"hash" => {
+                res.hash = Some(value.ascii_into()?);
+                Ok(())
+            }
+            b"error_code" => {
+                res.error_code = Some(value.ascii_into()?);
+                Ok(())
+            }
+            _ => Err(Error::Malformed)
+        })?;
+        Ok(res)
+    }
+
+    fn is_list_query(&self) -> bool {
+        self.uri.is_none() && self.hash.is_none() && self.error_code.is_none()
+    }
+
+    fn is_success(&self) -> bool {
+        self.tag.is_none() && self.is_list_query()
+    }
+}
+
+
+//------------ Kind ----------------------------------------------------------
+
+enum Kind {
+    Publish,
+    Withdraw,
+    List,
+    Success,
+    ReportError,
+}
+
+
+//------------ Helpers -------------------------------------------------------
+
+/// Returns whether a tag follows the rules of RFC 8181.
+///
+/// Tags are of type `xsd:token` with a maximum length of 1024 characters.
+fn is_valid_tag(tag: &str) -> bool {
+    tag.chars().count() <= 1024
+        && !tag.starts_with(' ') && !tag.ends_with(' ')
+        && !tag.contains("  ")
+        && !tag.contains(|ch: char| ch == '\t' || ch == '\n' || ch == '\r')
+}
+
+/// Checks the start of the `<msg>` element.
+///
+/// The element must have the right version and the given message type.
+fn take_msg_start(element: Element, msg_type: &str) -> Result<(), Error> {
+    if element.name() != MSG {
+        return Err(Error::Malformed)
+    }
+    let mut version = false;
+    let mut has_type = false;
+    element.attributes(|name, value| match name {
+        b"version" => {
+            let value = value.ascii_into::<String>()?;
+            if value != VERSION {
+                return Err(Error::UnsupportedVersion(value))
+            }
+            version = true;
+            Ok(())
+        }
+        b"type" => {
+            let value = value.ascii_into::<String>()?;
+            if value != msg_type {
+                return Err(Error::UnexpectedType(value))
+            }
+            has_type = true;
+            Ok(())
+        }
+        _ => Err(Error::Malformed)
+    })?;
+    if version && has_type {
+        Ok(())
+    }
+    else {
+        Err(Error::Malformed)
+    }
+}
+
+fn write_msg_start<W: io::Write>(
+    target: &mut W,
+    msg_type: &str
+) -> io::Result<()> {
+    writeln!(
+        target,
+        "<msg xmlns=\"{}\" version=\"{}\" type=\"{}\">",
+        str::from_utf8(NS).unwrap(), VERSION, msg_type
+    )
+}
+
+fn write_msg_end<W: io::Write>(target: &mut W) -> io::Result<()> {
+    writeln!(target, "</msg>")
+}
+
+
+//------------ Xml Names -----------------------------------------------------
+
+const NS: &[u8] = b"http://www.hactrn.net/uris/rpki/publication-spec/";
+const MSG: Name = Name::qualified(NS, b"msg");
+const PUBLISH: Name = Name::qualified(NS, b"publish");
+const WITHDRAW: Name = Name::qualified(NS, b"withdraw");
+const LIST: Name = Name::qualified(NS, b"list");
+const SUCCESS: Name = Name::qualified(NS, b"success");
+const REPORT_ERROR: Name = Name::qualified(NS, b"report_error");
+const ERROR_TEXT: Name = Name::qualified(NS, b"error_text");
+const FAILED_PDU: Name = Name::qualified(NS, b"failed_pdu");
+
+const VERSION: &str = "4";
+const QUERY_TYPE: &str = "query";
+const REPLY_TYPE: &str = "reply";
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn query(data: &[u8]) -> Result<PublicationQuery, Error> {
+        PublicationQuery::parse(data)
+    }
+
+    fn reply(data: &[u8]) -> Result<PublicationReply, Error> {
+        PublicationReply::parse(data)
+    }
+
+    #[test]
+    fn parse_query() {
+        let pdus = match query(
+            include_bytes!("../test-data/publication-query.xml")
+        ).unwrap() {
+            PublicationQuery::Update(pdus) => pdus.into_vec(),
+            _ => panic!("expected update query")
+        };
+        assert_eq!(pdus.len(), 3);
+        match pdus[0] {
+            QueryPdu::Publish(ref publish) => {
+                assert_eq!(publish.tag(), Some("first"));
+                assert_eq!(
+                    publish.uri().to_string(),
+                    "rsync://example.com/repo/ca/ca.mft"
+                );
+                assert!(publish.hash().is_none());
+                assert_eq!(publish.content().as_ref(), b"manifest");
+            }
+            _ => panic!("expected publish")
+        }
+        match pdus[1] {
+            QueryPdu::Publish(ref publish) => {
+                assert_eq!(publish.tag(), None);
+                assert_eq!(
+                    publish.hash().unwrap().to_string(),
+                    "e3b0c44298fc1c149afbf4c8996fb924\
+                     27ae41e4649b934ca495991b7852b855"
+                );
+                assert_eq!(publish.content().as_ref(), b"roa");
+            }
+            _ => panic!("expected publish")
+        }
+        match pdus[2] {
+            QueryPdu::Withdraw(ref withdraw) => {
+                assert_eq!(withdraw.tag(), Some("third"));
+                assert_eq!(
+                    withdraw.uri().to_string(),
+                    "rsync://example.com/repo/ca/old.roa"
+                );
+                assert_eq!(withdraw.hash().as_slice().len(), 32);
+            }
+            _ => panic!("expected withdraw")
+        }
+    }
+
+    #[test]
+    fn parse_list_query() {
+        match query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"query\">\
+              <list/></msg>"
+        ).unwrap() {
+            PublicationQuery::List(None) => { }
+            _ => panic!("expected list query")
+        }
+    }
+
+    #[test]
+    fn parse_bad_query() {
+        // Reply instead of query.
+        assert!(query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <list/></msg>"
+        ).is_err());
+        // Wrong version.
+        assert!(query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"3\" type=\"query\">\
+              <list/></msg>"
+        ).is_err());
+        // Withdraw without hash.
+        assert!(query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"query\">\
+              <withdraw uri=\"rsync://example.com/repo/a.roa\"/></msg>"
+        ).is_err());
+        // List mixed with publish.
+        assert!(query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"query\">\
+              <list/><publish uri=\"rsync://example.com/repo/a.roa\">\
+              cm9h</publish></msg>"
+        ).is_err());
+        // Invalid base64.
+        match query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"query\">\
+              <publish uri=\"rsync://example.com/repo/a.roa\">\
+              cm9h!</publish></msg>"
+        ) {
+            Err(Error::InvalidBase64(ref name)) => {
+                assert_eq!(name, "publish")
+            }
+            res => panic!("expected invalid base64, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn parse_version_and_type() {
+        match query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"3\" type=\"query\">\
+              <list/></msg>"
+        ) {
+            Err(Error::UnsupportedVersion(ref version))
+                if version == "3" => { }
+            res => panic!("expected unsupported version, got {:?}", res)
+        }
+        match query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <list/></msg>"
+        ) {
+            Err(Error::UnexpectedType(ref msg_type))
+                if msg_type == "reply" => { }
+            res => panic!("expected unexpected type, got {:?}", res)
+        }
+        match reply(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"answer\">\
+              <success/></msg>"
+        ) {
+            Err(Error::UnexpectedType(ref msg_type))
+                if msg_type == "answer" => { }
+            res => panic!("expected unexpected type, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn parse_reply() {
+        let errors = match reply(
+            include_bytes!("../test-data/publication-reply.xml")
+        ).unwrap() {
+            PublicationReply::ErrorReport(errors) => errors,
+            _ => panic!("expected error report")
+        };
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].error_code(), &ReportErrorCode::NoObjectMatchingHash
+        );
+        assert_eq!(errors[0].tag(), Some("first"));
+        assert_eq!(errors[0].error_text(), Some("Hash mismatch"));
+        match errors[0].failed_pdu() {
+            Some(QueryPdu::Publish(publish)) => {
+                assert_eq!(
+                    publish.uri().to_string(),
+                    "rsync://example.com/repo/ca/ca.mft"
+                );
+                assert_eq!(publish.content().as_ref(), b"manifest");
+            }
+            _ => panic!("expected failed publish")
+        }
+        assert_eq!(errors[1].error_code(), &ReportErrorCode::OtherError);
+        assert_eq!(errors[1].tag(), None);
+        assert!(errors[1].error_text().is_none());
+        assert!(errors[1].failed_pdu().is_none());
+
+        match reply(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <success/></msg>"
+        ).unwrap() {
+            PublicationReply::Success => { }
+            _ => panic!("expected success")
+        }
+
+        match reply(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <list uri=\"rsync://example.com/repo/a.roa\" \
+              hash=\"00ff00ff00ff00ff00ff00ff00ff00ff\
+              00ff00ff00ff00ff00ff00ff00ff00ff\"/></msg>"
+        ).unwrap() {
+            PublicationReply::List(list) => {
+                assert_eq!(list.len(), 1);
+                assert_eq!(list[0].hash().to_string(), "00ff".repeat(16));
+            }
+            _ => panic!("expected list")
+        }
+
+        // Success mixed with an error.
+        assert!(reply(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <success/><report_error error_code=\"xml_error\"/></msg>"
+        ).is_err());
+    }
+
+    /// Writes a query, parses it back and checks that it stays the same.
+    fn query_round_trip(query: &PublicationQuery) -> PublicationQuery {
+        let mut first = Vec::new();
+        query.write_xml(&mut first).unwrap();
+        let res = PublicationQuery::parse(first.as_slice()).unwrap();
+        let mut second = Vec::new();
+        res.write_xml(&mut second).unwrap();
+        assert_eq!(first, second);
+        res
+    }
+
+    /// Writes a reply, parses it back and checks that it stays the same.
+    fn reply_round_trip(reply: &PublicationReply) -> PublicationReply {
+        let mut first = Vec::new();
+        reply.write_xml(&mut first).unwrap();
+        let res = PublicationReply::parse(first.as_slice()).unwrap();
+        let mut second = Vec::new();
+        res.write_xml(&mut second).unwrap();
+        assert_eq!(first, second);
+        res
+    }
+
+    // The rfc8181-* test data are the examples from section 3.7 of
+    // RFC 8181 with the object content completed and the two publish and
+    // withdraw queries as well as the two error reports combined.
+
+    #[test]
+    fn rfc_query() {
+        let content = &include_bytes!("../test-data/gbr-ta.cer")[..180];
+        let check = |query: PublicationQuery| {
+            let pdus = match query {
+                PublicationQuery::Update(pdus) => pdus.into_vec(),
+                _ => panic!("expected update query")
+            };
+            assert_eq!(pdus.len(), 2);
+            match pdus[0] {
+                QueryPdu::Publish(ref publish) => {
+                    assert_eq!(publish.tag(), Some("foo"));
+                    assert_eq!(
+                        publish.uri().to_string(),
+                        "rsync://wombat.example/Alice/\
+                         blCrcCp9ltyPDNzYKPfxc.cer"
+                    );
+                    assert_eq!(
+                        publish.hash().unwrap().to_string(),
+                        "deadf00d".repeat(8)
+                    );
+                    assert_eq!(publish.content().as_ref(), content);
+                }
+                _ => panic!("expected publish")
+            }
+            match pdus[1] {
+                QueryPdu::Withdraw(ref withdraw) => {
+                    assert_eq!(withdraw.tag(), Some("foo"));
+                    assert_eq!(
+                        withdraw.uri().to_string(),
+                        "rsync://wombat.example/Alice/\
+                         6N4X8fnrEDAmtBkbKBGZG7Ew.roa"
+                    );
+                    assert_eq!(
+                        withdraw.hash().to_string(), "deadf00d".repeat(8)
+                    );
+                }
+                _ => panic!("expected withdraw")
+            }
+        };
+        let parsed = query(
+            include_bytes!("../test-data/rfc8181-query.xml")
+        ).unwrap();
+        check(query_round_trip(&parsed));
+        check(parsed);
+
+        match query_round_trip(&PublicationQuery::List(None)) {
+            PublicationQuery::List(None) => { }
+            _ => panic!("expected list query")
+        }
+    }
+
+    #[test]
+    fn rfc_replies() {
+        let content = &include_bytes!("../test-data/gbr-ta.cer")[..180];
+        let check = |reply: PublicationReply| {
+            let errors = match reply {
+                PublicationReply::ErrorReport(errors) => errors,
+                _ => panic!("expected error report")
+            };
+            assert_eq!(errors.len(), 2);
+            assert_eq!(
+                errors[0].error_code(), &ReportErrorCode::NoObjectPresent
+            );
+            assert_eq!(errors[0].tag(), Some("foo"));
+            assert_eq!(
+                errors[0].error_text(),
+                Some("Can't withdraw an object that isn't there.")
+            );
+            assert!(errors[0].failed_pdu().is_none());
+            assert_eq!(
+                errors[1].error_code(), &ReportErrorCode::ObjectAlreadyPresent
+            );
+            assert_eq!(errors[1].tag(), Some("bar"));
+            assert!(errors[1].error_text().is_none());
+            match errors[1].failed_pdu() {
+                Some(QueryPdu::Publish(publish)) => {
+                    assert_eq!(publish.tag(), Some("bar"));
+                    assert!(publish.hash().is_none());
+                    assert_eq!(publish.content().as_ref(), content);
+                }
+                _ => panic!("expected failed publish")
+            }
+        };
+        let parsed = reply(
+            include_bytes!("../test-data/rfc8181-error-reply.xml")
+        ).unwrap();
+        check(reply_round_trip(&parsed));
+        check(parsed);
+
+        let check = |reply: PublicationReply| {
+            let list = match reply {
+                PublicationReply::List(list) => list,
+                _ => panic!("expected list reply")
+            };
+            assert_eq!(list.len(), 2);
+            assert_eq!(
+                list[0].uri().to_string(),
+                "rsync://wombat.example/Alice/blCrcCp9ltyPDNzYKPfxc.cer"
+            );
+            assert_eq!(list[0].hash().as_ref(), &[0xeeu8; 32][..]);
+            assert_eq!(list[1].hash().as_ref(), &[0xffu8; 32][..]);
+        };
+        let parsed = reply(
+            include_bytes!("../test-data/rfc8181-list-reply.xml")
+        ).unwrap();
+        check(reply_round_trip(&parsed));
+        check(parsed);
+
+        match reply_round_trip(&PublicationReply::Success) {
+            PublicationReply::Success => { }
+            _ => panic!("expected success")
+        }
+    }
+
+    #[test]
+    fn error_reply() {
+        let check = |reply: PublicationReply| {
+            let errors = match reply {
+                PublicationReply::ErrorReport(errors) => errors,
+                _ => panic!("expected error report")
+            };
+            assert_eq!(errors.len(), 3);
+            assert_eq!(
+                errors[0].error_code(), &ReportErrorCode::PermissionFailure
+            );
+            assert_eq!(errors[0].error_text(), Some("Go away"));
+            assert_eq!(
+                errors[1].error_code(),
+                &ReportErrorCode::Unknown("bad_cms_sig".into())
+            );
+            assert_eq!(errors[1].error_code().as_str(), "bad_cms_sig");
+            assert_eq!(errors[1].tag(), Some("second"));
+            assert_eq!(
+                errors[2].error_code(), &ReportErrorCode::NoObjectMatchingHash
+            );
+            match errors[2].failed_pdu() {
+                Some(QueryPdu::Withdraw(withdraw)) => {
+                    assert_eq!(
+                        withdraw.hash().to_string(), "00ff".repeat(16)
+                    );
+                }
+                _ => panic!("expected failed withdraw")
+            }
+        };
+        let parsed = reply(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <report_error error_code=\"permission_failure\">\
+              <error_text>Go away</error_text></report_error>\
+              <report_error tag=\"second\" error_code=\"bad_cms_sig\"/>\
+              <report_error error_code=\"no_object_matching_hash\">\
+              <failed_pdu><withdraw uri=\"rsync://example.com/repo/a.roa\" \
+              hash=\"00ff00ff00ff00ff00ff00ff00ff00ff\
+              00ff00ff00ff00ff00ff00ff00ff00ff\"/></failed_pdu>\
+              </report_error></msg>"
+        ).unwrap();
+        check(reply_round_trip(&parsed));
+        check(parsed);
+
+        // List mixed with an error.
+        assert!(reply(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <list uri=\"rsync://example.com/repo/a.roa\" \
+              hash=\"00ff00ff00ff00ff00ff00ff00ff00ff\
+              00ff00ff00ff00ff00ff00ff00ff00ff\"/>\
+              <report_error error_code=\"xml_error\"/>\
+              </msg>"
+        ).is_err());
+
+        // Error text that isn’t valid UTF-8.
+        assert!(reply(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <report_error error_code=\"permission_failure\">\
+              <error_text>Go \xff away</error_text></report_error>\
+              </msg>"
+        ).is_err());
+    }
+
+    #[test]
+    fn write_query() {
+        let uri = uri::Rsync::from_str(
+            "rsync://example.com/repo/ca/ca.mft"
+        ).unwrap();
+        let hash = Sha256Hash::from([0xab; 32]);
+        let query = PublicationQuery::Update(vec![
+            Publish::new(
+                Some("a<b".into()), uri.clone(), None,
+                Bytes::from_static(b"manifest")
+            ).into(),
+            Withdraw::new(None, uri.clone(), hash).into(),
+        ].into());
+        let mut xml = Vec::new();
+        query.write_xml(&mut xml).unwrap();
+
+        let pdus = match PublicationQuery::parse(xml.as_slice()).unwrap() {
+            PublicationQuery::Update(pdus) => pdus.into_vec(),
+            _ => panic!("expected update query")
+        };
+        assert_eq!(pdus.len(), 2);
+        match pdus[0] {
+            QueryPdu::Publish(ref publish) => {
+                assert_eq!(publish.tag(), Some("a<b"));
+                assert_eq!(publish.uri(), &uri);
+                assert!(publish.hash().is_none());
+                assert_eq!(publish.content().as_ref(), b"manifest");
+            }
+            _ => panic!("expected publish")
+        }
+        match pdus[1] {
+            QueryPdu::Withdraw(ref withdraw) => {
+                assert_eq!(withdraw.uri(), &uri);
+                assert_eq!(withdraw.hash().as_ref(), &[0xabu8; 32][..]);
+            }
+            _ => panic!("expected withdraw")
+        }
+    }
+
+    #[test]
+    fn large_delta() {
+        let mut delta = PublishDelta::new();
+        for i in 0..1000 {
+            let uri = uri::Rsync::from_string(
+                format!("rsync://example.com/repo/ca/{}.roa", i)
+            ).unwrap();
+            match i % 3 {
+                0 => delta.add_publish(uri, format!("new {}", i).into()),
+                1 => {
+                    delta.add_update(
+                        uri, format!("update {}", i).into(),
+                        [i as u8; 32].into()
+                    )
+                }
+                _ => delta.add_withdraw(uri, [i as u8; 32].into()),
+            }
+        }
+        assert_eq!(delta.len(), 1000);
+        let query = PublicationQuery::Update(delta);
+        let delta = match query_round_trip(&query) {
+            PublicationQuery::Update(delta) => delta,
+            _ => panic!("expected update query")
+        };
+        assert_eq!(delta.len(), 1000);
+        for (i, pdu) in delta.iter().enumerate() {
+            let uri = format!("rsync://example.com/repo/ca/{}.roa", i);
+            match (i % 3, pdu) {
+                (0, QueryPdu::Publish(publish)) => {
+                    assert_eq!(publish.uri().to_string(), uri);
+                    assert!(publish.hash().is_none());
+                    assert_eq!(
+                        publish.content().as_ref(),
+                        format!("new {}", i).as_bytes()
+                    );
+                }
+                (1, QueryPdu::Publish(publish)) => {
+                    assert_eq!(publish.uri().to_string(), uri);
+                    assert_eq!(
+                        publish.hash().unwrap().as_ref(), &[i as u8; 32][..]
+                    );
+                    assert_eq!(
+                        publish.content().as_ref(),
+                        format!("update {}", i).as_bytes()
+                    );
+                }
+                (2, QueryPdu::Withdraw(withdraw)) => {
+                    assert_eq!(withdraw.uri().to_string(), uri);
+                    assert_eq!(withdraw.hash().as_ref(), &[i as u8; 32][..]);
+                }
+                _ => panic!("unexpected PDU at {}", i)
+            }
+        }
+    }
+
+    #[test]
+    fn empty_delta() {
+        let delta = match query_round_trip(
+            &PublicationQuery::Update(PublishDelta::new())
+        ) {
+            PublicationQuery::Update(delta) => delta,
+            _ => panic!("expected update query")
+        };
+        assert!(delta.is_empty());
+        for xml in &[
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"query\"/>".as_ref(),
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"query\">\
+              </msg>".as_ref(),
+        ] {
+            match query(xml).unwrap() {
+                PublicationQuery::Update(delta) => assert!(delta.is_empty()),
+                _ => panic!("expected update query")
+            }
+        }
+    }
+
+    #[test]
+    fn object_hash() {
+        let hash = Publish::hash_of(b"");
+        assert_eq!(
+            hash.to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert!(hash.matches(b""));
+    }
+
+    #[test]
+    fn hash_attributes() {
+        // Upper case hash in a withdraw.
+        match query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"query\">\
+              <withdraw uri=\"rsync://example.com/repo/a.roa\" \
+              hash=\"E3B0C44298FC1C149AFBF4C8996FB924\
+              27AE41E4649B934CA495991B7852B855\"/></msg>"
+        ).unwrap() {
+            PublicationQuery::Update(delta) => {
+                match delta.as_slice()[0] {
+                    QueryPdu::Withdraw(ref withdraw) => {
+                        assert_eq!(
+                            *withdraw.hash(), Publish::hash_of(b"")
+                        );
+                    }
+                    _ => panic!("expected withdraw")
+                }
+            }
+            _ => panic!("expected update query")
+        }
+
+        // Hash of the wrong length in a publish.
+        assert!(query(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"query\">\
+              <publish uri=\"rsync://example.com/repo/a.roa\" \
+              hash=\"e3b0c44298fc1c149afbf4c8996fb924\
+              27ae41e4649b934ca495991b7852b8\">cm9h</publish></msg>"
+        ).is_err());
+    }
+
+    #[test]
+    fn check_delta() {
+        let uri = |name: &str| {
+            uri::Rsync::from_string(
+                format!("rsync://example.com/repo/{}", name)
+            ).unwrap()
+        };
+        let mut current = HashMap::new();
+        current.insert(uri("a.roa"), Publish::hash_of(b"a"));
+        current.insert(uri("b.roa"), Publish::hash_of(b"b"));
+        let current = |uri: &uri::Rsync| current.get(uri).cloned();
+
+        let mut delta = PublishDelta::new();
+        delta.add_publish(uri("c.roa"), Bytes::from_static(b"c"));
+        delta.add_update(
+            uri("a.roa"), Bytes::from_static(b"a2"), Publish::hash_of(b"a")
+        );
+        delta.add_withdraw(uri("b.roa"), Publish::hash_of(b"b"));
+        delta.add_withdraw(uri("a.roa"), Publish::hash_of(b"a2"));
+        delta.add_publish(uri("b.roa"), Bytes::from_static(b"b2"));
+        assert!(delta.check(current).is_ok());
+
+        let mut delta = PublishDelta::new();
+        delta.add_publish(uri("a.roa"), Bytes::from_static(b"a2"));
+        delta.push(Withdraw::new(
+            Some("wrong".into()), uri("b.roa"), Publish::hash_of(b"a")
+        ));
+        delta.add_withdraw(uri("c.roa"), Publish::hash_of(b"c"));
+        delta.add_update(
+            uri("c.roa"), Bytes::from_static(b"c"), Publish::hash_of(b"c")
+        );
+        let errors = delta.check(current).unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert_eq!(
+            errors[0].error_code(), &ReportErrorCode::ObjectAlreadyPresent
+        );
+        assert_eq!(
+            errors[1].error_code(), &ReportErrorCode::NoObjectMatchingHash
+        );
+        assert_eq!(errors[1].tag(), Some("wrong"));
+        assert!(errors[1].failed_pdu().is_some());
+        assert_eq!(errors[2].error_code(), &ReportErrorCode::NoObjectPresent);
+        assert_eq!(errors[3].error_code(), &ReportErrorCode::NoObjectPresent);
+    }
+
+    #[test]
+    fn tags() {
+        let msg = |tag: &str| {
+            format!(
+                "<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+                 publication-spec/\" version=\"4\" type=\"query\">\
+                 <withdraw tag=\"{}\" uri=\"rsync://example.com/m/a.roa\" \
+                 hash=\"00ff00ff00ff00ff00ff00ff00ff00ff\
+                 00ff00ff00ff00ff00ff00ff00ff00ff\"/></msg>",
+                tag
+            )
+        };
+        let tag_of = |query: PublicationQuery| {
+            match query {
+                PublicationQuery::Update(delta) => {
+                    delta.iter().next().unwrap().tag().map(String::from)
+                }
+                _ => panic!("expected update query")
+            }
+        };
+
+        // Good tags. The length limit is in characters, not octets.
+        let long = "x".repeat(1024);
+        let wide = "\u{e4}".repeat(1024);
+        for tag in &["a", "with some spaces", long.as_str(), wide.as_str()] {
+            let xml = msg(*tag);
+            assert_eq!(
+                tag_of(
+                    PublicationQuery::parse_strict(xml.as_bytes()).unwrap()
+                ),
+                Some(String::from(*tag))
+            );
+        }
+
+        // Bad tags are only accepted in lenient mode.
+        let long = "x".repeat(1025);
+        for tag in &[
+            " leading", "trailing ", "two  spaces", "tab&#9;", "nl&#10;",
+            long.as_str()
+        ] {
+            let xml = msg(*tag);
+            assert!(PublicationQuery::parse_strict(xml.as_bytes()).is_err());
+            assert!(PublicationQuery::parse(xml.as_bytes()).is_ok());
+        }
+        assert!(reply(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <report_error tag=\" x\" error_code=\"xml_error\"/></msg>"
+        ).is_ok());
+        assert!(PublicationReply::parse_strict(
+            b"<msg xmlns=\"http://www.hactrn.net/uris/rpki/\
+              publication-spec/\" version=\"4\" type=\"reply\">\
+              <report_error tag=\" x\" error_code=\"xml_error\"/></msg>"
+            .as_ref()
+        ).is_err());
+    }
+
+    #[test]
+    fn tag_correlation() {
+        let uri = uri::Rsync::from_str(
+            "rsync://example.com/repo/ca/ca.roa"
+        ).unwrap();
+
+        // An error report for a failed PDU echoes its tag.
+        let mut delta = PublishDelta::new();
+        delta.push(Publish::new(
+            Some("req-1".into()), uri.clone(), None,
+            Bytes::from_static(b"roa")
+        ));
+        delta.add_withdraw(uri.clone(), Sha256Hash::from([0xabu8; 32]));
+        let query = query_round_trip(&PublicationQuery::Update(delta));
+        let delta = match query {
+            PublicationQuery::Update(delta) => delta,
+            _ => panic!("expected update query")
+        };
+        let reply = PublicationReply::ErrorReport(
+            delta.iter().map(|pdu| {
+                ReportError::for_pdu(
+                    ReportErrorCode::PermissionFailure, pdu, None
+                )
+            }).collect()
+        );
+        let errors = match reply_round_trip(&reply) {
+            PublicationReply::ErrorReport(errors) => errors,
+            _ => panic!("expected error report")
+        };
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].tag(), Some("req-1"));
+        assert_eq!(errors[0].failed_pdu().unwrap().tag(), Some("req-1"));
+        assert_eq!(errors[1].tag(), None);
+        match errors[1].failed_pdu() {
+            Some(QueryPdu::Withdraw(withdraw)) => {
+                assert_eq!(withdraw.uri(), &uri)
+            }
+            _ => panic!("expected failed withdraw")
+        }
+
+        // A list reply echoes the tag of the list query.
+        let tag = match query_round_trip(
+            &PublicationQuery::List(Some("list-1".into()))
+        ) {
+            PublicationQuery::List(tag) => tag,
+            _ => panic!("expected list query")
+        };
+        let reply = PublicationReply::list_for(
+            tag.as_deref(),
+            vec![
+                (uri.clone(), Sha256Hash::from([1u8; 32])),
+                (uri.clone(), Sha256Hash::from([2u8; 32])),
+            ]
+        );
+        match reply_round_trip(&reply) {
+            PublicationReply::List(list) => {
+                assert_eq!(list.len(), 2);
+                for item in &list {
+                    assert_eq!(item.tag(), Some("list-1"));
+                }
+            }
+            _ => panic!("expected list reply")
+        }
+        match PublicationReply::list_for(
+            None, vec![(uri, Sha256Hash::from([1u8; 32]))]
+        ) {
+            PublicationReply::List(list) => assert_eq!(list[0].tag(), None),
+            _ => panic!("expected list reply")
+        }
+    }
+
+    #[test]
+    fn write_reply() {
+        let uri = uri::Rsync::from_str(
+            "rsync://example.com/repo/ca/ca.roa"
+        ).unwrap();
+        let reply = PublicationReply::ErrorReport(vec![
+            ReportError::new(
+                ReportErrorCode::ObjectAlreadyPresent,
+                Some("tag".into()),
+                Some("Object & more".into()),
+                Some(Publish::new(
+                    None, uri.clone(), None, Bytes::from_static(b"roa")
+                ).into())
+            ),
+            ReportError::new(ReportErrorCode::XmlError, None, None, None),
+        ]);
+        let mut xml = Vec::new();
+        reply.write_xml(&mut xml).unwrap();
+
+        let errors = match PublicationReply::parse(xml.as_slice()).unwrap() {
+            PublicationReply::ErrorReport(errors) => errors,
+            _ => panic!("expected error report")
+        };
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].error_code(), &ReportErrorCode::ObjectAlreadyPresent
+        );
+        assert_eq!(errors[0].tag(), Some("tag"));
+        assert_eq!(errors[0].error_text(), Some("Object & more"));
+        match errors[0].failed_pdu() {
+            Some(QueryPdu::Publish(publish)) => {
+                assert_eq!(publish.uri(), &uri);
+                assert_eq!(publish.content().as_ref(), b"roa");
+            }
+            _ => panic!("expected failed publish")
+        }
+        assert_eq!(errors[1].error_code(), &ReportErrorCode::XmlError);
+
+        let mut xml = Vec::new();
+        PublicationReply::Success.write_xml(&mut xml).unwrap();
+        match PublicationReply::parse(xml.as_slice()).unwrap() {
+            PublicationReply::Success => { }
+            _ => panic!("expected success")
+        }
+    }
+}
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use std::str::FromStr;
+    use bcder::Oid;
+    use crate::oid;
+    use crate::sigmsg::SignedMessageBuilder;
+    use super::*;
+
+    fn query() -> PublicationQuery {
+        let mut delta = PublishDelta::new();
+        delta.add_publish(
+            uri::Rsync::from_str("rsync://example.com/repo/a.roa").unwrap(),
+            Bytes::from_static(b"roa")
+        );
+        PublicationQuery::Update(delta)
+    }
+
+    #[test]
+    fn sign_and_validate() {
+        let (alice, signer, alice_key) = Cert::generate_test_ta().unwrap();
+        let der = PublicationCms::sign(
+            &query(), &signer, &alice_key, Validity::from_secs(300)
+        ).unwrap();
+        match PublicationCms::validate_and_parse(der, &alice).unwrap() {
+            PublicationQuery::Update(delta) => {
+                assert_eq!(delta.len(), 1);
+                match delta.as_slice()[0] {
+                    QueryPdu::Publish(ref publish) => {
+                        assert_eq!(publish.content().as_ref(), b"roa")
+                    }
+                    _ => panic!("expected publish")
+                }
+            }
+            _ => panic!("expected update query")
+        }
+
+        let der = PublicationCms::sign_reply(
+            &PublicationReply::Success, &signer, &alice_key,
+            Validity::from_secs(300)
+        ).unwrap();
+        match PublicationCms::validate_and_parse_reply(
+            der.clone(), &alice
+        ).unwrap() {
+            PublicationReply::Success => { }
+            _ => panic!("expected success")
+        }
+
+        // A reply isn’t a query.
+        match PublicationCms::validate_and_parse(der, &alice) {
+            Err(PublicationCmsError::Xml(Error::UnexpectedType(_))) => { }
+            res => panic!("expected XML error, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn wrong_publisher() {
+        let (_, signer, alice_key) = Cert::generate_test_ta().unwrap();
+        let (bob, _, _) = Cert::generate_test_ta().unwrap();
+        let der = PublicationCms::sign(
+            &query(), &signer, &alice_key, Validity::from_secs(300)
+        ).unwrap();
+        match PublicationCms::validate_and_parse(der, &bob) {
+            Err(PublicationCmsError::Signed(
+                SignedXmlError::WrongSigner
+            )) => { }
+            res => panic!("expected wrong signer, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn wrong_content() {
+        let (alice, signer, alice_key) = Cert::generate_test_ta().unwrap();
+        let der = |content_type: Oid<Bytes>| {
+            SignedMessageBuilder::new(
+                1u64.into(), Validity::from_secs(300)
+            ).finalize(
+                content_type, Bytes::from_static(b"<msg/>"),
+                &signer, &alice_key
+            ).unwrap().to_captured().into_bytes()
+        };
+
+        match PublicationCms::validate_and_parse(
+            der(Oid(oid::CT_RPKI_MANIFEST.0.into())), &alice
+        ) {
+            Err(PublicationCmsError::Signed(
+                SignedXmlError::ContentType
+            )) => { }
+            res => panic!("expected content type error, got {:?}", res)
+        }
+        match PublicationCms::validate_and_parse(
+            der(Oid(oid::PROTOCOL_CONTENT_TYPE.0.into())), &alice
+        ) {
+            Err(PublicationCmsError::Xml(_)) => { }
+            res => panic!("expected XML error, got {:?}", res)
+        }
+        match PublicationCms::validate_and_parse(
+            Bytes::from_static(b"\x30\x00"), &alice
+        ) {
+            Err(PublicationCmsError::Signed(
+                SignedXmlError::Decode
+            )) => { }
+            res => panic!("expected decode error, got {:?}", res)
+        }
+    }
+}
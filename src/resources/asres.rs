@@ -70,6 +70,62 @@ impl AsResources {
     }
 }
 
+/// # Set operations
+///
+/// These methods assume that `other` are the resources of a certificate
+/// issued under a certificate with the resources of `self`. Inherited
+/// resources in `other` therefore are the same as those of `self`.
+/// Inherited resources in `self` can only be resolved with the resources of
+/// its own issuer via [`resolve`].
+///
+/// [`resolve`]: #method.resolve
+impl AsResources {
+    /// Returns the blocks of the resources, resolving inheritance.
+    ///
+    /// If the resources are inherited, returns the blocks of the issuer,
+    /// `issuer`.
+    pub fn resolve(&self, issuer: &AsBlocks) -> AsBlocks {
+        match self.0 {
+            ResourcesChoice::Inherit => issuer.clone(),
+            ResourcesChoice::Blocks(ref blocks) => blocks.clone(),
+        }
+    }
+
+    /// Returns whether these resources contain the other in their entirety.
+    ///
+    /// If `other` is inherited, it is always contained. If `self` is
+    /// inherited but `other` isn’t, returns `false` unless `other` is empty
+    /// since the resources can’t be resolved.
+    pub fn contains(&self, other: &Self) -> bool {
+        let that = match other.0 {
+            ResourcesChoice::Inherit => return true,
+            ResourcesChoice::Blocks(ref that) => that
+        };
+        match self.0 {
+            ResourcesChoice::Inherit => that.is_empty(),
+            ResourcesChoice::Blocks(ref this) => this.contains(that)
+        }
+    }
+
+    /// Returns the intersection of these resources and the other.
+    ///
+    /// If `other` is inherited, the intersection is `self`. If `self` is
+    /// inherited but `other` isn’t, the intersection can’t be determined
+    /// and `None` is returned.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let that = match other.0 {
+            ResourcesChoice::Inherit => return Some(self.clone()),
+            ResourcesChoice::Blocks(ref that) => that
+        };
+        match self.0 {
+            ResourcesChoice::Inherit => None,
+            ResourcesChoice::Blocks(ref this) => {
+                Some(AsResources::blocks(this.intersection(that)))
+            }
+        }
+    }
+}
+
 impl AsResources {
     /// Takes the AS resources from the beginning of an encoded value.
     ///
@@ -941,6 +997,64 @@ mod test {
         assert_eq!(expected_str, &blocks.to_string())
     }
 
+    #[test]
+    fn as_resources_contains() {
+        let res = |s: &str| AsResources::from_str(s).unwrap();
+        let issuer = res("AS10-AS19, AS20-AS29, AS100");
+
+        // Spanning the adjacent ranges.
+        assert!(issuer.contains(&res("AS15-AS25")));
+        assert!(issuer.contains(&res("AS10-AS29")));
+        assert!(issuer.contains(&res("AS19-AS20, AS100")));
+
+        // Overlapping the ends of the ranges.
+        assert!(!issuer.contains(&res("AS9-AS10")));
+        assert!(!issuer.contains(&res("AS29-AS30")));
+        assert!(!issuer.contains(&res("AS99-AS100")));
+
+        // Overlapping blocks in the issued resources.
+        assert!(issuer.contains(&res("AS10-AS15, AS12-AS22, AS21-AS29")));
+        assert!(!issuer.contains(&res("AS10-AS15, AS12-AS30")));
+
+        // Empty and inherited resources.
+        assert!(issuer.contains(&res("")));
+        assert!(issuer.contains(&res("inherit")));
+        assert!(res("inherit").contains(&res("inherit")));
+        assert!(res("inherit").contains(&res("")));
+        assert!(!res("inherit").contains(&res("AS10")));
+        assert!(!res("").contains(&res("AS10")));
+    }
+
+    #[test]
+    fn as_resources_intersection() {
+        let res = |s: &str| AsResources::from_str(s).unwrap();
+        let issuer = res("AS10-AS19, AS20-AS29, AS100");
+
+        assert_eq!(
+            issuer.intersection(&res("AS5-AS12, AS25-AS100")),
+            Some(res("AS10-AS12, AS25-AS29, AS100"))
+        );
+        assert_eq!(issuer.intersection(&res("AS30-AS99")), Some(res("")));
+        assert_eq!(
+            issuer.intersection(&res("inherit")), Some(issuer.clone())
+        );
+        assert_eq!(
+            res("inherit").intersection(&res("inherit")),
+            Some(res("inherit"))
+        );
+        assert_eq!(res("inherit").intersection(&issuer), None);
+    }
+
+    #[test]
+    fn as_resources_resolve() {
+        let blocks = AsBlocks::from_str("AS1-AS10").unwrap();
+        assert_eq!(AsResources::inherit().resolve(&blocks), blocks);
+        assert_eq!(
+            AsResources::from_str("AS5").unwrap().resolve(&blocks),
+            AsBlocks::from_str("AS5").unwrap()
+        );
+    }
+
     #[test]
     fn as_resources_inherit_serde() {
         let resources_str = "inherit";
@@ -411,26 +411,29 @@ fn from_iter_unsorted<T: Block, I: Iterator<Item=T>>(
     }
     res.sort_unstable_by_key(|block| block.min());
 
-    // The strategy may may lead to consecutive blocks. We can’t have those,
-    // so we need to merge them. This is a bit ugly. Not sure if there is a
-    // cleaner way?
+    // The strategy may lead to consecutive or, if a later block covers
+    // more than one earlier block, overlapping blocks. We can’t have those,
+    // so we need to merge them.
     if res.len() > 1 {
         let mut tail = 0;
-        let mut tail_next = T::next(res[0].max());
         for j in 1..res.len() {
-            if Some(res[j].min()) == tail_next {
-                // Neighbouring. Merge j into tail and continue.
-                res[tail] = T::new(res[tail].min(), res[j].max());
-                tail_next = T::next(res[j].max());
+            let (tail_min, tail_max) = res[tail].bounds();
+            if res[j].min() <= tail_max
+                || T::next(tail_max) == Some(res[j].min())
+            {
+                // Overlapping or neighbouring. Merge j into tail and
+                // continue.
+                if res[j].max() > tail_max {
+                    res[tail] = T::new(tail_min, res[j].max());
+                }
             }
             else {
-                // Not neighbouring. Move to the next tail. If this isn’t j,
-                // we need to copy j there.
-                tail +=1;
+                // Neither. Move to the next tail. If this isn’t j, we need
+                // to copy j there.
+                tail += 1;
                 if tail != j {
                     res[tail] = res[j].clone()
                 }
-                tail_next = T::next(res[tail].max());
             }
         }
         res.truncate(tail + 1);
@@ -646,6 +649,13 @@ mod test {
             ).as_slice(),
             &[(0, 1), (3, 9), (20, 22)][..]
         );
+        // A late block covering several earlier ones
+        assert_eq!(
+            OwnedChain::from(
+                [(20,22), (3,4), (7,9), (0, 30)].as_ref()
+            ).as_slice(),
+            &[(0, 30)][..]
+        );
     }
 
     #[test]
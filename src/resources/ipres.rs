@@ -59,6 +59,62 @@ impl IpResources {
     }
 }
 
+/// # Set operations
+///
+/// These methods assume that `other` are the resources of a certificate
+/// issued under a certificate with the resources of `self`. Inherited
+/// resources in `other` therefore are the same as those of `self`.
+/// Inherited resources in `self` can only be resolved with the resources of
+/// its own issuer via [`resolve`].
+///
+/// [`resolve`]: #method.resolve
+impl IpResources {
+    /// Returns the blocks of the resources, resolving inheritance.
+    ///
+    /// If the resources are inherited, returns the blocks of the issuer,
+    /// `issuer`.
+    pub fn resolve(&self, issuer: &IpBlocks) -> IpBlocks {
+        match self.0 {
+            ResourcesChoice::Inherit => issuer.clone(),
+            ResourcesChoice::Blocks(ref blocks) => blocks.clone(),
+        }
+    }
+
+    /// Returns whether these resources contain the other in their entirety.
+    ///
+    /// If `other` is inherited, it is always contained. If `self` is
+    /// inherited but `other` isn’t, returns `false` unless `other` is empty
+    /// since the resources can’t be resolved.
+    pub fn contains(&self, other: &Self) -> bool {
+        let that = match other.0 {
+            ResourcesChoice::Inherit => return true,
+            ResourcesChoice::Blocks(ref that) => that
+        };
+        match self.0 {
+            ResourcesChoice::Inherit => that.is_empty(),
+            ResourcesChoice::Blocks(ref this) => this.contains(that)
+        }
+    }
+
+    /// Returns the intersection of these resources and the other.
+    ///
+    /// If `other` is inherited, the intersection is `self`. If `self` is
+    /// inherited but `other` isn’t, the intersection can’t be determined
+    /// and `None` is returned.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let that = match other.0 {
+            ResourcesChoice::Inherit => return Some(self.clone()),
+            ResourcesChoice::Blocks(ref that) => that
+        };
+        match self.0 {
+            ResourcesChoice::Inherit => None,
+            ResourcesChoice::Blocks(ref this) => {
+                Some(IpResources::blocks(this.intersection(that)))
+            }
+        }
+    }
+}
+
 impl IpResources {
     /// Takes all IP resources from the beginning of a constructed value.
     ///
@@ -516,7 +572,7 @@ impl IpBlock {
     /// The smallest address of the block.
     pub fn min(&self) -> Addr {
         match *self {
-            IpBlock::Prefix(ref inner) => inner.min(),
+            IpBlock::Prefix(ref inner) => Prefix::min(*inner),
             IpBlock::Range(ref inner) => inner.min(),
         }
     }
@@ -524,7 +580,7 @@ impl IpBlock {
     /// The largest address of the block.
     pub fn max(&self) -> Addr {
         match *self {
-            IpBlock::Prefix(ref inner) => inner.max(),
+            IpBlock::Prefix(ref inner) => Prefix::max(*inner),
             IpBlock::Range(ref inner) => inner.max(),
         }
     }
@@ -913,7 +969,9 @@ impl Block for AddressRange {
 //------------ Prefix --------------------------------------------------------
 
 /// An IP address prefix.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+///
+/// Prefixes are ordered by their address first and their length second.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Prefix {
     /// The address of the prefix.
     ///
@@ -1333,6 +1391,14 @@ impl AddressFamily {
         }
     }
 
+    /// Returns the maximum length of a prefix in this address family.
+    pub fn max_addr_len(self) -> u8 {
+        match self {
+            AddressFamily::Ipv4 => 32,
+            AddressFamily::Ipv6 => 128,
+        }
+    }
+
     pub fn encode(self) -> impl encode::Values {
         OctetString::encode_slice(
             match self {
@@ -1456,6 +1522,85 @@ mod test {
         assert!(super_set.contains(&between));
     }
 
+    #[test]
+    fn ip_resources_contains() {
+        let res = |s: &str| {
+            IpResources::blocks(IpBlocks::from_str(s).unwrap())
+        };
+        let issuer = res("10.0.0.0/24, 10.0.1.0/24, 192.168.0.0/16");
+
+        // Spanning the adjacent prefixes.
+        assert!(issuer.contains(&res("10.0.0.128-10.0.1.127")));
+        assert!(issuer.contains(&res("10.0.0.0/23")));
+        assert!(issuer.contains(&res("10.0.0.0-10.0.1.255")));
+
+        // Overlapping the end of the adjacent prefixes.
+        assert!(!issuer.contains(&res("10.0.1.128-10.0.2.0")));
+        assert!(!issuer.contains(&res("9.255.255.255-10.0.0.10")));
+        assert!(!issuer.contains(&res("10.0.0.0/22")));
+
+        // Overlapping blocks in the issued resources.
+        assert!(issuer.contains(
+            &res("10.0.0.0/24, 10.0.0.128/25, 10.0.0.200-10.0.1.10")
+        ));
+        assert!(!issuer.contains(
+            &res("192.168.0.0/17, 192.168.100.0-192.169.0.0")
+        ));
+
+        // IPv6.
+        let issuer = res("2001:db8::/33, 2001:db8:8000::/33");
+        assert!(issuer.contains(&res("2001:db8::/32")));
+        assert!(!issuer.contains(&res("2001:db8::/31")));
+
+        // Empty and inherited resources.
+        assert!(issuer.contains(&res("")));
+        assert!(issuer.contains(&IpResources::inherit()));
+        assert!(IpResources::inherit().contains(&IpResources::inherit()));
+        assert!(IpResources::inherit().contains(&res("")));
+        assert!(!IpResources::inherit().contains(&res("2001:db8::/33")));
+        assert!(!res("").contains(&res("2001:db8::/33")));
+    }
+
+    #[test]
+    fn ip_resources_intersection() {
+        let res = |s: &str| {
+            IpResources::blocks(IpBlocks::from_str(s).unwrap())
+        };
+        let issuer = res("10.0.0.0/24, 10.0.1.0/24, 192.168.0.0/16");
+
+        assert_eq!(
+            issuer.intersection(&res(
+                "10.0.1.128-10.0.2.255, 192.167.255.0-192.168.0.0"
+            )),
+            Some(res("10.0.1.128-10.0.1.255, 192.168.0.0-192.168.0.0"))
+        );
+        assert_eq!(
+            issuer.intersection(&res("10.0.0.0/16")),
+            Some(res("10.0.0.0/23"))
+        );
+        assert_eq!(
+            issuer.intersection(&res("172.16.0.0/12")), Some(res(""))
+        );
+        assert_eq!(
+            issuer.intersection(&IpResources::inherit()), Some(issuer.clone())
+        );
+        assert_eq!(
+            IpResources::inherit().intersection(&IpResources::inherit()),
+            Some(IpResources::inherit())
+        );
+        assert_eq!(IpResources::inherit().intersection(&issuer), None);
+    }
+
+    #[test]
+    fn ip_resources_resolve() {
+        let blocks = IpBlocks::from_str("10.0.0.0/8").unwrap();
+        let other = IpBlocks::from_str("192.168.0.0/16").unwrap();
+        assert_eq!(IpResources::inherit().resolve(&blocks), blocks);
+        assert_eq!(
+            IpResources::blocks(other.clone()).resolve(&blocks), other
+        );
+    }
+
     #[test]
     fn ip_blocks_intersection() {
         // Note: the IpBlocks::intersection function delegates to Chain::trim
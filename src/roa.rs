@@ -2,24 +2,26 @@
 //!
 //! For details, see RFC 6482.
 
-use std::mem;
+use std::{cmp, error, fmt, mem};
 use std::iter::FromIterator;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
 use bcder::{decode, encode};
-use bcder::{Captured, Mode, OctetString, Oid, Tag, xerr};
+use bcder::{BitString, Captured, Mode, OctetString, Oid, Tag, xerr};
 use bcder::encode::{PrimitiveContent, Values};
 use bytes::Bytes;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use crate::oid;
 use crate::cert::{Cert, ResourceCert};
-use crate::crypto::{Signer, SigningError};
+use crate::crypto::{AlgorithmPolicy, Signer, SigningError};
 use crate::resources::{
     Addr, AddressFamily, AsId, IpBlocks, IpResources, Prefix
 };
-use crate::sigobj::{SignedObject, SignedObjectBuilder};
+use crate::sigobj::{
+    AsSignedObject, SignedObject, SignedObjectBuilder, SignedObjectError
+};
 use crate::tal::TalInfo;
-use crate::x509::ValidationError;
+use crate::x509::{Time, ValidationError};
 
 
 //------------ Roa -----------------------------------------------------------
@@ -35,16 +37,90 @@ impl Roa {
         source: S,
         strict: bool
     ) -> Result<Self, S::Err> {
-        let signed = SignedObject::decode(source, strict)?;
-        if signed.content_type().ne(&oid::ROUTE_ORIGIN_AUTHZ) {
-            return Err(decode::Malformed.into())
-        }
+        let signed = SignedObject::decode_if_type(
+            source, &oid::ROUTE_ORIGIN_AUTHZ, strict
+        )?;
         let content = signed.decode_content(|cons| {
-            RouteOriginAttestation::take_from(cons)
+            RouteOriginAttestation::take_from(cons, strict)
         })?;
         Ok(Roa { signed, content })
     }
 
+    /// Decodes a ROA from a source reporting why decoding failed.
+    ///
+    /// See [`SignedObject::decode_detailed`] for details.
+    ///
+    /// [`SignedObject::decode_detailed`]: ../sigobj/struct.SignedObject.html#method.decode_detailed
+    pub fn decode_detailed<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, SignedObjectError> {
+        let signed = SignedObject::decode_detailed(
+            source, &oid::ROUTE_ORIGIN_AUTHZ, strict
+        )?;
+        let content = signed.decode_content(|cons| {
+            RouteOriginAttestation::take_from(cons, strict)
+        }).map_err(|_| SignedObjectError::MalformedContent)?;
+        Ok(Roa { signed, content })
+    }
+
+    /// Validates the ROA.
+    ///
+    /// Checks the signed object and its EE certificate against `issuer` and
+    /// makes sure that all prefixes of the ROA are covered by the IP
+    /// resources of the EE certificate. If the EE certificate inherits its
+    /// resources, those of the issuer are used. Upon success, returns the
+    /// content of the ROA with its status set to valid.
+    ///
+    /// If a prefix is not covered, the error names the first such prefix.
+    /// In strict mode, an EE certificate with AS resources is rejected, too.
+    ///
+    /// This method does not check whether the EE certificate has been
+    /// revoked. Use [`process`] if you need to do that.
+    ///
+    /// [`process`]: #method.process
+    pub fn validate(
+        self,
+        issuer: &ResourceCert,
+        strict: bool
+    ) -> Result<RouteOriginAttestation, RoaValidationError> {
+        self.validate_at(issuer, strict, Time::now())
+    }
+
+    /// Validates the ROA at the given time.
+    pub fn validate_at(
+        mut self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+    ) -> Result<RouteOriginAttestation, RoaValidationError> {
+        let cert = self.signed.validate_at(issuer, strict, now)?;
+        self.content.validate(cert, strict)?;
+        Ok(self.content)
+    }
+
+    /// Validates the ROA under a local algorithm policy.
+    ///
+    /// This is the same as [`validate_at`] but the signature algorithms
+    /// used by the ROA must be permitted by `policy`. See the method of
+    /// the same name of [`SignedObject`] for details.
+    ///
+    /// [`validate_at`]: #method.validate_at
+    /// [`SignedObject`]: ../sigobj/struct.SignedObject.html
+    pub fn validate_with_policy(
+        mut self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+    ) -> Result<RouteOriginAttestation, RoaValidationError> {
+        let cert = self.signed.validate_with_policy(
+            issuer, strict, now, policy
+        )?;
+        self.content.validate(cert, strict)?;
+        Ok(self.content)
+    }
+
     pub fn process<F>(
         mut self,
         issuer: &ResourceCert,
@@ -54,7 +130,7 @@ impl Roa {
     where F: FnOnce(&Cert) -> Result<(), ValidationError> {
         let cert = self.signed.validate(issuer, strict)?;
         check_crl(cert.as_ref())?;
-        self.content.validate(cert)?;
+        self.content.validate(cert, strict)?;
         Ok(self.content)
     }
 
@@ -80,6 +156,15 @@ impl Roa {
 }
 
 
+//--- AsSignedObject
+
+impl AsSignedObject for Roa {
+    fn signed_object(&self) -> &SignedObject {
+        &self.signed
+    }
+}
+
+
 //--- Deserialize and Serialize
 
 impl Serialize for Roa {
@@ -110,9 +195,11 @@ pub struct RouteOriginAttestation {
     v4_addrs: RoaIpAddresses,
     v6_addrs: RoaIpAddresses,
     status: RoaStatus,
+    warnings: Vec<RoaWarning>,
 }
 
 impl RouteOriginAttestation {
+    /// Returns the AS number the ROA authorizes as origin.
     pub fn as_id(&self) -> AsId {
         self.as_id
     }
@@ -133,6 +220,17 @@ impl RouteOriginAttestation {
         self.status.take_cert()
     }
 
+    /// Returns the problems tolerated when decoding in lenient mode.
+    ///
+    /// In strict mode, all these problems result in a decoding error, so
+    /// the slice is always empty for ROAs decoded that way.
+    pub fn warnings(&self) -> &[RoaWarning] {
+        &self.warnings
+    }
+
+    /// Returns an iterator over all prefixes of the ROA.
+    ///
+    /// The IPv4 prefixes are returned first, followed by the IPv6 prefixes.
     pub fn iter<'a>(
         &'a self
     ) -> impl Iterator<Item=FriendlyRoaIpAddress> + 'a {
@@ -146,7 +244,8 @@ impl RouteOriginAttestation {
 
 impl RouteOriginAttestation {
     fn take_from<S: decode::Source>(
-        cons: &mut decode::Constructed<S>
+        cons: &mut decode::Constructed<S>,
+        strict: bool,
     ) -> Result<Self, S::Err> {
         cons.take_sequence(|cons| {
             // version [0] EXPLICIT INTEGER DEFAULT 0
@@ -154,6 +253,7 @@ impl RouteOriginAttestation {
             let as_id = AsId::take_from(cons)?;
             let mut v4 = None;
             let mut v6 = None;
+            let mut warnings = Vec::new();
             cons.take_sequence(|cons| {
                 while let Some(()) = cons.take_opt_sequence(|cons| {
                     match AddressFamily::take_from(cons)? {
@@ -161,17 +261,27 @@ impl RouteOriginAttestation {
                             if v4.is_some() {
                                 xerr!(return Err(decode::Malformed.into()));
                             }
-                            v4 = Some(RoaIpAddresses::take_from(cons)?);
+                            v4 = Some(RoaIpAddresses::take_from(
+                                cons, AddressFamily::Ipv4, strict,
+                                &mut warnings
+                            )?);
                         }
                         AddressFamily::Ipv6 => {
                             if v6.is_some() {
                                 xerr!(return Err(decode::Malformed.into()));
                             }
-                            v6 = Some(RoaIpAddresses::take_from(cons)?);
+                            v6 = Some(RoaIpAddresses::take_from(
+                                cons, AddressFamily::Ipv6, strict,
+                                &mut warnings
+                            )?);
                         }
                     }
                     Ok(())
                 })? { }
+                // ipAddrBlocks is SIZE(1..MAX).
+                if v4.is_none() && v6.is_none() {
+                    xerr!(return Err(decode::Malformed.into()));
+                }
                 Ok(())
             })?;
             Ok(RouteOriginAttestation {
@@ -185,34 +295,37 @@ impl RouteOriginAttestation {
                     None => RoaIpAddresses(Captured::empty(Mode::Der))
                 },
                 status: RoaStatus::Unknown,
+                warnings,
             })
         })
     }
 
     fn validate(
         &mut self,
-        cert: ResourceCert
-    ) -> Result<(), ValidationError> {
-        if !self.v4_addrs.is_empty() {
-            let blocks = cert.v4_resources();
-            if blocks.is_empty() {
-                return Err(ValidationError)
-            }
-            for addr in self.v4_addrs.iter() {
-                if !blocks.contains_roa(&addr) {
-                    return Err(ValidationError)
-                }
+        cert: ResourceCert,
+        strict: bool,
+    ) -> Result<(), RoaValidationError> {
+        // RFC 6482bis: The EE certificate must not contain AS resources.
+        if strict {
+            let ee: &Cert = cert.as_ref();
+            if ee.as_resources().is_some() {
+                return Err(RoaValidationError::AsResources)
             }
         }
-        if !self.v6_addrs.is_empty() {
-            let blocks = cert.v6_resources();
-            if blocks.is_empty() {
-                return Err(ValidationError)
+        // The resources of the resource cert already have inherited
+        // resources resolved.
+        for addr in self.v4_addrs.iter() {
+            if !cert.v4_resources().contains_roa(&addr) {
+                return Err(RoaValidationError::PrefixNotCovered(
+                    FriendlyRoaIpAddress::new(addr, true)
+                ))
             }
-            for addr in self.v6_addrs.iter() {
-                if !blocks.contains_roa(&addr) {
-                    return Err(ValidationError)
-                }
+        }
+        for addr in self.v6_addrs.iter() {
+            if !cert.v6_resources().contains_roa(&addr) {
+                return Err(RoaValidationError::PrefixNotCovered(
+                    FriendlyRoaIpAddress::new(addr, false)
+                ))
             }
         }
         self.status = RoaStatus::Valid { cert };
@@ -240,11 +353,23 @@ pub struct RoaIpAddresses(Captured);
 
 impl RoaIpAddresses {
     fn take_from<S: decode::Source>(
-        cons: &mut decode::Constructed<S>
+        cons: &mut decode::Constructed<S>,
+        family: AddressFamily,
+        strict: bool,
+        warnings: &mut Vec<RoaWarning>,
     ) -> Result<Self, S::Err> {
         cons.take_sequence(|cons| {
             cons.capture(|cons| {
-                while let Some(()) = RoaIpAddress::skip_opt_in(cons)? { }
+                // addresses is SIZE(1..MAX).
+                let first = RoaIpAddress::skip_opt_in(
+                    cons, family, strict, warnings
+                )?;
+                if first.is_none() {
+                    xerr!(return Err(decode::Malformed.into()));
+                }
+                while let Some(()) = RoaIpAddress::skip_opt_in(
+                    cons, family, strict, warnings
+                )? { }
                 Ok(())
             })
         }).map(RoaIpAddresses)
@@ -268,7 +393,7 @@ impl RoaIpAddresses {
         else {
             Some(encode::sequence((
                 OctetString::encode_slice(family),
-                &self.0
+                encode::sequence(&self.0)
             )))
         }
     }
@@ -299,7 +424,7 @@ impl<'a> Iterator for RoaIpAddressIter<'a> {
 
 //------------ RoaIpAddress --------------------------------------------------
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct RoaIpAddress {
     prefix: Prefix,
     max_length: Option<u8>
@@ -337,6 +462,10 @@ impl RoaIpAddress {
     // The address is the same as in section 2.1.1 of RFC 3779, that is, it
     // is a bit string with all the bits of the prefix.
 
+    /// Takes an optional address from the beginning of a constructed value.
+    ///
+    /// This doesn’t do any checking and is meant for addresses that have
+    /// been checked already via `skip_opt_in`.
     fn take_opt_from<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Option<Self>, S::Err> {
@@ -348,10 +477,70 @@ impl RoaIpAddress {
         })
     }
 
+    /// Checks and skips over an optional address of the given family.
+    ///
+    /// The prefix must not be longer than allowed for the family. The max
+    /// length, if present, must not be shorter than the prefix and not
+    /// longer than allowed for the family. In strict mode, the unused bits
+    /// of the prefix must be zero, too.
+    ///
+    /// In lenient mode, a bad max length is accepted and a warning is added
+    /// to `warnings` instead. The max length is then corrected when it is
+    /// accessed via `FriendlyRoaIpAddress::max_length`.
     fn skip_opt_in<S: decode::Source>(
-        cons: &mut decode::Constructed<S>
+        cons: &mut decode::Constructed<S>,
+        family: AddressFamily,
+        strict: bool,
+        warnings: &mut Vec<RoaWarning>,
     ) -> Result<Option<()>, S::Err> {
-        Self::take_opt_from(cons).map(|res| res.map(|_| ()))
+        cons.take_opt_sequence(|cons| {
+            let bits = BitString::take_from(cons)?;
+            if strict && !Self::unused_bits_clear(&bits) {
+                xerr!(return Err(decode::Malformed.into()));
+            }
+            let prefix = Prefix::from_bit_string(&bits)?;
+            if prefix.addr_len() > family.max_addr_len() {
+                xerr!(return Err(decode::Malformed.into()));
+            }
+            let max_length = cons.take_opt_u8()?;
+            let max_len = match max_length {
+                Some(max_len) => max_len,
+                None => return Ok(())
+            };
+            let too_short = max_len < prefix.addr_len();
+            let too_long = max_len > family.max_addr_len();
+            if !too_short && !too_long {
+                return Ok(())
+            }
+            if strict {
+                xerr!(return Err(decode::Malformed.into()));
+            }
+            let addr = FriendlyRoaIpAddress::new(
+                RoaIpAddress { prefix, max_length },
+                family == AddressFamily::Ipv4
+            );
+            warnings.push(
+                if too_short {
+                    RoaWarning::MaxLengthTooShort(addr)
+                }
+                else {
+                    RoaWarning::MaxLengthTooLong(addr)
+                }
+            );
+            Ok(())
+        })
+    }
+
+    /// Returns whether all unused bits of an address bit string are zero.
+    fn unused_bits_clear(bits: &BitString) -> bool {
+        let unused = bits.unused();
+        if unused == 0 {
+            return true
+        }
+        match bits.octets().last() {
+            Some(last) => last & ((1u8 << unused) - 1) == 0,
+            None => true
+        }
     }
 
     fn encode(&self) -> impl encode::Values {
@@ -376,6 +565,21 @@ impl FriendlyRoaIpAddress {
         FriendlyRoaIpAddress { addr, v4 }
     }
 
+    /// Returns the address family of the prefix.
+    pub fn address_family(&self) -> AddressFamily {
+        if self.v4 {
+            AddressFamily::Ipv4
+        }
+        else {
+            AddressFamily::Ipv6
+        }
+    }
+
+    /// Returns the prefix as a pair of address and prefix length.
+    pub fn prefix(&self) -> (IpAddr, u8) {
+        (self.address(), self.address_length())
+    }
+
     pub fn address(&self) -> IpAddr {
         if self.v4 {
             self.addr.prefix.to_v4().into()
@@ -389,9 +593,67 @@ impl FriendlyRoaIpAddress {
         self.addr.prefix.addr_len()
     }
 
+    /// Returns the effective max length of the prefix.
+    ///
+    /// If the ROA doesn’t contain a max length, this is the prefix length.
+    /// A max length that is longer than allowed for the address family is
+    /// clamped to the family’s maximum. A max length shorter than the prefix
+    /// is ignored and the prefix length is used instead. Such values can
+    /// only appear in ROAs decoded in lenient mode and are reported via
+    /// [`RouteOriginAttestation::warnings`].
+    ///
+    /// [`RouteOriginAttestation::warnings`]: struct.RouteOriginAttestation.html#method.warnings
     pub fn max_length(&self) -> u8 {
-        self.addr.max_length.unwrap_or_else(||
-            self.addr.prefix.addr_len()
+        let len = self.addr.prefix.addr_len();
+        match self.addr.max_length {
+            Some(max_len) if max_len < len => len,
+            Some(max_len) => {
+                cmp::min(max_len, self.address_family().max_addr_len())
+            }
+            None => len
+        }
+    }
+
+    /// Returns the max length as it was encoded in the ROA.
+    pub fn encoded_max_length(&self) -> Option<u8> {
+        self.addr.max_length
+    }
+}
+
+
+//------------ RoaWarning ----------------------------------------------------
+
+/// A problem with a ROA that was tolerated when decoding in lenient mode.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RoaWarning {
+    /// The max length is longer than allowed for the address family.
+    ///
+    /// The max length is clamped to 32 or 128, respectively.
+    MaxLengthTooLong(FriendlyRoaIpAddress),
+
+    /// The max length is shorter than the prefix length.
+    ///
+    /// The max length is ignored and the prefix length used instead.
+    MaxLengthTooShort(FriendlyRoaIpAddress),
+}
+
+
+//--- Display
+
+impl fmt::Display for RoaWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (addr, reason) = match *self {
+            RoaWarning::MaxLengthTooLong(ref addr) => {
+                (addr, "longer than address family")
+            }
+            RoaWarning::MaxLengthTooShort(ref addr) => {
+                (addr, "shorter than prefix")
+            }
+        };
+        write!(
+            f, "max length {} of prefix {}/{} is {}",
+            addr.encoded_max_length().unwrap_or(0),
+            addr.address(), addr.address_length(), reason
         )
     }
 }
@@ -433,12 +695,75 @@ impl RoaStatus {
 }
 
 
+//------------ RoaValidationError --------------------------------------------
+
+/// An error happened while validating a ROA.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RoaValidationError {
+    /// The signed object or its EE certificate failed validation.
+    Object(SignedObjectError),
+
+    /// A prefix of the ROA isn’t covered by the EE certificate’s resources.
+    PrefixNotCovered(FriendlyRoaIpAddress),
+
+    /// The EE certificate contains AS resources.
+    ///
+    /// This is only an error in strict mode.
+    AsResources,
+}
+
+
+//--- From
+
+impl From<SignedObjectError> for RoaValidationError {
+    fn from(err: SignedObjectError) -> Self {
+        RoaValidationError::Object(err)
+    }
+}
+
+impl From<RoaValidationError> for ValidationError {
+    fn from(_: RoaValidationError) -> Self {
+        ValidationError
+    }
+}
+
+
+//--- Display and Error
+
+impl fmt::Display for RoaValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RoaValidationError::Object(ref err) => err.fmt(f),
+            RoaValidationError::PrefixNotCovered(ref addr) => {
+                write!(
+                    f, "prefix {}/{}-{} not covered by EE certificate",
+                    addr.address(), addr.address_length(), addr.max_length()
+                )
+            }
+            RoaValidationError::AsResources => {
+                f.write_str("EE certificate contains AS resources")
+            }
+        }
+    }
+}
+
+impl error::Error for RoaValidationError { }
+
+
 //------------ RoaBuilder ----------------------------------------------------
 
+/// A builder for a ROA.
+///
+/// Prefixes are kept separately per address family. Within each family,
+/// they are kept in canonical order, i.e., ordered by address, prefix
+/// length, and max length, with duplicates removed. This makes the
+/// encoded ROA content only depend on the set of prefixes and not on the
+/// order in which they were added.
 pub struct RoaBuilder {
     as_id: AsId,
     v4: RoaIpAddressesBuilder,
     v6: RoaIpAddressesBuilder,
+    inherit_resources: bool,
 }
 
 impl RoaBuilder {
@@ -455,7 +780,7 @@ impl RoaBuilder {
         v4: RoaIpAddressesBuilder,
         v6: RoaIpAddressesBuilder
     ) -> Self {
-        Self { as_id, v4, v6 }
+        Self { as_id, v4, v6, inherit_resources: false }
     }
 
     pub fn as_id(&self) -> AsId {
@@ -481,7 +806,21 @@ impl RoaBuilder {
     pub fn v6_mut(&mut self) -> &mut RoaIpAddressesBuilder {
         &mut self.v6
     }
-    
+
+    /// Returns whether the EE certificate will inherit its IP resources.
+    pub fn inherit_resources(&self) -> bool {
+        self.inherit_resources
+    }
+
+    /// Sets whether the EE certificate will inherit its IP resources.
+    ///
+    /// By default, the EE certificate will contain exactly the prefixes of
+    /// the ROA as its IP resources. If this is set to `true`, it will
+    /// instead use inherit for each address family present in the ROA.
+    pub fn set_inherit_resources(&mut self, inherit: bool) {
+        self.inherit_resources = inherit
+    }
+
     pub fn push_addr(
         &mut self, addr: IpAddr, len: u8, max_len: Option<u8>
     ) {
@@ -525,6 +864,7 @@ impl RoaBuilder {
             v4_addrs: self.v4.to_addresses(),
             v6_addrs: self.v6.to_addresses(),
             status: RoaStatus::Unknown,
+            warnings: Vec::new(),
         }
     }
 
@@ -534,17 +874,21 @@ impl RoaBuilder {
     ///
     /// This method will panic if both the IPv4 and IPv6 addresses are empty
     /// as that is not allowed and would lead to a malformed ROA.
-    pub fn finalize<S: Signer>(
+    pub fn finalize<S: Signer + ?Sized>(
         self,
         mut sigobj: SignedObjectBuilder,
         signer: &S,
         issuer_key: &S::KeyId,
     ) -> Result<Roa, SigningError<S::Error>> {
         let content = self.to_attestation();
-        let v4 = self.v4.to_resources();
-        let v6 = self.v6.to_resources();
+        let mut v4 = self.v4.to_resources();
+        let mut v6 = self.v6.to_resources();
         // There must be some resources in order to make a valid ROA.
         assert!(v4.is_some() || v6.is_some());
+        if self.inherit_resources {
+            v4 = v4.map(|_| IpResources::inherit());
+            v6 = v6.map(|_| IpResources::inherit());
+        }
         sigobj.set_v4_resources(v4);
         sigobj.set_v6_resources(v6);
         let signed = sigobj.finalize(
@@ -560,6 +904,9 @@ impl RoaBuilder {
 
 //------------ RoaIpAddressesBuilder -----------------------------------------
 
+/// A builder for the addresses of one address family of a ROA.
+///
+/// The addresses are kept in canonical order without duplicates.
 #[derive(Clone, Debug)]
 pub struct RoaIpAddressesBuilder {
     addrs: Vec<RoaIpAddress>,
@@ -572,8 +919,11 @@ impl RoaIpAddressesBuilder {
         }
     }
 
+    /// Adds an address unless it is already present.
     pub fn push(&mut self, addr: RoaIpAddress) {
-        self.addrs.push(addr)
+        if let Err(idx) = self.addrs.binary_search(&addr) {
+            self.addrs.insert(idx, addr)
+        }
     }
 
     pub fn push_addr(&mut self, addr: IpAddr, len: u8, max_len: Option<u8>) {
@@ -581,7 +931,7 @@ impl RoaIpAddressesBuilder {
     }
 
     pub fn extend_from_slice(&mut self, addrs: &[RoaIpAddress]) {
-        self.addrs.extend_from_slice(addrs)
+        self.extend(addrs.iter().cloned())
     }
 
     pub fn to_addresses(&self) -> RoaIpAddresses {
@@ -590,7 +940,9 @@ impl RoaIpAddressesBuilder {
                 Captured::empty(Mode::Der)
             }
             else {
-                Captured::from_values(Mode::Der, self.encode_ref())
+                Captured::from_values(Mode::Der, encode::slice(
+                    self.addrs.as_slice(), |v: &RoaIpAddress| v.encode()
+                ))
             }
        )
     }
@@ -623,7 +975,9 @@ impl Default for RoaIpAddressesBuilder {
 impl Extend<RoaIpAddress> for RoaIpAddressesBuilder {
     fn extend<T>(&mut self, iter: T)
     where T: IntoIterator<Item=RoaIpAddress> {
-        self.addrs.extend(iter)
+        for addr in iter {
+            self.push(addr)
+        }
     }
 }
 
@@ -632,6 +986,8 @@ impl Extend<RoaIpAddress> for RoaIpAddressesBuilder {
 
 #[cfg(test)]
 mod test {
+    use std::str::FromStr;
+    use crate::origins::RouteOrigin;
     use super::*;
 
     #[test]
@@ -643,56 +999,296 @@ mod test {
             ).is_ok()
         )
     }
+
+    #[test]
+    fn decode_wrong_content_type() {
+        // A manifest is not a ROA.
+        let der = include_bytes!("../test-data/ta.mft");
+        assert!(Roa::decode(der.as_ref(), false).is_err());
+        assert!(Roa::decode(der.as_ref(), true).is_err());
+    }
+
+    #[test]
+    fn decode_roa_content() {
+        let roa = Roa::decode(
+            include_bytes!("../test-data/example-ripe.roa").as_ref(),
+            false
+        ).unwrap();
+        let content = roa.content();
+        assert_eq!(content.as_id(), AsId::from(209870));
+        assert!(content.v4_addrs().is_empty());
+        let addrs: Vec<_> = content.iter().collect();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(
+            addrs[0].address(),
+            IpAddr::from_str("2a0c:b642:fc0::").unwrap()
+        );
+        assert_eq!(addrs[0].address_length(), 43);
+        assert_eq!(addrs[0].max_length(), 43);
+    }
+
+    #[test]
+    fn decode_attestation_families() {
+        fn decode(
+            data: &[u8]
+        ) -> Result<RouteOriginAttestation, decode::Error> {
+            Mode::Der.decode(data, |cons| {
+                RouteOriginAttestation::take_from(cons, true)
+            })
+        }
+
+        // Both families, maxLength absent on the IPv4 prefix.
+        let both = decode(
+            b"\x30\x25\x02\x03\x00\xfb\xf0\x30\x1e\
+              \x30\x0c\x04\x02\x00\x01\x30\x06\x30\x04\x03\x02\x00\x0a\
+              \x30\x0e\x04\x02\x00\x02\x30\x08\x30\x06\x03\x01\x00\
+              \x02\x01\x30"
+        ).unwrap();
+        assert_eq!(both.as_id(), AsId::from(64496));
+        let addrs: Vec<_> = both.iter().collect();
+        assert_eq!(addrs.len(), 2);
+        assert_eq!(
+            addrs[0].address(), IpAddr::from_str("10.0.0.0").unwrap()
+        );
+        assert_eq!(addrs[0].address_length(), 8);
+        assert_eq!(addrs[0].max_length(), 8);
+        assert_eq!(addrs[1].address(), IpAddr::from_str("::").unwrap());
+        assert_eq!(addrs[1].address_length(), 0);
+        assert_eq!(addrs[1].max_length(), 48);
+
+        // Address family with SAFI.
+        assert!(decode(
+            b"\x30\x16\x02\x03\x00\xfb\xf0\x30\x0f\
+              \x30\x0d\x04\x03\x00\x01\x01\
+              \x30\x06\x30\x04\x03\x02\x00\x0a"
+        ).is_err());
+
+        // Empty ipAddrBlocks.
+        assert!(
+            decode(b"\x30\x07\x02\x03\x00\xfb\xf0\x30\x00").is_err()
+        );
+    }
+
+    fn encode_and_decode(
+        roa: &RoaBuilder, strict: bool
+    ) -> Result<RouteOriginAttestation, decode::Error> {
+        let content = roa.to_attestation().encode_ref().to_captured(
+            Mode::Der
+        );
+        Mode::Der.decode(content.as_slice(), |cons| {
+            RouteOriginAttestation::take_from(cons, strict)
+        })
+    }
+
+    #[test]
+    fn iter_prefixes() {
+        let mut roa = RoaBuilder::new(64511.into());
+        roa.push_v4_addr(Ipv4Addr::new(0, 0, 0, 0), 0, None);
+        roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 1), 32, None);
+        roa.push_v4_addr(Ipv4Addr::new(10, 0, 0, 0), 8, Some(16));
+        roa.push_v6_addr(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 128, Some(128)
+        );
+        let roa = encode_and_decode(&roa, true).unwrap();
+        assert_eq!(roa.as_id(), AsId::from(64511));
+
+        let addrs: Vec<_> = roa.iter().collect();
+        assert_eq!(addrs.len(), 4);
+        assert_eq!(addrs[0].address_family(), AddressFamily::Ipv4);
+        assert_eq!(
+            addrs[0].prefix(), (IpAddr::from_str("0.0.0.0").unwrap(), 0)
+        );
+        assert_eq!(addrs[0].max_length(), 0);
+        assert_eq!(
+            addrs[1].prefix(), (IpAddr::from_str("10.0.0.0").unwrap(), 8)
+        );
+        assert_eq!(addrs[1].max_length(), 16);
+        assert_eq!(
+            addrs[2].prefix(), (IpAddr::from_str("192.0.2.1").unwrap(), 32)
+        );
+        assert_eq!(addrs[2].max_length(), 32);
+        assert_eq!(addrs[3].address_family(), AddressFamily::Ipv6);
+        assert_eq!(
+            addrs[3].prefix(),
+            (IpAddr::from_str("2001:db8::1").unwrap(), 128)
+        );
+        assert_eq!(addrs[3].max_length(), 128);
+    }
+
+    #[test]
+    fn builder_canonical_content() {
+        let mut roa = RoaBuilder::new(64496.into());
+        roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, None);
+        roa.push_v6_addr(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32, None
+        );
+        roa.push_v4_addr(Ipv4Addr::new(10, 0, 0, 0), 8, Some(16));
+        roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, None);
+        let content = roa.to_attestation().encode_ref().to_captured(
+            Mode::Der
+        );
+        assert_eq!(
+            content.as_slice(),
+            b"\x30\x31\x02\x03\x00\xfb\xf0\x30\x2a\x30\x17\x04\
+              \x02\x00\x01\x30\x11\x30\x07\x03\x02\x00\x0a\x02\
+              \x01\x10\x30\x06\x03\x04\x00\xc0\x00\x02\x30\x0f\
+              \x04\x02\x00\x02\x30\x09\x30\x07\x03\x05\x00\x20\
+              \x01\x0d\xb8".as_ref()
+        );
+    }
+
+    #[test]
+    fn max_length_zero() {
+        let mut roa = RoaBuilder::new(64511.into());
+        roa.push_v4_addr(Ipv4Addr::new(0, 0, 0, 0), 0, Some(0));
+        let roa = encode_and_decode(&roa, true).unwrap();
+        assert_eq!(roa.iter().next().unwrap().max_length(), 0);
+        assert!(roa.warnings().is_empty());
+    }
+
+    #[test]
+    fn max_length_family_max() {
+        let mut roa = RoaBuilder::new(64511.into());
+        roa.push_v4_addr(Ipv4Addr::new(10, 0, 0, 0), 8, Some(32));
+        roa.push_v6_addr(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32, Some(128)
+        );
+        let roa = encode_and_decode(&roa, true).unwrap();
+        let addrs: Vec<_> = roa.iter().collect();
+        assert_eq!(addrs[0].max_length(), 32);
+        assert_eq!(addrs[1].max_length(), 128);
+        assert!(roa.warnings().is_empty());
+    }
+
+    #[test]
+    fn max_length_too_long() {
+        let mut roa = RoaBuilder::new(64511.into());
+        roa.push_v4_addr(Ipv4Addr::new(10, 0, 0, 0), 8, Some(40));
+        roa.push_v6_addr(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32, Some(129)
+        );
+        assert!(encode_and_decode(&roa, true).is_err());
+
+        let roa = encode_and_decode(&roa, false).unwrap();
+        let addrs: Vec<_> = roa.iter().collect();
+        assert_eq!(addrs[0].encoded_max_length(), Some(40));
+        assert_eq!(addrs[0].max_length(), 32);
+        assert_eq!(addrs[1].encoded_max_length(), Some(129));
+        assert_eq!(addrs[1].max_length(), 128);
+        assert_eq!(
+            roa.warnings(),
+            &[
+                RoaWarning::MaxLengthTooLong(addrs[0].clone()),
+                RoaWarning::MaxLengthTooLong(addrs[1].clone()),
+            ]
+        );
+
+        // Route origins only ever see the clamped value.
+        let origin = RouteOrigin::from_roa_address(&addrs[0], roa.as_id());
+        assert_eq!(origin.max_length(), 32);
+    }
+
+    #[test]
+    fn max_length_too_short() {
+        let mut roa = RoaBuilder::new(64511.into());
+        roa.push_v4_addr(Ipv4Addr::new(10, 0, 0, 0), 16, Some(8));
+        roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, Some(0));
+        assert!(encode_and_decode(&roa, true).is_err());
+
+        let roa = encode_and_decode(&roa, false).unwrap();
+        let addrs: Vec<_> = roa.iter().collect();
+        assert_eq!(addrs[0].max_length(), 16);
+        assert_eq!(addrs[1].max_length(), 24);
+        assert_eq!(
+            roa.warnings(),
+            &[
+                RoaWarning::MaxLengthTooShort(addrs[0].clone()),
+                RoaWarning::MaxLengthTooShort(addrs[1].clone()),
+            ]
+        );
+        let origin = RouteOrigin::from_roa_address(&addrs[1], roa.as_id());
+        assert_eq!(origin.max_length(), 24);
+    }
+
+    #[test]
+    fn unused_bits() {
+        fn decode(
+            data: &[u8], strict: bool
+        ) -> Result<RouteOriginAttestation, decode::Error> {
+            Mode::Der.decode(data, |cons| {
+                RouteOriginAttestation::take_from(cons, strict)
+            })
+        }
+
+        // 10.0.0.0/7 with the unused last bit set.
+        let data = b"\x30\x18\x02\x03\x00\xfb\xf0\x30\x11\
+                     \x30\x0f\x04\x02\x00\x01\x30\x09\x30\x07\
+                     \x03\x02\x01\x0b\x02\x01\x08";
+        assert!(decode(data, true).is_err());
+        let roa = decode(data, false).unwrap();
+        assert_eq!(
+            roa.iter().next().unwrap().prefix(),
+            (IpAddr::from_str("10.0.0.0").unwrap(), 7)
+        );
+    }
 }
 
 #[cfg(all(test, feature="softkeys"))]
 mod signer_test {
     use std::str::FromStr;
     use bcder::encode::Values;
-    use crate::cert::{KeyUsage, Overclaim, TbsCert};
+    use crate::cert::KeyUsage;
     use crate::crypto::{PublicKeyFormat, Signer};
-    use crate::crypto::softsigner::OpenSslSigner;
+    use crate::crypto::softsigner::{KeyId, OpenSslSigner};
     use crate::resources::{AsId, Prefix};
-    use crate::uri;
+    use crate::util::test::{make_cert, test_uri};
     use crate::x509::Validity;
     use super::*;
 
     fn make_roa() -> Roa {
+        make_roa_with_inherit(false)
+    }
+
+    fn make_ta(
+        signer: &OpenSslSigner, key: &KeyId, v4: Prefix
+    ) -> ResourceCert {
+        let pubkey = signer.get_key_info(key).unwrap();
+        let mut cert = make_cert(pubkey, None, KeyUsage::Ca);
+        cert.build_v4_resource_blocks(|b| b.push(v4));
+        cert.into_cert(signer, key).unwrap().validate_ta(
+            TalInfo::from_name("foo".into()).into_arc(), true
+        ).unwrap()
+    }
+
+    fn sigobj_builder() -> SignedObjectBuilder {
+        let uri = test_uri();
+        SignedObjectBuilder::new(
+            12u64.into(), Validity::from_secs(86400), uri.clone(),
+            uri.clone(), uri
+        )
+    }
+
+    fn make_roa_with_inherit(inherit: bool) -> Roa {
         let mut signer = OpenSslSigner::new();
         let key = signer.create_key(PublicKeyFormat::default()).unwrap();
-        let pubkey = signer.get_key_info(&key).unwrap();
-        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
-
-        let mut cert = TbsCert::new(
-            12u64.into(), pubkey.to_subject_name(),
-            Validity::from_secs(86400), None, pubkey, KeyUsage::Ca,
-            Overclaim::Trim
-        );
-        cert.set_basic_ca(Some(true));
-        cert.set_ca_repository(Some(uri.clone()));
-        cert.set_rpki_manifest(Some(uri.clone()));
-        cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
-        cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
-        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
-        let cert = cert.into_cert(&signer, &key).unwrap();
+        let cert = make_ta(&signer, &key, Prefix::new(0, 0));
 
         let mut roa = RoaBuilder::new(64496.into());
         roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, None);
+        roa.push_v6_addr(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32, Some(48)
+        );
+        roa.set_inherit_resources(inherit);
 
-        let roa = roa.finalize(
-            SignedObjectBuilder::new(
-                12u64.into(), Validity::from_secs(86400), uri.clone(),
-                uri.clone(), uri.clone()
-            ),
-            &signer, &key
-        ).unwrap();
+        let roa = roa.finalize(sigobj_builder(), &signer, &key).unwrap();
         let roa = roa.encode_ref().to_captured(Mode::Der);
 
         let roa = Roa::decode(roa.as_slice(), true).unwrap();
-        let cert = cert.validate_ta(
-            TalInfo::from_name("foo".into()).into_arc(), true
-        ).unwrap();
         roa.clone().process(&cert, true, |_| Ok(())).unwrap();
+        let content = roa.clone().validate(&cert, true).unwrap();
+        assert_eq!(content.as_id(), AsId::from(64496));
+        assert_eq!(content.iter().count(), 2);
+        assert!(content.status().tal().is_some());
 
         roa
     }
@@ -701,7 +1297,91 @@ mod signer_test {
     fn encode_roa() {
         make_roa();
     }
-        
+
+    #[test]
+    fn encode_roa_inherit() {
+        let roa = make_roa_with_inherit(true);
+        assert!(roa.cert().v4_resources().unwrap().is_inherited());
+        assert!(roa.cert().v6_resources().unwrap().is_inherited());
+    }
+
+    #[test]
+    fn prefix_not_covered() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let cert = make_ta(
+            &signer, &key, Prefix::new(Ipv4Addr::new(10, 0, 0, 0), 8)
+        );
+
+        // The EE certificate inherits 10.0.0.0/8 from the issuer, so the
+        // second prefix is not covered.
+        let mut roa = RoaBuilder::new(64496.into());
+        roa.push_v4_addr(Ipv4Addr::new(10, 1, 0, 0), 16, Some(24));
+        roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, None);
+        roa.set_inherit_resources(true);
+        let roa = roa.finalize(sigobj_builder(), &signer, &key).unwrap();
+        let roa = roa.encode_ref().to_captured(Mode::Der);
+        let roa = Roa::decode(roa.as_slice(), true).unwrap();
+
+        match roa.clone().validate(&cert, true) {
+            Err(RoaValidationError::PrefixNotCovered(addr)) => {
+                assert_eq!(
+                    addr.prefix(),
+                    (IpAddr::from_str("192.0.2.0").unwrap(), 24)
+                );
+            }
+            res => panic!("unexpected result {:?}", res.map(|_| ())),
+        }
+        assert!(roa.process(&cert, true, |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn validate_with_policy() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let cert = make_ta(&signer, &key, Prefix::new(0, 0));
+
+        let mut roa = RoaBuilder::new(64496.into());
+        roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, None);
+        let roa = roa.finalize(sigobj_builder(), &signer, &key).unwrap();
+        let roa = roa.encode_ref().to_captured(Mode::Der);
+        let roa = Roa::decode(roa.as_slice(), true).unwrap();
+
+        match roa.clone().validate_with_policy(
+            &cert, true, Time::now(), &AlgorithmPolicy::none()
+        ) {
+            Err(RoaValidationError::Object(
+                SignedObjectError::AlgorithmNotPermitted(_)
+            )) => { }
+            res => panic!("unexpected result {:?}", res.map(|_| ()))
+        }
+        let content = roa.validate_with_policy(
+            &cert, true, Time::now(), &AlgorithmPolicy::rpki()
+        ).unwrap();
+        assert_eq!(content.as_id(), AsId::from(64496));
+    }
+
+    #[test]
+    fn ee_as_resources() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let cert = make_ta(&signer, &key, Prefix::new(0, 0));
+
+        let mut roa = RoaBuilder::new(64496.into());
+        roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, None);
+        let mut sigobj = sigobj_builder();
+        sigobj.set_as_resources_inherit();
+        let roa = roa.finalize(sigobj, &signer, &key).unwrap();
+        let roa = roa.encode_ref().to_captured(Mode::Der);
+        let roa = Roa::decode(roa.as_slice(), true).unwrap();
+
+        assert_eq!(
+            roa.clone().validate(&cert, true).err(),
+            Some(RoaValidationError::AsResources)
+        );
+        assert!(roa.validate(&cert, false).is_ok());
+    }
+
 
     #[test]
     fn serde_roa() {
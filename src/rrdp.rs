@@ -1,23 +1,45 @@
 //! Parsing the XML representations.
 
-use std::{fmt, io, ops, str};
-use log::info;
-use ring::digest;
+use std::{error, fmt, io};
+use std::collections::HashSet;
+use bytes::Bytes;
 use uuid::Uuid;
+use crate::crypto::Sha256Hash;
 use crate::uri;
-use crate::xml::decode::{Reader, Name, Error};
+use crate::xml::decode::{Content, Reader, Name, Error};
 
 
 //------------ NotificationFile ----------------------------------------------
 
+/// The content of an RRDP notification file.
+///
+/// The notification file is the entry point of an RRDP repository as
+/// defined in [RFC 8182]. It names the current session and serial number
+/// and refers to the current snapshot and the deltas leading up to it.
+///
+/// [RFC 8182]: https://tools.ietf.org/html/rfc8182
 pub struct NotificationFile {
+    /// The identifier of the current session of the repository.
     pub session_id: Uuid,
-    pub serial: usize,
+
+    /// The serial number of the most recent update.
+    pub serial: u64,
+
+    /// The URI and hash of the current snapshot.
     pub snapshot: UriAndHash,
-    pub deltas: Vec<(usize, UriAndHash)>,
+
+    /// The serial numbers, URIs, and hashes of the available deltas.
+    pub deltas: Vec<(u64, UriAndHash)>,
 }
 
 impl NotificationFile {
+    /// Parses a notification file from a reader.
+    ///
+    /// The version attribute must be 1. Otherwise
+    /// [`Error::UnsupportedVersion`] is returned. All hashes must be
+    /// SHA-256 hashes and each delta serial number may only appear once.
+    ///
+    /// [`Error::UnsupportedVersion`]: ../xml/decode/enum.Error.html
     pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
         let mut reader = Reader::new(reader);
 
@@ -30,8 +52,9 @@ impl NotificationFile {
 
             element.attributes(|name, value| match name {
                 b"version" => {
-                    if value.ascii_into::<u8>()? != 1 {
-                        return Err(Error::Malformed)
+                    let value = value.ascii_into::<String>()?;
+                    if value != "1" {
+                        return Err(Error::UnsupportedVersion(value))
                     }
                     Ok(())
                 }
@@ -49,6 +72,7 @@ impl NotificationFile {
 
         let mut snapshot = None;
         let mut deltas = Vec::new();
+        let mut delta_serials = HashSet::new();
         while let Some(mut content) = outer.take_opt_element(&mut reader,
                                                              |element| {
             match element.name() {
@@ -98,6 +122,9 @@ impl NotificationFile {
                     })?;
                     match (serial, uri, hash) {
                         (Some(serial), Some(uri), Some(hash)) => {
+                            if !delta_serials.insert(serial) {
+                                return Err(Error::Malformed)
+                            }
                             deltas.push((serial, UriAndHash::new(uri, hash)));
                             Ok(())
                         }
@@ -120,253 +147,584 @@ impl NotificationFile {
             _ => Err(Error::Malformed)
         }
     }
+
+    /// Determines how to update from the given state to this notification.
+    ///
+    /// The current state is given as the session ID and serial number of
+    /// the last applied update or `None` if there isn’t any. If the
+    /// session is the same and the notification file lists a delta for
+    /// every serial number from the next one up to its own serial number,
+    /// these deltas are returned in the order they need to be applied.
+    /// If the serial numbers are equal, there is nothing to do. In all
+    /// other cases, including a current serial number that is ahead of the
+    /// notification file, the snapshot has to be used.
+    pub fn update_plan(&self, current: Option<(Uuid, u64)>) -> UpdatePlan {
+        let serial = match current {
+            Some((session_id, serial)) if session_id == self.session_id => {
+                serial
+            }
+            _ => return UpdatePlan::UseSnapshot
+        };
+        if serial == self.serial {
+            return UpdatePlan::NothingToDo
+        }
+        if serial > self.serial {
+            return UpdatePlan::UseSnapshot
+        }
+        let mut deltas: Vec<_> = self.deltas.iter().filter(|item| {
+            item.0 > serial && item.0 <= self.serial
+        }).cloned().collect();
+        deltas.sort_by_key(|item| item.0);
+        let contiguous = deltas.iter().zip(serial + 1..).all(|(item, next)| {
+            item.0 == next
+        });
+        if !contiguous || deltas.len() as u64 != self.serial - serial {
+            return UpdatePlan::UseSnapshot
+        }
+        UpdatePlan::UseDeltas(deltas)
+    }
 }
 
 
-//------------ ProcessSnapshot -----------------------------------------------
+//------------ UpdatePlan ----------------------------------------------------
 
-pub trait ProcessSnapshot {
-    type Err: From<Error>;
+/// How to update a local copy of an RRDP repository.
+///
+/// This is the result of [`NotificationFile::update_plan`].
+///
+/// [`NotificationFile::update_plan`]: struct.NotificationFile.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UpdatePlan {
+    /// The local copy is up to date.
+    NothingToDo,
 
-    fn meta(
-        &mut self,
-        session_id: Uuid,
-        serial: usize
-    ) -> Result<(), Self::Err>;
+    /// Apply these deltas in the given order.
+    UseDeltas(Vec<(u64, UriAndHash)>),
 
-    fn publish(
-        &mut self,
-        uri: uri::Rsync,
-        data: Vec<u8>,
-    ) -> Result<(), Self::Err>;
+    /// Replace the local copy with the snapshot.
+    UseSnapshot,
+}
 
-    fn process<R: io::BufRead>(
-        &mut self,
-        reader: R
-    ) -> Result<(), Self::Err> {
+
+//------------ SnapshotFile --------------------------------------------------
+
+/// A streaming processor for RRDP snapshot files.
+///
+/// Snapshot files of large repositories can be hundreds of megabytes in
+/// size. Instead of reading the whole file, [`process`] hands each
+/// published object to a closure as soon as it has been read. At any time,
+/// only the encoded and decoded content of a single object is kept in
+/// memory.
+///
+/// The value holds the session ID and serial number the snapshot is
+/// expected to have, normally taken from the notification file.
+///
+/// [`process`]: #method.process
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotFile {
+    /// The expected session ID.
+    session_id: Uuid,
+
+    /// The expected serial number.
+    serial: u64,
+}
+
+impl SnapshotFile {
+    /// Creates a new snapshot processor expecting session and serial.
+    pub fn new(session_id: Uuid, serial: u64) -> Self {
+        SnapshotFile { session_id, serial }
+    }
+
+    /// Returns the expected session ID.
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// Returns the expected serial number.
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    /// Processes a snapshot file from a reader.
+    ///
+    /// The closure `op` is called with the URI and decoded content of each
+    /// published object in the order they appear in the file. If it returns
+    /// an error, processing stops and the error is returned wrapped in
+    /// [`ProcessError::Process`].
+    ///
+    /// If the session ID or serial number in the file differs from the
+    /// expected values, processing stops before `op` is called at all. If
+    /// an object’s content is not valid base64, processing stops with an
+    /// error containing the object’s URI.
+    ///
+    /// [`ProcessError::Process`]: enum.ProcessError.html
+    pub fn process<R, F, E>(
+        &self,
+        reader: R,
+        op: F
+    ) -> Result<(), ProcessError<E>>
+    where
+        R: io::BufRead,
+        F: FnMut(uri::Rsync, Bytes) -> Result<(), E>
+    {
         let mut reader = Reader::new(reader);
-        
-        let mut session_id = None;
-        let mut serial = None;
-        let mut outer = reader.start(|element| {
-            if element.name() != SNAPSHOT {
-                info!("Bad outer: not snapshot, but {:?}", element.name());
+        let outer = take_header(
+            &mut reader, SNAPSHOT, self.session_id, self.serial
+        )?;
+        take_snapshot_objects(outer, &mut reader, op)
+    }
+}
+
+//------------ DeltaFile -----------------------------------------------------
+
+/// A streaming processor for RRDP delta files.
+///
+/// This works like [`SnapshotFile`] but hands each change contained in the
+/// delta file to the closure as a [`DeltaElement`].
+///
+/// [`SnapshotFile`]: struct.SnapshotFile.html
+/// [`DeltaElement`]: enum.DeltaElement.html
+#[derive(Clone, Copy, Debug)]
+pub struct DeltaFile {
+    /// The expected session ID.
+    session_id: Uuid,
+
+    /// The expected serial number.
+    serial: u64,
+}
+
+impl DeltaFile {
+    /// Creates a new delta processor expecting session and serial.
+    ///
+    /// Both values are normally taken from the delta’s entry in the
+    /// notification file.
+    pub fn new(session_id: Uuid, serial: u64) -> Self {
+        DeltaFile { session_id, serial }
+    }
+
+    /// Returns the expected session ID.
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// Returns the expected serial number.
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    /// Processes a delta file from a reader.
+    ///
+    /// The closure `op` is called for each element of the delta in the
+    /// order they appear in the file. Errors are handled the same way as
+    /// by [`SnapshotFile::process`]. In addition, a withdraw element without
+    /// a hash and any malformed hash attribute are rejected.
+    ///
+    /// [`SnapshotFile::process`]: struct.SnapshotFile.html#method.process
+    pub fn process<R, F, E>(
+        &self,
+        reader: R,
+        op: F
+    ) -> Result<(), ProcessError<E>>
+    where
+        R: io::BufRead,
+        F: FnMut(DeltaElement) -> Result<(), E>
+    {
+        let mut reader = Reader::new(reader);
+        let outer = take_header(
+            &mut reader, DELTA, self.session_id, self.serial
+        )?;
+        take_delta_elements(outer, &mut reader, op)
+    }
+}
+
+
+//------------ DeltaElement --------------------------------------------------
+
+/// A single change contained in an RRDP delta file.
+#[derive(Clone, Debug)]
+pub enum DeltaElement {
+    /// A new object is published.
+    Publish {
+        uri: uri::Rsync,
+        data: Bytes,
+    },
+
+    /// An existing object with the given hash is replaced.
+    Update {
+        uri: uri::Rsync,
+        data: Bytes,
+        old_hash: Sha256Hash,
+    },
+
+    /// An existing object with the given hash is withdrawn.
+    Withdraw {
+        uri: uri::Rsync,
+        hash: Sha256Hash,
+    },
+}
+
+impl DeltaElement {
+    /// Returns the URI of the object the element refers to.
+    pub fn uri(&self) -> &uri::Rsync {
+        match *self {
+            DeltaElement::Publish { ref uri, .. } => uri,
+            DeltaElement::Update { ref uri, .. } => uri,
+            DeltaElement::Withdraw { ref uri, .. } => uri,
+        }
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Takes the start of a snapshot or delta file.
+///
+/// Checks that the outer element is `name` with version 1 and the expected
+/// session ID and serial number.
+fn take_header<R: io::BufRead, E>(
+    reader: &mut Reader<R>,
+    tag: Name,
+    expected_session_id: Uuid,
+    expected_serial: u64,
+) -> Result<Content, ProcessError<E>> {
+    let (outer, session_id, serial) = read_header(reader, tag)?;
+    if session_id != expected_session_id {
+        return Err(ProcessError::SessionMismatch {
+            expected: expected_session_id,
+            found: session_id
+        })
+    }
+    if serial != expected_serial {
+        return Err(ProcessError::SerialMismatch {
+            expected: expected_serial,
+            found: serial
+        })
+    }
+    Ok(outer)
+}
+
+/// Reads the start of a snapshot or delta file.
+///
+/// Checks that the outer element is `name` with version 1 and returns its
+/// content together with the session ID and serial number.
+fn read_header<R: io::BufRead>(
+    reader: &mut Reader<R>,
+    tag: Name,
+) -> Result<(Content, Uuid, u64), Error> {
+    let mut session_id = None;
+    let mut serial = None;
+    let outer = reader.start(|element| {
+        if element.name() != tag {
+            return Err(Error::Malformed)
+        }
+        element.attributes(|name, value| match name {
+            b"version" => {
+                let value = value.ascii_into::<String>()?;
+                if value != "1" {
+                    return Err(Error::UnsupportedVersion(value))
+                }
+                Ok(())
+            }
+            b"session_id" => {
+                session_id = Some(value.ascii_into()?);
+                Ok(())
+            }
+            b"serial" => {
+                serial = Some(value.ascii_into()?);
+                Ok(())
+            }
+            _ => Err(Error::Malformed)
+        })
+    })?;
+
+    match (session_id, serial) {
+        (Some(session_id), Some(serial)) => Ok((outer, session_id, serial)),
+        _ => Err(Error::Malformed)
+    }
+}
+
+/// Takes the publish elements of a snapshot file and the end of the file.
+fn take_snapshot_objects<R, F, E>(
+    mut outer: Content,
+    reader: &mut Reader<R>,
+    mut op: F
+) -> Result<(), ProcessError<E>>
+where
+    R: io::BufRead,
+    F: FnMut(uri::Rsync, Bytes) -> Result<(), E>
+{
+    loop {
+        let mut uri = None;
+        let inner = outer.take_opt_element(reader, |element| {
+            if element.name() != PUBLISH {
                 return Err(Error::Malformed)
             }
             element.attributes(|name, value| match name {
-                b"version" => {
-                    if value.ascii_into::<u8>()? != 1 {
-                        info!("Bad version");
-                        return Err(Error::Malformed)
-                    }
+                b"uri" => {
+                    uri = Some(value.ascii_into()?);
                     Ok(())
                 }
-                b"session_id" => {
-                    session_id = Some(value.ascii_into()?);
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        let mut inner = match inner {
+            Some(inner) => inner,
+            None => break
+        };
+        let uri: uri::Rsync = match uri {
+            Some(uri) => uri,
+            None => return Err(Error::Malformed.into())
+        };
+        let data = take_object(&mut inner, reader, &uri)?;
+        op(uri, data).map_err(ProcessError::Process)?;
+        inner.take_end(reader)?;
+    }
+
+    outer.take_end(reader)?;
+    reader.end()?;
+    Ok(())
+}
+
+/// Takes the elements of a delta file and the end of the file.
+fn take_delta_elements<R, F, E>(
+    mut outer: Content,
+    reader: &mut Reader<R>,
+    mut op: F
+) -> Result<(), ProcessError<E>>
+where
+    R: io::BufRead,
+    F: FnMut(DeltaElement) -> Result<(), E>
+{
+    loop {
+        let mut action = None;
+        let mut uri = None;
+        let mut hash = None;
+        let inner = outer.take_opt_element(reader, |element| {
+            match element.name() {
+                PUBLISH => action = Some(Action::Publish),
+                WITHDRAW => action = Some(Action::Withdraw),
+                _ => return Err(Error::Malformed),
+            };
+            element.attributes(|name, value| match name {
+                b"uri" => {
+                    uri = Some(value.ascii_into()?);
                     Ok(())
                 }
-                b"serial" => {
-                    serial = Some(value.ascii_into()?);
+                b"hash" => {
+                    hash = Some(value.ascii_into()?);
                     Ok(())
                 }
-                _ => {
-                    info!("Bad attribute on snapshot.");
-                    Err(Error::Malformed)
-                }
+                _ => Err(Error::Malformed)
             })
         })?;
-
-        match (session_id, serial) {
-            (Some(session_id), Some(serial)) => {
-                self.meta(session_id, serial)?;
+        let mut inner = match inner {
+            Some(inner) => inner,
+            None => break
+        };
+        let uri: uri::Rsync = match uri {
+            Some(uri) => uri,
+            None => return Err(Error::Malformed.into())
+        };
+        let element = match action.unwrap() { // Or we'd have exited.
+            Action::Publish => {
+                let data = take_object(&mut inner, reader, &uri)?;
+                match hash {
+                    Some(old_hash) => {
+                        DeltaElement::Update { uri, data, old_hash }
+                    }
+                    None => DeltaElement::Publish { uri, data }
+                }
             }
-            _ => {
-                info!("Missing session or serial");
-                return Err(Error::Malformed.into())
+            Action::Withdraw => {
+                match hash {
+                    Some(hash) => DeltaElement::Withdraw { uri, hash },
+                    None => return Err(Error::Malformed.into())
+                }
             }
+        };
+        op(element).map_err(ProcessError::Process)?;
+        inner.take_end(reader)?;
+    }
+
+    outer.take_end(reader)?;
+    reader.end()?;
+    Ok(())
+}
+
+/// Takes the base64 encoded content of a published object.
+fn take_object<R: io::BufRead, E>(
+    content: &mut Content,
+    reader: &mut Reader<R>,
+    uri: &uri::Rsync,
+) -> Result<Bytes, ProcessError<E>> {
+    content.take_text(reader, |text| {
+        match decode_base64(text.to_ascii()?.as_bytes()) {
+            Some(data) => Ok(data),
+            None => Err(ProcessError::InvalidBase64(uri.clone()))
         }
+    })
+}
 
-        loop {
-            let mut uri = None;
-            let inner = outer.take_opt_element(&mut reader, |element| {
-                if element.name() != PUBLISH {
-                info!("Bad inner: not publish");
-                    return Err(Error::Malformed)
-                }
-                element.attributes(|name, value| match name {
-                    b"uri" => {
-                        uri = Some(value.ascii_into()?);
-                        Ok(())
-                    }
-                    _ => {
-                        info!("Bad attribute on publish.");
-                        Err(Error::Malformed)
-                    }
-                })
-            })?;
-            let mut inner = match inner {
-                Some(inner) => inner,
-                None => break
-            };
-            let uri = match uri {
-                Some(uri) => uri,
-                None => return Err(Error::Malformed.into())
-            };
-            let data = inner.take_text(&mut reader, |text| {
-                let text: Vec<_> = text.to_ascii()?.as_bytes()
-                .iter().filter_map(|b| {
-                    if b.is_ascii_whitespace() { None }
-                    else { Some(*b) }
-                }).collect();
-                base64::decode(&text).map_err(|_| {
-                    Error::Malformed
-                })
-            })?;
-            self.publish(uri, data)?;
-            inner.take_end(&mut reader)?;
+/// Decodes base64 text ignoring white space.
+///
+/// The text is decoded in chunks so that at no point a copy of the whole
+/// text needs to be made. Padding is optional but if present, it can only
+/// appear at the very end, can be at most two characters long, and must
+/// complete the last group of four characters.
+fn decode_base64(text: &[u8]) -> Option<Bytes> {
+    const CHUNK_LEN: usize = 1024;
+
+    let mut res = Vec::with_capacity(text.len() / 4 * 3 + 3);
+    let mut chunk = [0u8; CHUNK_LEN];
+    let mut chunk_len = 0;
+    let mut len = 0;
+    let mut padding = 0;
+    for &ch in text {
+        if ch.is_ascii_whitespace() {
+            continue
+        }
+        if ch == b'=' {
+            padding += 1;
+            if padding > 2 {
+                return None
+            }
+            continue
+        }
+        if padding > 0 {
+            return None
         }
+        chunk[chunk_len] = ch;
+        chunk_len += 1;
+        len += 1;
+        if chunk_len == CHUNK_LEN {
+            base64::decode_config_buf(
+                &chunk[..], base64::STANDARD_NO_PAD, &mut res
+            ).ok()?;
+            chunk_len = 0;
+        }
+    }
+    if padding > 0 && (len + padding) % 4 != 0 {
+        return None
+    }
+    base64::decode_config_buf(
+        &chunk[..chunk_len], base64::STANDARD_NO_PAD, &mut res
+    ).ok()?;
+    Some(res.into())
+}
 
-        outer.take_end(&mut reader)?;
-        reader.end()?;
-        Ok(())
+
+//------------ ProcessSnapshot -----------------------------------------------
+
+/// A type that processes the content of a snapshot file.
+///
+/// Unlike [`SnapshotFile`], this doesn’t check the session ID and serial
+/// number but hands them to [`meta`] instead.
+///
+/// [`SnapshotFile`]: struct.SnapshotFile.html
+/// [`meta`]: #tymethod.meta
+pub trait ProcessSnapshot {
+    type Err: From<Error>;
+
+    fn meta(
+        &mut self,
+        session_id: Uuid,
+        serial: u64
+    ) -> Result<(), Self::Err>;
+
+    fn publish(
+        &mut self,
+        uri: uri::Rsync,
+        data: Bytes,
+    ) -> Result<(), Self::Err>;
+
+    fn process<R: io::BufRead>(
+        &mut self,
+        reader: R
+    ) -> Result<(), Self::Err> {
+        let mut reader = Reader::new(reader);
+        let (outer, session_id, serial) = read_header(
+            &mut reader, SNAPSHOT
+        )?;
+        self.meta(session_id, serial)?;
+        take_snapshot_objects(
+            outer, &mut reader, |uri, data| self.publish(uri, data)
+        ).map_err(ProcessError::into_err)
     }
 }
 
 
 //------------ ProcessDelta --------------------------------------------------
 
+/// A type that processes the content of a delta file.
+///
+/// Unlike [`DeltaFile`], this doesn’t check the session ID and serial
+/// number but hands them to [`meta`] instead. A publish element with a
+/// hash, i.e., an update of an existing object, is handed to [`publish`]
+/// together with the hash of the object it replaces.
+///
+/// [`DeltaFile`]: struct.DeltaFile.html
+/// [`meta`]: #tymethod.meta
+/// [`publish`]: #tymethod.publish
 pub trait ProcessDelta {
     type Err: From<Error>;
 
     fn meta(
         &mut self,
         session_id: Uuid,
-        serial: usize
+        serial: u64
     ) -> Result<(), Self::Err>;
 
     fn publish(
         &mut self,
         uri: uri::Rsync,
-        hash: Option<DigestHex>,
-        data: Vec<u8>,
+        hash: Option<Sha256Hash>,
+        data: Bytes,
     ) -> Result<(), Self::Err>;
 
     fn withdraw(
         &mut self,
         uri: uri::Rsync,
-        hash: DigestHex,
+        hash: Sha256Hash,
     ) -> Result<(), Self::Err>;
 
-
     fn process<R: io::BufRead>(
         &mut self,
         reader: R
     ) -> Result<(), Self::Err> {
         let mut reader = Reader::new(reader);
-        
-        let mut session_id = None;
-        let mut serial = None;
-        let mut outer = reader.start(|element| {
-            if element.name() != DELTA {
-                return Err(Error::Malformed)
+        let (outer, session_id, serial) = read_header(&mut reader, DELTA)?;
+        self.meta(session_id, serial)?;
+        take_delta_elements(outer, &mut reader, |element| match element {
+            DeltaElement::Publish { uri, data } => {
+                self.publish(uri, None, data)
             }
-            element.attributes(|name, value| match name {
-                b"version" => {
-                    if value.ascii_into::<u8>()? != 1 {
-                        return Err(Error::Malformed)
-                    }
-                    Ok(())
-                }
-                b"session_id" => {
-                    session_id = Some(value.ascii_into()?);
-                    Ok(())
-                }
-                b"serial" => {
-                    serial = Some(value.ascii_into()?);
-                    Ok(())
-                }
-                _ => Err(Error::Malformed)
-            })
-        })?;
-
-        match (session_id, serial) {
-            (Some(session_id), Some(serial)) => {
-                self.meta(session_id, serial)?;
+            DeltaElement::Update { uri, data, old_hash } => {
+                self.publish(uri, Some(old_hash), data)
             }
-            _ => return Err(Error::Malformed.into()),
-        }
-
-        loop {
-            let mut action = None;
-            let mut uri = None;
-            let mut hash = None;
-            let inner = outer.take_opt_element(&mut reader, |element| {
-                match element.name() {
-                    PUBLISH => action = Some(Action::Publish),
-                    WITHDRAW => action = Some(Action::Withdraw),
-                    _ => return Err(Error::Malformed),
-                };
-                element.attributes(|name, value| match name {
-                    b"uri" => {
-                        uri = Some(value.ascii_into()?);
-                        Ok(())
-                    }
-                    b"hash" => {
-                        hash = Some(value.ascii_into()?);
-                        Ok(())
-                    }
-                    _ => Err(Error::Malformed)
-                })
-            })?;
-            let mut inner = match inner {
-                Some(inner) => inner,
-                None => break
-            };
-            let uri = match uri {
-                Some(uri) => uri,
-                None => return Err(Error::Malformed.into())
-            };
-            match action.unwrap() { // Or we'd have exited already.
-                Action::Publish => {
-                    let data = inner.take_text(&mut reader, |text| {
-                        let text: Vec<_> = text.to_ascii()?.as_bytes()
-                        .iter().filter_map(|b| {
-                            if b.is_ascii_whitespace() { None }
-                            else { Some(*b) }
-                        }).collect();
-                        base64::decode(&text)
-                            .map_err(|_| Error::Malformed)
-                    })?;
-                    self.publish(uri, hash, data)?;
-                }
-                Action::Withdraw => {
-                    let hash = match hash {
-                        Some(hash) => hash,
-                        None => return Err(Error::Malformed.into())
-                    };
-                    self.withdraw(uri, hash)?;
-                }
-            }
-            inner.take_end(&mut reader)?;
-        }
-        outer.take_end(&mut reader)?;
-        reader.end()?;
-        Ok(())
+            DeltaElement::Withdraw { uri, hash } => self.withdraw(uri, hash)
+        }).map_err(ProcessError::into_err)
     }
-
 }
 
 
 //------------ UriAndHash ----------------------------------------------------
 
-#[derive(Clone, Debug)]
+/// The URI of a snapshot or delta file and the hash of its content.
+///
+/// The notification file uses this type for both the snapshot and the
+/// deltas rather than having separate types for them. Consequently,
+/// checking a fetched file against its hash happens via [`verify`] for
+/// either kind of file.
+///
+/// [`verify`]: #method.verify
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct UriAndHash {
     uri: uri::Https,
-    hash: DigestHex,
+    hash: Sha256Hash,
 }
 
 impl UriAndHash {
-    pub fn new(uri: uri::Https, hash: DigestHex) -> Self {
+    pub fn new(uri: uri::Https, hash: Sha256Hash) -> Self {
         UriAndHash { uri, hash }
     }
 
@@ -374,70 +732,92 @@ impl UriAndHash {
         &self.uri
     }
 
-    pub fn hash(&self) -> &DigestHex {
+    pub fn hash(&self) -> &Sha256Hash {
         &self.hash
     }
-}
 
+    /// Returns whether the fetched content of the file matches the hash.
+    ///
+    /// The comparison is done in constant time.
+    pub fn verify(&self, data: &[u8]) -> bool {
+        self.hash.matches(data)
+    }
+}
 
-//------------ DigestHex -----------------------------------------------------
 
-/// A helper type to encode a digest as a sequence of hex-digits.
-#[derive(Clone, Debug)]
-pub struct DigestHex(Vec<u8>);
+//------------ ProcessError --------------------------------------------------
 
-impl From<Vec<u8>> for DigestHex {
-    fn from(value: Vec<u8>) -> DigestHex {
-        DigestHex(value)
-    }
-}
+/// An error happened while processing a snapshot or delta file.
+#[derive(Debug)]
+pub enum ProcessError<E> {
+    /// The file was not a well-formed snapshot or delta file.
+    Xml(Error),
 
-impl From<digest::Digest> for DigestHex {
-    fn from(value: digest::Digest) -> DigestHex {
-        DigestHex(Vec::from(value.as_ref()))
-    }
-}
+    /// The file belongs to a different session.
+    SessionMismatch {
+        expected: Uuid,
+        found: Uuid
+    },
 
-impl str::FromStr for DigestHex {
-    type Err = &'static str;
+    /// The file has a different serial number.
+    SerialMismatch {
+        expected: u64,
+        found: u64,
+    },
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut res = Vec::new();
-        let mut s = s.chars();
-        while let Some(first) = s.next() {
-            let first = first.to_digit(16).ok_or("invalid digest")?;
-            let second = s.next().ok_or("invalid digest")?
-                .to_digit(16).ok_or("invalid digest")?;
+    /// The content of the object with the given URI is not valid base64.
+    InvalidBase64(uri::Rsync),
 
-            res.push((first << 4 | second) as u8);
-        }
-        Ok(DigestHex(res))
-    }
+    /// Processing an object failed.
+    Process(E),
 }
 
-impl ops::Deref for DigestHex {
-    type Target = [u8];
-
-    fn deref(&self) -> &[u8] {
-        self.0.as_ref()
+impl<E: From<Error>> ProcessError<E> {
+    /// Converts the error into the error type of the processing trait.
+    fn into_err(self) -> E {
+        match self {
+            ProcessError::Xml(err) => err.into(),
+            ProcessError::InvalidBase64(_) => {
+                Error::InvalidBase64(String::from("publish")).into()
+            }
+            ProcessError::Process(err) => err,
+            _ => Error::Malformed.into()
+        }
     }
 }
 
-impl AsRef<[u8]> for DigestHex {
-    fn as_ref(&self) -> &[u8] {
-        self.0.as_ref()
+impl<E> From<Error> for ProcessError<E> {
+    fn from(err: Error) -> Self {
+        ProcessError::Xml(err)
     }
 }
 
-impl fmt::Display for DigestHex {
+impl<E: fmt::Display> fmt::Display for ProcessError<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for &ch in self.0.as_slice() {
-            write!(f, "{:02x}", ch)?;
+        match *self {
+            ProcessError::Xml(ref err) => err.fmt(f),
+            ProcessError::SessionMismatch { expected, found } => {
+                write!(
+                    f, "session ID mismatch (expected {}, found {})",
+                    expected, found
+                )
+            }
+            ProcessError::SerialMismatch { expected, found } => {
+                write!(
+                    f, "serial number mismatch (expected {}, found {})",
+                    expected, found
+                )
+            }
+            ProcessError::InvalidBase64(ref uri) => {
+                write!(f, "invalid base64 content for {}", uri)
+            }
+            ProcessError::Process(ref err) => err.fmt(f),
         }
-        Ok(())
     }
 }
 
+impl<E: fmt::Debug + fmt::Display> error::Error for ProcessError<E> { }
+
 
 //------------ Action --------------------------------------------------------
 
@@ -471,7 +851,7 @@ mod test {
         fn meta(
             &mut self,
             _session_id: Uuid,
-            _serial: usize
+            _serial: u64
         ) -> Result<(), Self::Err> {
             Ok(())
         }
@@ -479,7 +859,7 @@ mod test {
         fn publish(
             &mut self,
             _uri: uri::Rsync,
-            _data: Vec<u8>,
+            _data: Bytes,
         ) -> Result<(), Self::Err> {
             Ok(())
         }
@@ -491,7 +871,7 @@ mod test {
         fn meta(
             &mut self,
             _session_id: Uuid,
-            _serial: usize
+            _serial: u64
         ) -> Result<(), Self::Err> {
             Ok(())
         }
@@ -499,8 +879,8 @@ mod test {
         fn publish(
             &mut self,
             _uri: uri::Rsync,
-            _hash: Option<DigestHex>,
-            _data: Vec<u8>,
+            _hash: Option<Sha256Hash>,
+            _data: Bytes,
         ) -> Result<(), Self::Err> {
             Ok(())
         }
@@ -508,7 +888,7 @@ mod test {
         fn withdraw(
             &mut self,
             _uri: uri::Rsync,
-            _hash: DigestHex,
+            _hash: Sha256Hash,
         ) -> Result<(), Self::Err> {
             Ok(())
         }
@@ -516,9 +896,173 @@ mod test {
 
     #[test]
     fn ripe_notification() {
-        NotificationFile::parse(
+        let notify = NotificationFile::parse(
             include_bytes!("../test-data/ripe-notification.xml").as_ref()
         ).unwrap();
+        assert_eq!(
+            notify.session_id,
+            "a2d845c4-5b91-4015-a2b7-988c03ce232a".parse::<Uuid>().unwrap()
+        );
+        assert_eq!(notify.serial, 1742);
+        assert_eq!(
+            notify.snapshot.uri().as_str(),
+            "https://rrdp.ripe.net/a2d845c4-5b91-4015-a2b7-988c03ce232a/\
+             1742/snapshot.xml"
+        );
+        assert_eq!(
+            notify.snapshot.hash().to_string(),
+            "c047e305fe71f2936720948e129a14c0819ded9cdecf31cfaf02c71200eb6f7c"
+        );
+        assert!(!notify.deltas.is_empty());
+        assert_eq!(notify.deltas[0].0, 1742);
+        assert_eq!(
+            notify.deltas[0].1.uri().as_str(),
+            "https://rrdp.ripe.net/a2d845c4-5b91-4015-a2b7-988c03ce232a/\
+             1742/delta.xml"
+        );
+        assert_eq!(
+            notify.deltas[0].1.hash().to_string(),
+            "fa2bdce6b32ddf7f61f91b4549abc61b6d6986fa91061b37c72f045fa1b7ba79"
+        );
+        for (serial, item) in &notify.deltas {
+            assert!(item.uri().path().ends_with(
+                &format!("/{}/delta.xml", serial)
+            ));
+        }
+    }
+
+    fn update_notification() -> NotificationFile {
+        let hash = "ab".repeat(32);
+        let delta = |serial| format!(
+            "<delta serial=\"{}\" uri=\"https://host/{}.xml\" \
+             hash=\"{}\"/>",
+            serial, serial, hash
+        );
+        NotificationFile::parse(notification(
+            "1", &hash, &format!("{}{}{}", delta(3), delta(1), delta(2))
+        ).as_bytes()).unwrap()
+    }
+
+    fn plan_serials(plan: UpdatePlan) -> Option<Vec<u64>> {
+        match plan {
+            UpdatePlan::UseDeltas(deltas) => {
+                Some(deltas.iter().map(|item| item.0).collect())
+            }
+            _ => None
+        }
+    }
+
+    #[test]
+    fn update_plan() {
+        let notify = update_notification();
+        let session = notify.session_id;
+
+        // No current state.
+        assert_eq!(notify.update_plan(None), UpdatePlan::UseSnapshot);
+
+        // Different session.
+        assert_eq!(
+            notify.update_plan(Some((Uuid::nil(), 3))),
+            UpdatePlan::UseSnapshot
+        );
+
+        // Up to date.
+        assert_eq!(
+            notify.update_plan(Some((session, 3))), UpdatePlan::NothingToDo
+        );
+
+        // Ahead of the notification.
+        assert_eq!(
+            notify.update_plan(Some((session, 4))), UpdatePlan::UseSnapshot
+        );
+
+        // Contiguous deltas in order.
+        assert_eq!(
+            plan_serials(notify.update_plan(Some((session, 0)))),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(
+            plan_serials(notify.update_plan(Some((session, 2)))),
+            Some(vec![3])
+        );
+
+        // Gap in the deltas.
+        let mut notify = notify;
+        notify.deltas.retain(|item| item.0 != 2);
+        assert_eq!(
+            notify.update_plan(Some((session, 0))), UpdatePlan::UseSnapshot
+        );
+        assert_eq!(
+            plan_serials(notify.update_plan(Some((session, 2)))),
+            Some(vec![3])
+        );
+
+        // Too far behind.
+        notify.deltas.retain(|item| item.0 != 1);
+        assert_eq!(
+            notify.update_plan(Some((session, 0))), UpdatePlan::UseSnapshot
+        );
+    }
+
+    #[test]
+    fn uri_and_hash_verify() {
+        let item = UriAndHash::new(
+            "https://host/snapshot.xml".parse().unwrap(),
+            Sha256Hash::from_content(b"foo")
+        );
+        assert!(item.verify(b"foo"));
+        assert!(!item.verify(b"fo"));
+    }
+
+    fn notification(
+        version: &str, snapshot_hash: &str, deltas: &str
+    ) -> String {
+        format!(
+            "<notification xmlns=\"http://www.ripe.net/rpki/rrdp\" \
+               version=\"{}\" \
+               session_id=\"9df4b597-af9e-4dca-bdda-719cce2c4e28\" \
+               serial=\"3\">\
+               <snapshot uri=\"https://host/snapshot.xml\" hash=\"{}\"/>\
+               {}\
+             </notification>",
+            version, snapshot_hash, deltas
+        )
+    }
+
+    #[test]
+    fn notification_reject() {
+        let hash = "ab".repeat(32);
+        let delta = |serial| format!(
+            "<delta serial=\"{}\" uri=\"https://host/{}.xml\" \
+             hash=\"{}\"/>",
+            serial, serial, hash
+        );
+        let deltas = format!("{}{}", delta(3), delta(2));
+        let notify = NotificationFile::parse(
+            notification("1", &hash, &deltas).as_bytes()
+        ).unwrap();
+        assert_eq!(notify.serial, 3);
+        assert_eq!(notify.deltas.len(), 2);
+        assert_eq!(notify.snapshot.hash().as_slice(), &[0xab; 32][..]);
+
+        let deltas = format!("{}{}", delta(3), delta(3));
+        assert!(NotificationFile::parse(
+            notification("1", &hash, &deltas).as_bytes()
+        ).is_err());
+        assert!(NotificationFile::parse(
+            notification("1", &hash[2..], "").as_bytes()
+        ).is_err());
+        assert!(NotificationFile::parse(
+            notification("1", &format!("{}ab", hash), "").as_bytes()
+        ).is_err());
+        match NotificationFile::parse(
+            notification("2", &hash, "").as_bytes()
+        ) {
+            Err(Error::UnsupportedVersion(ref version)) => {
+                assert_eq!(version, "2")
+            }
+            _ => panic!("expected unsupported version")
+        }
     }
 
     #[test]
@@ -529,6 +1073,247 @@ mod test {
         ).unwrap();
     }
 
+    #[test]
+    fn process_snapshot_errors() {
+        let xml = snapshot(
+            "<publish uri=\"rsync://example.com/repo/a.cer\">\
+               AA!A\
+             </publish>"
+        );
+        match <Test as ProcessSnapshot>::process(&mut Test, xml.as_bytes()) {
+            Err(Error::InvalidBase64(_)) => { }
+            _ => panic!("expected invalid base64")
+        }
+        let xml = xml.replace("version=\"1\"", "version=\"2\"");
+        match <Test as ProcessSnapshot>::process(&mut Test, xml.as_bytes()) {
+            Err(Error::UnsupportedVersion(_)) => { }
+            _ => panic!("expected unsupported version")
+        }
+    }
+
+    #[test]
+    fn decode_base64_padding() {
+        assert_eq!(decode_base64(b"AwQ=").unwrap().as_ref(), b"\x03\x04");
+        assert_eq!(decode_base64(b"AwQ").unwrap().as_ref(), b"\x03\x04");
+        assert_eq!(decode_base64(b"Aw==").unwrap().as_ref(), b"\x03");
+        assert_eq!(decode_base64(b"Aw = =\n").unwrap().as_ref(), b"\x03");
+        assert!(decode_base64(b"Aw===").is_none());
+        assert!(decode_base64(b"AwQ==").is_none());
+        assert!(decode_base64(b"AAEC=").is_none());
+        assert!(decode_base64(b"Aw=A").is_none());
+    }
+
+    #[test]
+    fn snapshot_file_ripe() {
+        let session = "a2d845c4-5b91-4015-a2b7-988c03ce232a".parse().unwrap();
+        let data = include_bytes!("../test-data/ripe-snapshot.xml");
+        let mut count = 0;
+        SnapshotFile::new(session, 1742).process(
+            data.as_ref(),
+            |uri, data| {
+                assert!(uri.as_str().starts_with("rsync://rpki.ripe.net/"));
+                assert!(!data.is_empty());
+                count += 1;
+                Ok::<_, Error>(())
+            }
+        ).unwrap();
+        assert_eq!(count, 277);
+
+        match SnapshotFile::new(session, 1741).process(
+            data.as_ref(), |_, _| -> Result<(), Error> { panic!() }
+        ) {
+            Err(ProcessError::SerialMismatch { expected, found }) => {
+                assert_eq!((expected, found), (1741, 1742))
+            }
+            _ => panic!("expected serial mismatch")
+        }
+        match SnapshotFile::new(Uuid::nil(), 1742).process(
+            data.as_ref(), |_, _| -> Result<(), Error> { panic!() }
+        ) {
+            Err(ProcessError::SessionMismatch { expected, found }) => {
+                assert_eq!(expected, Uuid::nil());
+                assert_eq!(found, session);
+            }
+            _ => panic!("expected session mismatch")
+        }
+    }
+
+    const SESSION: &str = "9df4b597-af9e-4dca-bdda-719cce2c4e28";
+
+    fn snapshot(publish: &str) -> String {
+        format!(
+            "<snapshot xmlns=\"http://www.ripe.net/rpki/rrdp\" \
+               version=\"1\" session_id=\"{}\" serial=\"3\">\
+               {}\
+             </snapshot>",
+            SESSION, publish
+        )
+    }
+
+    #[test]
+    fn snapshot_file_errors() {
+        let file = SnapshotFile::new(SESSION.parse().unwrap(), 3);
+        let xml = snapshot(
+            "<publish uri=\"rsync://example.com/repo/a.cer\">\
+               AAEC\n  AwQ=\
+             </publish>\
+             <publish uri=\"rsync://example.com/repo/b.cer\">AA!A</publish>"
+        );
+        let mut seen = Vec::new();
+        match file.process(xml.as_bytes(), |uri, data| {
+            seen.push((uri, data));
+            Ok::<_, Error>(())
+        }) {
+            Err(ProcessError::InvalidBase64(uri)) => {
+                assert_eq!(uri.as_str(), "rsync://example.com/repo/b.cer")
+            }
+            _ => panic!("expected invalid base64")
+        }
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].0.as_str(), "rsync://example.com/repo/a.cer");
+        assert_eq!(seen[0].1.as_ref(), b"\x00\x01\x02\x03\x04");
+
+        let xml = snapshot(
+            "<publish uri=\"rsync://example.com/repo/a.cer\">\
+               AA==AAAA\
+             </publish>"
+        );
+        match file.process(xml.as_bytes(), |_, _| Ok::<_, Error>(())) {
+            Err(ProcessError::InvalidBase64(_)) => { }
+            _ => panic!("expected invalid base64")
+        }
+
+        let xml = snapshot(
+            "<publish uri=\"rsync://example.com/repo/a.cer\">\
+               AwQ===\
+             </publish>"
+        );
+        match file.process(xml.as_bytes(), |_, _| Ok::<_, Error>(())) {
+            Err(ProcessError::InvalidBase64(_)) => { }
+            _ => panic!("expected invalid base64")
+        }
+
+        let xml = snapshot(
+            "<publish uri=\"rsync://example.com/repo/a.cer\">\
+               AAEC\
+             </publish>"
+        );
+        match file.process(xml.as_bytes(), |_, _| Err("stop")) {
+            Err(ProcessError::Process("stop")) => { }
+            _ => panic!("expected process error")
+        }
+        match SnapshotFile::new(SESSION.parse().unwrap(), 3).process(
+            xml.replace("version=\"1\"", "version=\"2\"").as_bytes(),
+            |_, _| Ok::<_, Error>(())
+        ) {
+            Err(ProcessError::Xml(Error::UnsupportedVersion(_))) => { }
+            _ => panic!("expected unsupported version")
+        }
+    }
+
+    #[test]
+    fn delta_file_ripe() {
+        let session = "a2d845c4-5b91-4015-a2b7-988c03ce232a".parse().unwrap();
+        let data = include_bytes!("../test-data/ripe-delta.xml");
+        let (mut publish, mut update, mut withdraw) = (0, 0, 0);
+        DeltaFile::new(session, 1739).process(data.as_ref(), |element| {
+            match element {
+                DeltaElement::Publish { .. } => publish += 1,
+                DeltaElement::Update { .. } => update += 1,
+                DeltaElement::Withdraw { .. } => withdraw += 1,
+            }
+            Ok::<_, Error>(())
+        }).unwrap();
+        assert_eq!((publish, update, withdraw), (1, 64, 1));
+    }
+
+    fn delta(elements: &str) -> String {
+        format!(
+            "<delta xmlns=\"http://www.ripe.net/rpki/rrdp\" \
+               version=\"1\" session_id=\"{}\" serial=\"4\">\
+               {}\
+             </delta>",
+            SESSION, elements
+        )
+    }
+
+    #[test]
+    fn delta_file() {
+        let file = DeltaFile::new(SESSION.parse().unwrap(), 4);
+        let hash = "ab".repeat(32);
+        let xml = delta(&format!(
+            "<publish uri=\"rsync://example.com/repo/a.cer\">\
+               AAEC\
+             </publish>\
+             <publish uri=\"rsync://example.com/repo/b.cer\" hash=\"{}\">\
+               AwQ=\
+             </publish>\
+             <withdraw uri=\"rsync://example.com/repo/c.cer\" \
+                       hash=\"{}\"/>",
+            hash, hash
+        ));
+        let mut elements = Vec::new();
+        file.process(xml.as_bytes(), |element| {
+            elements.push(element);
+            Ok::<_, Error>(())
+        }).unwrap();
+        assert_eq!(elements.len(), 3);
+        match elements[0] {
+            DeltaElement::Publish { ref uri, ref data } => {
+                assert_eq!(uri.as_str(), "rsync://example.com/repo/a.cer");
+                assert_eq!(data.as_ref(), b"\x00\x01\x02");
+            }
+            _ => panic!("expected publish")
+        }
+        match elements[1] {
+            DeltaElement::Update { ref uri, ref data, old_hash } => {
+                assert_eq!(uri.as_str(), "rsync://example.com/repo/b.cer");
+                assert_eq!(data.as_ref(), b"\x03\x04");
+                assert_eq!(old_hash.as_slice(), &[0xab; 32][..]);
+            }
+            _ => panic!("expected update")
+        }
+        match elements[2] {
+            DeltaElement::Withdraw { ref uri, hash } => {
+                assert_eq!(uri.as_str(), "rsync://example.com/repo/c.cer");
+                assert_eq!(hash.as_slice(), &[0xab; 32][..]);
+            }
+            _ => panic!("expected withdraw")
+        }
+        assert_eq!(
+            elements[2].uri().as_str(), "rsync://example.com/repo/c.cer"
+        );
+
+        match DeltaFile::new(Uuid::nil(), 4).process(
+            xml.as_bytes(), |_| -> Result<(), Error> { panic!() }
+        ) {
+            Err(ProcessError::SessionMismatch { expected, found }) => {
+                assert_eq!(expected, Uuid::nil());
+                assert_eq!(found, SESSION.parse::<Uuid>().unwrap());
+            }
+            _ => panic!("expected session mismatch")
+        }
+
+        // Withdraw without a hash.
+        let xml = delta(
+            "<withdraw uri=\"rsync://example.com/repo/c.cer\"/>"
+        );
+        assert!(
+            file.process(xml.as_bytes(), |_| Ok::<_, Error>(())).is_err()
+        );
+
+        // Publish with a malformed hash.
+        let xml = delta(&format!(
+            "<publish uri=\"rsync://example.com/repo/b.cer\" hash=\"{}\">\
+               AwQ=\
+             </publish>",
+            &hash[1..]
+        ));
+        assert!(
+            file.process(xml.as_bytes(), |_| Ok::<_, Error>(())).is_err()
+        );
+    }
+
     #[test]
     fn ripe_delta() {
         <Test as ProcessDelta>::process(
@@ -536,4 +1321,34 @@ mod test {
             include_bytes!("../test-data/ripe-delta.xml").as_ref()
         ).unwrap();
     }
+
+    #[test]
+    fn process_delta_errors() {
+        // Withdraw without a hash.
+        let xml = delta(
+            "<withdraw uri=\"rsync://example.com/repo/c.cer\"/>"
+        );
+        match <Test as ProcessDelta>::process(&mut Test, xml.as_bytes()) {
+            Err(Error::Malformed) => { }
+            _ => panic!("expected malformed")
+        }
+
+        // Hash of the wrong length.
+        let xml = delta(&format!(
+            "<withdraw uri=\"rsync://example.com/repo/c.cer\" \
+                       hash=\"{}\"/>",
+            "ab".repeat(20)
+        ));
+        match <Test as ProcessDelta>::process(&mut Test, xml.as_bytes()) {
+            Err(Error::Malformed) => { }
+            _ => panic!("expected malformed")
+        }
+
+        // Unsupported version.
+        let xml = delta("").replace("version=\"1\"", "version=\"2\"");
+        match <Test as ProcessDelta>::process(&mut Test, xml.as_bytes()) {
+            Err(Error::UnsupportedVersion(_)) => { }
+            _ => panic!("expected unsupported version")
+        }
+    }
 }
@@ -0,0 +1,1127 @@
+//! Signed messages of the remote protocols.
+//!
+//! Both the provisioning protocol defined in [RFC 6492] and the publication
+//! protocol defined in [RFC 8181] wrap their XML messages into CMS signed
+//! data. The CMS profile used is similar to that of RPKI signed objects but
+//! uses identity certificates rather than resource certificates and
+//! includes a CRL.
+//!
+//! This module provides [`SignedMessage`] for decoding and validating such
+//! messages and [`SignedMessageBuilder`] for creating them.
+//!
+//! Since the crate doesn’t have a separate type for identity certificates,
+//! the certificates are represented by [`Cert`]. As this type requires
+//! certificates to have resources, the EE certificates created by the
+//! builder explicitly hold all IPv4, IPv6, and AS resources. Since an
+//! identity certificate may lack resources altogether, they can’t inherit
+//! them from their issuer.
+//!
+//! [RFC 6492]: https://tools.ietf.org/html/rfc6492
+//! [RFC 8181]: https://tools.ietf.org/html/rfc8181
+//! [`SignedMessage`]: struct.SignedMessage.html
+//! [`SignedMessageBuilder`]: struct.SignedMessageBuilder.html
+//! [`Cert`]: ../cert/struct.Cert.html
+
+use std::{error, fmt};
+use std::convert::TryFrom;
+use bcder::{decode, encode};
+use bcder::{Captured, Mode, OctetString, Oid, Tag, xerr};
+use bcder::encode::PrimitiveContent;
+use bytes::Bytes;
+use chrono::{Duration, LocalResult, TimeZone, Utc};
+use crate::oid;
+use crate::cert::{Cert, KeyUsage, Overclaim, TbsCert};
+use crate::crl::Crl;
+use crate::crypto::{
+    DigestAlgorithm, KeyIdentifier, Signature, SignatureAlgorithm, Signer,
+    SigningError
+};
+use crate::resources::{AsId, Prefix};
+use crate::sigobj::{MessageDigest, SignedAttrs};
+use crate::x509::{Serial, Time, ValidationError, Validity};
+
+
+//------------ SignedMessage -------------------------------------------------
+
+/// A CMS signed message of the provisioning or publication protocols.
+#[derive(Clone, Debug)]
+pub struct SignedMessage {
+    //--- From SignedData
+    //
+    digest_algorithm: DigestAlgorithm,
+    content_type: Oid<Bytes>,
+    content: Bytes,
+    cert: Cert,
+    crl: Option<Crl>,
+
+    //--- From SignerInfo
+    //
+    sid: KeyIdentifier,
+    signed_attrs: SignedAttrs,
+    signature: Signature,
+
+    //--- SignedAttributes
+    //
+    message_digest: MessageDigest,
+    signing_time: Option<Time>,
+    binary_signing_time: Option<u64>,
+}
+
+/// # Data Access
+///
+impl SignedMessage {
+    /// Returns a reference to the message’s content type.
+    ///
+    /// This is the eContentType of the message which is also present in
+    /// the content-type signed attribute. It can be used to decide how to
+    /// process the content before validating the message.
+    pub fn content_type(&self) -> &Oid<Bytes> {
+        &self.content_type
+    }
+
+    /// Returns a reference to the raw content of the message.
+    ///
+    /// The content is returned as is, i.e., the bytes of the eContent octet
+    /// string without any further interpretation.
+    pub fn content(&self) -> &Bytes {
+        &self.content
+    }
+
+    /// Returns the key identifier of the signer.
+    ///
+    /// This is the subject key identifier given in the signer info. For
+    /// a validated message, it is the key identifier of the EE certificate.
+    pub fn signer_key_identifier(&self) -> KeyIdentifier {
+        self.sid
+    }
+
+    /// Returns a reference to the EE certificate the message is signed with.
+    pub fn cert(&self) -> &Cert {
+        &self.cert
+    }
+
+    /// Returns the encoding of the EE certificate.
+    ///
+    /// This is DER unless the message itself was decoded from BER, in
+    /// which case the certificate is returned as it was received.
+    pub fn cert_der(&self) -> Bytes {
+        Captured::from_values(Mode::Ber, self.cert.encode_ref()).into_bytes()
+    }
+
+    /// Returns a reference to the CRL included with the message if present.
+    pub fn crl(&self) -> Option<&Crl> {
+        self.crl.as_ref()
+    }
+
+    /// Returns the signing time if present.
+    pub fn signing_time(&self) -> Option<Time> {
+        self.signing_time
+    }
+
+    /// Returns the binary signing time if present.
+    ///
+    /// The value is the number of seconds since the Unix epoch as defined
+    /// in [RFC 6019].
+    ///
+    /// [RFC 6019]: https://tools.ietf.org/html/rfc6019
+    pub fn binary_signing_time(&self) -> Option<u64> {
+        self.binary_signing_time
+    }
+}
+
+/// # Decoding, Validation, and Encoding
+///
+impl SignedMessage {
+    /// Decodes a signed message from the given source.
+    ///
+    /// Because the profile of these messages isn’t very well defined,
+    /// decoding accepts BER and ignores unknown signed attributes.
+    pub fn decode<S: decode::Source>(source: S) -> Result<Self, S::Err> {
+        Mode::Ber.decode(source, Self::take_from)
+    }
+
+    /// Takes a signed message from an encoded constructed value.
+    pub fn take_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| { // ContentInfo
+            oid::SIGNED_DATA.skip_if(cons)?; // contentType
+            cons.take_constructed_if(Tag::CTX_0, |cons| { // content
+                cons.take_sequence(|cons| { // SignedData
+                    cons.skip_u8_if(3)?; // version -- must be 3
+                    let digest_algorithm =
+                        DigestAlgorithm::take_set_from(cons)?;
+                    let (content_type, content) = {
+                        cons.take_sequence(|cons| { // encapContentInfo
+                            Ok((
+                                Oid::take_from(cons)?,
+                                cons.take_constructed_if(
+                                    Tag::CTX_0,
+                                    OctetString::take_from
+                                )?
+                            ))
+                        })?
+                    };
+                    let cert = cons.take_constructed_if( // certificates
+                        Tag::CTX_0,
+                        Cert::take_from
+                    )?;
+                    let crl = cons.take_opt_constructed_if( // crls
+                        Tag::CTX_1,
+                        |cons| Crl::take_from(cons, false)
+                    )?;
+                    let (sid, attrs, signature) = { // signerInfos
+                        cons.take_set(|cons| {
+                            cons.take_sequence(|cons| {
+                                cons.skip_u8_if(3)?;
+                                let sid = cons.take_value_if(
+                                    Tag::CTX_0, |content| {
+                                        KeyIdentifier::from_content(content)
+                                    }
+                                )?;
+                                let alg = DigestAlgorithm::take_from(cons)?;
+                                if alg != digest_algorithm {
+                                    xerr!(
+                                        return Err(decode::Malformed.into())
+                                    )
+                                }
+                                let attrs =
+                                    SignedAttrs::take_from_signed_message(
+                                        cons
+                                    )?;
+                                if attrs.2 != content_type {
+                                    xerr!(
+                                        return Err(decode::Malformed.into())
+                                    )
+                                }
+                                let signature = Signature::new(
+                                    SignatureAlgorithm::cms_take_from(cons)?,
+                                    OctetString::take_from(cons)?.into_bytes()
+                                );
+                                // no unsignedAttributes
+                                Ok((sid, attrs, signature))
+                            })
+                        })?
+                    };
+                    Ok(Self {
+                        digest_algorithm,
+                        content_type,
+                        content: content.into_bytes(),
+                        cert,
+                        crl,
+                        sid,
+                        signed_attrs: attrs.0,
+                        signature,
+                        message_digest: attrs.1,
+                        signing_time: attrs.3,
+                        binary_signing_time: attrs.4,
+                    })
+                })
+            })
+        })
+    }
+
+    /// Validates the signed message against the issuer of its certificate.
+    ///
+    /// The issuer is the identity certificate of the sender of the message.
+    /// The signing times of the message are checked against the default
+    /// [`SigningTimeWindow`].
+    ///
+    /// [`SigningTimeWindow`]: struct.SigningTimeWindow.html
+    pub fn validate(
+        &self,
+        issuer: &Cert
+    ) -> Result<Option<SignedMessageWarning>, SignedMessageValidationError> {
+        self.validate_at(issuer, Time::now())
+    }
+
+    /// Validates the signed message at the given time.
+    ///
+    /// This is the same as [`validate`] but uses `now` as the current time.
+    ///
+    /// [`validate`]: #method.validate
+    pub fn validate_at(
+        &self,
+        issuer: &Cert,
+        now: Time
+    ) -> Result<Option<SignedMessageWarning>, SignedMessageValidationError> {
+        self.validate_with_window_at(
+            issuer, SigningTimeWindow::default(), now
+        )
+    }
+
+    /// Validates the signed message and requires it to include a CRL.
+    ///
+    /// This is the same as [`validate`] but fails if the message doesn’t
+    /// include a CRL. As with [`validate`], the EE certificate must not be
+    /// listed on the CRL.
+    ///
+    /// [`validate`]: #method.validate
+    pub fn validate_with_crl(
+        &self,
+        issuer: &Cert
+    ) -> Result<Option<SignedMessageWarning>, SignedMessageValidationError> {
+        self.validate_with_crl_at(issuer, Time::now())
+    }
+
+    /// Validates the signed message requiring a CRL at the given time.
+    pub fn validate_with_crl_at(
+        &self,
+        issuer: &Cert,
+        now: Time
+    ) -> Result<Option<SignedMessageWarning>, SignedMessageValidationError> {
+        if self.crl.is_none() {
+            return Err(ValidationError.into())
+        }
+        self.validate_at(issuer, now)
+    }
+
+    /// Validates the signed message with the given signing time window.
+    pub fn validate_with_window(
+        &self,
+        issuer: &Cert,
+        window: SigningTimeWindow
+    ) -> Result<Option<SignedMessageWarning>, SignedMessageValidationError> {
+        self.validate_with_window_at(issuer, window, Time::now())
+    }
+
+    /// Validates the signed message with a signing time window at a time.
+    ///
+    /// Checks that the EE certificate was issued by `issuer`, is valid at
+    /// `now`, and hasn’t been revoked by the included CRL if there is one.
+    /// The CRL itself must have been signed by the issuer. The signature of
+    /// the message is verified against the EE certificate.
+    ///
+    /// Finally, both the signing time and the binary signing time, if
+    /// present, must fall within `window` around `now`. A message without
+    /// either attribute is accepted but the `NoSigningTime` variant of
+    /// [`SignedMessageWarning`] is returned.
+    ///
+    /// [`SignedMessageWarning`]: enum.SignedMessageWarning.html
+    pub fn validate_with_window_at(
+        &self,
+        issuer: &Cert,
+        window: SigningTimeWindow,
+        now: Time
+    ) -> Result<Option<SignedMessageWarning>, SignedMessageValidationError> {
+        if self.sid != self.cert.subject_key_identifier() {
+            return Err(ValidationError.into())
+        }
+        self.cert.verify_issued_by(issuer)?;
+        self.cert.validity().validate_at(now)?;
+        if let Some(ref crl) = self.crl {
+            crl.validate(issuer.subject_public_key_info())?;
+            if crl.contains(self.cert.serial_number()) {
+                return Err(ValidationError.into())
+            }
+        }
+        self.verify_signature()?;
+
+        if let Some(time) = self.signing_time {
+            if !window.contains(time, now) {
+                return Err(SignedMessageValidationError::SigningTime)
+            }
+        }
+        if let Some(secs) = self.binary_signing_time {
+            match binary_time(secs) {
+                Some(time) if window.contains(time, now) => { }
+                _ => return Err(SignedMessageValidationError::SigningTime)
+            }
+        }
+        if self.signing_time.is_none() && self.binary_signing_time.is_none() {
+            Ok(Some(SignedMessageWarning::NoSigningTime))
+        }
+        else {
+            Ok(None)
+        }
+    }
+
+    /// Verifies the signature of the message against the EE certificate.
+    fn verify_signature(&self) -> Result<(), ValidationError> {
+        let digest = self.digest_algorithm.digest(&self.content);
+        if digest.as_ref() != self.message_digest.as_ref() {
+            return Err(ValidationError)
+        }
+        let msg = self.signed_attrs.encode_verify();
+        self.cert.subject_public_key_info().verify(
+            &msg,
+            &self.signature
+        ).map_err(Into::into)
+    }
+
+    /// Returns a value encoder for a reference to a signed message.
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        encode::sequence((
+            oid::SIGNED_DATA.encode(), // contentType
+            encode::sequence_as(Tag::CTX_0, // content
+                encode::sequence((
+                    3u8.encode(), // version
+                    self.digest_algorithm.encode_set(), // digestAlgorithms
+                    encode::sequence(( // encapContentInfo
+                        self.content_type.encode_ref(),
+                        encode::sequence_as(Tag::CTX_0,
+                            OctetString::encode_slice(self.content.as_ref())
+                        ),
+                    )),
+                    encode::sequence_as(Tag::CTX_0, // certificates
+                        self.cert.encode_ref(),
+                    ),
+                    self.crl.as_ref().map(|crl| { // crls
+                        encode::sequence_as(Tag::CTX_1, crl.encode_ref())
+                    }),
+                    encode::set( // signerInfo
+                        encode::sequence(( // SignerInfo
+                            3u8.encode(), // version
+                            self.sid.encode_ref_as(Tag::CTX_0),
+                            self.digest_algorithm.encode(), // digestAlgorithm
+                            self.signed_attrs.encode_ref(), // signedAttrs
+                            self.signature.algorithm().cms_encode(),
+                                                        // signatureAlgorithm
+                            OctetString::encode_slice( // signature
+                                self.signature.value().as_ref()
+                            ),
+                            // unsignedAttrs omitted
+                        ))
+                    )
+                ))
+            )
+        ))
+    }
+
+    /// Returns an encoding of the signed message.
+    ///
+    /// A message created by [`SignedMessageBuilder`] or decoded from DER
+    /// is encoded in DER. Otherwise, the parts captured while decoding
+    /// are kept as they were.
+    ///
+    /// [`SignedMessageBuilder`]: struct.SignedMessageBuilder.html
+    pub fn to_captured(&self) -> Captured {
+        Captured::from_values(Mode::Ber, self.encode_ref())
+    }
+}
+
+
+/// # Protocol Messages
+///
+/// Both protocols wrap their XML messages in the same way. The functions
+/// in this section perform all the steps between the XML and the DER
+/// encoded signed message and are used by the protocol specific types.
+///
+impl SignedMessage {
+    /// Wraps XML content into a DER encoded signed message.
+    ///
+    /// The EE certificate of the signed message is issued by `issuer_key`
+    /// which should be the key of the sender’s identity certificate. It
+    /// will have a random serial number and the given validity.
+    pub fn sign_xml<S: Signer + ?Sized>(
+        xml: Bytes,
+        signer: &S,
+        issuer_key: &S::KeyId,
+        ee_validity: Validity,
+    ) -> Result<Bytes, SigningError<S::Error>> {
+        let msg = SignedMessageBuilder::new(
+            Serial::random(signer)?, ee_validity
+        ).finalize(
+            Oid(oid::PROTOCOL_CONTENT_TYPE.0.into()), xml, signer, issuer_key
+        )?;
+        Ok(msg.to_captured().into_bytes())
+    }
+
+    /// Decodes and validates a signed message and returns its XML content.
+    ///
+    /// The signed message must have been issued by the identity certificate
+    /// of the sender, `sender`, and be valid at `now`. Warnings produced by
+    /// validating the signed message are dropped.
+    pub fn validate_xml_at(
+        der: Bytes,
+        sender: &Cert,
+        now: Time,
+    ) -> Result<Bytes, SignedXmlError> {
+        let msg = SignedMessage::decode(der).map_err(|_| {
+            SignedXmlError::Decode
+        })?;
+        if msg.content_type() != &oid::PROTOCOL_CONTENT_TYPE {
+            return Err(SignedXmlError::ContentType)
+        }
+        if msg.cert().verify_issued_by(sender).is_err() {
+            return Err(SignedXmlError::WrongSigner)
+        }
+        msg.validate_at(sender, now).map_err(SignedXmlError::Invalid)?;
+        Ok(msg.content().clone())
+    }
+}
+
+
+//------------ SignedMessageBuilder ------------------------------------------
+
+/// A builder for signed messages.
+///
+/// The builder signs the content with a one-off key and issues an EE
+/// certificate for this key under the sender’s identity key.
+#[derive(Clone, Debug)]
+pub struct SignedMessageBuilder {
+    /// The digest algorithm to be used for the message digest attribute.
+    ///
+    /// By default, this will be the default algorithm.
+    digest_algorithm: DigestAlgorithm,
+
+    /// The serial number of the EE certificate.
+    ///
+    /// Must be provided.
+    serial_number: Serial,
+
+    /// The validity of the EE certificate.
+    ///
+    /// Must be provided.
+    validity: Validity,
+
+    /// The signing time attribute of the signed message.
+    ///
+    /// By default, this is the time the builder was created. If this is
+    /// `None`, the attribute is omitted.
+    signing_time: Option<Time>,
+
+    /// The binary signing time attribute of the signed message.
+    ///
+    /// By default, this is `None` and the attribute is omitted.
+    binary_signing_time: Option<u64>,
+
+    /// The CRL to include in the signed message.
+    ///
+    /// By default, no CRL is included.
+    crl: Option<Crl>,
+}
+
+impl SignedMessageBuilder {
+    pub fn new(serial_number: Serial, validity: Validity) -> Self {
+        SignedMessageBuilder {
+            digest_algorithm: DigestAlgorithm::default(),
+            serial_number,
+            validity,
+            signing_time: Some(Time::now()),
+            binary_signing_time: None,
+            crl: None,
+        }
+    }
+
+    pub fn digest_algorithm(&self) -> DigestAlgorithm {
+        self.digest_algorithm
+    }
+
+    pub fn set_digest_algorithm(&mut self, algorithm: DigestAlgorithm) {
+        self.digest_algorithm = algorithm
+    }
+
+    pub fn serial_number(&self) -> Serial {
+        self.serial_number
+    }
+
+    pub fn set_serial_number(&mut self, serial: Serial) {
+        self.serial_number = serial
+    }
+
+    pub fn validity(&self) -> Validity {
+        self.validity
+    }
+
+    pub fn set_validity(&mut self, validity: Validity) {
+        self.validity = validity
+    }
+
+    pub fn signing_time(&self) -> Option<Time> {
+        self.signing_time
+    }
+
+    /// Sets the signing time attribute.
+    ///
+    /// If set to `None`, the attribute is omitted.
+    pub fn set_signing_time(&mut self, signing_time: Option<Time>) {
+        self.signing_time = signing_time
+    }
+
+    pub fn binary_signing_time(&self) -> Option<u64> {
+        self.binary_signing_time
+    }
+
+    /// Sets the binary signing time attribute.
+    ///
+    /// If set to `None`, the attribute is omitted.
+    pub fn set_binary_signing_time(&mut self, time: Option<u64>) {
+        self.binary_signing_time = time
+    }
+
+    pub fn crl(&self) -> Option<&Crl> {
+        self.crl.as_ref()
+    }
+
+    pub fn set_crl(&mut self, crl: Option<Crl>) {
+        self.crl = crl
+    }
+
+    /// Creates the signed message.
+    ///
+    /// The EE certificate is issued by `issuer_key` which should be the key
+    /// of the sender’s identity certificate.
+    pub fn finalize<S: Signer + ?Sized>(
+        self,
+        content_type: Oid<Bytes>,
+        content: Bytes,
+        signer: &S,
+        issuer_key: &S::KeyId,
+    ) -> Result<SignedMessage, SigningError<S::Error>> {
+        let issuer_pub = signer.get_key_info(issuer_key)?;
+
+        // Produce signed attributes.
+        let message_digest = self.digest_algorithm.digest(&content).into();
+        let signed_attrs = SignedAttrs::new(
+            &content_type,
+            &message_digest,
+            self.signing_time,
+            self.binary_signing_time
+        );
+
+        // Sign signed attributes with a one-off key.
+        let (signature, key_info) = signer.sign_one_off(
+            SignatureAlgorithm::default(), &signed_attrs.encode_verify()
+        )?;
+        let sid = KeyIdentifier::from_public_key(&key_info);
+
+        // Make the certificate.
+        let mut cert = TbsCert::new(
+            self.serial_number,
+            issuer_pub.to_subject_name(),
+            self.validity,
+            None,
+            key_info,
+            KeyUsage::Ee,
+            Overclaim::Refuse,
+        );
+        cert.set_authority_key_identifier(Some(issuer_pub.key_identifier()));
+        cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+        cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let cert = cert.into_cert(signer, issuer_key)?;
+
+        Ok(SignedMessage {
+            digest_algorithm: self.digest_algorithm,
+            content_type,
+            content,
+            cert,
+            crl: self.crl,
+            sid,
+            signed_attrs,
+            signature,
+            message_digest,
+            signing_time: self.signing_time,
+            binary_signing_time: self.binary_signing_time,
+        })
+    }
+}
+
+
+//------------ SigningTimeWindow ---------------------------------------------
+
+/// The window of acceptable signing times for a signed message.
+///
+/// A signing time is acceptable if it is no more than the maximum clock
+/// skew after the validation time and no more than the maximum age plus the
+/// maximum clock skew before it. Rejecting messages outside this window
+/// limits the possibility of replaying old messages.
+///
+/// The default window allows for five minutes of clock skew and a maximum
+/// age of ten minutes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SigningTimeWindow {
+    max_skew: Duration,
+    max_age: Duration,
+}
+
+impl SigningTimeWindow {
+    pub fn new(max_skew: Duration, max_age: Duration) -> Self {
+        SigningTimeWindow { max_skew, max_age }
+    }
+
+    pub fn max_skew(self) -> Duration {
+        self.max_skew
+    }
+
+    pub fn max_age(self) -> Duration {
+        self.max_age
+    }
+
+    /// Returns whether `time` is within the window around `now`.
+    pub fn contains(self, time: Time, now: Time) -> bool {
+        time <= now + self.max_skew
+            && time >= now - self.max_age - self.max_skew
+    }
+}
+
+impl Default for SigningTimeWindow {
+    fn default() -> Self {
+        SigningTimeWindow::new(Duration::minutes(5), Duration::minutes(10))
+    }
+}
+
+
+//------------ SignedMessageWarning ------------------------------------------
+
+/// A problem with a signed message that was tolerated during validation.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SignedMessageWarning {
+    /// The message has neither a signing time nor a binary signing time.
+    NoSigningTime,
+}
+
+impl fmt::Display for SignedMessageWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SignedMessageWarning::NoSigningTime => {
+                f.write_str("signed message has no signing time")
+            }
+        }
+    }
+}
+
+
+//------------ SignedMessageValidationError ----------------------------------
+
+/// An error happened while validating a signed message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignedMessageValidationError {
+    /// The certificate, CRL, or signature of the message is invalid.
+    Invalid(ValidationError),
+
+    /// A signing time of the message is outside the acceptable window.
+    SigningTime,
+}
+
+
+//--- From
+
+impl From<ValidationError> for SignedMessageValidationError {
+    fn from(err: ValidationError) -> Self {
+        SignedMessageValidationError::Invalid(err)
+    }
+}
+
+impl From<SignedMessageValidationError> for ValidationError {
+    fn from(_: SignedMessageValidationError) -> Self {
+        ValidationError
+    }
+}
+
+
+//--- Display and Error
+
+impl fmt::Display for SignedMessageValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SignedMessageValidationError::Invalid(ref err) => err.fmt(f),
+            SignedMessageValidationError::SigningTime => {
+                f.write_str("signing time outside of acceptable window")
+            }
+        }
+    }
+}
+
+impl error::Error for SignedMessageValidationError { }
+
+
+//------------ SignedXmlError ------------------------------------------------
+
+/// An error happened while receiving the XML content of a signed message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignedXmlError {
+    /// The signed message could not be decoded.
+    Decode,
+
+    /// The signed message has a content type other than XML.
+    ContentType,
+
+    /// The signed message wasn’t signed under the sender’s identity.
+    WrongSigner,
+
+    /// The signed message failed validation.
+    Invalid(SignedMessageValidationError),
+}
+
+impl fmt::Display for SignedXmlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SignedXmlError::Decode => {
+                f.write_str("malformed signed message")
+            }
+            SignedXmlError::ContentType => {
+                f.write_str("signed message doesn’t contain XML")
+            }
+            SignedXmlError::WrongSigner => {
+                f.write_str("signed message not signed by the sender")
+            }
+            SignedXmlError::Invalid(ref err) => {
+                write!(f, "invalid signed message: {}", err)
+            }
+        }
+    }
+}
+
+impl error::Error for SignedXmlError { }
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Converts a binary signing time into a time value.
+///
+/// Returns `None` if the value is out of the range supported by `Time`.
+fn binary_time(secs: u64) -> Option<Time> {
+    match Utc.timestamp_opt(i64::try_from(secs).ok()?, 0) {
+        LocalResult::Single(time) => Some(Time::new(time)),
+        _ => None
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The fixtures contain test-data/publication-query.xml signed at
+    // 2020-03-01T12:00:00Z by an EE certificate with serial number 12
+    // issued under sigmsg-ta.cer. The CRL in sigmsg-crl.der revokes
+    // serial number 5 only, the one in sigmsg-revoked.der revokes 5 and 12.
+
+    fn ta() -> Cert {
+        Cert::decode(
+            include_bytes!("../test-data/sigmsg-ta.cer").as_ref()
+        ).unwrap()
+    }
+
+    fn now() -> Time {
+        Time::utc(2020, 3, 1, 12, 1, 0)
+    }
+
+    #[test]
+    fn decode_with_crl() {
+        let msg = SignedMessage::decode(
+            include_bytes!("../test-data/sigmsg-crl.der").as_ref()
+        ).unwrap();
+        assert!(msg.content_type() == &oid::PROTOCOL_CONTENT_TYPE);
+        assert_eq!(
+            &msg.content()[..],
+            &include_bytes!("../test-data/publication-query.xml")[..]
+        );
+        assert_eq!(msg.signing_time(), Some(Time::utc(2020, 3, 1, 12, 0, 0)));
+        let crl = msg.crl().unwrap();
+        assert!(crl.contains(5u64.into()));
+        assert!(!crl.contains(msg.cert().serial_number()));
+
+        assert_eq!(msg.validate_at(&ta(), now()), Ok(None));
+        assert_eq!(msg.validate_with_crl_at(&ta(), now()), Ok(None));
+    }
+
+    #[test]
+    fn decode_without_crl() {
+        let msg = SignedMessage::decode(
+            include_bytes!("../test-data/sigmsg-no-crl.der").as_ref()
+        ).unwrap();
+        assert!(msg.crl().is_none());
+        assert_eq!(msg.validate_at(&ta(), now()), Ok(None));
+        assert!(msg.validate_with_crl_at(&ta(), now()).is_err());
+    }
+
+    #[test]
+    fn decode_revoked() {
+        let msg = SignedMessage::decode(
+            include_bytes!("../test-data/sigmsg-revoked.der").as_ref()
+        ).unwrap();
+        assert!(msg.crl().unwrap().contains(msg.cert().serial_number()));
+        assert!(msg.validate_at(&ta(), now()).is_err());
+        assert!(msg.validate_with_crl_at(&ta(), now()).is_err());
+    }
+}
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use crate::crl::{CrlEntry, TbsCertList};
+    use crate::crypto::softsigner::{KeyId, OpenSslSigner};
+    use super::*;
+
+    fn xml_type() -> Oid<Bytes> {
+        Oid(oid::PROTOCOL_CONTENT_TYPE.0.into())
+    }
+
+    /// Returns the current time truncated to full seconds.
+    fn now_secs() -> Time {
+        Time::new(Utc.timestamp(Time::now().timestamp(), 0))
+    }
+
+    fn make_msg(
+        signing_time: Option<Time>,
+        binary_signing_time: Option<u64>,
+    ) -> (Cert, SignedMessage) {
+        let (ta, signer, ta_key) = Cert::generate_test_ta().unwrap();
+        let mut builder = SignedMessageBuilder::new(
+            1u64.into(), Validity::from_secs(3600)
+        );
+        builder.set_signing_time(signing_time);
+        builder.set_binary_signing_time(binary_signing_time);
+        let msg = builder.finalize(
+            xml_type(), Bytes::from_static(b"<msg/>"), &signer, &ta_key
+        ).unwrap();
+        let msg = SignedMessage::decode(
+            msg.to_captured().as_slice()
+        ).unwrap();
+        (ta, msg)
+    }
+
+    fn make_crl(
+        signer: &OpenSslSigner,
+        key: &KeyId,
+        revoked: Vec<CrlEntry>
+    ) -> Crl {
+        let pubkey = signer.get_key_info(key).unwrap();
+        TbsCertList::new(
+            Default::default(),
+            pubkey.to_subject_name(),
+            Time::now(),
+            Time::tomorrow(),
+            revoked,
+            KeyIdentifier::from_public_key(&pubkey),
+            1u64.into()
+        ).into_crl(signer, key).unwrap()
+    }
+
+    #[test]
+    fn sign_and_validate() {
+        let (ta, signer, ta_key) = Cert::generate_test_ta().unwrap();
+        let content = Bytes::from_static(b"<msg/>");
+        let signing_time = now_secs();
+
+        let mut builder = SignedMessageBuilder::new(
+            12u64.into(), Validity::from_secs(3600)
+        );
+        builder.set_signing_time(Some(signing_time));
+        builder.set_crl(Some(make_crl(&signer, &ta_key, Vec::new())));
+        let msg = builder.finalize(
+            xml_type(), content.clone(), &signer, &ta_key
+        ).unwrap();
+        let der = msg.to_captured();
+
+        let msg = SignedMessage::decode(der.as_slice()).unwrap();
+        assert_eq!(msg.validate(&ta), Ok(None));
+        assert_eq!(msg.validate_with_crl(&ta), Ok(None));
+        assert!(msg.content_type() == &oid::PROTOCOL_CONTENT_TYPE);
+        assert_eq!(msg.content(), &content);
+        assert_eq!(msg.signing_time(), Some(signing_time));
+        assert!(msg.crl().is_some());
+        assert_eq!(msg.cert().serial_number(), Serial::from(12u64));
+        assert!(!msg.cert().v4_resources().unwrap().is_inherited());
+        assert!(!msg.cert().v6_resources().unwrap().is_inherited());
+        assert!(!msg.cert().as_resources().unwrap().is_inherited());
+
+        // The encoding is DER and stable.
+        assert_eq!(der.as_slice(), msg.to_captured().as_slice());
+        crate::x509::verify_canonical(der.as_slice()).unwrap();
+
+        // Not valid under a different identity.
+        let (other, _, _) = Cert::generate_test_ta().unwrap();
+        assert!(msg.validate(&other).is_err());
+
+        // Not valid once the EE certificate has expired.
+        assert!(
+            msg.validate_at(&ta, Time::now() + chrono::Duration::days(1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn sign_without_crl() {
+        let (ta, signer, ta_key) = Cert::generate_test_ta().unwrap();
+        let msg = SignedMessageBuilder::new(
+            1u64.into(), Validity::from_secs(3600)
+        ).finalize(
+            xml_type(), Bytes::from_static(b"<msg/>"), &signer, &ta_key
+        ).unwrap();
+        let msg = SignedMessage::decode(
+            msg.to_captured().as_slice()
+        ).unwrap();
+        assert!(msg.crl().is_none());
+        assert!(msg.signing_time().is_some());
+        msg.validate(&ta).unwrap();
+        assert!(msg.validate_with_crl(&ta).is_err());
+    }
+
+    #[test]
+    fn revoked_cert() {
+        let (ta, signer, ta_key) = Cert::generate_test_ta().unwrap();
+        let mut builder = SignedMessageBuilder::new(
+            12u64.into(), Validity::from_secs(3600)
+        );
+        builder.set_crl(Some(make_crl(
+            &signer, &ta_key, vec![CrlEntry::new(12u64.into(), Time::now())]
+        )));
+        let msg = builder.finalize(
+            xml_type(), Bytes::from_static(b"<msg/>"), &signer, &ta_key
+        ).unwrap();
+        let msg = SignedMessage::decode(
+            msg.to_captured().as_slice()
+        ).unwrap();
+        assert!(msg.validate(&ta).is_err());
+    }
+
+    #[test]
+    fn old_signing_time() {
+        let (ta, msg) = make_msg(
+            Some(now_secs() - Duration::hours(1)), None
+        );
+        assert_eq!(
+            msg.validate(&ta),
+            Err(SignedMessageValidationError::SigningTime)
+        );
+        assert_eq!(
+            msg.validate_with_window(
+                &ta,
+                SigningTimeWindow::new(
+                    Duration::minutes(5), Duration::hours(2)
+                )
+            ),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn future_signing_time() {
+        let (ta, msg) = make_msg(
+            Some(now_secs() + Duration::minutes(2)), None
+        );
+        assert_eq!(msg.validate(&ta), Ok(None));
+
+        let (ta, msg) = make_msg(
+            Some(now_secs() + Duration::minutes(10)), None
+        );
+        assert_eq!(
+            msg.validate(&ta),
+            Err(SignedMessageValidationError::SigningTime)
+        );
+    }
+
+    #[test]
+    fn binary_signing_time() {
+        let now = Time::now().to_binary_time() as u64;
+        let (ta, msg) = make_msg(None, Some(now));
+        assert_eq!(msg.signing_time(), None);
+        assert_eq!(msg.binary_signing_time(), Some(now));
+        assert_eq!(msg.validate(&ta), Ok(None));
+
+        let (ta, msg) = make_msg(None, Some(now - 7200));
+        assert_eq!(
+            msg.validate(&ta),
+            Err(SignedMessageValidationError::SigningTime)
+        );
+
+        // Both times present, only one of them is acceptable.
+        let (ta, msg) = make_msg(Some(now_secs()), Some(now - 7200));
+        assert_eq!(
+            msg.validate(&ta),
+            Err(SignedMessageValidationError::SigningTime)
+        );
+    }
+
+    #[test]
+    fn no_signing_time() {
+        let (ta, msg) = make_msg(None, None);
+        assert_eq!(
+            msg.validate(&ta),
+            Ok(Some(SignedMessageWarning::NoSigningTime))
+        );
+    }
+
+    #[test]
+    fn dispatch_on_content_type() {
+        use crate::{provisioning, publication};
+
+        fn dispatch(msg: &SignedMessage) -> &'static str {
+            if msg.content_type() != &oid::PROTOCOL_CONTENT_TYPE {
+                "unknown"
+            }
+            else if publication::PublicationQuery::parse(
+                &msg.content()[..]
+            ).is_ok() {
+                "publication"
+            }
+            else if provisioning::Message::parse(
+                &msg.content()[..]
+            ).is_ok() {
+                "provisioning"
+            }
+            else {
+                "unknown"
+            }
+        }
+
+        let (_, signer, ta_key) = Cert::generate_test_ta().unwrap();
+        let sign = |content_type: Oid<Bytes>, content: &'static [u8]| {
+            let msg = SignedMessageBuilder::new(
+                1u64.into(), Validity::from_secs(3600)
+            ).finalize(
+                content_type, Bytes::from_static(content), &signer, &ta_key
+            ).unwrap();
+            SignedMessage::decode(msg.to_captured().as_slice()).unwrap()
+        };
+
+        let xml = include_bytes!("../test-data/publication-query.xml");
+        let msg = sign(xml_type(), xml);
+        assert_eq!(&msg.content()[..], &xml[..]);
+        assert_eq!(dispatch(&msg), "publication");
+
+        let xml = include_bytes!("../test-data/provisioning-issue.xml");
+        let msg = sign(xml_type(), xml);
+        assert_eq!(&msg.content()[..], &xml[..]);
+        assert_eq!(dispatch(&msg), "provisioning");
+
+        let msg = sign(Oid(oid::CT_RPKI_MANIFEST.0.into()), xml);
+        assert_eq!(dispatch(&msg), "unknown");
+
+        // The signer and its certificate are available without validation.
+        assert_eq!(
+            msg.signer_key_identifier(),
+            msg.cert().subject_key_identifier()
+        );
+        let cert = Cert::decode(msg.cert_der()).unwrap();
+        assert_eq!(
+            cert.subject_key_identifier(), msg.signer_key_identifier()
+        );
+    }
+
+    #[test]
+    fn sign_and_validate_xml() {
+        let (ta, signer, ta_key) = Cert::generate_test_ta().unwrap();
+        let xml = Bytes::from_static(b"<msg/>");
+        let der = SignedMessage::sign_xml(
+            xml.clone(), &signer, &ta_key, Validity::from_secs(3600)
+        ).unwrap();
+        let now = Time::now();
+        assert_eq!(
+            SignedMessage::validate_xml_at(der.clone(), &ta, now), Ok(xml)
+        );
+
+        let (other, _, _) = Cert::generate_test_ta().unwrap();
+        assert_eq!(
+            SignedMessage::validate_xml_at(der.clone(), &other, now),
+            Err(SignedXmlError::WrongSigner)
+        );
+        match SignedMessage::validate_xml_at(
+            der, &ta, now + chrono::Duration::days(1)
+        ) {
+            Err(SignedXmlError::Invalid(_)) => { }
+            res => panic!("unexpected result {:?}", res)
+        }
+        assert_eq!(
+            SignedMessage::validate_xml_at(
+                Bytes::from_static(b"foo"), &ta, now
+            ),
+            Err(SignedXmlError::Decode)
+        );
+    }
+}
@@ -1,16 +1,16 @@
 // Signed objects.
 
-use std::{cmp, io};
+use std::{cmp, error, fmt, io};
 use bcder::{decode, encode};
-use bcder::{Captured, Mode, OctetString, Oid, Tag, xerr};
+use bcder::{Captured, ConstOid, Mode, OctetString, Oid, Tag, xerr};
 use bcder::encode::PrimitiveContent;
 use bcder::string::OctetStringSource;
 use bytes::Bytes;
 use crate::{oid, uri};
 use crate::cert::{Cert, KeyUsage, Overclaim, ResourceCert, TbsCert};
 use crate::crypto::{
-    Digest, DigestAlgorithm, KeyIdentifier, Signature, SignatureAlgorithm,
-    Signer, SigningError
+    AlgorithmPolicy, Digest, DigestAlgorithm, KeyIdentifier, Signature,
+    SignatureAlgorithm, Signer, SigningError
 };
 use crate::resources::{
     AsBlocksBuilder, AsResources, AsResourcesBuilder, IpBlocksBuilder,
@@ -69,6 +69,11 @@ impl SignedObject {
     pub fn cert(&self) -> &Cert {
         &self.cert
     }
+
+    /// Returns the signing time of the object if present.
+    pub fn signing_time(&self) -> Option<Time> {
+        self.signing_time
+    }
 }
 
 /// # Decoding, Validation, and Encoding
@@ -84,17 +89,77 @@ impl SignedObject {
             .decode(source, Self::take_from)
     }
 
+    /// Decodes a signed object with the given content type reporting why.
+    ///
+    /// This is the same as [`decode_if_type`] but returns a
+    /// [`SignedObjectError`] that explains which part of the profile
+    /// defined in [RFC 6488] the object violates if decoding fails.
+    ///
+    /// [`decode_if_type`]: #method.decode_if_type
+    /// [`SignedObjectError`]: enum.SignedObjectError.html
+    /// [RFC 6488]: https://tools.ietf.org/html/rfc6488
+    pub fn decode_detailed<S: decode::Source>(
+        source: S,
+        content_type: &ConstOid,
+        strict: bool
+    ) -> Result<Self, SignedObjectError> {
+        let mut err = None;
+        let res = if strict { Mode::Der } else { Mode::Ber }.decode(
+            source, |cons| Self::take_from_detailed(cons, &mut err)
+        );
+        let res = match res {
+            Ok(res) => res,
+            Err(_) => return Err(err.unwrap_or(SignedObjectError::Malformed))
+        };
+        if res.content_type().ne(content_type) {
+            return Err(SignedObjectError::WrongContentType {
+                expected: Oid(Bytes::from_static(content_type.0)),
+                found: res.content_type,
+            })
+        }
+        Ok(res)
+    }
+
+    /// Decodes a signed object with the given content type.
+    ///
+    /// This is the same as [`decode`] but additionally checks that both the
+    /// eContentType and the content-type signed attribute, which have to be
+    /// equal, are `content_type`. Decoding of specific types of signed
+    /// objects should use this method and only deal with the content.
+    ///
+    /// [`decode`]: #method.decode
+    pub fn decode_if_type<S: decode::Source>(
+        source: S,
+        content_type: &ConstOid,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        let res = Self::decode(source, strict)?;
+        if res.content_type().ne(content_type) {
+            xerr!(return Err(decode::Malformed.into()))
+        }
+        Ok(res)
+    }
+
     /// Takes a signed object from an encoded constructed value.
     pub fn take_from<S: decode::Source>(
         cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        Self::take_from_detailed(cons, &mut None)
+    }
+
+    /// Takes a signed object noting the reason for failing in `err`.
+    fn take_from_detailed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        err: &mut Option<SignedObjectError>
     ) -> Result<Self, S::Err> {
         cons.take_sequence(|cons| { // ContentInfo
             oid::SIGNED_DATA.skip_if(cons)?; // contentType
             cons.take_constructed_if(Tag::CTX_0, |cons| { // content
                 cons.take_sequence(|cons| { // SignedData
                     cons.skip_u8_if(3)?; // version -- must be 3
-                    let digest_algorithm =
-                        DigestAlgorithm::take_set_from(cons)?;
+                    let digest_algorithm = cons.take_set(|cons| {
+                        take_digest_algorithm(cons, err)
+                    })?;
                     let (content_type, content) = {
                         cons.take_sequence(|cons| { // encapContentInfo
                             Ok((
@@ -108,24 +173,45 @@ impl SignedObject {
                     };
                     let cert = cons.take_constructed_if( // certificates
                         Tag::CTX_0,
-                        Cert::take_from
+                        |cons| {
+                            let cert = Cert::take_from(cons)?;
+                            if skip_opt_sequence(cons)? {
+                                *err = Some(
+                                    SignedObjectError::MultipleCertificates
+                                );
+                                xerr!(return Err(decode::Malformed.into()))
+                            }
+                            Ok(cert)
+                        }
                     )?;
                     // no crls
+                    if cons.take_opt_constructed_if(
+                        Tag::CTX_1, |cons| cons.skip_all()
+                    )?.is_some() {
+                        *err = Some(SignedObjectError::CrlPresent);
+                        xerr!(return Err(decode::Malformed.into()))
+                    }
                     let (sid, attrs, signature) = { // signerInfos
                         cons.take_set(|cons| {
-                            cons.take_sequence(|cons| {
+                            let res = cons.take_sequence(|cons| {
                                 cons.skip_u8_if(3)?;
                                 let sid = cons.take_value_if(
                                     Tag::CTX_0, |content| {
                                         KeyIdentifier::from_content(content)
                                     }
                                 )?;
-                                let alg = DigestAlgorithm::take_from(cons)?;
+                                let alg = take_digest_algorithm(cons, err)?;
                                 if alg != digest_algorithm {
                                     return Err(decode::Malformed.into())
                                 }
-                                let attrs = SignedAttrs::take_from(cons)?;
+                                let attrs = SignedAttrs::take_from_detailed(
+                                    cons, err
+                                )?;
                                 if attrs.2 != content_type {
+                                    *err = Some(
+                                        SignedObjectError::
+                                            ContentTypeAttrMismatch
+                                    );
                                     return Err(decode::Malformed.into())
                                 }
                                 let signature = Signature::new(
@@ -134,7 +220,14 @@ impl SignedObject {
                                 );
                                 // no unsignedAttributes
                                 Ok((sid, attrs, signature))
-                            })
+                            })?;
+                            if skip_opt_sequence(cons)? {
+                                *err = Some(
+                                    SignedObjectError::MultipleSignerInfos
+                                );
+                                xerr!(return Err(decode::Malformed.into()))
+                            }
+                            Ok(res)
                         })?
                     };
                     Ok(Self {
@@ -157,12 +250,12 @@ impl SignedObject {
     /// Validates the signed object.
     ///
     /// Upon success, the method returns the validated EE certificate of the
-    /// object.
+    /// object. Upon failure, the error explains which check failed.
     pub fn validate(
         self,
         issuer: &ResourceCert,
         strict: bool,
-    ) -> Result<ResourceCert, ValidationError> {
+    ) -> Result<ResourceCert, SignedObjectError> {
         self.validate_at(issuer, strict, Time::now())
     }
 
@@ -172,10 +265,80 @@ impl SignedObject {
         issuer: &ResourceCert,
         strict: bool,
         now: Time,
-    ) -> Result<ResourceCert, ValidationError> {
+    ) -> Result<ResourceCert, SignedObjectError> {
+        self.validate_with_scratch(issuer, strict, now, &mut Vec::new())
+    }
+
+    /// Validates the signed object reusing a scratch buffer.
+    ///
+    /// Verifying the signature requires re-encoding the signed attributes.
+    /// This method uses `scratch` for that instead of allocating a new
+    /// buffer. When validating many objects, passing the same buffer each
+    /// time avoids this allocation once the buffer has grown large enough.
+    /// Any content of `scratch` is discarded.
+    pub fn validate_with_scratch(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        scratch: &mut Vec<u8>,
+    ) -> Result<ResourceCert, SignedObjectError> {
+        self.validate_checks(
+            issuer, strict, now, &AlgorithmPolicy::default(), scratch
+        )
+    }
+
+    /// Validates the signed object under a local algorithm policy.
+    ///
+    /// This is the same as [`validate_at`] but the signature algorithms of
+    /// both the SignerInfo and the EE certificate must be permitted by
+    /// `policy` rather than be the one mandated for RPKI. A SignerInfo
+    /// algorithm not permitted is reported as [`AlgorithmNotPermitted`].
+    ///
+    /// [`validate_at`]: #method.validate_at
+    /// [`AlgorithmNotPermitted`]: enum.SignedObjectError.html
+    pub fn validate_with_policy(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+    ) -> Result<ResourceCert, SignedObjectError> {
+        self.validate_checks(issuer, strict, now, policy, &mut Vec::new())
+    }
+
+    /// Performs all validation checks.
+    fn validate_checks(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        policy: &AlgorithmPolicy,
+        scratch: &mut Vec<u8>,
+    ) -> Result<ResourceCert, SignedObjectError> {
         self.verify_compliance(strict)?;
-        self.verify_signature(strict)?;
-        self.cert.validate_ee_at(issuer, strict, now)
+        self.verify_signature(strict, policy, scratch)?;
+        self.cert.validate_ee_with_policy(
+            issuer, strict, now, policy
+        ).map_err(SignedObjectError::Cert)
+    }
+
+    /// Checks that the signing time is within the EE certificate’s validity.
+    ///
+    /// The signing-time signed attribute is optional in RPKI signed objects,
+    /// so the check succeeds if the attribute is missing. It isn’t
+    /// required by [RFC 6488] and thus not part of [`validate`]. Relying
+    /// parties can use it as an additional plausibility check.
+    ///
+    /// [`validate`]: #method.validate
+    pub fn verify_signing_time(&self) -> Result<(), SignedObjectError> {
+        if let Some(time) = self.signing_time {
+            let validity = self.cert.validity();
+            if time < validity.not_before() || time > validity.not_after() {
+                return Err(SignedObjectError::SigningTimeOutsideValidity)
+            }
+        }
+        Ok(())
     }
 
     /// Validates that the signed object complies with the specification.
@@ -184,14 +347,14 @@ impl SignedObject {
     fn verify_compliance(
         &self,
         _strict: bool
-    ) -> Result<(), ValidationError> {
+    ) -> Result<(), SignedObjectError> {
         // Sub-items a, b, d, e, f, g, h, i, j, k, l have been validated while
         // parsing. This leaves these:
         //
         // c. cert is an EE cert with the SubjectKeyIdentifer matching
         //    the sid field of the SignerInfo.
         if self.sid != self.cert.subject_key_identifier() {
-            return Err(ValidationError)
+            return Err(SignedObjectError::SignerKeyMismatch)
         }
         Ok(())
     }
@@ -199,20 +362,30 @@ impl SignedObject {
     /// Verifies the signature of the object against contained certificate.
     ///
     /// This is item 2 of [RFC 6488]’s section 3.
-    fn verify_signature(&self, _strict: bool) -> Result<(), ValidationError> {
+    fn verify_signature(
+        &self,
+        _strict: bool,
+        policy: &AlgorithmPolicy,
+        scratch: &mut Vec<u8>,
+    ) -> Result<(), SignedObjectError> {
+        if !policy.permits(self.signature.algorithm()) {
+            return Err(SignedObjectError::AlgorithmNotPermitted(
+                self.signature.algorithm()
+            ))
+        }
         let digest = {
             let mut context = self.digest_algorithm.start();
             self.content.iter().for_each(|x| context.update(x));
             context.finish()
         };
         if digest.as_ref() != self.message_digest.as_ref() {
-            return Err(ValidationError)
+            return Err(SignedObjectError::MessageDigestMismatch)
         }
-        let msg = self.signed_attrs.encode_verify();
+        self.signed_attrs.encode_verify_into(scratch);
         self.cert.subject_public_key_info().verify(
-            &msg,
+            scratch,
             &self.signature
-        ).map_err(Into::into)
+        ).map_err(|_| SignedObjectError::InvalidSignature)
     }
 
     /// Returns a value encoder for a reference to a signed object.
@@ -254,6 +427,79 @@ impl SignedObject {
 }
 
 
+//------------ AsSignedObject ------------------------------------------------
+
+/// A type of RPKI object that is based on a signed object.
+///
+/// Manifests, ROAs, and Ghostbusters records all wrap a [`SignedObject`]
+/// with an EE certificate and a CMS signature. This trait provides access
+/// to these common parts, allowing generic code to check any of these
+/// objects in the same way.
+///
+/// [`SignedObject`]: struct.SignedObject.html
+pub trait AsSignedObject {
+    /// Returns a reference to the underlying signed object.
+    fn signed_object(&self) -> &SignedObject;
+
+    /// Returns a reference to the EE certificate of the object.
+    fn ee_cert(&self) -> &Cert {
+        self.signed_object().cert()
+    }
+
+    /// Returns the signing time of the object if present.
+    fn signing_time(&self) -> Option<Time> {
+        self.signed_object().signing_time()
+    }
+
+    /// Returns a reference to the content type of the object.
+    fn content_type(&self) -> &Oid<Bytes> {
+        self.signed_object().content_type()
+    }
+
+    /// Verifies the signed object against the issuing CA certificate.
+    ///
+    /// This performs the checks of [`SignedObject::validate`] and returns
+    /// the validated EE certificate. Any checks specific to the content
+    /// of the object, such as whether the EE certificate covers the
+    /// prefixes of a ROA, are not performed. The object itself is left
+    /// untouched.
+    ///
+    /// [`SignedObject::validate`]: struct.SignedObject.html#method.validate
+    fn verify(
+        &self,
+        issuer: &ResourceCert,
+        strict: bool
+    ) -> Result<ResourceCert, SignedObjectError> {
+        self.verify_at(issuer, strict, Time::now())
+    }
+
+    /// Verifies the signed object at the given time.
+    fn verify_at(
+        &self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time
+    ) -> Result<ResourceCert, SignedObjectError> {
+        self.signed_object().clone().validate_at(issuer, strict, now)
+    }
+
+    /// Checks that the signing time is within the EE certificate’s validity.
+    ///
+    /// See [`SignedObject::verify_signing_time`] for details.
+    ///
+    /// [`SignedObject::verify_signing_time`]: struct.SignedObject.html#method.verify_signing_time
+    fn verify_signing_time(&self) -> Result<(), SignedObjectError> {
+        self.signed_object().verify_signing_time()
+    }
+}
+
+impl AsSignedObject for SignedObject {
+    fn signed_object(&self) -> &SignedObject {
+        self
+    }
+}
+
+
 //------------ SignedAttrs ---------------------------------------------------
 
 /// A private helper type that contains the raw signed attributes content.
@@ -279,7 +525,7 @@ impl SignedObject {
 pub struct SignedAttrs(Captured);
 
 impl SignedAttrs {
-    fn new(
+    pub(crate) fn new(
         content_type: &Oid<Bytes>,
         digest: &MessageDigest,
         signing_time: Option<Time>,
@@ -368,7 +614,8 @@ impl SignedAttrs {
     #[allow(clippy::type_complexity)]
     fn take_from_with_mode<S: decode::Source>(
         cons: &mut decode::Constructed<S>,
-        strict: bool
+        strict: bool,
+        err: &mut Option<SignedObjectError>
     ) -> Result<
         (Self, MessageDigest, Oid<Bytes>, Option<Time>, Option<u64>),
         S::Err
@@ -410,11 +657,17 @@ impl SignedAttrs {
         }
         let message_digest = match message_digest {
             Some(some) => MessageDigest(some.into_bytes()),
-            None => return Err(decode::Malformed.into())
+            None => {
+                *err = Some(SignedObjectError::MissingMessageDigestAttr);
+                return Err(decode::Malformed.into())
+            }
         };
         let content_type = match content_type {
             Some(some) => some,
-            None => return Err(decode::Malformed.into())
+            None => {
+                *err = Some(SignedObjectError::MissingContentTypeAttr);
+                return Err(decode::Malformed.into())
+            }
         };
         Ok((
             Self(raw), message_digest, content_type, signing_time,
@@ -435,7 +688,19 @@ impl SignedAttrs {
         (Self, MessageDigest, Oid<Bytes>, Option<Time>, Option<u64>),
         S::Err
     > {
-        Self::take_from_with_mode(cons, true)
+        Self::take_from_with_mode(cons, true, &mut None)
+    }
+
+    /// Takes the signed attributes noting the reason for failing in `err`.
+    #[allow(clippy::type_complexity)]
+    fn take_from_detailed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        err: &mut Option<SignedObjectError>
+    ) -> Result<
+        (Self, MessageDigest, Oid<Bytes>, Option<Time>, Option<u64>),
+        S::Err
+    > {
+        Self::take_from_with_mode(cons, true, err)
     }
 
     /// Takes the signed attributes from the beginning of a constructed value.
@@ -455,7 +720,7 @@ impl SignedAttrs {
         (Self, MessageDigest, Oid<Bytes>, Option<Time>, Option<u64>),
         S::Err
     > {
-        Self::take_from_with_mode(cons, false)
+        Self::take_from_with_mode(cons, false, &mut None)
     }
 
     /// Parses the Content Type attribute.
@@ -504,22 +769,36 @@ impl SignedAttrs {
 
     /// Creates the message for verification.
     pub fn encode_verify(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(self.0.len() + 4);
+        self.encode_verify_into(&mut res);
+        res
+    }
+
+    /// Writes the message for verification into the given buffer.
+    ///
+    /// The buffer is cleared first. It only allocates if the buffer is too
+    /// small for the message.
+    pub fn encode_verify_into(&self, target: &mut Vec<u8>) {
         let len = self.0.len();
-        let mut res = Vec::with_capacity(len + 4);
-        res.push(0x31); // SET
-        if len < 128 {
-            res.push(len as u8)
+        target.clear();
+        target.reserve(len + 4);
+        target.push(0x31); // SET
+        if len < 0x80 {
+            target.push(len as u8)
+        }
+        else if len < 0x100 {
+            target.push(0x81);
+            target.push(len as u8);
         }
         else if len < 0x10000 {
-            res.push(2);
-            res.push((len >> 8) as u8);
-            res.push(len as u8);
+            target.push(0x82);
+            target.push((len >> 8) as u8);
+            target.push(len as u8);
         }
         else {
             panic!("overly long signed attrs");
         }
-        res.extend_from_slice(self.0.as_ref());
-        res
+        target.extend_from_slice(self.0.as_ref());
     }
 }
 
@@ -810,7 +1089,7 @@ impl SignedObjectBuilder {
         self.binary_signing_time = time
     }
 
-    pub fn finalize<S: Signer>(
+    pub fn finalize<S: Signer + ?Sized>(
         self,
         content_type: Oid<Bytes>,
         content: Bytes,
@@ -914,6 +1193,168 @@ impl io::Write for StartOfValue {
 }
 
 
+//------------ SignedObjectError ---------------------------------------------
+
+/// A signed object violates the profile of RFC 6488 or failed validation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SignedObjectError {
+    /// The object isn’t correctly encoded.
+    ///
+    /// This is used for all encoding problems not covered by one of the
+    /// more specific variants.
+    Malformed,
+
+    /// The content of the object isn’t correctly encoded.
+    MalformedContent,
+
+    /// The eContentType isn’t the one expected for the type of object.
+    WrongContentType {
+        expected: Oid<Bytes>,
+        found: Oid<Bytes>,
+    },
+
+    /// The content-type signed attribute differs from the eContentType.
+    ContentTypeAttrMismatch,
+
+    /// The content-type signed attribute is missing.
+    MissingContentTypeAttr,
+
+    /// The message-digest signed attribute is missing.
+    MissingMessageDigestAttr,
+
+    /// A digest algorithm other than SHA-256 is used.
+    WrongDigestAlgorithm(Oid<Bytes>),
+
+    /// The SignedData contains more than one certificate.
+    MultipleCertificates,
+
+    /// The SignedData contains CRLs.
+    CrlPresent,
+
+    /// The SignedData contains more than one SignerInfo.
+    MultipleSignerInfos,
+
+    /// The signer identifier doesn’t match the EE certificate’s key.
+    SignerKeyMismatch,
+
+    /// The message-digest signed attribute doesn’t match the content.
+    MessageDigestMismatch,
+
+    /// The signature doesn’t verify against the EE certificate’s key.
+    InvalidSignature,
+
+    /// The SignerInfo’s signature algorithm isn’t permitted by the policy.
+    AlgorithmNotPermitted(SignatureAlgorithm),
+
+    /// The EE certificate failed validation.
+    Cert(ValidationError),
+
+    /// The signing time lies outside the EE certificate’s validity.
+    SigningTimeOutsideValidity,
+}
+
+
+//--- From
+
+impl From<SignedObjectError> for ValidationError {
+    fn from(_: SignedObjectError) -> Self {
+        ValidationError
+    }
+}
+
+
+//--- Display and Error
+
+impl fmt::Display for SignedObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::SignedObjectError::*;
+
+        match *self {
+            Malformed => f.write_str("malformed signed object"),
+            MalformedContent => {
+                f.write_str("malformed signed object content")
+            }
+            WrongContentType { ref expected, ref found } => {
+                write!(
+                    f, "eContentType is {} but should be {}",
+                    found, expected
+                )
+            }
+            ContentTypeAttrMismatch => {
+                f.write_str(
+                    "content-type signed attribute differs from eContentType"
+                )
+            }
+            MissingContentTypeAttr => {
+                f.write_str("missing content-type signed attribute")
+            }
+            MissingMessageDigestAttr => {
+                f.write_str("missing message-digest signed attribute")
+            }
+            WrongDigestAlgorithm(ref alg) => {
+                write!(f, "digest algorithm {} used instead of SHA-256", alg)
+            }
+            MultipleCertificates => {
+                f.write_str("SignedData contains more than one certificate")
+            }
+            CrlPresent => f.write_str("SignedData contains CRLs"),
+            MultipleSignerInfos => {
+                f.write_str("SignedData contains more than one SignerInfo")
+            }
+            SignerKeyMismatch => {
+                f.write_str(
+                    "SignerInfo sid doesn’t match the subject key identifier \
+                     of the EE certificate"
+                )
+            }
+            MessageDigestMismatch => {
+                f.write_str(
+                    "message-digest signed attribute doesn’t match content"
+                )
+            }
+            InvalidSignature => f.write_str("invalid signature"),
+            AlgorithmNotPermitted(alg) => {
+                write!(f, "signature algorithm {:?} not permitted", alg)
+            }
+            Cert(ref err) => write!(f, "invalid EE certificate: {}", err),
+            SigningTimeOutsideValidity => {
+                f.write_str(
+                    "signing time outside of the EE certificate’s validity"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for SignedObjectError { }
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Takes a digest algorithm identifier noting a wrong algorithm in `err`.
+fn take_digest_algorithm<S: decode::Source>(
+    cons: &mut decode::Constructed<S>,
+    err: &mut Option<SignedObjectError>
+) -> Result<DigestAlgorithm, S::Err> {
+    cons.take_sequence(|cons| {
+        let alg = Oid::take_from(cons)?;
+        if alg != oid::SHA256 {
+            *err = Some(SignedObjectError::WrongDigestAlgorithm(alg));
+            xerr!(return Err(decode::Malformed.into()))
+        }
+        cons.take_opt_null()?;
+        Ok(DigestAlgorithm::default())
+    })
+}
+
+/// Skips over a sequence if there is one and returns whether it did.
+fn skip_opt_sequence<S: decode::Source>(
+    cons: &mut decode::Constructed<S>
+) -> Result<bool, S::Err> {
+    cons.take_opt_sequence(|cons| cons.skip_all()).map(|res| res.is_some())
+}
+
+
 //============ Tests =========================================================
 
 #[cfg(test)]
@@ -940,18 +1381,297 @@ mod test {
         ).unwrap();
         assert!(obj.validate_at(&issuer, false, at).is_err());
     }
+
+    fn check_signed_object<T: AsSignedObject>(
+        obj: &T,
+        issuer: &ResourceCert,
+        content_type: &ConstOid,
+        at: Time
+    ) {
+        assert_eq!(obj.content_type(), content_type);
+        assert_eq!(
+            obj.ee_cert().subject_key_identifier(),
+            obj.signed_object().cert().subject_key_identifier()
+        );
+        let cert = obj.verify_at(issuer, false, at).unwrap();
+        assert_eq!(
+            cert.as_cert().subject_key_identifier(),
+            obj.ee_cert().subject_key_identifier()
+        );
+    }
+
+    #[test]
+    fn as_signed_object() {
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+        let issuer = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let issuer = issuer.validate_ta_at(talinfo, false, at).unwrap();
+        let der = include_bytes!("../test-data/ta.mft");
+
+        let obj = SignedObject::decode(der.as_ref(), false).unwrap();
+        check_signed_object(&obj, &issuer, &oid::CT_RPKI_MANIFEST, at);
+        let mft = crate::manifest::Manifest::decode(
+            der.as_ref(), false
+        ).unwrap();
+        check_signed_object(&mft, &issuer, &oid::CT_RPKI_MANIFEST, at);
+        assert_eq!(mft.signing_time(), obj.signing_time());
+
+        // Verifying doesn’t consume the object, so it can still be
+        // validated in full afterwards.
+        mft.validate_at(&issuer, false, at).unwrap();
+    }
+
+    #[test]
+    fn decode_der() {
+        // ta.mft re-encoded in DER so it can be decoded in strict mode.
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+        let issuer = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let issuer = issuer.validate_ta_at(talinfo, false, at).unwrap();
+        let der = include_bytes!("../test-data/sigobj-der.mft");
+        let obj = SignedObject::decode_if_type(
+            der.as_ref(), &oid::CT_RPKI_MANIFEST, true
+        ).unwrap();
+        obj.validate_at(&issuer, false, at).unwrap();
+        assert!(
+            SignedObject::decode_if_type(
+                der.as_ref(), &oid::ROUTE_ORIGIN_AUTHZ, true
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn decode_structural_violations() {
+        for der in &[
+            include_bytes!("../test-data/sigobj-two-signers.mft").as_ref(),
+            include_bytes!("../test-data/sigobj-two-certs.mft").as_ref(),
+            include_bytes!("../test-data/sigobj-crls.mft").as_ref(),
+            include_bytes!("../test-data/sigobj-sha1.mft").as_ref(),
+            include_bytes!(
+                "../test-data/sigobj-no-content-type.mft"
+            ).as_ref(),
+            include_bytes!(
+                "../test-data/sigobj-no-message-digest.mft"
+            ).as_ref(),
+            include_bytes!(
+                "../test-data/sigobj-type-mismatch.mft"
+            ).as_ref(),
+        ] {
+            assert!(SignedObject::decode(*der, true).is_err());
+            assert!(SignedObject::decode(*der, false).is_err());
+        }
+    }
+
+    #[test]
+    fn decode_wrong_content_type() {
+        // A manifest claiming to be a ROA.
+        let der = include_bytes!("../test-data/sigobj-roa-type.mft");
+        SignedObject::decode(der.as_ref(), true).unwrap();
+        SignedObject::decode_if_type(
+            der.as_ref(), &oid::ROUTE_ORIGIN_AUTHZ, true
+        ).unwrap();
+        assert!(
+            SignedObject::decode_if_type(
+                der.as_ref(), &oid::CT_RPKI_MANIFEST, true
+            ).is_err()
+        );
+        assert!(
+            crate::manifest::Manifest::decode(der.as_ref(), true).is_err()
+        );
+    }
+
+    #[test]
+    fn decode_detailed_errors() {
+        fn check(der: &[u8], expected: SignedObjectError) {
+            assert_eq!(
+                SignedObject::decode_detailed(
+                    der, &oid::CT_RPKI_MANIFEST, true
+                ).unwrap_err(),
+                expected
+            );
+        }
+
+        check(
+            include_bytes!("../test-data/sigobj-two-signers.mft"),
+            SignedObjectError::MultipleSignerInfos
+        );
+        check(
+            include_bytes!("../test-data/sigobj-two-certs.mft"),
+            SignedObjectError::MultipleCertificates
+        );
+        check(
+            include_bytes!("../test-data/sigobj-crls.mft"),
+            SignedObjectError::CrlPresent
+        );
+        check(
+            include_bytes!("../test-data/sigobj-sha1.mft"),
+            SignedObjectError::WrongDigestAlgorithm(
+                Oid(Bytes::from_static(&[43, 14, 3, 2, 26]))
+            )
+        );
+        check(
+            include_bytes!("../test-data/sigobj-no-content-type.mft"),
+            SignedObjectError::MissingContentTypeAttr
+        );
+        check(
+            include_bytes!("../test-data/sigobj-no-message-digest.mft"),
+            SignedObjectError::MissingMessageDigestAttr
+        );
+        check(
+            include_bytes!("../test-data/sigobj-type-mismatch.mft"),
+            SignedObjectError::ContentTypeAttrMismatch
+        );
+        check(
+            include_bytes!("../test-data/sigobj-roa-type.mft"),
+            SignedObjectError::WrongContentType {
+                expected: Oid(Bytes::from_static(oid::CT_RPKI_MANIFEST.0)),
+                found: Oid(Bytes::from_static(oid::ROUTE_ORIGIN_AUTHZ.0)),
+            }
+        );
+        check(b"foo", SignedObjectError::Malformed);
+
+        assert_eq!(
+            SignedObjectError::MultipleSignerInfos.to_string(),
+            "SignedData contains more than one SignerInfo"
+        );
+    }
+
+    #[test]
+    fn validate_detailed_errors() {
+        // These are variations of sigobj-der.mft which itself is valid.
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+        let issuer = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let issuer = issuer.validate_ta_at(talinfo, false, at).unwrap();
+        let check = |der: &[u8], expected: SignedObjectError| {
+            let obj = SignedObject::decode_detailed(
+                der, &oid::CT_RPKI_MANIFEST, true
+            ).unwrap();
+            assert_eq!(
+                obj.validate_at(&issuer, false, at).unwrap_err(),
+                expected
+            );
+        };
+
+        check(
+            include_bytes!("../test-data/sigobj-wrong-sid.mft"),
+            SignedObjectError::SignerKeyMismatch
+        );
+        check(
+            include_bytes!("../test-data/sigobj-digest-mismatch.mft"),
+            SignedObjectError::MessageDigestMismatch
+        );
+        check(
+            include_bytes!("../test-data/sigobj-bad-signature.mft"),
+            SignedObjectError::InvalidSignature
+        );
+
+        let obj = SignedObject::decode_detailed(
+            include_bytes!("../test-data/sigobj-der.mft").as_ref(),
+            &oid::CT_RPKI_MANIFEST, true
+        ).unwrap();
+        let later = Time::utc(2030, 1, 1, 0, 0, 0);
+        match obj.validate_at(&issuer, false, later) {
+            Err(SignedObjectError::Cert(_)) => { }
+            res => panic!("unexpected result {:?}", res.map(|_| ()))
+        }
+    }
+
+    #[test]
+    fn validate_with_policy() {
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+        let issuer = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let issuer = issuer.validate_ta_at(talinfo, false, at).unwrap();
+        let obj = SignedObject::decode(
+            include_bytes!("../test-data/sigobj-der.mft").as_ref(), true
+        ).unwrap();
+
+        assert_eq!(
+            obj.clone().validate_with_policy(
+                &issuer, false, at,
+                &AlgorithmPolicy::all().forbid(SignatureAlgorithm::RsaSha256)
+            ).unwrap_err(),
+            SignedObjectError::AlgorithmNotPermitted(
+                SignatureAlgorithm::RsaSha256
+            )
+        );
+        obj.validate_with_policy(
+            &issuer, false, at,
+            &AlgorithmPolicy::none().allow(SignatureAlgorithm::RsaSha256)
+        ).unwrap();
+    }
+
+    #[test]
+    fn validate_with_scratch() {
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+        let issuer = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let issuer = issuer.validate_ta_at(talinfo, false, at).unwrap();
+        let decode = || {
+            SignedObject::decode(
+                include_bytes!("../test-data/sigobj-der.mft").as_ref(), true
+            ).unwrap()
+        };
+
+        let mut scratch = vec![0xFFu8; 7];
+        decode().validate_with_scratch(
+            &issuer, false, at, &mut scratch
+        ).unwrap();
+        let ptr = scratch.as_ptr();
+        let capacity = scratch.capacity();
+
+        // The buffer is large enough now and must not be reallocated.
+        for _ in 0..10 {
+            decode().validate_with_scratch(
+                &issuer, false, at, &mut scratch
+            ).unwrap();
+            assert_eq!(scratch.as_ptr(), ptr);
+            assert_eq!(scratch.capacity(), capacity);
+        }
+    }
+
+    #[test]
+    fn encode_verify_lengths() {
+        fn attrs(len: usize) -> SignedAttrs {
+            // A primitive value with a content of `len - 3` octets has a
+            // length of `len` octets if `len` is between 131 and 258.
+            SignedAttrs(Captured::from_values(
+                Mode::Der,
+                OctetString::new(Bytes::from(vec![0u8; len - 3])).encode_ref()
+            ))
+        }
+
+        let mut buf = Vec::new();
+        attrs(131).encode_verify_into(&mut buf);
+        assert_eq!(&buf[..3], b"\x31\x81\x83".as_ref());
+        assert_eq!(buf.len(), 134);
+        assert_eq!(buf, attrs(131).encode_verify());
+
+        attrs(258).encode_verify_into(&mut buf);
+        assert_eq!(&buf[..4], b"\x31\x82\x01\x02".as_ref());
+        assert_eq!(buf.len(), 262);
+    }
 }
 
 #[cfg(all(test, feature="softkeys"))]
 mod signer_test {
-    use std::str::FromStr;
     use bcder::Oid;
     use bcder::encode::Values;
-    use crate::uri;
     use crate::crypto::PublicKeyFormat;
     use crate::crypto::softsigner::OpenSslSigner;
-    use crate::resources::{AsId, Prefix};
     use crate::tal::TalInfo;
+    use crate::util::test::{make_cert, test_uri};
     use super::*;
         
     #[test]
@@ -959,19 +1679,9 @@ mod signer_test {
         let mut signer = OpenSslSigner::new();
         let key = signer.create_key(PublicKeyFormat::default()).unwrap();
         let pubkey = signer.get_key_info(&key).unwrap();
-        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+        let uri = test_uri();
 
-        let mut cert = TbsCert::new(
-            12u64.into(), pubkey.to_subject_name(),
-            Validity::from_secs(86400), None, pubkey, KeyUsage::Ca,
-            Overclaim::Trim
-        );
-        cert.set_basic_ca(Some(true));
-        cert.set_ca_repository(Some(uri.clone()));
-        cert.set_rpki_manifest(Some(uri.clone()));
-        cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
-        cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
-        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let cert = make_cert(pubkey, None, KeyUsage::Ca);
         let cert = cert.into_cert(&signer, &key).unwrap();
 
         let mut sigobj = SignedObjectBuilder::new(
@@ -993,6 +1703,50 @@ mod signer_test {
         ).unwrap();
         sigobj.validate(&cert, true).unwrap();
     }
+
+    fn make_sigobj(signing_time: Option<Time>) -> SignedObject {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let uri = test_uri();
+        let mut sigobj = SignedObjectBuilder::new(
+            12u64.into(), Validity::new(hours(-1), hours(1)), uri.clone(),
+            uri.clone(), uri
+        );
+        sigobj.set_v4_resources_inherit();
+        sigobj.set_signing_time(signing_time);
+        let sigobj = sigobj.finalize(
+            Oid(oid::SIGNED_DATA.0.into()),
+            Bytes::from(b"1234".as_ref()),
+            &signer,
+            &key,
+        ).unwrap();
+        let sigobj = sigobj.encode_ref().to_captured(Mode::Der);
+        SignedObject::decode(sigobj.as_slice(), true).unwrap()
+    }
+
+    fn hours(hours: i64) -> Time {
+        Time::new(*Time::now() + chrono::Duration::hours(hours))
+    }
+
+    #[test]
+    fn signing_time() {
+        let sigobj = make_sigobj(None);
+        assert_eq!(sigobj.signing_time(), None);
+        assert_eq!(sigobj.verify_signing_time(), Ok(()));
+
+        let sigobj = make_sigobj(Some(Time::now()));
+        assert!(sigobj.signing_time().is_some());
+        assert_eq!(sigobj.verify_signing_time(), Ok(()));
+
+        assert_eq!(
+            make_sigobj(Some(hours(-2))).verify_signing_time(),
+            Err(SignedObjectError::SigningTimeOutsideValidity)
+        );
+        assert_eq!(
+            make_sigobj(Some(hours(2))).verify_signing_time(),
+            Err(SignedObjectError::SigningTimeOutsideValidity)
+        );
+    }
 }
 
 
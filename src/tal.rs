@@ -1,16 +1,18 @@
 //! Trust Anchor Locators
 
-use std::{fmt, str};
+use std::{error, fmt, str};
 use std::convert::TryFrom;
 use std::fs::{read_dir, DirEntry, File, ReadDir};
 use std::io::{self, Read};
 use std::path::Path;
 use std::sync::Arc;
 use bytes::Bytes;
-use bcder::decode;
+use bcder::{decode, Mode, Tag, xerr};
 use log::{debug, error};
 use serde::{Deserialize, Serialize};
-use crate::crypto::PublicKey;
+use crate::cert::{Cert, ResourceCert};
+use crate::crypto::{KeyIdentifier, PublicKey};
+use crate::x509::{Time, ValidationError};
 use super::uri;
 
 
@@ -20,10 +22,31 @@ use super::uri;
 pub struct Tal {
     uris: Vec<TalUri>,
     key_info: PublicKey,
+    raw_key_info: Bytes,
     info: Arc<TalInfo>,
 }
 
 impl Tal {
+    /// Creates a new TAL from its name, URIs, and key info.
+    ///
+    /// Returns `ReadError::NoUris` if `uris` is empty since such a TAL
+    /// would be rejected when reading it back.
+    pub fn new(
+        name: String,
+        uris: Vec<TalUri>,
+        key_info: PublicKey
+    ) -> Result<Self, ReadError> {
+        if uris.is_empty() {
+            return Err(ReadError::NoUris)
+        }
+        Ok(Tal {
+            uris,
+            raw_key_info: key_info.to_info_bytes(),
+            key_info,
+            info: Arc::new(TalInfo::from_name(name))
+        })
+    }
+
     pub fn read_dir<P: AsRef<Path>>(path: P) -> Result<TalIter, io::Error> {
         read_dir(path).map(TalIter)
     }
@@ -55,15 +78,19 @@ impl Tal {
         while let Some(uri) = Self::take_uri(&mut data)? {
             uris.push(uri)
         }
+        if uris.is_empty() {
+            return Err(ReadError::NoUris)
+        }
         let data: Vec<_> = data.iter().filter_map(|b|
             if b.is_ascii_whitespace() { None }
             else { Some(*b) }
         ).collect();
-        let key_info = base64::decode(&data)?;
-        let key_info = PublicKey::decode(key_info.as_ref())?;
+        let raw_key_info = Bytes::from(base64::decode(&data)?);
+        let key_info = PublicKey::decode(raw_key_info.as_ref())?;
         Ok(Tal {
             uris,
             key_info,
+            raw_key_info,
             info: Arc::new(TalInfo::from_name(name))
         })
     }
@@ -86,16 +113,56 @@ impl Tal {
             Ok(None)
         }
         else {
-            Ok(Some(TalUri::from_slice(line)?))
+            match TalUri::from_slice(line) {
+                Ok(uri) => Ok(Some(uri)),
+                Err(uri::Error::BadScheme) => {
+                    let scheme = line.splitn(2, |&ch| ch == b':').next()
+                        .unwrap_or(line);
+                    Err(ReadError::UnknownScheme(
+                        String::from_utf8_lossy(scheme).into_owned()
+                    ))
+                }
+                Err(err) => Err(err.into())
+            }
         }
     }
 }
 
 impl Tal {
+    /// Returns an iterator over the URIs in the order given in the TAL.
     pub fn uris(&self) -> ::std::slice::Iter<TalUri> {
         self.uris.iter()
     }
 
+    /// Returns an iterator over the URIs in the order they should be tried.
+    ///
+    /// As encouraged by [RFC 8630], HTTPS URIs are returned first followed
+    /// by the rsync URIs. Within each scheme, the order of the TAL is
+    /// kept.
+    ///
+    /// [RFC 8630]: https://tools.ietf.org/html/rfc8630
+    pub fn preferred_uris(&self) -> impl Iterator<Item = &TalUri> {
+        self.uris.iter().filter(|uri| uri.is_https()).chain(
+            self.uris.iter().filter(|uri| uri.is_rsync())
+        )
+    }
+
+    /// Returns an iterator over the HTTPS URIs of the TAL.
+    pub fn https_uris(&self) -> impl Iterator<Item = &uri::Https> {
+        self.uris.iter().filter_map(|uri| match *uri {
+            TalUri::Https(ref uri) => Some(uri),
+            _ => None
+        })
+    }
+
+    /// Returns an iterator over the rsync URIs of the TAL.
+    pub fn rsync_uris(&self) -> impl Iterator<Item = &uri::Rsync> {
+        self.uris.iter().filter_map(|uri| match *uri {
+            TalUri::Rsync(ref uri) => Some(uri),
+            _ => None
+        })
+    }
+
     pub fn key_info(&self) -> &PublicKey {
         &self.key_info
     }
@@ -105,6 +172,122 @@ impl Tal {
     }
 }
 
+impl Tal {
+    /// Checks that a certificate has the key given in the TAL.
+    ///
+    /// This is the first step of validating a trust anchor certificate
+    /// retrieved via one of the TAL’s URIs. As required by [RFC 8630],
+    /// the encoded subjectPublicKeyInfo of the certificate has to be
+    /// identical to the one in the TAL. Keys that merely decode to the same
+    /// value are rejected.
+    ///
+    /// [RFC 8630]: https://tools.ietf.org/html/rfc8630
+    pub fn check_cert(&self, cert: &Cert) -> Result<(), TalMismatch> {
+        match raw_key_info(cert) {
+            Ok(ref raw) if *raw == self.raw_key_info => Ok(()),
+            _ => {
+                Err(TalMismatch {
+                    expected: self.key_info.key_identifier(),
+                    found: cert.subject_public_key_info().key_identifier(),
+                })
+            }
+        }
+    }
+
+    /// Validates a trust anchor certificate retrieved for the TAL.
+    ///
+    /// Checks that the certificate has the TAL’s key via [`check_cert`]
+    /// and then validates it as a trust anchor certificate.
+    ///
+    /// [`check_cert`]: #method.check_cert
+    pub fn validate_ta(
+        &self,
+        cert: Cert,
+        strict: bool
+    ) -> Result<ResourceCert, TalValidationError> {
+        self.validate_ta_at(cert, strict, Time::now())
+    }
+
+    /// Validates a trust anchor certificate at the given time.
+    pub fn validate_ta_at(
+        &self,
+        cert: Cert,
+        strict: bool,
+        now: Time
+    ) -> Result<ResourceCert, TalValidationError> {
+        self.check_cert(&cert)?;
+        cert.validate_ta_at(
+            self.info.clone(), strict, now
+        ).map_err(TalValidationError::Invalid)
+    }
+
+    /// Writes the TAL to a target.
+    ///
+    /// The TAL is written with LF line endings as the URIs one per line,
+    /// an empty line, and the base64 encoded key info wrapped at 64
+    /// characters.
+    pub fn write<W: io::Write>(&self, target: &mut W) -> io::Result<()> {
+        for uri in &self.uris {
+            writeln!(target, "{}", uri)?;
+        }
+        writeln!(target)?;
+        let key_info = base64::encode(&self.raw_key_info);
+        for line in key_info.as_bytes().chunks(64) {
+            target.write_all(line)?;
+            writeln!(target)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the TAL to a target preceded by a comment.
+    ///
+    /// Each line of `comment` is written prefixed by `"# "` or, if the
+    /// line is empty, a lone `"#"`. The TAL itself follows as written by
+    /// [`write`].
+    ///
+    /// [`write`]: #method.write
+    pub fn write_with_comment<W: io::Write>(
+        &self,
+        comment: &str,
+        target: &mut W
+    ) -> io::Result<()> {
+        for line in comment.lines() {
+            if line.is_empty() {
+                writeln!(target, "#")?;
+            }
+            else {
+                writeln!(target, "# {}", line)?;
+            }
+        }
+        self.write(target)
+    }
+}
+
+/// Returns the encoded subjectPublicKeyInfo of a certificate.
+fn raw_key_info(cert: &Cert) -> Result<Bytes, decode::Error> {
+    Mode::Ber.decode(cert.to_captured().into_bytes(), |cons| {
+        cons.take_sequence(|cons| {
+            let res = cons.take_sequence(|cons| {
+                // Skip version, serialNumber, signature, issuer, validity,
+                // and subject.
+                cons.take_opt_constructed_if(Tag::CTX_0, |cons| {
+                    cons.skip_all()
+                })?;
+                for _ in 0..5 {
+                    if cons.skip_one()?.is_none() {
+                        xerr!(return Err(decode::Malformed.into()))
+                    }
+                }
+                let res = cons.capture_one()?;
+                cons.skip_all()?;
+                Ok(res)
+            })?;
+            cons.skip_all()?;
+            Ok(res.into_bytes())
+        })
+    })
+}
+
 
 //------------ TalIter -------------------------------------------------------
 
@@ -163,10 +346,13 @@ impl TalUri {
     }
 
     pub fn from_bytes(bytes: Bytes) -> Result<Self, uri::Error> {
-        if let Ok(uri) = uri::Rsync::from_bytes(bytes.clone()) {
-            return Ok(TalUri::Rsync(uri))
+        match uri::Rsync::from_bytes(bytes.clone()) {
+            Ok(uri) => Ok(TalUri::Rsync(uri)),
+            Err(uri::Error::BadScheme) => {
+                uri::Https::from_bytes(bytes).map(Into::into)
+            }
+            Err(err) => Err(err)
         }
-        uri::Https::from_bytes(bytes).map(Into::into)
     }
 
     pub fn is_rsync(&self) -> bool {
@@ -253,12 +439,92 @@ impl TalInfo {
 }
 
 
+//--- Display
+
+impl fmt::Display for TalInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+
+//------------ TalMismatch ---------------------------------------------------
+
+/// A certificate doesn’t have the key given in a TAL.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TalMismatch {
+    /// The identifier of the key in the TAL.
+    expected: KeyIdentifier,
+
+    /// The identifier of the key in the certificate.
+    found: KeyIdentifier,
+}
+
+impl TalMismatch {
+    /// Returns the identifier of the key in the TAL.
+    pub fn expected(&self) -> KeyIdentifier {
+        self.expected
+    }
+
+    /// Returns the identifier of the key in the certificate.
+    ///
+    /// This can be the same as the expected identifier if the keys only
+    /// differ in their encoding.
+    pub fn found(&self) -> KeyIdentifier {
+        self.found
+    }
+}
+
+impl fmt::Display for TalMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "certificate key {} doesn’t match TAL key {}",
+            self.found, self.expected
+        )
+    }
+}
+
+impl error::Error for TalMismatch { }
+
+
+//------------ TalValidationError --------------------------------------------
+
+/// An error happened while validating a trust anchor certificate for a TAL.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TalValidationError {
+    /// The certificate doesn’t have the TAL’s key.
+    Mismatch(TalMismatch),
+
+    /// The certificate isn’t a valid trust anchor certificate.
+    Invalid(ValidationError),
+}
+
+impl From<TalMismatch> for TalValidationError {
+    fn from(err: TalMismatch) -> Self {
+        TalValidationError::Mismatch(err)
+    }
+}
+
+impl fmt::Display for TalValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TalValidationError::Mismatch(ref err) => err.fmt(f),
+            TalValidationError::Invalid(ref err) => err.fmt(f)
+        }
+    }
+}
+
+impl error::Error for TalValidationError { }
+
+
 //------------ ReadError -----------------------------------------------------
 
 #[derive(Debug)]
 pub enum ReadError {
     Io(io::Error),
     UnexpectedEof,
+    NoUris,
+    UnknownScheme(String),
     BadUri(uri::Error),
     BadKeyInfoEncoding(base64::DecodeError),
     BadKeyInfo(decode::Error),
@@ -294,6 +560,10 @@ impl fmt::Display for ReadError {
             ReadError::Io(ref err) => err.fmt(f),
             ReadError::UnexpectedEof
                 => f.write_str("unexpected end of file"),
+            ReadError::NoUris
+                => f.write_str("no trust anchor URIs"),
+            ReadError::UnknownScheme(ref scheme)
+                => write!(f, "unknown trust anchor URI scheme '{}'", scheme),
             ReadError::BadUri(ref err)
                 => write!(f, "bad trust anchor URI: {}", err),
             ReadError::BadKeyInfoEncoding(ref err)
@@ -325,4 +595,316 @@ mod test {
             cert.subject_public_key_info(),
         );
     }
+
+    fn uris(tal: &Tal) -> Vec<String> {
+        tal.uris().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn tal_read_fixtures() {
+        let tal = Tal::read(
+            "tals/rfc8630.tal",
+            &mut include_bytes!("../test-data/tals/rfc8630.tal").as_ref()
+        ).unwrap();
+        assert_eq!(tal.info().to_string(), "rfc8630");
+        assert_eq!(
+            uris(&tal),
+            [
+                "https://rpki.example.net/ta/ta.cer",
+                "rsync://rpki.example.net/ta/ta.cer"
+            ]
+        );
+        assert!(tal.uris().next().unwrap().is_https());
+        assert_eq!(tal.key_info().key_size(), Some(2048));
+
+        let tal = Tal::read(
+            "tals/comments.tal",
+            &mut include_bytes!("../test-data/tals/comments.tal").as_ref()
+        ).unwrap();
+        assert_eq!(tal.info().name(), "comments");
+        assert_eq!(
+            uris(&tal),
+            ["rsync://rpki.example.org/repository/example-ta.cer"]
+        );
+        assert_eq!(tal.key_info().key_size(), Some(2048));
+
+        let tal = Tal::read(
+            "tals/crlf.tal",
+            &mut include_bytes!("../test-data/tals/crlf.tal").as_ref()
+        ).unwrap();
+        assert_eq!(
+            uris(&tal),
+            [
+                "rsync://rpki.example.com/ta/root.cer",
+                "rsync://rpki-backup.example.com/ta/root.cer"
+            ]
+        );
+        assert_eq!(tal.key_info().key_size(), Some(2048));
+
+        // The TALs as published by the five RIRs.
+        let tal = Tal::read(
+            "tals/afrinic.tal",
+            &mut include_bytes!("../test-data/tals/afrinic.tal").as_ref()
+        ).unwrap();
+        assert_eq!(tal.info().name(), "afrinic");
+        assert_eq!(
+            uris(&tal),
+            [
+                "https://rpki.afrinic.net/repository/AfriNIC.cer",
+                "rsync://rpki.afrinic.net/repository/AfriNIC.cer"
+            ]
+        );
+        assert_eq!(tal.key_info().key_size(), Some(2048));
+
+        let tal = Tal::read(
+            "tals/apnic.tal",
+            &mut include_bytes!("../test-data/tals/apnic.tal").as_ref()
+        ).unwrap();
+        assert_eq!(tal.info().name(), "apnic");
+        assert_eq!(
+            uris(&tal),
+            [
+                "https://tal.apnic.net/apnic.cer",
+                "rsync://rpki.apnic.net/repository/\
+                 apnic-rpki-root-iana-origin.cer"
+            ]
+        );
+        assert_eq!(tal.key_info().key_size(), Some(2048));
+
+        let tal = Tal::read(
+            "tals/arin.tal",
+            &mut include_bytes!("../test-data/tals/arin.tal").as_ref()
+        ).unwrap();
+        assert_eq!(tal.info().name(), "arin");
+        assert_eq!(
+            uris(&tal),
+            [
+                "rsync://rpki.arin.net/repository/arin-rpki-ta.cer",
+                "https://rrdp.arin.net/arin-rpki-ta.cer"
+            ]
+        );
+        assert_eq!(tal.key_info().key_size(), Some(2048));
+
+        let tal = Tal::read(
+            "tals/lacnic.tal",
+            &mut include_bytes!("../test-data/tals/lacnic.tal").as_ref()
+        ).unwrap();
+        assert_eq!(tal.info().name(), "lacnic");
+        assert_eq!(
+            uris(&tal),
+            ["rsync://repository.lacnic.net/rpki/lacnic/rta-lacnic-rpki.cer"]
+        );
+        assert_eq!(tal.key_info().key_size(), Some(2048));
+
+        let tal = Tal::read(
+            "tals/ripe-ncc.tal",
+            &mut include_bytes!("../test-data/tals/ripe-ncc.tal").as_ref()
+        ).unwrap();
+        assert_eq!(tal.info().name(), "ripe-ncc");
+        assert_eq!(
+            uris(&tal),
+            [
+                "https://rpki.ripe.net/ta/ripe-ncc-ta.cer",
+                "rsync://rpki.ripe.net/ta/ripe-ncc-ta.cer"
+            ]
+        );
+        assert_eq!(tal.key_info().key_size(), Some(2048));
+    }
+
+    #[test]
+    fn tal_uri_order() {
+        let tal = Tal::read(
+            "tals/mixed.tal",
+            &mut include_bytes!("../test-data/tals/mixed.tal").as_ref()
+        ).unwrap();
+        assert_eq!(
+            uris(&tal),
+            [
+                "rsync://rpki.example.net/ta/ta.cer",
+                "https://rpki.example.net/ta/ta.cer",
+                "rsync://rpki-backup.example.net/ta/ta.cer",
+                "https://rpki-backup.example.net/ta/ta.cer",
+            ]
+        );
+        assert_eq!(
+            tal.preferred_uris().map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "https://rpki.example.net/ta/ta.cer",
+                "https://rpki-backup.example.net/ta/ta.cer",
+                "rsync://rpki.example.net/ta/ta.cer",
+                "rsync://rpki-backup.example.net/ta/ta.cer",
+            ]
+        );
+        assert_eq!(
+            tal.https_uris().map(|uri| uri.authority()).collect::<Vec<_>>(),
+            ["rpki.example.net", "rpki-backup.example.net"]
+        );
+        assert_eq!(
+            tal.rsync_uris().map(|uri| uri.authority())
+                .collect::<Vec<_>>(),
+            ["rpki.example.net", "rpki-backup.example.net"]
+        );
+
+        // A TAL with a single scheme keeps its order.
+        let tal = Tal::read(
+            "tals/crlf.tal",
+            &mut include_bytes!("../test-data/tals/crlf.tal").as_ref()
+        ).unwrap();
+        assert!(tal.preferred_uris().eq(tal.uris()));
+        assert_eq!(tal.https_uris().count(), 0);
+        assert_eq!(tal.rsync_uris().count(), 2);
+    }
+
+    #[test]
+    fn tal_write() {
+        // Both fixtures are in the canonical format, so writing the TALs
+        // must result in the exact same files.
+        let data = include_bytes!("../test-data/tals/rfc8630.tal");
+        let tal = Tal::read("rfc8630.tal", &mut data.as_ref()).unwrap();
+        let mut written = Vec::new();
+        tal.write(&mut written).unwrap();
+        assert_eq!(written, data.as_ref());
+
+        let data = include_bytes!("../test-data/tals/comments.tal");
+        let tal = Tal::read("comments.tal", &mut data.as_ref()).unwrap();
+        let mut written = Vec::new();
+        tal.write_with_comment(
+            "Trust anchor locator of the example RIR.\n\
+             \n\
+             See https://rpki.example.org/ for details.",
+            &mut written
+        ).unwrap();
+        assert_eq!(written, data.as_ref());
+
+        // Round trip of a new TAL.
+        let tal = Tal::new(
+            "new".into(),
+            vec![
+                "https://rpki.example.net/ta.cer".parse().unwrap(),
+                "rsync://rpki.example.net/ta/ta.cer".parse().unwrap(),
+            ],
+            tal.key_info().clone()
+        ).unwrap();
+        let mut written = Vec::new();
+        tal.write(&mut written).unwrap();
+        assert!(!written.contains(&b'\r'));
+        let read = Tal::read("new.tal", &mut written.as_slice()).unwrap();
+        assert_eq!(uris(&read), uris(&tal));
+        assert_eq!(read.key_info(), tal.key_info());
+        assert_eq!(read.info().name(), "new");
+    }
+
+    #[test]
+    fn tal_new_without_uris() {
+        let data = include_bytes!("../test-data/ripe.tal");
+        let tal = Tal::read("ripe.tal", &mut data.as_ref()).unwrap();
+        match Tal::new("ripe".into(), Vec::new(), tal.key_info().clone()) {
+            Err(ReadError::NoUris) => { }
+            res => panic!("expected missing URIs, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn check_cert() {
+        let tal = Tal::read(
+            "ripe.tal", &mut include_bytes!("../test-data/ripe.tal").as_ref()
+        ).unwrap();
+        let ta = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let ca = Cert::decode(
+            include_bytes!("../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        let at = Time::utc(2019, 5, 1, 0, 0, 0);
+
+        assert_eq!(tal.check_cert(&ta), Ok(()));
+        tal.validate_ta_at(ta.clone(), false, at).unwrap();
+
+        // A certificate with a different key.
+        let err = tal.check_cert(&ca).unwrap_err();
+        assert_eq!(
+            err.expected(), ta.subject_public_key_info().key_identifier()
+        );
+        assert_eq!(
+            err.found(), ca.subject_public_key_info().key_identifier()
+        );
+        assert_eq!(
+            tal.validate_ta_at(ca, false, at).unwrap_err(),
+            TalValidationError::Mismatch(err)
+        );
+
+        // The same key without the NULL parameters is still a mismatch.
+        let tal = Tal::read(
+            "ripe-no-null.tal",
+            &mut include_bytes!("../test-data/ripe-no-null.tal").as_ref()
+        ).unwrap();
+        assert_eq!(tal.key_info(), ta.subject_public_key_info());
+        let err = tal.check_cert(&ta).unwrap_err();
+        assert_eq!(err.expected(), err.found());
+    }
+
+    #[test]
+    fn tal_read_dir() {
+        let mut names: Vec<_> = Tal::read_dir("test-data/tals").unwrap().map(
+            |tal| tal.unwrap().info().name().to_string()
+        ).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "afrinic", "apnic", "arin", "comments", "crlf", "lacnic",
+                "mixed", "rfc8630", "ripe-ncc"
+            ]
+        );
+    }
+
+    #[test]
+    fn tal_read_errors() {
+        let key = "\n\
+        MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA0URYSGqUz2myBsOzeW1j\n\
+        Q6NsxNvlLMyhWknvnl8NiBCs/T/S2XuNKQNZ+wBZxIgPPV2pFBFeQAvoH/WK83Hw\n\
+        A26V2siwm/MY2nKZ+Olw+wlpzlZ1p3Ipj2eNcKrmit8BwBC8xImzuCGaV0jkRB0G\n\
+        Z0hoH6Ml03umLprRsn6v0xOP0+l6Qc1ZHMFVFb385IQ7FQQTcVIxrdeMsoyJq9eM\n\
+        kE6DoclHhF/NlSllXubASQ9KUWqJ0+Ot3QCXr4LXECMfkpkVR2TZT+v5v658bHVs\n\
+        6ZxRD1b6Uk1uQKAyHUbn/tXvP8lrjAibGzVsXDT2L0x4Edx+QdixPgOji3gBMyL2\n\
+        VwIDAQAB\n";
+        let read = |data: String| Tal::read("test.tal", &mut data.as_bytes());
+
+        assert!(
+            read(format!("rsync://example.net/ta/ta.cer\n{}", key)).is_ok()
+        );
+        match read(key.into()) {
+            Err(ReadError::NoUris) => { }
+            res => panic!("expected no URIs, got {:?}", res)
+        }
+        match read(format!("# comment\n{}", key)) {
+            Err(ReadError::NoUris) => { }
+            res => panic!("expected no URIs, got {:?}", res)
+        }
+        match read(format!("ftp://example.net/ta.cer\n{}", key)) {
+            Err(ReadError::UnknownScheme(ref scheme)) if scheme == "ftp" => {
+            }
+            res => panic!("expected unknown scheme, got {:?}", res)
+        }
+        match read(
+            format!("https://example.net/ta.cer\nhttp://x\n{}", key)
+        ) {
+            Err(ReadError::UnknownScheme(ref scheme)) if scheme == "http" => {
+            }
+            res => panic!("expected unknown scheme, got {:?}", res)
+        }
+        match read(format!("rsync://example.net/ta/../ta.cer\n{}", key)) {
+            Err(ReadError::BadUri(_)) => { }
+            res => panic!("expected bad URI, got {:?}", res)
+        }
+        match read("rsync://example.net/ta/ta.cer\n\nMIIB!IjAN\n".into()) {
+            Err(ReadError::BadKeyInfoEncoding(_)) => { }
+            res => panic!("expected bad key encoding, got {:?}", res)
+        }
+        match read("rsync://example.net/ta/ta.cer\n\nMIIBIjAN\n".into()) {
+            Err(ReadError::BadKeyInfo(_)) => { }
+            res => panic!("expected bad key info, got {:?}", res)
+        }
+    }
 }
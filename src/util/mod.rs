@@ -1 +1,4 @@
 pub mod hex;
+
+#[cfg(all(test, feature="softkeys"))]
+pub mod test;
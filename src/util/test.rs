@@ -0,0 +1,63 @@
+//! Helpers for tests that create certificates.
+
+use std::str::FromStr;
+use crate::uri;
+use crate::cert::{Cert, KeyUsage, Overclaim, TbsCert};
+use crate::crypto::PublicKey;
+use crate::resources::{AsId, Prefix};
+use crate::x509::Validity;
+
+
+/// Returns the rsync URI used for all URIs of test objects.
+pub fn test_uri() -> uri::Rsync {
+    uri::Rsync::from_str("rsync://example.com/m/p").unwrap()
+}
+
+/// Creates the content of a certificate for tests.
+///
+/// The certificate for `pubkey` has serial number 12, is valid for a day,
+/// and holds all IPv4, IPv6, and AS resources. Its URIs are all
+/// [`test_uri`] and its extensions are those required by `key_usage`.
+///
+/// If `issuer` is `None`, the certificate is a self-signed trust anchor
+/// certificate. Otherwise it is issued by `issuer`.
+///
+/// The result can be modified further before signing it.
+///
+/// [`test_uri`]: fn.test_uri.html
+pub fn make_cert(
+    pubkey: PublicKey,
+    issuer: Option<&Cert>,
+    key_usage: KeyUsage,
+) -> TbsCert {
+    let uri = test_uri();
+    let (issuer_name, overclaim) = match issuer {
+        Some(issuer) => (issuer.subject().clone(), Overclaim::Refuse),
+        None => (pubkey.to_subject_name(), Overclaim::Trim),
+    };
+    let mut cert = TbsCert::new(
+        12u64.into(), issuer_name, Validity::from_secs(86400), None,
+        pubkey, key_usage, overclaim
+    );
+    if let Some(issuer) = issuer {
+        cert.set_authority_key_identifier(
+            Some(issuer.subject_key_identifier())
+        );
+        cert.set_crl_uri(Some(uri.clone()));
+        cert.set_ca_issuer(Some(uri.clone()));
+    }
+    match key_usage {
+        KeyUsage::Ca => {
+            cert.set_basic_ca(Some(true));
+            cert.set_ca_repository(Some(uri.clone()));
+            cert.set_rpki_manifest(Some(uri));
+        }
+        KeyUsage::Ee => {
+            cert.set_signed_object(Some(uri));
+        }
+    }
+    cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+    cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+    cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+    cert
+}
@@ -2,6 +2,7 @@
 
 use std::{error, fmt, io, ops, str};
 use std::cmp::{min, max};
+use std::io::Read;
 use std::str::FromStr;
 use std::time::SystemTime;
 use bcder::{decode, encode};
@@ -11,13 +12,15 @@ use bcder::{
 use bcder::string::PrintableString;
 use bcder::decode::Source;
 use bcder::encode::PrimitiveContent;
+use bytes::Bytes;
 use chrono::{
     Datelike, DateTime, Duration, LocalResult, Timelike, TimeZone, Utc
 };
 use serde::de;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use crate::crypto::{
-    PublicKey, Signature, SignatureAlgorithm, Signer, VerificationError
+    AlgorithmPolicy, PublicKey, Signature, SignatureAlgorithm, Signer,
+    VerificationError
 };
 use crate::oid;
 
@@ -70,6 +73,230 @@ pub fn encode_extension<V: encode::Values>(
     ))
 }
 
+/// Checks that data is a single value in canonical DER encoding.
+///
+/// Decoding in DER mode already rejects some BER features, such as
+/// indefinite lengths. This function walks the complete encoding of a
+/// value, including content that is only captured or skipped during
+/// decoding, and also checks those rules of X.690 that need to look at
+/// the content: the identifier and length octets must be minimal,
+/// booleans, integers, bit strings, and nulls must be encoded in their
+/// only valid form, only sequences and sets may be constructed among the
+/// universal types, and the elements of a set must be sorted by their
+/// encoding.
+///
+/// Since the tagging of context specific values can’t be known without
+/// the syntax definition, the content of those values is only checked if
+/// it is constructed and sets with implicit tags are not checked for their
+/// sort order.
+///
+/// Returns a malformed error if any of these rules is violated or if
+/// there is trailing data after the value.
+pub fn verify_canonical(data: &[u8]) -> Result<(), decode::Error> {
+    if verify_canonical_value(data, 0)?.is_empty() {
+        Ok(())
+    }
+    else {
+        xerr!(Err(decode::Malformed))
+    }
+}
+
+/// The maximum nesting of constructed values in `verify_canonical`.
+const MAX_CANONICAL_DEPTH: usize = 64;
+
+/// Checks a single value at the start of data and returns the rest.
+fn verify_canonical_value(
+    data: &[u8], depth: usize
+) -> Result<&[u8], decode::Error> {
+    if depth > MAX_CANONICAL_DEPTH {
+        xerr!(return Err(decode::Malformed))
+    }
+
+    // Identifier octets.
+    let (&first, mut data) = data.split_first().ok_or(decode::Malformed)?;
+    let universal = first & 0xC0 == 0;
+    let constructed = first & 0x20 != 0;
+    let mut number = u32::from(first & 0x1F);
+    if number == 0x1F {
+        // Long form: no leading zero bits and only for numbers above 30.
+        if data.first() == Some(&0x80) {
+            xerr!(return Err(decode::Malformed))
+        }
+        number = 0;
+        loop {
+            let (&octet, rest) = data.split_first().ok_or(
+                decode::Malformed
+            )?;
+            data = rest;
+            if number > (u32::max_value() >> 7) {
+                xerr!(return Err(decode::Malformed))
+            }
+            number = (number << 7) | u32::from(octet & 0x7F);
+            if octet & 0x80 == 0 {
+                break
+            }
+        }
+        if number < 0x1F {
+            xerr!(return Err(decode::Malformed))
+        }
+    }
+
+    // Length octets: definite and in the shortest possible form.
+    let (&first, mut data) = data.split_first().ok_or(decode::Malformed)?;
+    let len = if first < 0x80 {
+        usize::from(first)
+    }
+    else {
+        let count = usize::from(first & 0x7F);
+        if count == 0 || count == 0x7F || count > data.len() {
+            xerr!(return Err(decode::Malformed))
+        }
+        let (octets, rest) = data.split_at(count);
+        data = rest;
+        if octets[0] == 0 || count > std::mem::size_of::<usize>() {
+            xerr!(return Err(decode::Malformed))
+        }
+        let len = octets.iter().fold(0usize, |len, &octet| {
+            (len << 8) | usize::from(octet)
+        });
+        if len < 0x80 {
+            xerr!(return Err(decode::Malformed))
+        }
+        len
+    };
+    if len > data.len() {
+        xerr!(return Err(decode::Malformed))
+    }
+    let (content, rest) = data.split_at(len);
+
+    if constructed {
+        // Of the universal types, only SEQUENCE and SET are constructed.
+        if universal && number != 16 && number != 17 {
+            xerr!(return Err(decode::Malformed))
+        }
+        let is_set = universal && number == 17;
+        let mut prev: Option<&[u8]> = None;
+        let mut content = content;
+        while !content.is_empty() {
+            let next = verify_canonical_value(content, depth + 1)?;
+            let element = &content[..content.len() - next.len()];
+            if is_set {
+                if let Some(prev) = prev {
+                    if element < prev {
+                        xerr!(return Err(decode::Malformed))
+                    }
+                }
+                prev = Some(element);
+            }
+            content = next;
+        }
+    }
+    else if universal {
+        let ok = match number {
+            // BOOLEAN
+            1 => content == b"\x00" || content == b"\xFF",
+            // INTEGER and ENUMERATED
+            2 | 10 => {
+                match (content.first(), content.get(1)) {
+                    (None, _) => false,
+                    (Some(&0), Some(second)) => second & 0x80 != 0,
+                    (Some(&0xFF), Some(second)) => second & 0x80 == 0,
+                    _ => true,
+                }
+            }
+            // BIT STRING
+            3 => {
+                match content.split_first() {
+                    Some((&0, _)) => true,
+                    Some((&unused, bits)) if unused < 8 => {
+                        match bits.last() {
+                            Some(last) => last & ((1 << unused) - 1) == 0,
+                            None => false
+                        }
+                    }
+                    _ => false
+                }
+            }
+            // NULL
+            5 => content.is_empty(),
+            // SEQUENCE and SET
+            16 | 17 => false,
+            _ => true
+        };
+        if !ok {
+            xerr!(return Err(decode::Malformed))
+        }
+    }
+    Ok(rest)
+}
+
+/// Reads the encoding of a single value from a reader.
+///
+/// The function reads the identifier and length octets to learn the size
+/// of the value and then reads exactly that many content octets. It never
+/// reads past the end of the value, so further values can be read from the
+/// same reader afterwards. The returned bytes contain the complete
+/// encoding, including identifier and length octets.
+///
+/// Since the length has to be known in advance, values with indefinite
+/// length are rejected. So are values with more than `max_len` content
+/// octets before anything is read. The content is not checked at all.
+pub fn read_value<R: io::Read>(
+    mut reader: R,
+    max_len: u64
+) -> Result<Bytes, ReadError> {
+    let mut res = Vec::new();
+
+    // Identifier octets. If the tag number is 31, it is continued in
+    // subsequent octets for as long as their most significant bit is set.
+    let byte = read_u8(&mut reader, &mut res)?;
+    if byte & 0x1f == 0x1f {
+        while read_u8(&mut reader, &mut res)? & 0x80 != 0 { }
+    }
+
+    // Length octets.
+    let byte = read_u8(&mut reader, &mut res)?;
+    let len = if byte < 0x80 {
+        u64::from(byte)
+    }
+    else if byte == 0x80 || byte == 0xFF {
+        xerr!(return Err(decode::Malformed.into()))
+    }
+    else {
+        let mut len = 0u64;
+        for _ in 0..(byte & 0x7F) {
+            if len > u64::max_value() >> 8 {
+                xerr!(return Err(decode::Unimplemented.into()))
+            }
+            len = (len << 8) | u64::from(read_u8(&mut reader, &mut res)?);
+        }
+        len
+    };
+    if len > max_len {
+        return Err(ReadError::TooLarge(len))
+    }
+
+    // Content octets. Using `take` avoids allocating a possibly bogus
+    // length up front.
+    let start = res.len();
+    reader.take(len).read_to_end(&mut res)?;
+    if ((res.len() - start) as u64) < len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+    }
+    Ok(res.into())
+}
+
+/// Reads a single octet and appends it to `buf`.
+fn read_u8<R: io::Read>(
+    reader: &mut R,
+    buf: &mut Vec<u8>
+) -> Result<u8, io::Error> {
+    let mut byte = [0u8];
+    reader.read_exact(&mut byte)?;
+    buf.push(byte[0]);
+    Ok(byte[0])
+}
+
 
 //------------ Name ----------------------------------------------------------
 
@@ -176,6 +403,247 @@ impl Name {
 }
 
 
+//--- Display
+
+/// Formats the name as a comma-separated list of attributes.
+///
+/// Attributes of the types listed in [`NameAttribute`] are shown with
+/// their short name, such as `CN` or `serialNumber`, all other attributes
+/// with their object identifier. Values are shown as is without any
+/// escaping. If the name cannot be decoded, `<invalid name>` is shown
+/// instead.
+///
+/// [`NameAttribute`]: enum.NameAttribute.html
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let attrs = self.0.clone().decode(|cons| {
+            let mut res = Vec::new();
+            cons.take_sequence(|cons| {
+                while let Some(()) = cons.take_opt_set(|cons| {
+                    while let Some(()) = cons.take_opt_sequence(|cons| {
+                        let id = Oid::take_from(cons)?;
+                        let value = cons.take_value(|_, content| {
+                            content.as_primitive()?.take_all()
+                        })?;
+                        res.push((id, value));
+                        Ok(())
+                    })? { }
+                    Ok(())
+                })? { }
+                Ok(())
+            })?;
+            Ok(res)
+        });
+        let attrs = match attrs {
+            Ok(attrs) => attrs,
+            Err(_) => return f.write_str("<invalid name>")
+        };
+        for (idx, (id, value)) in attrs.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            match NameAttribute::from_oid(id) {
+                Some(attr) => f.write_str(attr.short_name())?,
+                None => write!(f, "{}", id)?,
+            }
+            write!(f, "={}", String::from_utf8_lossy(value.as_ref()))?;
+        }
+        Ok(())
+    }
+}
+
+
+//------------ NameBuilder ---------------------------------------------------
+
+/// A builder for names.
+///
+/// A name is a sequence of relative distinguished names (RDNs) each of
+/// which is a set of one or more attributes. [`push`] starts a new RDN with
+/// a single attribute while [`push_to_last`] adds an attribute to the last
+/// RDN. The attributes of an RDN are encoded in the order required by DER,
+/// so the order in which they are added doesn’t matter.
+///
+/// [`push`]: #method.push
+/// [`push_to_last`]: #method.push_to_last
+#[derive(Clone, Debug)]
+pub struct NameBuilder {
+    /// The RDNs with the DER encoding of each attribute.
+    rdns: Vec<Vec<Captured>>,
+}
+
+impl NameBuilder {
+    /// Creates a new, empty builder.
+    pub fn new() -> Self {
+        NameBuilder { rdns: Vec::new() }
+    }
+
+    /// Adds an attribute as a new RDN.
+    pub fn push(
+        &mut self,
+        attr: NameAttribute,
+        value: &str
+    ) -> Result<(), NameValueError> {
+        let attr = attr.encode_value(value)?;
+        self.rdns.push(vec![attr]);
+        Ok(())
+    }
+
+    /// Adds an attribute to the last RDN.
+    ///
+    /// If there is no RDN yet, a new one is started.
+    pub fn push_to_last(
+        &mut self,
+        attr: NameAttribute,
+        value: &str
+    ) -> Result<(), NameValueError> {
+        let attr = attr.encode_value(value)?;
+        match self.rdns.last_mut() {
+            Some(rdn) => rdn.push(attr),
+            None => self.rdns.push(vec![attr])
+        }
+        Ok(())
+    }
+
+    /// Converts the builder into a name.
+    pub fn finalize(self) -> Name {
+        let mut rdns = Captured::builder(Mode::Der);
+        for mut rdn in self.rdns {
+            // DER requires the elements of a SET OF to be ordered by their
+            // encoding. Since the attributes are complete values, plain
+            // lexicographic order is the same thing.
+            rdn.sort_by(|left, right| left.as_slice().cmp(right.as_slice()));
+            let mut attrs = Captured::builder(Mode::Der);
+            for attr in rdn {
+                attrs.extend(attr)
+            }
+            rdns.extend(encode::set(attrs.freeze()))
+        }
+        Name(Captured::from_values(
+            Mode::Der, encode::sequence(rdns.freeze())
+        ))
+    }
+}
+
+impl Default for NameBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+//------------ NameAttribute -------------------------------------------------
+
+/// The attribute types supported by the name builder.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NameAttribute {
+    /// The common name, `CN`.
+    CommonName,
+
+    /// The serial number, `serialNumber`.
+    ///
+    /// The value must be a printable string.
+    SerialNumber,
+
+    /// The country, `C`.
+    ///
+    /// The value must be a two letter ISO 3166 country code.
+    Country,
+
+    /// The organization, `O`.
+    Organization,
+
+    /// The organizational unit, `OU`.
+    OrganizationalUnit,
+}
+
+impl NameAttribute {
+    const ALL: [NameAttribute; 5] = [
+        NameAttribute::CommonName,
+        NameAttribute::SerialNumber,
+        NameAttribute::Country,
+        NameAttribute::Organization,
+        NameAttribute::OrganizationalUnit,
+    ];
+
+    /// Returns the attribute for an attribute type identifier.
+    pub fn from_oid<T: AsRef<[u8]>>(oid: &Oid<T>) -> Option<Self> {
+        Self::ALL.iter().cloned().find(|attr| *oid == attr.oid())
+    }
+
+    /// Returns the attribute type identifier.
+    pub fn oid(self) -> ConstOid {
+        match self {
+            NameAttribute::CommonName => oid::AT_COMMON_NAME,
+            NameAttribute::SerialNumber => oid::AT_SERIAL_NUMBER,
+            NameAttribute::Country => oid::AT_COUNTRY_NAME,
+            NameAttribute::Organization => oid::AT_ORGANIZATION_NAME,
+            NameAttribute::OrganizationalUnit => {
+                oid::AT_ORGANIZATIONAL_UNIT_NAME
+            }
+        }
+    }
+
+    /// Returns the short name used when displaying names.
+    pub fn short_name(self) -> &'static str {
+        match self {
+            NameAttribute::CommonName => "CN",
+            NameAttribute::SerialNumber => "serialNumber",
+            NameAttribute::Country => "C",
+            NameAttribute::Organization => "O",
+            NameAttribute::OrganizationalUnit => "OU",
+        }
+    }
+
+    /// Returns the DER encoding of the attribute with the given value.
+    ///
+    /// Values are encoded as a PrintableString if possible and as a
+    /// UTF8String otherwise. Attributes that only allow PrintableString
+    /// values return an error for other values.
+    fn encode_value(self, value: &str) -> Result<Captured, NameValueError> {
+        if value.is_empty() {
+            return Err(NameValueError)
+        }
+        let printable = value.bytes().all(|ch| {
+            ch.is_ascii_alphanumeric() || b" '()+,-./:=?".contains(&ch)
+        });
+        let tag = match self {
+            NameAttribute::SerialNumber if !printable => {
+                return Err(NameValueError)
+            }
+            NameAttribute::Country => {
+                if value.len() != 2
+                    || !value.bytes().all(|ch| ch.is_ascii_uppercase())
+                {
+                    return Err(NameValueError)
+                }
+                Tag::PRINTABLE_STRING
+            }
+            _ if printable => Tag::PRINTABLE_STRING,
+            _ => Tag::UTF8_STRING,
+        };
+        Ok(Captured::from_values(Mode::Der, encode::sequence((
+            self.oid().encode(),
+            OctetString::encode_slice_as(value.as_bytes(), tag),
+        ))))
+    }
+}
+
+
+//------------ NameValueError ------------------------------------------------
+
+/// A value is not allowed for a name attribute.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NameValueError;
+
+impl fmt::Display for NameValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid value for name attribute")
+    }
+}
+
+impl error::Error for NameValueError { }
+
+
 //------------ Serial --------------------------------------------------------
 
 /// A certificate serial number.
@@ -202,7 +670,7 @@ impl Serial {
     }
 
     /// Creates a random new serial number.
-    pub fn random<S: Signer>(signer: &S) -> Result<Self, S::Error> {
+    pub fn random<S: Signer + ?Sized>(signer: &S) -> Result<Self, S::Error> {
         let mut res = <[u8; 20]>::default();
         signer.rand(&mut res)?;
         res[0] &= 0x7F;
@@ -217,7 +685,7 @@ impl Serial {
     /// # Panics
     ///
     /// The function panics if `len` is more than 20.
-    pub fn short_random<S: Signer>(
+    pub fn short_random<S: Signer + ?Sized>(
         signer: &S,
         len: usize
     ) -> Result<Self, S::Error> {
@@ -236,6 +704,33 @@ impl Serial {
             
     }
 
+    /// Takes a serial number with a content of at most `max_len` octets.
+    ///
+    /// The length is checked before the content of the value is taken. If
+    /// it is exceeded, a malformed error is returned.
+    pub fn take_limited_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        max_len: usize,
+    ) -> Result<Self, S::Err> {
+        cons.take_primitive_if(Tag::INTEGER, |prim| {
+            if prim.remaining() > max_len {
+                xerr!(return Err(decode::Malformed.into()))
+            }
+            Unsigned::from_primitive(prim).and_then(|s| {
+                Self::from_slice(s.as_ref()).map_err(Into::into)
+            })
+        })
+    }
+
+    /// Returns whether the serial number is zero.
+    ///
+    /// RFC 5280 requires serial numbers of certificates to be positive.
+    /// Since negative values are rejected when decoding, a serial number
+    /// that isn’t zero is positive.
+    pub fn is_zero(self) -> bool {
+        self == Serial::default()
+    }
+
     /// Returns the index of the first octet to encode.
     fn start(self) -> usize {
         let start = self.0.iter().enumerate().find_map(|(idx, &val)| {
@@ -292,10 +787,6 @@ impl Serial {
         step as u8
     }
 
-    fn is_zero(self) -> bool {
-        self == Serial::default()
-    }
-
     fn encode_dec(mut self, target: &mut [u8; 49]) -> &str {
         let mut len = 49;
         while !self.is_zero() {
@@ -380,6 +871,21 @@ impl fmt::Debug for Serial {
     }
 }
 
+/// Formats the serial number in hexadecimal without leading zeros.
+impl fmt::LowerHex for Serial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut octets = self.0.iter().skip_while(|&&octet| octet == 0);
+        match octets.next() {
+            Some(first) => write!(f, "{:x}", first)?,
+            None => return f.write_str("0"),
+        }
+        for octet in octets {
+            write!(f, "{:02x}", octet)?
+        }
+        Ok(())
+    }
+}
+
 
 //--- PrimitiveContent
 
@@ -421,8 +927,143 @@ impl<'de> Deserialize<'de> for Serial {
 }
 
 
+//------------ SerialSource --------------------------------------------------
+
+/// A source of serial numbers for newly issued objects.
+///
+/// Serial numbers of RPKI certificates need to be positive, at most 20
+/// octets long, and unique for each issuer. Implementations of this trait
+/// only ever return serial numbers that meet the first two requirements.
+/// How far they guarantee uniqueness is described with each type.
+pub trait SerialSource {
+    /// The error returned if no serial number can be provided.
+    type Error;
+
+    /// Returns the next serial number.
+    fn next_serial(&mut self) -> Result<Serial, Self::Error>;
+}
+
+
+//------------ SerialCounter -------------------------------------------------
+
+/// A serial number source counting upwards.
+///
+/// Each serial number is one more than the previous one. This makes the
+/// serial numbers deterministic which is useful for tests and for CAs that
+/// keep track of the serial numbers they have issued.
+///
+/// Serial numbers are unique for as long as only one counter is used per
+/// issuer. If a CA restarts, it needs to create the new counter from the
+/// serial number returned by [`peek`] before the shutdown. Once the largest
+/// positive 20 octet value has been issued, the counter is exhausted and
+/// returns an error.
+///
+/// [`peek`]: #method.peek
+#[derive(Clone, Copy, Debug)]
+pub struct SerialCounter {
+    /// The serial number to be returned next.
+    next: Option<Serial>,
+}
+
+impl SerialCounter {
+    /// Creates a new counter starting at the given serial number.
+    ///
+    /// Since serial numbers need to be positive, a start of zero is
+    /// replaced by one.
+    pub fn new(start: Serial) -> Self {
+        SerialCounter {
+            next: Some(if start.is_zero() { 1u64.into() } else { start })
+        }
+    }
+
+    /// Returns the serial number that will be returned next.
+    ///
+    /// Returns `None` if the counter is exhausted.
+    pub fn peek(&self) -> Option<Serial> {
+        self.next
+    }
+}
+
+impl Default for SerialCounter {
+    fn default() -> Self {
+        Self::new(Serial::default())
+    }
+}
+
+impl SerialSource for SerialCounter {
+    type Error = SerialExhausted;
+
+    fn next_serial(&mut self) -> Result<Serial, Self::Error> {
+        let res = self.next.ok_or(SerialExhausted)?;
+        self.next = res.checked_add_u8(1);
+        Ok(res)
+    }
+}
+
+
+//------------ RandomSerial --------------------------------------------------
+
+/// A serial number source generating random serial numbers.
+///
+/// The serial numbers are created from 20 octets provided by the signer’s
+/// random number generator with the most significant bit cleared, so they
+/// carry 159 bits of randomness. Uniqueness is not guaranteed but a
+/// collision becomes likely only after about 2^79 serial numbers. A serial
+/// number of zero is never returned.
+pub struct RandomSerial<'a, S: ?Sized> {
+    /// The signer providing randomness.
+    signer: &'a S,
+}
+
+impl<'a, S: Signer + ?Sized> RandomSerial<'a, S> {
+    /// Creates a new random source using the given signer.
+    pub fn new(signer: &'a S) -> Self {
+        RandomSerial { signer }
+    }
+}
+
+impl<'a, S: Signer + ?Sized> SerialSource for RandomSerial<'a, S> {
+    type Error = S::Error;
+
+    fn next_serial(&mut self) -> Result<Serial, Self::Error> {
+        loop {
+            let res = Serial::random(self.signer)?;
+            if !res.is_zero() {
+                return Ok(res)
+            }
+        }
+    }
+}
+
+
+//------------ SerialExhausted -----------------------------------------------
+
+/// A serial number counter has run out of serial numbers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SerialExhausted;
+
+impl fmt::Display for SerialExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("serial numbers exhausted")
+    }
+}
+
+impl error::Error for SerialExhausted { }
+
+
 //------------ SignedData ----------------------------------------------------
 
+/// The outer structure of a signed X.509 object.
+///
+/// This keeps the signed data as captured during decoding together with
+/// the signature. Both are kept in reference counted `Bytes` values, so
+/// cloning a value is cheap and the clone shares its data with the
+/// original.
+///
+/// The signature is always verified against the exact octets of the signed
+/// data as they appeared in the decoded object. The data is never
+/// re-encoded, so verification succeeds for whatever was actually signed
+/// even if it isn’t encoded canonically.
 #[derive(Clone, Debug)]
 pub struct SignedData {
     data: Captured,
@@ -463,6 +1104,7 @@ impl SignedData {
         })
     }
 
+    /// Returns the signed data exactly as it was decoded.
     pub fn data(&self) -> &Captured {
         &self.data
     }
@@ -477,6 +1119,65 @@ impl SignedData {
         ).map_err(Into::into)
     }
 
+    /// Verifies the signature reusing a scratch buffer.
+    ///
+    /// This is the counterpart of
+    /// [`SignedObject::validate_with_scratch`][validate] for plain signed
+    /// data. The signed data is verified exactly as captured, so nothing
+    /// needs to be re-encoded and `scratch` is only cleared. Any
+    /// allocations that remain happen inside _ring_’s RSA arithmetic.
+    ///
+    /// [validate]: ../sigobj/struct.SignedObject.html#method.validate_with_scratch
+    pub fn verify_signature_into(
+        &self,
+        public_key: &PublicKey,
+        scratch: &mut Vec<u8>,
+    ) -> Result<(), ValidationError> {
+        scratch.clear();
+        self.verify_signature(public_key)
+    }
+
+    /// Verifies the signature if its algorithm is permitted by a policy.
+    ///
+    /// If the signature algorithm isn’t permitted by `policy`, returns
+    /// [`SignatureError::Algorithm`] without attempting to verify the
+    /// signature at all.
+    ///
+    /// [`SignatureError::Algorithm`]: enum.SignatureError.html
+    pub fn verify_signature_with_policy(
+        &self,
+        public_key: &PublicKey,
+        policy: &AlgorithmPolicy,
+    ) -> Result<(), SignatureError> {
+        let algorithm = self.signature.algorithm();
+        if !policy.permits(algorithm) {
+            return Err(SignatureError::Algorithm(algorithm))
+        }
+        public_key.verify(
+            self.data.as_ref(),
+            &self.signature
+        ).map_err(Into::into)
+    }
+
+    /// Verifies the signature using a precomputed digest of the data.
+    ///
+    /// The digest must have been created with the digest algorithm of the
+    /// signature’s algorithm. This is only available with the `prehashed`
+    /// feature. See [`PublicKey::verify_prehashed`] for details.
+    ///
+    /// [`PublicKey::verify_prehashed`]: ../crypto/keys/struct.PublicKey.html#method.verify_prehashed
+    #[cfg(feature = "prehashed")]
+    pub fn verify_signature_prehashed(
+        &self,
+        public_key: &PublicKey,
+        digest: &[u8]
+    ) -> Result<(), ValidationError> {
+        public_key.verify_prehashed(
+            digest,
+            &self.signature
+        ).map_err(Into::into)
+    }
+
     pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
         encode::sequence((
             &self.data,
@@ -510,6 +1211,15 @@ impl<'a> PrimitiveContent for SignatureValueContent<'a> {
 
 //------------ Time ----------------------------------------------------------
 
+/// A point in time.
+///
+/// This is the time type used throughout the crate for validity periods,
+/// the update times of manifests and CRLs, and the `now` argument of the
+/// various validation methods. It wraps a chrono `DateTime<Utc>` and
+/// converts from and into it via `From`, [`new`], and [`to_chrono`].
+///
+/// [`new`]: #method.new
+/// [`to_chrono`]: #method.to_chrono
 #[derive(
     Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd,
     Serialize
@@ -517,14 +1227,21 @@ impl<'a> PrimitiveContent for SignatureValueContent<'a> {
 pub struct Time(DateTime<Utc>);
 
 impl Time {
+    /// Creates a time from a chrono date and time.
     pub fn new(dt: DateTime<Utc>) -> Self {
         Time(dt)
     }
 
+    /// Returns the current time.
     pub fn now() -> Self {
         Self::new(Utc::now())
     }
 
+    /// Converts the time into a chrono date and time.
+    pub fn to_chrono(self) -> DateTime<Utc> {
+        self.0
+    }
+
     pub fn five_minutes_ago() -> Self {
         Self::now() - Duration::minutes(5)
     }
@@ -584,44 +1301,18 @@ impl Time {
         self.0.timestamp()
     }
 
+    /// Takes a time value from the beginning of a constructed value.
+    ///
+    /// The time can be encoded either as a UTCTime or a GeneralizedTime.
+    /// RFC 5280 demands the former for times before 2050 and the latter
+    /// for all later times, but both are accepted for any time.
     pub fn take_from<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
         cons.take_primitive(|tag, prim| {
             match tag {
-                Tag::UTC_TIME => {
-                    // RFC 5280 requires the format YYMMDDHHMMSSZ
-                    let year = read_two_char(prim)? as i32;
-                    let year = if year >= 50 { year + 1900 }
-                               else { year + 2000 };
-                    let res = (
-                        year,
-                        read_two_char(prim)?,
-                        read_two_char(prim)?,
-                        read_two_char(prim)?,
-                        read_two_char(prim)?,
-                        read_two_char(prim)?,
-                    );
-                    if prim.take_u8()? != b'Z' {
-                        return Err(decode::Malformed.into())
-                    }
-                    Self::from_parts(res).map_err(Into::into)
-                }
-                Tag::GENERALIZED_TIME => {
-                    // RFC 5280 requires the format YYYYMMDDHHMMSSZ
-                    let res = (
-                        read_four_char(prim)? as i32,
-                        read_two_char(prim)?,
-                        read_two_char(prim)?,
-                        read_two_char(prim)?,
-                        read_two_char(prim)?,
-                        read_two_char(prim)?,
-                    );
-                    if prim.take_u8()? != b'Z' {
-                        return Err(decode::Malformed.into())
-                    }
-                    Self::from_parts(res).map_err(Into::into)
-                }
+                Tag::UTC_TIME => Self::from_utc_time(prim),
+                Tag::GENERALIZED_TIME => Self::from_generalized_time(prim),
                 _ => {
                     xerr!(Err(decode::Malformed.into()))
                 }
@@ -629,43 +1320,64 @@ impl Time {
         })
     }
 
+    /// Takes an optional time value from the beginning of a value.
+    ///
+    /// Like [`take_from`], this accepts both UTCTime and GeneralizedTime.
+    ///
+    /// [`take_from`]: #method.take_from
     pub fn take_opt_from<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Option<Self>, S::Err> {
-        let res = cons.take_opt_primitive_if(Tag::UTC_TIME, |prim| {
-            let year = read_two_char(prim)? as i32;
-            let year = if year >= 50 { year + 1900 }
-                       else { year + 2000 };
-            let res = (
-                year,
-                read_two_char(prim)?,
-                read_two_char(prim)?,
-                read_two_char(prim)?,
-                read_two_char(prim)?,
-                read_two_char(prim)?,
-            );
-            if prim.take_u8()? != b'Z' {
-                return Err(decode::Malformed.into())
-            }
-            Self::from_parts(res).map_err(Into::into)
-        })?;
+        let res = cons.take_opt_primitive_if(
+            Tag::UTC_TIME, Self::from_utc_time
+        )?;
         if let Some(res) = res {
             return Ok(Some(res))
         }
-        cons.take_opt_primitive_if(Tag::GENERALIZED_TIME, |prim| {
-            let res = (
-                read_four_char(prim)? as i32,
-                read_two_char(prim)?,
-                read_two_char(prim)?,
-                read_two_char(prim)?,
-                read_two_char(prim)?,
-                read_two_char(prim)?,
-            );
-            if prim.take_u8()? != b'Z' {
-                return Err(decode::Malformed.into())
-            }
-            Self::from_parts(res).map_err(Into::into)
-        })
+        cons.take_opt_primitive_if(
+            Tag::GENERALIZED_TIME, Self::from_generalized_time
+        )
+    }
+
+    /// Parses the content of a UTCTime value.
+    fn from_utc_time<S: decode::Source>(
+        prim: &mut decode::Primitive<S>
+    ) -> Result<Self, S::Err> {
+        // RFC 5280 requires the format YYMMDDHHMMSSZ
+        let year = read_two_char(prim)? as i32;
+        let year = if year >= 50 { year + 1900 }
+                   else { year + 2000 };
+        let res = (
+            year,
+            read_two_char(prim)?,
+            read_two_char(prim)?,
+            read_two_char(prim)?,
+            read_two_char(prim)?,
+            read_two_char(prim)?,
+        );
+        if prim.take_u8()? != b'Z' {
+            return Err(decode::Malformed.into())
+        }
+        Self::from_parts(res).map_err(Into::into)
+    }
+
+    /// Parses the content of a GeneralizedTime value.
+    fn from_generalized_time<S: decode::Source>(
+        prim: &mut decode::Primitive<S>
+    ) -> Result<Self, S::Err> {
+        // RFC 5280 requires the format YYYYMMDDHHMMSSZ
+        let res = (
+            read_four_char(prim)? as i32,
+            read_two_char(prim)?,
+            read_two_char(prim)?,
+            read_two_char(prim)?,
+            read_two_char(prim)?,
+            read_two_char(prim)?,
+        );
+        if prim.take_u8()? != b'Z' {
+            return Err(decode::Malformed.into())
+        }
+        Self::from_parts(res).map_err(Into::into)
     }
 
     fn from_parts(
@@ -991,6 +1703,50 @@ impl fmt::Display for RepresentationError {
 impl error::Error for RepresentationError { }
 
 
+//------------ ReadError -----------------------------------------------------
+
+/// An error happened while reading a value from a reader.
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading from the reader failed.
+    Io(io::Error),
+
+    /// The data read is not a correctly encoded value.
+    Decode(decode::Error),
+
+    /// The value is longer than allowed.
+    ///
+    /// Contains the length of the value’s content.
+    TooLarge(u64),
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl From<decode::Error> for ReadError {
+    fn from(err: decode::Error) -> Self {
+        ReadError::Decode(err)
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadError::Io(ref err) => err.fmt(f),
+            ReadError::Decode(ref err) => err.fmt(f),
+            ReadError::TooLarge(len) => {
+                write!(f, "value of {} octets is too large", len)
+            }
+        }
+    }
+}
+
+impl error::Error for ReadError { }
+
+
 //------------ ValidationError -----------------------------------------------
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -1017,6 +1773,44 @@ impl fmt::Display for ValidationError {
 impl error::Error for ValidationError { }
 
 
+//------------ SignatureError ------------------------------------------------
+
+/// An error happened while verifying a signature under a policy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignatureError {
+    /// The signature algorithm isn’t permitted by the policy.
+    Algorithm(SignatureAlgorithm),
+
+    /// The signature is invalid.
+    Invalid,
+}
+
+impl From<VerificationError> for SignatureError {
+    fn from(_: VerificationError) -> SignatureError {
+        SignatureError::Invalid
+    }
+}
+
+impl From<SignatureError> for ValidationError {
+    fn from(_: SignatureError) -> ValidationError {
+        ValidationError
+    }
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SignatureError::Algorithm(alg) => {
+                write!(f, "signature algorithm {:?} not permitted", alg)
+            }
+            SignatureError::Invalid => f.write_str("invalid signature")
+        }
+    }
+}
+
+impl error::Error for SignatureError { }
+
+
 //------------ Testing. One. Two. Three --------------------------------------
 
 #[cfg(test)]
@@ -1033,6 +1827,175 @@ mod test {
         obj.encode_ref().write_encoded(Mode::Der, &mut encoded).unwrap();
         assert_eq!(data.len(), encoded.len());
         assert_eq!(data.as_ref(), AsRef::<[u8]>::as_ref(&encoded));
+        assert_eq!(obj.data().as_slice(), &data[4..0x2fa]);
+    }
+
+    #[test]
+    fn verify_signature_into() {
+        use crate::cert::Cert;
+
+        let data = include_bytes!("../test-data/ta.cer");
+        let obj = SignedData::decode(data.as_ref()).unwrap();
+        let cert = Cert::decode(data.as_ref()).unwrap();
+        let mut scratch = vec![0xFFu8; 7];
+        obj.verify_signature_into(
+            cert.subject_public_key_info(), &mut scratch
+        ).unwrap();
+        assert!(scratch.is_empty());
+    }
+
+    #[test]
+    fn verify_signature_with_policy() {
+        use crate::cert::Cert;
+
+        let data = include_bytes!("../test-data/ta.cer");
+        let obj = SignedData::decode(data.as_ref()).unwrap();
+        let cert = Cert::decode(data.as_ref()).unwrap();
+        let key = cert.subject_public_key_info();
+        obj.verify_signature_with_policy(
+            key, &AlgorithmPolicy::default()
+        ).unwrap();
+        assert_eq!(
+            obj.verify_signature_with_policy(key, &AlgorithmPolicy::none()),
+            Err(SignatureError::Algorithm(SignatureAlgorithm::RsaSha256))
+        );
+
+        let other = include_bytes!("../test-data/ca1.cer");
+        let other = Cert::decode(other.as_ref()).unwrap();
+        assert_eq!(
+            obj.verify_signature_with_policy(
+                other.subject_public_key_info(), &AlgorithmPolicy::all()
+            ),
+            Err(SignatureError::Invalid)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "prehashed")]
+    fn verify_signature_prehashed() {
+        use crate::cert::Cert;
+        use crate::crypto::digest::DigestAlgorithm;
+
+        let data = include_bytes!("../test-data/ta.cer");
+        let obj = SignedData::decode(data.as_ref()).unwrap();
+        let cert = Cert::decode(data.as_ref()).unwrap();
+        let key = cert.subject_public_key_info();
+        let digest = DigestAlgorithm::default().digest(obj.data().as_slice());
+        obj.verify_signature_prehashed(key, digest.as_ref()).unwrap();
+
+        let mut wrong = digest.as_ref().to_vec();
+        wrong[0] ^= 1;
+        assert!(obj.verify_signature_prehashed(key, &wrong).is_err());
+        assert!(
+            obj.verify_signature_prehashed(key, &digest.as_ref()[..20])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn verify_canonical_der() {
+        for data in &[
+            b"\x30\x06\x02\x01\x05\x01\x01\xff".as_ref(),
+            b"\x31\x06\x02\x01\x01\x02\x01\x02".as_ref(),
+            b"\x03\x02\x04\xf0".as_ref(),
+            b"\x05\x00".as_ref(),
+            b"\xa0\x03\x02\x01\x01".as_ref(),
+            b"\x9f\x1f\x00".as_ref(),
+            b"\x02\x02\xff\x7f".as_ref(),
+            b"\x02\x02\x00\x80".as_ref(),
+            include_bytes!("../test-data/ta.crl").as_ref(),
+        ] {
+            assert!(verify_canonical(data).is_ok(), "{:?}", data);
+        }
+
+        let mut long_len = b"\x04\x82\x00\x80".to_vec();
+        long_len.extend_from_slice(&[0; 128]);
+        for data in &[
+            // Trailing data.
+            b"\x05\x00\x00".as_ref(),
+            // Non-minimal lengths.
+            b"\x04\x81\x01\x00".as_ref(),
+            long_len.as_slice(),
+            // Indefinite length.
+            b"\x30\x80\x05\x00\x00\x00".as_ref(),
+            // Non-minimal tag numbers.
+            b"\x9f\x1e\x00".as_ref(),
+            b"\x9f\x80\x1f\x00".as_ref(),
+            // Boolean true must be all ones.
+            b"\x01\x01\x01".as_ref(),
+            // Non-minimal and empty integers.
+            b"\x02\x02\x00\x05".as_ref(),
+            b"\x02\x02\xff\x80".as_ref(),
+            b"\x02\x00".as_ref(),
+            // Unused bits must be zero and need bits.
+            b"\x03\x02\x04\xf8".as_ref(),
+            b"\x03\x01\x04".as_ref(),
+            // Null must be empty.
+            b"\x05\x01\x00".as_ref(),
+            // Sets must be sorted.
+            b"\x31\x06\x02\x01\x02\x02\x01\x01".as_ref(),
+            // Constructed octet string and primitive sequence.
+            b"\x24\x03\x04\x01\x00".as_ref(),
+            b"\x10\x00".as_ref(),
+            // Nested violations.
+            b"\x30\x05\xa0\x03\x01\x01\x01".as_ref(),
+            // Short content.
+            b"\x04\x02\x00".as_ref(),
+        ] {
+            assert!(verify_canonical(data).is_err(), "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn time_chrono() {
+        let dt = Utc.ymd(2020, 3, 1).and_hms(12, 0, 0);
+        let time = Time::from(dt);
+        assert_eq!(time, Time::utc(2020, 3, 1, 12, 0, 0));
+        assert_eq!(time.to_chrono(), dt);
+        assert_eq!(DateTime::<Utc>::from(time), dt);
+        assert!(Time::new(dt) < Time::now());
+        assert!(Time::new(dt + Duration::seconds(1)) > time);
+    }
+
+    #[test]
+    fn name_builder() {
+        let mut builder = NameBuilder::new();
+        builder.push(NameAttribute::Country, "NL").unwrap();
+        builder.push(
+            NameAttribute::Organization, "Example Organization"
+        ).unwrap();
+        builder.push_to_last(
+            NameAttribute::OrganizationalUnit, "RPKI"
+        ).unwrap();
+        builder.push(NameAttribute::CommonName, "Grüße").unwrap();
+        builder.push_to_last(NameAttribute::SerialNumber, "1234").unwrap();
+        let der = Captured::from_values(
+            Mode::Der, builder.finalize().encode_ref()
+        ).into_bytes();
+
+        // The attributes within an RDN are sorted by their encoding, so
+        // the shorter ones come first.
+        verify_canonical(der.as_ref()).unwrap();
+        let name = Mode::Der.decode(der.clone(), Name::take_from).unwrap();
+        assert_eq!(
+            name.to_string(),
+            "C=NL, OU=RPKI, O=Example Organization, serialNumber=1234, \
+             CN=Grüße"
+        );
+        assert_eq!(
+            Captured::from_values(Mode::Der, name.encode_ref()).into_bytes(),
+            der
+        );
+
+        let mut builder = NameBuilder::default();
+        assert!(builder.push(NameAttribute::CommonName, "").is_err());
+        assert!(builder.push(NameAttribute::Country, "nl").is_err());
+        assert!(builder.push(NameAttribute::Country, "NLD").is_err());
+        assert!(builder.push(NameAttribute::SerialNumber, "1_2").is_err());
+        builder.push_to_last(NameAttribute::CommonName, "Only").unwrap();
+        let name = builder.finalize();
+        assert_eq!(name.to_string(), "CN=Only");
+        name.validate_rpki(true).unwrap();
     }
 
     #[test]
@@ -1057,6 +2020,64 @@ mod test {
             ).unwrap(),
             Serial([0,0,0,0,0,0,0,0,0,0, 0,0,0,0,0,0,0,1,2,3])
         );
+
+        // Zero is fine for decoding.
+        assert!(
+            Constructed::decode(
+                b"\x02\x01\x00".as_ref(), Mode::Der, Serial::take_from
+            ).unwrap().is_zero()
+        );
+
+        // Negative.
+        assert!(
+            Constructed::decode(
+                b"\x02\x01\xff".as_ref(), Mode::Der, Serial::take_from
+            ).is_err()
+        );
+
+        // Twenty octets is the maximum.
+        assert!(
+            Constructed::decode(
+                b"\x02\x14\x7f\xff\xff\xff\xff\xff\xff\xff\xff\xff\
+                  \xff\xff\xff\xff\xff\xff\xff\xff\xff\xff".as_ref(),
+                Mode::Der, Serial::take_from
+            ).is_ok()
+        );
+        assert!(
+            Constructed::decode(
+                b"\x02\x15\x00\xff\xff\xff\xff\xff\xff\xff\xff\xff\
+                  \xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff".as_ref(),
+                Mode::Der, Serial::take_from
+            ).is_err()
+        );
+        assert!(
+            Constructed::decode(
+                b"\x02\x15\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+                  \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".as_ref(),
+                Mode::Der, Serial::take_from
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn serial_counter() {
+        let mut counter = SerialCounter::default();
+        assert_eq!(counter.peek(), Some(1u64.into()));
+        assert_eq!(counter.next_serial(), Ok(1u64.into()));
+        assert_eq!(counter.next_serial(), Ok(2u64.into()));
+
+        let mut counter = SerialCounter::new(255u64.into());
+        assert_eq!(counter.next_serial(), Ok(255u64.into()));
+        assert_eq!(counter.next_serial(), Ok(256u64.into()));
+        assert_eq!(counter.peek(), Some(257u64.into()));
+
+        let mut max = [0xFFu8; 20];
+        max[0] = 0x7F;
+        let max = Serial::from_slice(&max).unwrap();
+        let mut counter = SerialCounter::new(max);
+        assert_eq!(counter.next_serial(), Ok(max));
+        assert_eq!(counter.peek(), None);
+        assert_eq!(counter.next_serial(), Err(SerialExhausted));
     }
 
     #[test]
@@ -1100,6 +2121,13 @@ mod test {
         assert_eq!(Serial::from_str("0").unwrap(), Serial::default());
     }
 
+    #[test]
+    fn serial_lower_hex() {
+        assert_eq!(format!("{:x}", Serial::from(0u64)), "0");
+        assert_eq!(format!("{:x}", Serial::from(201u64)), "c9");
+        assert_eq!(format!("{:x}", Serial::from(0x10203u64)), "10203");
+    }
+
     #[test]
     fn string_from_serial() {
         assert_eq!(
@@ -1127,6 +2155,78 @@ mod test {
         );
     }
 
+    #[test]
+    fn validity_take_from() {
+        // UTCTime for both.
+        let validity = Constructed::decode(
+            b"\x30\x1e\
+              \x17\x0d190226131444Z\
+              \x17\x0d490226131444Z".as_ref(),
+            Mode::Der, Validity::take_from
+        ).unwrap();
+        assert_eq!(validity.not_before(), Time::utc(2019, 2, 26, 13, 14, 44));
+        assert_eq!(validity.not_after(), Time::utc(2049, 2, 26, 13, 14, 44));
+
+        // UTCTime and GeneralizedTime mixed.
+        let validity = Constructed::decode(
+            b"\x30\x20\
+              \x17\x0d190226131444Z\
+              \x18\x0f20510226131444Z".as_ref(),
+            Mode::Der, Validity::take_from
+        ).unwrap();
+        assert_eq!(validity.not_before(), Time::utc(2019, 2, 26, 13, 14, 44));
+        assert_eq!(validity.not_after().year(), 2051);
+        assert_eq!(validity.not_after(), Time::utc(2051, 2, 26, 13, 14, 44));
+        assert!(
+            validity.validate_at(Time::utc(2050, 1, 1, 0, 0, 0)).is_ok()
+        );
+        assert!(
+            validity.validate_at(Time::utc(2051, 3, 1, 0, 0, 0)).is_err()
+        );
+
+        // GeneralizedTime for both, even if before 2050.
+        let validity = Constructed::decode(
+            b"\x30\x22\
+              \x18\x0f20190226131444Z\
+              \x18\x0f20510226131444Z".as_ref(),
+            Mode::Der, Validity::take_from
+        ).unwrap();
+        assert_eq!(validity.not_before(), Time::utc(2019, 2, 26, 13, 14, 44));
+        assert_eq!(validity.not_after(), Time::utc(2051, 2, 26, 13, 14, 44));
+
+        // Two-digit years of 50 and up are in the last century.
+        let validity = Constructed::decode(
+            b"\x30\x1e\
+              \x17\x0d500226131444Z\
+              \x17\x0d990226131444Z".as_ref(),
+            Mode::Der, Validity::take_from
+        ).unwrap();
+        assert_eq!(validity.not_before().year(), 1950);
+        assert_eq!(validity.not_after().year(), 1999);
+    }
+
+    #[test]
+    fn validity_encode() {
+        let validity = Validity::new(
+            Time::utc(2019, 2, 26, 13, 14, 44),
+            Time::utc(2051, 2, 26, 13, 14, 44),
+        );
+        let mut target = Vec::new();
+        validity.encode().write_encoded(Mode::Der, &mut target).unwrap();
+        assert_eq!(
+            target,
+            b"\x30\x20\
+              \x17\x0d190226131444Z\
+              \x18\x0f20510226131444Z".as_ref()
+        );
+        assert_eq!(
+            Constructed::decode(
+                target.as_slice(), Mode::Der, Validity::take_from
+            ).unwrap(),
+            validity
+        );
+    }
+
     #[test]
     fn next_year() {
         let now = DateTime::parse_from_rfc3339("2014-10-21T16:39:57-00:00").unwrap();
@@ -1190,3 +2290,55 @@ mod test {
     }
 }
 
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use crate::crypto::softsigner::OpenSslSigner;
+    use super::*;
+
+    #[test]
+    fn signed_data_verify_original() {
+        use bcder::encode::Values;
+        use crate::crypto::PublicKeyFormat;
+
+        // The TBS of a real certificate with the length of the outer
+        // sequence encoded in three rather than the two octets required by
+        // DER. Re-encoding would produce the original, minimal length.
+        let ta = SignedData::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap();
+        let orig = ta.data().as_slice();
+        assert_eq!(&orig[..2], b"\x30\x82");
+        let mut tbs = vec![0x30, 0x83, 0x00];
+        tbs.extend_from_slice(&orig[2..]);
+        let tbs = Bytes::from(tbs);
+
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let signature = signer.sign(
+            &key, SignatureAlgorithm::default(), tbs.as_ref()
+        ).unwrap();
+        let data = Mode::Ber.decode(tbs.clone(), |cons| {
+            cons.capture_one()
+        }).unwrap();
+        let encoded = SignedData::new(
+            data, signature
+        ).encode_ref().to_captured(Mode::Ber).into_bytes();
+
+        // DER doesn’t allow the long length, so decode as BER.
+        let obj = Mode::Ber.decode(encoded, SignedData::take_from).unwrap();
+        assert_eq!(obj.data().as_slice(), tbs.as_ref());
+        obj.verify_signature(&signer.get_key_info(&key).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn random_serial() {
+        let signer = OpenSslSigner::new();
+        let mut source = RandomSerial::new(&signer);
+        let first = source.next_serial().unwrap();
+        let second = source.next_serial().unwrap();
+        assert_ne!(first, second);
+        for serial in &[first, second] {
+            assert!(!serial.is_zero());
+        }
+    }
+}
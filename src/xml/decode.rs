@@ -212,6 +212,36 @@ impl Content {
         }
     }
 
+    /// Takes base64 encoded text as the content of the element `name`.
+    ///
+    /// Any white space in the text, such as line breaks inserted to keep
+    /// lines short, is ignored. So is missing or superfluous padding at
+    /// the end. If the text is not valid base64 otherwise, an error
+    /// naming the element is returned.
+    pub fn take_base64<R: io::BufRead>(
+        &mut self,
+        reader: &mut Reader<R>,
+        name: Name
+    ) -> Result<Bytes, Error> {
+        self.take_text(reader, |text| {
+            let mut text: Vec<_> = text.to_ascii()?.as_bytes()
+            .iter().filter_map(|b| {
+                if b.is_ascii_whitespace() { None }
+                else { Some(*b) }
+            }).collect();
+            while text.last() == Some(&b'=') {
+                text.pop();
+            }
+            base64::decode_config(&text, base64::STANDARD_NO_PAD)
+                .map(Into::into)
+                .map_err(|_| {
+                    Error::InvalidBase64(
+                        String::from_utf8_lossy(name.local).into_owned()
+                    )
+                })
+        })
+    }
+
     pub fn take_end<R: io::BufRead>(
         &mut self,
         reader: &mut Reader<R>
@@ -301,6 +331,14 @@ impl<'a> AttrValue<'a> {
         }
         Ok(s.into_owned().into())
     }
+
+    /// Returns the unescaped value as a string.
+    ///
+    /// Unlike the other methods, this accepts any valid UTF-8.
+    pub fn into_string(self) -> Result<String, Error> {
+        let s = self.0.unescaped_value()?;
+        String::from_utf8(s.into_owned()).map_err(|_| Error::Malformed)
+    }
 }
 
 
@@ -309,15 +347,21 @@ impl<'a> AttrValue<'a> {
 pub struct Text<'a>(quick_xml::events::BytesText<'a>);
 
 impl<'a> Text<'a> {
+    /// Returns the unescaped text as a string.
+    ///
+    /// The text is received from a remote party and therefore checked to
+    /// be valid UTF-8. If it isn’t, a malformed error is returned.
     pub fn to_ascii(&self) -> Result<Cow<str>, Error> {
         match self.0.unescaped()? {
             Cow::Borrowed(s) => {
-                Ok(Cow::Borrowed(
-                    unsafe { str::from_utf8_unchecked(s) }
-                ))
+                str::from_utf8(s).map(Cow::Borrowed).map_err(|_| {
+                    Error::Malformed
+                })
             }
             Cow::Owned(s) => {
-                Ok(Cow::Owned(unsafe { String::from_utf8_unchecked(s) }))
+                String::from_utf8(s).map(Cow::Owned).map_err(|_| {
+                    Error::Malformed
+                })
             }
         }
     }
@@ -330,6 +374,15 @@ impl<'a> Text<'a> {
 pub enum Error {
     Xml(quick_xml::Error),
     Malformed,
+
+    /// The message is of a protocol version we don't support.
+    UnsupportedVersion(String),
+
+    /// The message is of an unknown or unexpected type.
+    UnexpectedType(String),
+
+    /// The content of the element with the given name is not valid base64.
+    InvalidBase64(String),
 }
 
 impl From<quick_xml::Error> for Error {
@@ -343,6 +396,15 @@ impl fmt::Display for Error {
         match *self {
             Error::Xml(ref err) => err.fmt(f),
             Error::Malformed => f.write_str("malformed XML"),
+            Error::UnsupportedVersion(ref version) => {
+                write!(f, "unsupported protocol version '{}'", version)
+            }
+            Error::UnexpectedType(ref msg_type) => {
+                write!(f, "unexpected message type '{}'", msg_type)
+            }
+            Error::InvalidBase64(ref name) => {
+                write!(f, "invalid base64 in element '{}'", name)
+            }
         }
     }
 }
@@ -0,0 +1,72 @@
+//! Helpers for writing XML.
+//!
+//! Our XML documents are simple enough that we write them directly. These
+//! helpers take care of the bits that are easy to get wrong.
+
+use std::{fmt, io};
+
+
+//------------ Functions -----------------------------------------------------
+
+/// Writes an attribute with its value escaped.
+///
+/// The attribute is preceded by a single space so it can be written
+/// directly after the element name or a preceding attribute.
+pub fn write_attr<W: io::Write, T: fmt::Display>(
+    target: &mut W,
+    name: &str,
+    value: T
+) -> io::Result<()> {
+    write!(target, " {}=\"{}\"", name, Escaped(&value.to_string()))
+}
+
+/// Writes an attribute if a value is present.
+pub fn write_opt_attr<W: io::Write, T: fmt::Display>(
+    target: &mut W,
+    name: &str,
+    value: Option<T>
+) -> io::Result<()> {
+    match value {
+        Some(value) => write_attr(target, name, value),
+        None => Ok(())
+    }
+}
+
+
+//------------ Escaped -------------------------------------------------------
+
+/// A helper type to write a string with XML special characters escaped.
+pub struct Escaped<'a>(pub &'a str);
+
+impl<'a> fmt::Display for Escaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for ch in self.0.chars() {
+            match ch {
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '&' => f.write_str("&amp;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                _ => write!(f, "{}", ch)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaped() {
+        assert_eq!(
+            Escaped("<a href=\"x\">&'</a>").to_string(),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;&lt;/a&gt;"
+        );
+        assert_eq!(Escaped("plain").to_string(), "plain");
+    }
+}
@@ -1,3 +1,4 @@
 //! XML decoding and encoding.
 
 pub mod decode;
+pub mod encode;
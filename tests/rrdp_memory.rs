@@ -0,0 +1,147 @@
+//! Checks that processing an RRDP snapshot needs bounded memory.
+//!
+//! This lives in its own test binary because it installs a global
+//! allocator that tracks the amount of memory currently allocated.
+
+use std::{cmp, io};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rpki::rrdp::SnapshotFile;
+
+
+//------------ Counting ------------------------------------------------------
+
+/// An allocator that keeps track of current and peak memory use.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(
+                layout.size(), Ordering::SeqCst
+            ) + layout.size();
+            let mut peak = PEAK.load(Ordering::SeqCst);
+            while current > peak {
+                match PEAK.compare_exchange(
+                    peak, current, Ordering::SeqCst, Ordering::SeqCst
+                ) {
+                    Ok(_) => break,
+                    Err(old) => peak = old
+                }
+            }
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+
+//------------ SyntheticSnapshot ---------------------------------------------
+
+const SESSION: &str = "9df4b597-af9e-4dca-bdda-719cce2c4e28";
+
+/// A reader producing a large snapshot file one object at a time.
+struct SyntheticSnapshot {
+    count: usize,
+    next: usize,
+    buf: Vec<u8>,
+    pos: usize,
+    total: usize,
+}
+
+impl SyntheticSnapshot {
+    fn new(count: usize) -> Self {
+        SyntheticSnapshot {
+            count, next: 0, buf: Vec::with_capacity(4096), pos: 0, total: 0
+        }
+    }
+
+    fn object(idx: usize) -> Vec<u8> {
+        format!("object {:08}|", idx).repeat(100).into_bytes()
+    }
+
+    fn fill(&mut self) {
+        self.buf.clear();
+        self.pos = 0;
+        if self.next == 0 {
+            self.buf.extend_from_slice(format!(
+                "<snapshot xmlns=\"http://www.ripe.net/rpki/rrdp\" \
+                   version=\"1\" session_id=\"{}\" serial=\"3\">\n",
+                SESSION
+            ).as_bytes());
+        }
+        else if self.next <= self.count {
+            let idx = self.next - 1;
+            self.buf.extend_from_slice(format!(
+                "<publish uri=\"rsync://example.com/repo/{}.cer\">\n",
+                idx
+            ).as_bytes());
+            let data = base64::encode(&Self::object(idx));
+            for line in data.as_bytes().chunks(64) {
+                self.buf.extend_from_slice(line);
+                self.buf.push(b'\n');
+            }
+            self.buf.extend_from_slice(b"</publish>\n");
+        }
+        else if self.next == self.count + 1 {
+            self.buf.extend_from_slice(b"</snapshot>\n");
+        }
+        self.next += 1;
+    }
+}
+
+impl io::Read for SyntheticSnapshot {
+    fn read(&mut self, out: &mut [u8]) -> Result<usize, io::Error> {
+        if self.pos == self.buf.len() {
+            self.fill();
+        }
+        let len = cmp::min(out.len(), self.buf.len() - self.pos);
+        out[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+        self.total += len;
+        Ok(len)
+    }
+}
+
+
+//------------ Tests ---------------------------------------------------------
+
+#[test]
+fn snapshot_file_bounded_memory() {
+    let mut source = SyntheticSnapshot::new(3000);
+    let file = SnapshotFile::new(SESSION.parse().unwrap(), 3);
+    let reader = io::BufReader::new(&mut source);
+    let mut next = 0;
+
+    // Everything allocated from here on is used by the parser, the
+    // closure, or the source while producing the next object.
+    let base = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(base, Ordering::SeqCst);
+    file.process(reader, |uri, data| {
+        assert_eq!(
+            uri.as_str(),
+            format!("rsync://example.com/repo/{}.cer", next)
+        );
+        assert_eq!(
+            data.as_ref(), SyntheticSnapshot::object(next).as_slice()
+        );
+        next += 1;
+        Ok::<_, ()>(())
+    }).unwrap();
+    let peak = PEAK.load(Ordering::SeqCst) - base;
+
+    assert_eq!(next, 3000);
+    assert!(source.total > 6_000_000);
+    assert!(peak < 64 * 1024, "peak allocation of {} bytes", peak);
+}
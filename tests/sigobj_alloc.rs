@@ -0,0 +1,97 @@
+//! Counts the allocations made while validating a signed object.
+//!
+//! Validating a signed object with a warmed up scratch buffer allocates
+//! only inside _ring_, which needs a handful of heap allocated big
+//! integers for every RSA signature it verifies. There are two of those:
+//! the signature over the signed attributes and the signature of the EE
+//! certificate. The test checks that nothing else allocates.
+//!
+//! This lives in its own test binary because it installs a global
+//! allocator that counts allocations. It should only contain a single
+//! test so that no other test allocates at the same time.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rpki::cert::{Cert, ResourceCert};
+use rpki::sigobj::SignedObject;
+use rpki::tal::TalInfo;
+use rpki::x509::{SignedData, Time};
+
+
+//------------ Counting ------------------------------------------------------
+
+/// An allocator that counts the number of allocations.
+struct Counting;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Returns the number of allocations made by `op`.
+fn count_allocs<T, F: FnOnce() -> T>(op: F) -> usize {
+    let start = ALLOCS.load(Ordering::SeqCst);
+    let res = op();
+    let count = ALLOCS.load(Ordering::SeqCst) - start;
+    drop(res);
+    count
+}
+
+
+//------------ Tests ---------------------------------------------------------
+
+#[test]
+fn validate_with_scratch_allocs() {
+    let talinfo = TalInfo::from_name("foo".into()).into_arc();
+    let at = Time::utc(2019, 5, 1, 0, 0, 0);
+    let issuer = Cert::decode(
+        include_bytes!("../test-data/ta.cer").as_ref()
+    ).unwrap().validate_ta_at(talinfo, false, at).unwrap();
+    let obj = SignedObject::decode(
+        include_bytes!("../test-data/sigobj-der.mft").as_ref(), true
+    ).unwrap();
+
+    let ee = SignedData::decode(obj.cert().to_der().as_ref()).unwrap();
+    let key = issuer.subject_public_key_info();
+
+    // Warm up the scratch buffer.
+    let mut scratch = Vec::new();
+    obj.clone().validate_with_scratch(
+        &issuer, false, at, &mut scratch
+    ).unwrap();
+
+    // The allocations ring makes for verifying a single signature.
+    let per_signature = count_allocs(|| {
+        ee.verify_signature(key).unwrap()
+    });
+    assert_eq!(
+        count_allocs(|| ee.verify_signature_into(key, &mut scratch).unwrap()),
+        per_signature
+    );
+
+    // With scratch, only the two signature verifications allocate.
+    let cloned = obj.clone();
+    let with_scratch = count_allocs(|| -> ResourceCert {
+        cloned.validate_with_scratch(
+            &issuer, false, at, &mut scratch
+        ).unwrap()
+    });
+    assert_eq!(with_scratch, 2 * per_signature);
+
+    // Without, re-encoding the signed attributes needs a new buffer.
+    let without = count_allocs(|| -> ResourceCert {
+        obj.validate_at(&issuer, false, at).unwrap()
+    });
+    assert_eq!(without, with_scratch + 1);
+}
@@ -0,0 +1,81 @@
+//! Measures how long validating a signed object takes.
+//!
+//! The test is ignored by default since it only reports timings. Run it in
+//! release mode to get meaningful numbers:
+//!
+//! ```text
+//! cargo test --release --test validate_bench -- --ignored --nocapture
+//! ```
+//!
+//! Besides the time for a complete validation, it reports the time a
+//! validation would spend on calculating the SHA-1 key identifiers if they
+//! weren’t calculated once when decoding a key.
+
+use std::time::{Duration, Instant};
+use rpki::cert::Cert;
+use rpki::crypto::digest::sha1_digest;
+use rpki::sigobj::SignedObject;
+use rpki::tal::TalInfo;
+use rpki::x509::Time;
+
+
+//------------ Constants -----------------------------------------------------
+
+/// The number of rounds measured.
+const ROUNDS: u32 = 10_000;
+
+
+//------------ Helpers -------------------------------------------------------
+
+/// Runs `op` `ROUNDS` times and returns the average duration of a round.
+fn measure<F: FnMut()>(mut op: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        op()
+    }
+    start.elapsed() / ROUNDS
+}
+
+
+//------------ Tests ---------------------------------------------------------
+
+#[test]
+#[ignore]
+fn validation_throughput() {
+    let talinfo = TalInfo::from_name("foo".into()).into_arc();
+    let at = Time::utc(2019, 5, 1, 0, 0, 0);
+    let issuer = Cert::decode(
+        include_bytes!("../test-data/ta.cer").as_ref()
+    ).unwrap().validate_ta_at(talinfo, false, at).unwrap();
+    let obj = SignedObject::decode(
+        include_bytes!("../test-data/sigobj-der.mft").as_ref(), true
+    ).unwrap();
+    let mut scratch = Vec::new();
+
+    let validate = measure(|| {
+        obj.clone().validate_with_scratch(
+            &issuer, false, at, &mut scratch
+        ).unwrap();
+    });
+
+    // Validation needs the key identifiers of both the issuer’s and the
+    // EE certificate’s key.
+    let keys = [
+        issuer.subject_public_key_info(),
+        obj.cert().subject_public_key_info(),
+    ];
+    let cached = measure(|| {
+        for key in keys.iter() {
+            assert!(!key.key_identifier().as_slice().is_empty());
+        }
+    });
+    let hashed = measure(|| {
+        for key in keys.iter() {
+            assert!(!sha1_digest(key.bits()).as_ref().is_empty());
+        }
+    });
+
+    println!("validating a signed object: {:?}", validate);
+    println!("key identifiers, stored:     {:?}", cached);
+    println!("key identifiers, hashed:     {:?}", hashed);
+}